@@ -2,7 +2,11 @@
 //!
 //! See the docs of the [`crate::metadata`] module for a description of the `M` parameter.
 
-use crate::metadata::Metadata;
+use std::fmt::{self, Display, Formatter};
+
+use regex::Regex;
+
+use crate::{formats::IntEncoding, metadata::Metadata};
 
 /// Represents an anonymous type.
 #[derive(Debug, Clone)]
@@ -18,8 +22,18 @@ pub enum TypeType<M: Metadata> {
     Struct(Struct<M>),
     Enum(Enum<M>),
     List(List<M>),
+    Tuple(Tuple<M>),
     Primitive(Primitive<M>),
     Identifier(Identifier<M>),
+    Parameter(Parameter<M>),
+    Extern(Extern<M>),
+}
+
+impl<M: Metadata> TypeType<M> {
+    /// Checks whether the type is `unit`, so its only value carries no data.
+    pub fn is_unit(&self) -> bool {
+        matches!(self, TypeType::Primitive(primitive) if primitive.r#type == PrimitiveType::Unit)
+    }
 }
 
 /// Represents a `struct { }` node.
@@ -34,13 +48,28 @@ pub struct Struct<M: Metadata> {
 pub struct Field<M: Metadata> {
     pub name: String,
     pub r#type: Type<M>,
+    pub default: Option<Value>,
+    /// Whether this is a spread like `..Timestamps`, which includes the fields of another struct.
+    ///
+    /// The name of a spread is the name of the struct, and its type is an identifier referring to it.
+    pub spread: bool,
     pub metadata: M::Field,
 }
 
-/// Represents an `enum { }` node.
+/// Represents a literal value, used as the default value of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Value {
+    Number(i64),
+    String(String),
+    List(Vec<Value>),
+}
+
+/// Represents an `enum { }` or `open enum { }` node.
 #[derive(Debug, Clone)]
 pub struct Enum<M: Metadata> {
     pub variants: Vec<Variant<M>>,
+    /// Whether the enum is open, accepting variants it doesn't know of, which are added in later versions.
+    pub open: bool,
     pub metadata: M::Enum,
 }
 
@@ -59,44 +88,291 @@ pub struct List<M: Metadata> {
     pub metadata: M::List,
 }
 
-/// Represents an `int`/`string`/`unit` node.
+/// Represents a `( , )` node, with at least two elements.
+///
+/// Parentheses around a single type only group it, so they don't produce a tuple.
+#[derive(Debug, Clone)]
+pub struct Tuple<M: Metadata> {
+    pub elements: Vec<Type<M>>,
+    pub metadata: M::Tuple,
+}
+
+/// Represents an `int`/`string`/`unit` node, possibly refined like `int(0..150)`.
 #[derive(Debug, Clone)]
 pub struct Primitive<M: Metadata> {
     pub r#type: PrimitiveType,
+    pub refinements: Refinements,
     pub metadata: M::Primitive,
 }
 
+impl<M: Metadata> Display for Primitive<M> {
+    /// Formats the primitive type like it's written in a schema, including its refinements.
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.write_str(self.r#type.name())?;
+
+        let Refinements { min, max, pattern } = &self.refinements;
+
+        if self.refinements.is_empty() {
+            Ok(())
+        } else if self.r#type == PrimitiveType::Number {
+            f.write_str("(")?;
+            if let Some(min) = min {
+                write!(f, "{min}")?;
+            }
+            f.write_str("..")?;
+            if let Some(max) = max {
+                write!(f, "{max}")?;
+            }
+            f.write_str(")")
+        } else {
+            let min = min.map(|min| format!("min = {min}"));
+            let max = max.map(|max| format!("max = {max}"));
+            let pattern = pattern
+                .as_ref()
+                .map(|pattern| format!("pattern = {:?}", pattern.as_str()));
+            let arguments: Vec<String> = [min, max, pattern].into_iter().flatten().collect();
+
+            write!(f, "({})", arguments.join(", "))
+        }
+    }
+}
+
 /// Represents the type of a primitive type.
+///
+/// The built-in types after `Unit` are encoded as strings in JSON, with a fixed format each.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum PrimitiveType {
     String,
     Number,
     Unit,
+    /// A point in time, encoded as an RFC 3339 timestamp, like `2024-05-06T07:08:09Z`.
+    Timestamp,
+    /// A calendar date, encoded like `2024-05-06`.
+    Date,
+    /// A signed length of time, encoded as an ISO 8601 duration in seconds, like `PT1.5S`.
+    Duration,
+    /// A UUID, encoded in its hyphenated form.
+    Uuid,
+    /// A sequence of bytes, encoded as standard Base64 with padding.
+    Bytes,
+}
+
+impl PrimitiveType {
+    /// The built-in types whose names are only keywords where a type is expected.
+    pub const BUILT_IN: [PrimitiveType; 5] = [
+        PrimitiveType::Timestamp,
+        PrimitiveType::Date,
+        PrimitiveType::Duration,
+        PrimitiveType::Uuid,
+        PrimitiveType::Bytes,
+    ];
+
+    /// Gets the name of the primitive type, as it's written in a schema.
+    pub fn name(self) -> &'static str {
+        match self {
+            PrimitiveType::String => "string",
+            PrimitiveType::Number => "int",
+            PrimitiveType::Unit => "unit",
+            PrimitiveType::Timestamp => "timestamp",
+            PrimitiveType::Date => "date",
+            PrimitiveType::Duration => "duration",
+            PrimitiveType::Uuid => "uuid",
+            PrimitiveType::Bytes => "bytes",
+        }
+    }
+
+    /// Finds the built-in type with a name.
+    pub fn built_in(name: &str) -> Option<PrimitiveType> {
+        Self::BUILT_IN
+            .into_iter()
+            .find(|r#type| r#type.name() == name)
+    }
+
+    /// Checks whether the primitive type is one of the built-in types encoded as formatted strings.
+    pub fn is_formatted(self) -> bool {
+        Self::BUILT_IN.contains(&self)
+    }
+}
+
+/// Constraints on the values of an `int` or a `string`, like `int(0..150)` or `string(max = 64)`.
+///
+/// The bounds are inclusive. For strings, they limit the length, counted in Unicode code points.
+#[derive(Debug, Clone, Default)]
+pub struct Refinements {
+    pub min: Option<i64>,
+    pub max: Option<i64>,
+    /// A regular expression, which has to match some part of a string.
+    pub pattern: Option<Regex>,
+}
+
+impl Refinements {
+    /// The refinements of an unrefined primitive type.
+    pub const NONE: Refinements = Refinements {
+        min: None,
+        max: None,
+        pattern: None,
+    };
+
+    /// Checks whether there are no refinements.
+    pub fn is_empty(&self) -> bool {
+        self.min.is_none() && self.max.is_none() && self.pattern.is_none()
+    }
+
+    /// Checks whether an int satisfies the refinements, describing the expected value if it doesn't.
+    pub fn check_int(&self, value: i64) -> Result<(), String> {
+        match self.expected_int() {
+            Some(expected) if !self.is_within_bounds(value) => Err(expected),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks whether a string satisfies the refinements, describing the expected value if it doesn't.
+    pub fn check_string(&self, value: &str) -> Result<(), String> {
+        let length = i64::try_from(value.chars().count()).unwrap_or(i64::MAX);
+
+        match (self.expected_length(), &self.pattern) {
+            (Some(expected), _) if !self.is_within_bounds(length) => Err(expected),
+            (_, Some(pattern)) if !pattern.is_match(value) => {
+                Err(self.expected_pattern().unwrap_or_default())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    /// Describes the ints allowed by the bounds, like `expected an int between 0 and 150`, if there are any.
+    pub fn expected_int(&self) -> Option<String> {
+        self.describe_bounds("an int", "an int of")
+    }
+
+    /// Describes the string lengths allowed by the bounds, if there are any.
+    pub fn expected_length(&self) -> Option<String> {
+        self.describe_bounds("a string of length", "a string of length")
+    }
+
+    /// Describes the strings allowed by the pattern, if there is one.
+    pub fn expected_pattern(&self) -> Option<String> {
+        self.pattern.as_ref().map(|pattern| {
+            format!(
+                "expected a string matching the pattern {:?}",
+                pattern.as_str()
+            )
+        })
+    }
+
+    /// Checks whether a number is within the bounds.
+    fn is_within_bounds(&self, value: i64) -> bool {
+        self.min.is_none_or(|min| value >= min) && self.max.is_none_or(|max| value <= max)
+    }
+
+    /// Describes the numbers allowed by the bounds.
+    ///
+    /// The value is described by `range` if there are both bounds and by `bound` if there's only one.
+    fn describe_bounds(&self, range: &str, bound: &str) -> Option<String> {
+        match (self.min, self.max) {
+            (Some(min), Some(max)) => Some(format!("expected {range} between {min} and {max}")),
+            (Some(min), None) => Some(format!("expected {bound} at least {min}")),
+            (None, Some(max)) => Some(format!("expected {bound} at most {max}")),
+            (None, None) => None,
+        }
+    }
 }
 
-/// Represents an identifier node.
+impl PartialEq for Refinements {
+    fn eq(&self, other: &Self) -> bool {
+        self.min == other.min
+            && self.max == other.max
+            && self.pattern.as_ref().map(Regex::as_str) == other.pattern.as_ref().map(Regex::as_str)
+    }
+}
+
+impl Eq for Refinements {}
+
+/// Represents an identifier node, with the type arguments of a generic type, like `Page<User>`.
 #[derive(Debug, Clone)]
 pub struct Identifier<M: Metadata> {
     pub ident: String,
+    pub arguments: Vec<Type<M>>,
     pub metadata: M::Identifier,
 }
 
+/// Represents a type parameter of a named type, either where it's declared or where it's used.
+///
+/// The parser only produces declarations, uses get turned from identifiers into parameters during name resolution.
+#[derive(Debug, Clone)]
+pub struct Parameter<M: Metadata> {
+    pub name: String,
+    pub metadata: M::Parameter,
+}
+
+/// Represents the body of an `extern Name;` declaration, a type that Versed doesn't know.
+///
+/// It only appears as the type of a named type, and every backend maps it to a type of its target language.
+#[derive(Debug, Clone)]
+pub struct Extern<M: Metadata> {
+    /// The name of the declaration, which the mappings refer to.
+    pub name: String,
+    pub metadata: M::Extern,
+}
+
 /// Represents the root of a schema file, or half of a migration file.
 #[derive(Debug, Clone)]
 pub struct TypeSet<M: Metadata> {
     pub version: String,
+    /// How ints are encoded in JSON, set with an `int_encoding` directive.
+    pub int_encoding: IntEncoding,
     pub types: Vec<NamedType<M>>,
     pub metadata: M::TypeSet,
 }
 
-/// Represents a top-level type with a name assigned to it.
+impl<M: Metadata> TypeSet<M> {
+    /// Finds the index of the named type with a given name.
+    pub fn index_of(&self, name: &str) -> Option<usize> {
+        self.types.iter().position(|r#type| r#type.name == name)
+    }
+}
+
+/// Represents a top-level type with a name assigned to it, and possibly type parameters.
 #[derive(Debug, Clone)]
 pub struct NamedType<M: Metadata> {
     pub name: String,
+    pub parameters: Vec<Parameter<M>>,
     pub r#type: Type<M>,
     pub metadata: M::Named,
 }
 
+/// The type arguments in scope while following identifiers into generic named types.
+///
+/// Used to find the type a [`Parameter`] stands for.
+/// Arguments belong to the scope where they were written, which is the parent scope.
+#[derive(Debug, Clone, Copy)]
+pub struct Scope<'a, M: Metadata> {
+    pub arguments: &'a [Type<M>],
+    pub parent: Option<&'a Scope<'a, M>>,
+}
+
+impl<'a, M: Metadata> Scope<'a, M> {
+    /// The scope of a top-level type, with no type arguments.
+    pub const ROOT: Scope<'a, M> = Scope {
+        arguments: &[],
+        parent: None,
+    };
+
+    /// Creates the scope inside a generic named type, with the arguments of an identifier.
+    pub fn enter(&'a self, arguments: &'a [Type<M>]) -> Self {
+        Self {
+            arguments,
+            parent: Some(self),
+        }
+    }
+
+    /// Gets the argument of the parameter with a given index, along with the scope it belongs to.
+    ///
+    /// Returns `None` if the argument is missing, which can only happen in malformed schemas.
+    pub fn argument(&self, index: usize) -> Option<(&'a Type<M>, &'a Scope<'a, M>)> {
+        self.arguments.get(index).zip(self.parent)
+    }
+}
+
 /// Represents the root of a migration file.
 #[derive(Debug, Clone)]
 pub struct Migration<M: Metadata> {
@@ -3,8 +3,8 @@
 use std::{
     cmp::Reverse,
     fmt,
-    fs::{File, OpenOptions},
-    io::{BufWriter, Read, Result, Seek, SeekFrom, Write},
+    fs::{File, OpenOptions, remove_file, rename},
+    io::{BufWriter, IntoInnerError, Read, Result, Seek, SeekFrom, Write},
     ops::Range,
     path::{Path, PathBuf},
 };
@@ -84,6 +84,58 @@ pub fn concat_files(a: &str, b: &str, path: &Path) -> Result<()> {
     Ok(())
 }
 
+/// How [`write_file`] treats a file that already exists.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WriteMode {
+    /// Fails if the file already exists.
+    New,
+    /// Truncates the file and writes it again.
+    Overwrite,
+    /// Writes a temporary file next to it and renames it over the file,
+    /// so that the file is never left half-written.
+    ///
+    /// Used for files that were generated by an earlier run in watch mode.
+    Replace,
+}
+
+/// Writes a file, treating an existing file according to `mode`.
+pub fn write_file(
+    path: &Path,
+    mode: WriteMode,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<()>,
+) -> Result<()> {
+    let file = match mode {
+        WriteMode::New => File::create_new(path)?,
+        WriteMode::Overwrite => File::create(path)?,
+        WriteMode::Replace => return replace_file(path, write),
+    };
+
+    let mut file = BufWriter::new(file);
+    write(&mut file)?;
+    file.flush()
+}
+
+/// Writes a file by writing a temporary file next to it and then renaming it, see [`WriteMode::Replace`].
+fn replace_file(path: &Path, write: impl FnOnce(&mut BufWriter<File>) -> Result<()>) -> Result<()> {
+    let temp_path = add_extension(path, ".tmp");
+
+    let result = File::create(&temp_path)
+        .and_then(|file| {
+            let mut file = BufWriter::new(file);
+            write(&mut file)?;
+            file.into_inner()
+                .map_err(IntoInnerError::into_error)?
+                .sync_all()
+        })
+        .and_then(|()| rename(&temp_path, path));
+
+    if result.is_err() {
+        let _ = remove_file(&temp_path);
+    }
+
+    result
+}
+
 /// Text to be inserted into a file at a specified byte position.
 #[derive(Debug)]
 pub struct AddEdit {
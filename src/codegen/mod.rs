@@ -3,4 +3,6 @@
 pub mod file_patching;
 pub mod idents;
 pub mod naming_pass;
+pub mod output;
 pub mod source_writer;
+pub mod validation;
@@ -4,8 +4,8 @@ use std::{collections::HashSet, marker::PhantomData};
 
 use crate::{
     ast::{
-        Enum, Field, Identifier, List, NamedType, Primitive, Struct, Type, TypeSet, TypeType,
-        Variant,
+        Enum, Extern, Field, Identifier, List, NamedType, Parameter, Primitive, Struct, Tuple,
+        Type, TypeSet, TypeType, Variant,
     },
     codegen::idents::{CaseType, IdentRules, convert_case, disambiguate},
     metadata::{MapMetadata, Metadata},
@@ -38,7 +38,7 @@ impl<C: CaseType + Copy, I: IdentRules + Copy> NamingRule for (C, I) {
 
 /// A collection of rules to name every type of named entity.
 pub trait NamingRules {
-    /// Gets the rules for naming top level types.
+    /// Gets the rules for naming top level types, which are used for type parameters as well.
     fn r#type(&self) -> impl NamingRule;
     /// Gets the rules for naming fields.
     fn field(&self) -> impl NamingRule;
@@ -61,6 +61,8 @@ struct NamingContext<A, B, Map, Rules> {
     ///
     /// Used for naming anonymous types.
     type_name_stack: Vec<String>,
+    /// The original and new names of the type parameters of the current named type.
+    parameters: Vec<(String, String)>,
 
     _phantom_a: PhantomData<A>,
     _phantom_b: PhantomData<B>,
@@ -81,6 +83,7 @@ where
 
             used_types: HashSet::new(),
             type_name_stack: Vec::new(),
+            parameters: Vec::new(),
 
             _phantom_a: PhantomData,
             _phantom_b: PhantomData,
@@ -92,6 +95,7 @@ where
         &mut self,
         TypeSet {
             version,
+            int_encoding,
             types,
             metadata,
         }: TypeSet<A>,
@@ -100,14 +104,17 @@ where
 
         for NamedType {
             name,
+            parameters,
             r#type,
             metadata,
         } in types
         {
+            let parameters = self.name_parameters(parameters);
             let (r#type, name) = self.push_and_name_type(r#type, name);
 
             new_types.push(NamedType {
                 name,
+                parameters,
                 r#type,
                 metadata: self.map.map_named(metadata, ()),
             });
@@ -117,6 +124,7 @@ where
 
         TypeSet {
             version,
+            int_encoding,
             types: new_types,
             metadata: self.map.map_type_set(metadata, version_name),
         }
@@ -128,13 +136,43 @@ where
             TypeType::Struct(r#struct) => TypeType::Struct(self.name_struct(r#struct)),
             TypeType::Enum(r#enum) => TypeType::Enum(self.name_enum(r#enum)),
             TypeType::List(list) => TypeType::List(self.name_list(list)),
+            TypeType::Tuple(tuple) => TypeType::Tuple(self.name_tuple(tuple)),
             TypeType::Primitive(primitive) => TypeType::Primitive(self.name_primitive(primitive)),
             TypeType::Identifier(identifier) => {
                 TypeType::Identifier(self.name_identifier(identifier))
             }
+            TypeType::Parameter(parameter) => TypeType::Parameter(self.name_parameter(parameter)),
+            TypeType::Extern(Extern { name, metadata }) => TypeType::Extern(Extern {
+                name,
+                metadata: self.map.map_extern(metadata, ()),
+            }),
         }
     }
 
+    /// Names the type parameters of a named type, and makes them the current ones.
+    ///
+    /// Type parameters are named by the rules for types and can't collide with each other
+    /// or with the types named so far.
+    fn name_parameters(&mut self, parameters: Vec<Parameter<A>>) -> Vec<Parameter<B>> {
+        self.parameters.clear();
+
+        parameters
+            .into_iter()
+            .map(|Parameter { name, metadata }| {
+                let converted_name = self.rules.r#type().name([name.as_str()], |converted| {
+                    self.used_types.contains(converted)
+                        || self.parameters.iter().any(|(_, used)| used == converted)
+                });
+                self.parameters.push((name.clone(), converted_name.clone()));
+
+                Parameter {
+                    name,
+                    metadata: self.map.map_parameter(metadata, converted_name),
+                }
+            })
+            .collect()
+    }
+
     /// Visits and names a type recursively, with `name` added to [`NamingContext::type_name_stack`].
     fn push_and_name_type(&mut self, r#type: Type<A>, name: String) -> (Type<B>, String) {
         self.type_name_stack.push(name);
@@ -159,39 +197,71 @@ where
     }
 
     /// Visits and names a struct recursively.
+    ///
+    /// Spread fields are named after the other fields, as their names don't appear in the JSON
+    /// representation, so they must not change the names of the fields that do.
     fn name_struct(&mut self, Struct { fields, metadata }: Struct<A>) -> Struct<B> {
-        let mut new_fields = Vec::with_capacity(fields.len());
+        let fields: Vec<_> = fields
+            .into_iter()
+            .map(
+                |Field {
+                     name,
+                     r#type,
+                     default,
+                     spread,
+                     metadata,
+                 }| {
+                    let (r#type, name) = self.push_and_name_type(r#type, name);
+                    (name, r#type, default, spread, metadata)
+                },
+            )
+            .collect();
+
+        let mut converted_names = vec![String::new(); fields.len()];
         let mut used_names = HashSet::new();
 
-        for Field {
-            name,
-            r#type,
-            metadata,
-        } in fields
-        {
-            let (r#type, name) = self.push_and_name_type(r#type, name);
-
-            let converted_name = self
-                .rules
-                .field()
-                .name([name.as_str()], |name| used_names.contains(name));
-            used_names.insert(converted_name.clone());
-
-            new_fields.push(Field {
-                name,
-                r#type,
-                metadata: self.map.map_field(metadata, converted_name),
-            });
+        for spreads in [false, true] {
+            for (index, (name, _, _, spread, _)) in fields.iter().enumerate() {
+                if *spread == spreads {
+                    let converted_name = self
+                        .rules
+                        .field()
+                        .name([name.as_str()], |name| used_names.contains(name));
+                    used_names.insert(converted_name.clone());
+                    converted_names[index] = converted_name;
+                }
+            }
         }
 
+        let fields = fields
+            .into_iter()
+            .zip(converted_names)
+            .map(
+                |((name, r#type, default, spread, metadata), converted_name)| Field {
+                    name,
+                    r#type,
+                    default,
+                    spread,
+                    metadata: self.map.map_field(metadata, converted_name),
+                },
+            )
+            .collect();
+
         Struct {
-            fields: new_fields,
+            fields,
             metadata: self.map.map_struct(metadata, ()),
         }
     }
 
     /// Visits and names an enum recursively.
-    fn name_enum(&mut self, Enum { variants, metadata }: Enum<A>) -> Enum<B> {
+    fn name_enum(
+        &mut self,
+        Enum {
+            variants,
+            open,
+            metadata,
+        }: Enum<A>,
+    ) -> Enum<B> {
         let mut new_variants = Vec::with_capacity(variants.len());
         let mut used_names = HashSet::new();
 
@@ -218,6 +288,7 @@ where
 
         Enum {
             variants: new_variants,
+            open,
             metadata: self.map.map_enum(metadata, ()),
         }
     }
@@ -235,22 +306,82 @@ where
         }
     }
 
+    /// Visits and names a tuple recursively.
+    ///
+    /// Elements are named like list elements, followed by their index.
+    fn name_tuple(&mut self, Tuple { elements, metadata }: Tuple<A>) -> Tuple<B> {
+        let elements = elements
+            .into_iter()
+            .enumerate()
+            .map(|(index, element)| {
+                let name = format!("{}_{index}", Self::LIST_ELEMENT_NAME);
+                self.push_and_name_type(element, name).0
+            })
+            .collect();
+
+        Tuple {
+            elements,
+            metadata: self.map.map_tuple(metadata, ()),
+        }
+    }
+
     /// Visits and names a primitive.
-    fn name_primitive(&mut self, Primitive { r#type, metadata }: Primitive<A>) -> Primitive<B> {
+    fn name_primitive(
+        &mut self,
         Primitive {
             r#type,
+            refinements,
+            metadata,
+        }: Primitive<A>,
+    ) -> Primitive<B> {
+        Primitive {
+            r#type,
+            refinements,
             metadata: self.map.map_primitive(metadata, ()),
         }
     }
 
-    /// Visits and names an identifier.
-    fn name_identifier(&mut self, Identifier { ident, metadata }: Identifier<A>) -> Identifier<B> {
+    /// The name added to [`NamingContext::type_name_stack`] to refer to a type argument.
+    const ARGUMENT_NAME: &str = "argument";
+
+    /// Visits and names an identifier, with its type arguments.
+    fn name_identifier(
+        &mut self,
+        Identifier {
+            ident,
+            arguments,
+            metadata,
+        }: Identifier<A>,
+    ) -> Identifier<B> {
+        let arguments = arguments
+            .into_iter()
+            .map(|argument| {
+                self.push_and_name_type(argument, Self::ARGUMENT_NAME.to_owned())
+                    .0
+            })
+            .collect();
+
         Identifier {
             ident,
+            arguments,
             metadata: self.map.map_identifier(metadata, ()),
         }
     }
 
+    /// Names a use of a type parameter after its declaration.
+    fn name_parameter(&mut self, Parameter { name, metadata }: Parameter<A>) -> Parameter<B> {
+        let converted_name = self
+            .parameters
+            .iter()
+            .find(|(original, _)| *original == name)
+            .map_or_else(|| name.clone(), |(_, converted)| converted.clone());
+
+        Parameter {
+            name,
+            metadata: self.map.map_parameter(metadata, converted_name),
+        }
+    }
+
     /// Constructs a name for the current type based on [`NamingContext::type_name_stack`].
     fn current_type_name(&mut self) -> String {
         let parts = self.type_name_stack.iter().map(String::as_str);
@@ -277,8 +408,11 @@ impl Metadata for NameMetadata {
     type Struct = ();
     type Enum = ();
     type List = ();
+    type Tuple = ();
     type Primitive = ();
     type Identifier = ();
+    type Parameter = String;
+    type Extern = ();
 
     type Field = String;
     type Variant = String;
@@ -0,0 +1,34 @@
+//! Defines where backends write the generated code.
+
+use std::{
+    io::{self, BufWriter, StdoutLock, Write, stdout},
+    path::Path,
+};
+
+use crate::{
+    codegen::source_writer::SourceWriter,
+    error::{Error, ResultExt},
+};
+
+/// The destination of generated code.
+#[derive(Debug, Clone, Copy)]
+pub enum Output<'a> {
+    /// A new file named after the schema version inside a directory,
+    /// which is also referenced from the index file of the directory.
+    Directory(&'a Path),
+    /// A specific file, which is overwritten if it exists.
+    File(&'a Path),
+    /// Standard output, without touching any files.
+    Stdout,
+}
+
+/// Writes generated code to standard output using `emit`.
+pub fn write_to_stdout(
+    emit: impl FnOnce(&mut SourceWriter<BufWriter<StdoutLock>>) -> io::Result<()>,
+) -> Result<(), Error> {
+    let mut writer = SourceWriter::new(BufWriter::new(stdout().lock()));
+
+    emit(&mut writer)
+        .and_then(|()| writer.into_inner().flush())
+        .with_stdout()
+}
@@ -120,6 +120,20 @@ impl<Writer: Write> SourceWriter<Writer> {
         }
     }
 
+    /// Appends source code written separately, such as by another [`SourceWriter`],
+    /// one line at a time, so that it's indented and its blank lines are deduplicated.
+    pub fn write_source(&mut self, source: &str) -> Result<()> {
+        for line in source.lines() {
+            if line.is_empty() {
+                self.blank_line();
+            } else {
+                self.write_nl(line)?;
+            }
+        }
+
+        Ok(())
+    }
+
     /// Unwraps the [`SourceWriter`], returning the contained stream.
     pub fn into_inner(self) -> Writer {
         self.writer
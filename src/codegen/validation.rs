@@ -0,0 +1,506 @@
+//! Finds out which values need to be validated and walks their types,
+//! which is shared by the backends generating validation functions and type guards.
+//!
+//! Validating a value checks the refinements of all primitive types inside it.
+//! Every named type gets a validation function, which is generic over the type parameters it checks
+//! and takes a function validating their values for each of them.
+
+use std::{
+    collections::BTreeSet,
+    io::{Result, Write},
+};
+
+use crate::{
+    ast::{
+        Enum, Field, Identifier, Parameter, Primitive, PrimitiveType, Struct, Tuple, Type, TypeSet,
+        TypeType,
+    },
+    codegen::source_writer::SourceWriter,
+    metadata::{GetMetadata, Metadata},
+    preprocessing::ResolutionMetadata,
+};
+
+/// Which named types have anything to validate, and which of their type parameters they validate.
+#[derive(Debug, Clone)]
+pub struct Checks {
+    /// The primitive types whose string formats are checked, as their types don't guarantee them.
+    formats: Vec<PrimitiveType>,
+    /// Whether validating a value of each named type can fail.
+    needed: Vec<bool>,
+    /// The indices of the type parameters of each named type whose values are validated.
+    parameters: Vec<BTreeSet<usize>>,
+}
+
+impl Checks {
+    /// Finds the checks of all named types.
+    ///
+    /// This is a fixed point computation, as named types can refer to each other in cycles.
+    /// Values of the primitive types in `formats`, like `timestamp`, have their string formats checked as well.
+    pub fn new<M, GM>(types: &TypeSet<M>, get: GM, formats: Vec<PrimitiveType>) -> Self
+    where
+        M: Metadata,
+        GM: GetMetadata<M, ResolutionMetadata> + Copy,
+    {
+        let mut checks = Checks {
+            formats,
+            needed: vec![false; types.types.len()],
+            parameters: vec![BTreeSet::new(); types.types.len()],
+        };
+        let mut changed = true;
+
+        while changed {
+            changed = false;
+
+            for (index, r#type) in types.types.iter().enumerate() {
+                let mut parameters = BTreeSet::new();
+                let needed = checks.scan(&r#type.r#type, &mut parameters, get);
+
+                if needed != checks.needed[index] || parameters != checks.parameters[index] {
+                    checks.needed[index] = needed;
+                    checks.parameters[index] = parameters;
+                    changed = true;
+                }
+            }
+        }
+
+        checks
+    }
+
+    /// Checks whether validating a value of a type can fail.
+    ///
+    /// Type parameters count as checked, as their type arguments could be.
+    pub fn has_checks<M, GM>(&self, r#type: &Type<M>, get: GM) -> bool
+    where
+        M: Metadata,
+        GM: GetMetadata<M, ResolutionMetadata> + Copy,
+    {
+        self.scan(r#type, &mut BTreeSet::new(), get)
+    }
+
+    /// Checks whether validating a value of a named type can fail.
+    pub fn is_needed(&self, index: usize) -> bool {
+        self.needed[index]
+    }
+
+    /// Gets the indices of the type parameters of a named type whose values get validated.
+    ///
+    /// These are the parameters its validation function takes a function for.
+    pub fn parameters(&self, index: usize) -> &BTreeSet<usize> {
+        &self.parameters[index]
+    }
+
+    /// Checks whether validating a value of a type can fail, and collects the type parameters it validates.
+    fn scan<M, GM>(&self, r#type: &Type<M>, parameters: &mut BTreeSet<usize>, get: GM) -> bool
+    where
+        M: Metadata,
+        GM: GetMetadata<M, ResolutionMetadata> + Copy,
+    {
+        // Members are all scanned, even after one has checks, to collect all parameters
+        let mut any = |types: &mut dyn Iterator<Item = &Type<M>>| {
+            let mut needed = false;
+
+            for r#type in types {
+                needed |= self.scan(r#type, parameters, get);
+            }
+
+            needed
+        };
+
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) => {
+                any(&mut r#struct.fields.iter().map(|field| &field.r#type))
+            }
+            TypeType::Enum(r#enum) => {
+                any(&mut r#enum.variants.iter().map(|variant| &variant.r#type))
+            }
+            TypeType::List(list) => any(&mut [&*list.r#type].into_iter()),
+            TypeType::Tuple(tuple) => any(&mut tuple.elements.iter()),
+            TypeType::Primitive(primitive) => {
+                !primitive.refinements.is_empty() || self.formats.contains(&primitive.r#type)
+            }
+            // Values of extern types are opaque
+            TypeType::Extern(_) => false,
+            TypeType::Identifier(identifier) => {
+                let index = *get.get_identifier(&identifier.metadata);
+
+                if self.needed[index] {
+                    let arguments = &identifier.arguments;
+                    any(&mut self.parameters[index]
+                        .iter()
+                        .filter_map(|&parameter| arguments.get(parameter)));
+                }
+
+                self.needed[index]
+            }
+            TypeType::Parameter(parameter) => {
+                parameters.insert(*get.get_parameter(&parameter.metadata));
+                true
+            }
+        }
+    }
+}
+
+/// Formats a segment of a JSON path accessing a key of an object.
+///
+/// Keys that aren't ASCII identifiers are quoted, like `["first name"]`.
+pub fn key_segment(key: &str) -> String {
+    let simple = key.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+        && key
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+
+    if simple {
+        format!(".{key}")
+    } else {
+        format!("[{key:?}]")
+    }
+}
+
+/// Builds a condition that is true if a number is outside of inclusive bounds.
+///
+/// `number` evaluates to the number and `suffix` is appended to the bounds, like `n` for bigints.
+/// If there's a `reference` to the number, two bounds are checked with a range instead, like `!(0..=9).contains(x)`.
+pub fn bounds_condition(
+    min: Option<i64>,
+    max: Option<i64>,
+    number: &str,
+    suffix: &str,
+    reference: Option<&str>,
+) -> String {
+    match (min, max, reference) {
+        (Some(min), Some(max), Some(reference)) => {
+            format!("!({min}{suffix}..={max}{suffix}).contains({reference})")
+        }
+        (Some(min), Some(max), None) => {
+            format!("{number} < {min}{suffix} || {number} > {max}{suffix}")
+        }
+        (Some(min), None, _) => format!("{number} < {min}{suffix}"),
+        (None, Some(max), _) => format!("{number} > {max}{suffix}"),
+        (None, None, _) => "false".to_owned(),
+    }
+}
+
+/// The state of emitting the body of a function checking values.
+#[derive(Debug, Clone)]
+pub struct Body {
+    /// The names of the functions checking the type parameters, by the index of the parameter.
+    pub parameters: Vec<(usize, String)>,
+    /// The number of variables declared so far, which is used to give them unique names.
+    variables: usize,
+}
+
+impl Body {
+    /// Creates a new [`Body`] of a function taking the functions checking `parameters`.
+    pub fn new(parameters: Vec<(usize, String)>) -> Self {
+        Self {
+            parameters,
+            variables: 0,
+        }
+    }
+
+    /// Returns a new variable name, like `value0`.
+    pub fn variable(&mut self, prefix: &str) -> String {
+        let name = format!("{prefix}{}", self.variables);
+        self.variables += 1;
+        name
+    }
+
+    /// Gets the name of the function checking a type parameter.
+    pub fn parameter(&self, index: usize) -> &str {
+        let (_, checker) = self
+            .parameters
+            .iter()
+            .find(|(parameter, _)| *parameter == index)
+            .expect("checked type parameters get a checker");
+        checker
+    }
+}
+
+/// A backend emitting the statements checking a value, for the parts of its type that [`emit_checks`] walks.
+///
+/// The statements return early with an error holding the JSON path to the first invalid value.
+/// Values of named types and type parameters are checked by calling their check functions,
+/// which take a function checking each of the type arguments that are checked, see [`write_checker`].
+pub trait CheckEmitter<M: Metadata> {
+    /// An expression evaluating to a checked value.
+    type Value: Clone;
+    /// The JSON path to a checked value.
+    type Path: Clone;
+
+    /// Gets the state of the function body being emitted.
+    fn body(&mut self) -> &mut Body;
+
+    /// Checks whether anything about a value of a type is checked.
+    fn has_checks(&self, r#type: &Type<M>) -> bool;
+
+    /// Checks whether values of a type are boxed, so their check functions can't be passed directly.
+    fn is_boxed(&self, _type: &Type<M>) -> bool {
+        false
+    }
+
+    /// Gets the value that a value of a type is checked as, like the value inside its box.
+    fn unbox(&self, _type: &Type<M>, value: &Self::Value) -> Self::Value {
+        value.clone()
+    }
+
+    /// Emits the checks of the shape of a struct, returning the value its fields are read from.
+    fn begin_struct(
+        &mut self,
+        _writer: &mut SourceWriter<impl Write>,
+        _struct: &Struct<M>,
+        value: &Self::Value,
+        _path: &Self::Path,
+    ) -> Result<Self::Value> {
+        Ok(value.clone())
+    }
+
+    /// Emits the statements reading a field, returning its value and path.
+    ///
+    /// `object` is the value returned by [`CheckEmitter::begin_struct`],
+    /// or the struct itself for spreads, whose fields are flattened into the same object.
+    fn field(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        object: &Self::Value,
+        field: &Field<M>,
+        path: &Self::Path,
+    ) -> Result<(Self::Value, Self::Path)>;
+
+    /// Emits the start of a block only checking a field if it's present, if it may be missing.
+    ///
+    /// Returns whether it did, in which case [`emit_checks`] closes the block.
+    fn begin_optional(
+        &mut self,
+        _writer: &mut SourceWriter<impl Write>,
+        _field: &Field<M>,
+        _value: &Self::Value,
+    ) -> Result<bool> {
+        Ok(false)
+    }
+
+    /// Emits the checks of a value of an enum, calling [`emit_checks`] for the values of its variants.
+    fn emit_enum(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        r#type: &Type<M>,
+        r#enum: &Enum<M>,
+        value: &Self::Value,
+        path: &Self::Path,
+    ) -> Result<()>;
+
+    /// Emits the checks of the shape of a list.
+    fn begin_list(
+        &mut self,
+        _writer: &mut SourceWriter<impl Write>,
+        _value: &Self::Value,
+        _path: &Self::Path,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Emits the start of a loop over the elements of a list, returning an element and its path.
+    fn begin_loop(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        value: &Self::Value,
+        path: &Self::Path,
+    ) -> Result<(Self::Value, Self::Path)>;
+
+    /// Emits the checks of the shape of a tuple.
+    fn begin_tuple(
+        &mut self,
+        _writer: &mut SourceWriter<impl Write>,
+        _tuple: &Tuple<M>,
+        _value: &Self::Value,
+        _path: &Self::Path,
+    ) -> Result<()> {
+        Ok(())
+    }
+
+    /// Emits the statements reading an element of a tuple, returning its value and path.
+    fn element(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        value: &Self::Value,
+        index: usize,
+        path: &Self::Path,
+    ) -> Result<(Self::Value, Self::Path)>;
+
+    /// Emits the checks of a value of a primitive type.
+    fn emit_primitive(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        primitive: &Primitive<M>,
+        value: &Self::Value,
+        path: &Self::Path,
+    ) -> Result<()>;
+
+    /// Gets the name of the check function of the named type an identifier refers to.
+    fn function_name(&self, identifier: &Identifier<M>) -> String;
+
+    /// Gets the type arguments whose check functions are passed to the check function of a named type.
+    fn checked_arguments<'t>(&self, identifier: &'t Identifier<M>) -> Vec<&'t Type<M>>;
+
+    /// Gets the index of a type parameter.
+    fn parameter_index(&self, parameter: &Parameter<M>) -> usize;
+
+    /// Emits the start of a call of a check function, up to the value.
+    ///
+    /// Returns the variable the result is assigned to, which is passed to [`CheckEmitter::end_call`].
+    fn begin_call(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        function: &str,
+        value: &Self::Value,
+    ) -> Result<String>;
+
+    /// Emits the end of a call of a check function, after the check functions of the type arguments,
+    /// and returns its error, if there is one.
+    fn end_call(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        result: &str,
+        path: &Self::Path,
+    ) -> Result<()>;
+
+    /// Writes a function accepting every value, which is passed for type arguments without checks.
+    fn write_no_checks(&mut self, writer: &mut SourceWriter<impl Write>) -> Result<()>;
+
+    /// Writes the check function of a named type as an argument.
+    fn write_function(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        function: &str,
+    ) -> Result<()> {
+        writer.write(function)
+    }
+
+    /// Writes the start of a function checking a value of a type, returning its value and path.
+    fn begin_closure(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        r#type: &Type<M>,
+    ) -> Result<(Self::Value, Self::Path)>;
+
+    /// Writes the end of a function started by [`CheckEmitter::begin_closure`].
+    fn end_closure(&mut self, writer: &mut SourceWriter<impl Write>) -> Result<()>;
+}
+
+/// Emits the statements checking a value of a type, returning early with the first error.
+pub fn emit_checks<M, E>(
+    emitter: &mut E,
+    writer: &mut SourceWriter<impl Write>,
+    r#type: &Type<M>,
+    value: &E::Value,
+    path: &E::Path,
+) -> Result<()>
+where
+    M: Metadata,
+    E: CheckEmitter<M>,
+{
+    if !emitter.has_checks(r#type) {
+        return Ok(());
+    }
+
+    let value = &emitter.unbox(r#type, value);
+
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => {
+            let object = emitter.begin_struct(writer, r#struct, value, path)?;
+
+            for field in &r#struct.fields {
+                if !emitter.has_checks(&field.r#type) {
+                    continue;
+                }
+
+                let object = if field.spread { value } else { &object };
+                let (field_value, field_path) = emitter.field(writer, object, field, path)?;
+                let optional = emitter.begin_optional(writer, field, &field_value)?;
+                emit_checks(emitter, writer, &field.r#type, &field_value, &field_path)?;
+
+                if optional {
+                    end_block(writer)?;
+                }
+            }
+        }
+        TypeType::Enum(r#enum) => emitter.emit_enum(writer, r#type, r#enum, value, path)?,
+        TypeType::List(list) => {
+            emitter.begin_list(writer, value, path)?;
+
+            if emitter.has_checks(&list.r#type) {
+                let (element, element_path) = emitter.begin_loop(writer, value, path)?;
+                emit_checks(emitter, writer, &list.r#type, &element, &element_path)?;
+                end_block(writer)?;
+            }
+        }
+        TypeType::Tuple(tuple) => {
+            emitter.begin_tuple(writer, tuple, value, path)?;
+
+            for (index, element) in tuple.elements.iter().enumerate() {
+                if emitter.has_checks(element) {
+                    let (element_value, element_path) =
+                        emitter.element(writer, value, index, path)?;
+                    emit_checks(emitter, writer, element, &element_value, &element_path)?;
+                }
+            }
+        }
+        TypeType::Primitive(primitive) => emitter.emit_primitive(writer, primitive, value, path)?,
+        TypeType::Extern(_) => unreachable!("extern types to have no checks"),
+        TypeType::Identifier(identifier) => {
+            let function = emitter.function_name(identifier);
+            let result = emitter.begin_call(writer, &function, value)?;
+
+            for argument in emitter.checked_arguments(identifier) {
+                writer.write(", ")?;
+                write_checker(emitter, writer, argument)?;
+            }
+
+            emitter.end_call(writer, &result, path)?;
+        }
+        TypeType::Parameter(parameter) => {
+            let index = emitter.parameter_index(parameter);
+            let checker = emitter.body().parameter(index).to_owned();
+            let result = emitter.begin_call(writer, &checker, value)?;
+            emitter.end_call(writer, &result, path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a function checking a type argument, which is passed to the check function of a generic type.
+pub fn write_checker<M, E>(
+    emitter: &mut E,
+    writer: &mut SourceWriter<impl Write>,
+    r#type: &Type<M>,
+) -> Result<()>
+where
+    M: Metadata,
+    E: CheckEmitter<M>,
+{
+    match &r#type.r#type {
+        TypeType::Parameter(parameter) if !emitter.is_boxed(r#type) => {
+            let index = emitter.parameter_index(parameter);
+            return writer.write(emitter.body().parameter(index));
+        }
+        _ if !emitter.has_checks(r#type) => return emitter.write_no_checks(writer),
+        // Functions of named types that don't take checkers of their own can be passed directly
+        TypeType::Identifier(identifier)
+            if !emitter.is_boxed(r#type) && emitter.checked_arguments(identifier).is_empty() =>
+        {
+            let function = emitter.function_name(identifier);
+            return emitter.write_function(writer, &function);
+        }
+        _ => {}
+    }
+
+    let (value, path) = emitter.begin_closure(writer, r#type)?;
+    emit_checks(emitter, writer, r#type, &value, &path)?;
+    emitter.end_closure(writer)
+}
+
+/// Emits the end of a block started by a [`CheckEmitter`], which both backends write in braces.
+fn end_block(writer: &mut SourceWriter<impl Write>) -> Result<()> {
+    writer.dedent();
+    writer.write_nl("}")
+}
@@ -0,0 +1,447 @@
+//! Builds changelogs by comparing the versions in migration files.
+
+use std::{fs::read_dir, path::Path};
+
+use crate::{
+    ast::{Field, Migration, NamedType, Type, TypeSet, TypeType, Value, Variant},
+    docs::Inline,
+    error::{Error, ResultExt},
+    graph::{field_text, type_text, value_text, variant_text},
+    loading::load_migration_with_source,
+    preprocessing::{BasicMetadata, check_migration_continuity},
+    reports::{Reports, handle_reports},
+};
+
+/// The changes between all versions of a schema, from newest to oldest.
+#[derive(Debug, Clone)]
+pub struct Changelog {
+    pub releases: Vec<Release>,
+}
+
+/// The changes from one version to the next.
+#[derive(Debug, Clone)]
+pub struct Release {
+    pub old: String,
+    pub new: String,
+    /// A sentence for every change.
+    pub changes: Vec<Vec<Inline>>,
+}
+
+/// Loads all migration files in a directory and builds the changelog.
+///
+/// The migrations are ordered so that every migration starts at the version the previous one ends with,
+/// and it's an error if that isn't possible.
+pub fn build_changelog(directory: &Path) -> Result<Changelog, Error> {
+    let mut paths = vec![];
+
+    for entry in read_dir(directory).with_path(directory)? {
+        let path = entry.with_path(directory)?.path();
+
+        if path.extension().is_some_and(|extension| extension == "vsm") {
+            paths.push(path);
+        }
+    }
+
+    paths.sort();
+
+    let mut migrations = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        let (migration, src) = load_migration_with_source(&path)?;
+        migrations.push((path, migration, src));
+    }
+
+    let migrations = order_migrations(migrations);
+
+    for pair in migrations.windows(2) {
+        let [(_, previous, _), (path, next, src)] = pair else {
+            unreachable!("windows to have two elements");
+        };
+
+        let filename = path.to_string_lossy();
+        let mut reports = Reports::default();
+        check_migration_continuity(&previous.new, &next.old, &mut reports, &filename);
+        handle_reports(&reports, &filename, src)?;
+    }
+
+    let releases = migrations
+        .iter()
+        .rev()
+        .map(|(_, migration, _)| build_release(migration))
+        .collect();
+
+    Ok(Changelog { releases })
+}
+
+/// Orders migrations so that every migration starts at the version the previous one ends with,
+/// keeping the given order where there are several candidates.
+fn order_migrations<P, S>(
+    mut migrations: Vec<(P, Migration<BasicMetadata>, S)>,
+) -> Vec<(P, Migration<BasicMetadata>, S)> {
+    let mut ordered: Vec<(P, Migration<BasicMetadata>, S)> = Vec::with_capacity(migrations.len());
+
+    while !migrations.is_empty() {
+        let next = ordered
+            .last()
+            .and_then(|(_, previous, _)| {
+                migrations
+                    .iter()
+                    .position(|(_, migration, _)| migration.old.version == previous.new.version)
+            })
+            .or_else(|| {
+                migrations.iter().position(|(_, migration, _)| {
+                    !migrations
+                        .iter()
+                        .any(|(_, other, _)| other.new.version == migration.old.version)
+                })
+            })
+            .unwrap_or(0);
+
+        ordered.push(migrations.remove(next));
+    }
+
+    ordered
+}
+
+/// Checks whether two types are linked by their migration markers.
+fn is_linked(old: &Type<BasicMetadata>, new: &Type<BasicMetadata>) -> bool {
+    old.number.is_some() && old.number == new.number
+}
+
+/// Describes the changes made by a migration.
+fn build_release(migration: &Migration<BasicMetadata>) -> Release {
+    let Migration { old, new } = migration;
+    let mut changes = vec![];
+
+    for new_named in &new.types {
+        let old_named = old
+            .types
+            .iter()
+            .find(|old_named| is_linked(&old_named.r#type, &new_named.r#type));
+
+        match old_named {
+            None => changes.push(vec![
+                text("Added the type "),
+                code(&new_named.name),
+                text("."),
+            ]),
+            Some(old_named) => diff_named(migration, old_named, new_named, &mut changes),
+        }
+    }
+
+    for old_named in &old.types {
+        if !new
+            .types
+            .iter()
+            .any(|new_named| is_linked(&old_named.r#type, &new_named.r#type))
+        {
+            changes.push(vec![
+                text("Removed the type "),
+                code(&old_named.name),
+                text("."),
+            ]);
+        }
+    }
+
+    Release {
+        old: old.version.clone(),
+        new: new.version.clone(),
+        changes,
+    }
+}
+
+/// Describes the changes to a named type.
+fn diff_named(
+    migration: &Migration<BasicMetadata>,
+    old_named: &NamedType<BasicMetadata>,
+    new_named: &NamedType<BasicMetadata>,
+    changes: &mut Vec<Vec<Inline>>,
+) {
+    let Migration { old, new } = migration;
+
+    if old_named.name != new_named.name {
+        changes.push(vec![
+            text("Renamed the type "),
+            code(&old_named.name),
+            text(" to "),
+            code(&new_named.name),
+            text("."),
+        ]);
+    }
+
+    let owner = &new_named.name;
+
+    match (&old_named.r#type.r#type, &new_named.r#type.r#type) {
+        (TypeType::Struct(old_struct), TypeType::Struct(new_struct)) => {
+            diff_members(
+                migration,
+                owner,
+                &old_struct.fields,
+                &new_struct.fields,
+                changes,
+            );
+        }
+        (TypeType::Enum(old_enum), TypeType::Enum(new_enum)) => {
+            if old_enum.open != new_enum.open {
+                changes.push(vec![
+                    text(if new_enum.open {
+                        "Opened the enum "
+                    } else {
+                        "Closed the enum "
+                    }),
+                    code(owner),
+                    text("."),
+                ]);
+            }
+
+            diff_members(
+                migration,
+                owner,
+                &old_enum.variants,
+                &new_enum.variants,
+                changes,
+            );
+        }
+        _ if !same_type(migration, &old_named.r#type, &new_named.r#type) => {
+            changes.push(vec![
+                text("Changed the type "),
+                code(owner),
+                text(" from "),
+                code(&type_text(old, &old_named.r#type)),
+                text(" to "),
+                code(&type_text(new, &new_named.r#type)),
+                text("."),
+            ]);
+        }
+        _ => {}
+    }
+}
+
+/// A field or a variant.
+trait Member {
+    /// The word used to refer to this kind of member.
+    const KIND: &str;
+
+    fn name(&self) -> &str;
+    fn r#type(&self) -> &Type<BasicMetadata>;
+    fn default(&self) -> Option<&Value>;
+    /// Writes the member in the syntax of the schema language.
+    fn text(&self, types: &TypeSet<BasicMetadata>) -> String;
+}
+
+impl Member for Field<BasicMetadata> {
+    const KIND: &str = "field";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn r#type(&self) -> &Type<BasicMetadata> {
+        &self.r#type
+    }
+
+    fn default(&self) -> Option<&Value> {
+        self.default.as_ref()
+    }
+
+    fn text(&self, types: &TypeSet<BasicMetadata>) -> String {
+        field_text(types, self)
+    }
+}
+
+impl Member for Variant<BasicMetadata> {
+    const KIND: &str = "variant";
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn r#type(&self) -> &Type<BasicMetadata> {
+        &self.r#type
+    }
+
+    fn default(&self) -> Option<&Value> {
+        None
+    }
+
+    fn text(&self, types: &TypeSet<BasicMetadata>) -> String {
+        variant_text(types, &self.name, &self.r#type)
+    }
+}
+
+/// Describes the changes to the fields or variants of a named type.
+fn diff_members<M: Member>(
+    migration: &Migration<BasicMetadata>,
+    owner: &str,
+    old_members: &[M],
+    new_members: &[M],
+    changes: &mut Vec<Vec<Inline>>,
+) {
+    let Migration { old, new } = migration;
+
+    for new_member in new_members {
+        let old_member = old_members
+            .iter()
+            .find(|old_member| is_linked(old_member.r#type(), new_member.r#type()));
+
+        let Some(old_member) = old_member else {
+            changes.push(vec![
+                text(&format!("Added the {} ", M::KIND)),
+                code(&new_member.text(new)),
+                text(" to "),
+                code(owner),
+                text("."),
+            ]);
+            continue;
+        };
+
+        if old_member.name() != new_member.name() {
+            changes.push(vec![
+                text(&format!("Renamed the {} ", M::KIND)),
+                code(old_member.name()),
+                text(" of "),
+                code(owner),
+                text(" to "),
+                code(new_member.name()),
+                text("."),
+            ]);
+        }
+
+        if !same_type(migration, old_member.r#type(), new_member.r#type()) {
+            changes.push(vec![
+                text(&format!("Changed the type of the {} ", M::KIND)),
+                code(new_member.name()),
+                text(" of "),
+                code(owner),
+                text(" from "),
+                code(&type_text(old, old_member.r#type())),
+                text(" to "),
+                code(&type_text(new, new_member.r#type())),
+                text("."),
+            ]);
+        }
+
+        match (old_member.default(), new_member.default()) {
+            (None, Some(default)) => changes.push(vec![
+                text("Added the default value "),
+                code(&value_text(default)),
+                text(" to the field "),
+                code(new_member.name()),
+                text(" of "),
+                code(owner),
+                text("."),
+            ]),
+            (Some(_), None) => changes.push(vec![
+                text("Removed the default value of the field "),
+                code(new_member.name()),
+                text(" of "),
+                code(owner),
+                text("."),
+            ]),
+            (Some(old_default), Some(new_default)) if old_default != new_default => {
+                changes.push(vec![
+                    text("Changed the default value of the field "),
+                    code(new_member.name()),
+                    text(" of "),
+                    code(owner),
+                    text(" from "),
+                    code(&value_text(old_default)),
+                    text(" to "),
+                    code(&value_text(new_default)),
+                    text("."),
+                ]);
+            }
+            _ => {}
+        }
+    }
+
+    for old_member in old_members {
+        if !new_members
+            .iter()
+            .any(|new_member| is_linked(old_member.r#type(), new_member.r#type()))
+        {
+            changes.push(vec![
+                text(&format!("Removed the {} ", M::KIND)),
+                code(old_member.name()),
+                text(" from "),
+                code(owner),
+                text("."),
+            ]);
+        }
+    }
+}
+
+/// Checks whether two types have the same structure,
+/// treating references to linked named types as equal even if they were renamed.
+fn same_type(
+    migration: &Migration<BasicMetadata>,
+    old: &Type<BasicMetadata>,
+    new: &Type<BasicMetadata>,
+) -> bool {
+    match (&old.r#type, &new.r#type) {
+        (TypeType::Struct(old_struct), TypeType::Struct(new_struct)) => {
+            old_struct.fields.len() == new_struct.fields.len()
+                && old_struct
+                    .fields
+                    .iter()
+                    .zip(&new_struct.fields)
+                    .all(|(old_field, new_field)| {
+                        old_field.name == new_field.name
+                            && old_field.default == new_field.default
+                            && same_type(migration, &old_field.r#type, &new_field.r#type)
+                    })
+        }
+        (TypeType::Enum(old_enum), TypeType::Enum(new_enum)) => {
+            old_enum.open == new_enum.open
+                && old_enum.variants.len() == new_enum.variants.len()
+                && old_enum.variants.iter().zip(&new_enum.variants).all(
+                    |(old_variant, new_variant)| {
+                        old_variant.name == new_variant.name
+                            && same_type(migration, &old_variant.r#type, &new_variant.r#type)
+                    },
+                )
+        }
+        (TypeType::List(old_list), TypeType::List(new_list)) => {
+            same_type(migration, &old_list.r#type, &new_list.r#type)
+        }
+        (TypeType::Tuple(old_tuple), TypeType::Tuple(new_tuple)) => {
+            old_tuple.elements.len() == new_tuple.elements.len()
+                && old_tuple
+                    .elements
+                    .iter()
+                    .zip(&new_tuple.elements)
+                    .all(|(old, new)| same_type(migration, old, new))
+        }
+        (TypeType::Primitive(old_primitive), TypeType::Primitive(new_primitive)) => {
+            old_primitive.r#type == new_primitive.r#type
+                && old_primitive.refinements == new_primitive.refinements
+        }
+        (TypeType::Identifier(old_identifier), TypeType::Identifier(new_identifier)) => {
+            let old_named = &migration.old.types[old_identifier.metadata.resolution];
+            let new_named = &migration.new.types[new_identifier.metadata.resolution];
+
+            (is_linked(&old_named.r#type, &new_named.r#type)
+                || (old_named.r#type.number.is_none() && old_named.name == new_named.name))
+                && old_identifier.arguments.len() == new_identifier.arguments.len()
+                && old_identifier
+                    .arguments
+                    .iter()
+                    .zip(&new_identifier.arguments)
+                    .all(|(old, new)| same_type(migration, old, new))
+        }
+        (TypeType::Parameter(old_parameter), TypeType::Parameter(new_parameter)) => {
+            old_parameter.metadata.resolution == new_parameter.metadata.resolution
+        }
+        _ => false,
+    }
+}
+
+/// Creates a piece of plain text.
+fn text(text: &str) -> Inline {
+    Inline::Text(text.to_owned())
+}
+
+/// Creates a piece of code.
+fn code(code: &str) -> Inline {
+    Inline::Code(code.to_owned())
+}
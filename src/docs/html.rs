@@ -0,0 +1,323 @@
+//! Emits documentation as standalone HTML pages.
+
+use std::io::{Result, Write};
+
+use crate::{
+    codegen::source_writer::SourceWriter,
+    docs::{Definition, Inline, MemberDoc, Reference, TypeDoc, changelog::Changelog, paragraphs},
+};
+
+/// The style sheet embedded into every page.
+const STYLE: &str = "body { font-family: sans-serif; max-width: 60em; margin: auto; padding: 1em; } \
+    table { border-collapse: collapse; } \
+    th, td { border: 1px solid #ccc; padding: 0.3em 0.6em; text-align: left; vertical-align: top; } \
+    pre { background: #f4f4f4; padding: 0.6em; }";
+
+/// Emits the documentation of a schema.
+pub fn emit_reference(writer: &mut SourceWriter<impl Write>, reference: &Reference) -> Result<()> {
+    let title = format!("Version {}", reference.version);
+    emit_header(writer, &title)?;
+
+    if reference.changelog {
+        writer.write_nl(
+            "<p>See the <a href=\"changelog.html\">changelog</a> for the changes between versions.</p>",
+        )?;
+    }
+
+    writer.write_nl("<ul>")?;
+    writer.indent();
+
+    for r#type in &reference.types {
+        writer.write_fmt_nl(format_args!(
+            "<li><a href=\"#{}\"><code>{}</code></a></li>",
+            r#type.anchor,
+            escape(&r#type.name)
+        ))?;
+    }
+
+    writer.dedent();
+    writer.write_nl("</ul>")?;
+
+    for r#type in &reference.types {
+        emit_type(writer, r#type)?;
+    }
+
+    emit_footer(writer)
+}
+
+/// Emits the documentation of a named type.
+fn emit_type(writer: &mut SourceWriter<impl Write>, r#type: &TypeDoc) -> Result<()> {
+    writer.write_fmt_nl(format_args!(
+        "<h2 id=\"{}\"><code>{}</code></h2>",
+        r#type.anchor,
+        escape(&r#type.name)
+    ))?;
+
+    emit_docs(writer, &r#type.docs)?;
+
+    let kind = match &r#type.definition {
+        Definition::Struct(_) => "A struct",
+        Definition::Enum(_) => "An enum",
+        Definition::OpenEnum(_) => "An open enum",
+        Definition::Alias(_) => "An alias",
+        Definition::Extern => "An extern type",
+    };
+
+    writer.write_fmt(format_args!(
+        "<p>{kind}, named <code>{}</code> in Rust and <code>{}</code> in TypeScript",
+        escape(&r#type.rust_name),
+        escape(&r#type.typescript_name),
+    ))?;
+
+    match &r#type.definition {
+        Definition::Extern => {
+            writer.write_nl(", defined outside of the schema.</p>")?;
+        }
+        Definition::Alias(aliased) => {
+            writer.write(", of ")?;
+            write_inlines(writer, aliased)?;
+            writer.write_nl(".</p>")?;
+        }
+        Definition::Struct(members) | Definition::Enum(members) | Definition::OpenEnum(members)
+            if members.is_empty() =>
+        {
+            writer.write_nl(", without any members.</p>")?;
+        }
+        Definition::Struct(fields) => {
+            writer.write_nl(".</p>")?;
+            emit_table(
+                writer,
+                &[
+                    "Field",
+                    "JSON key",
+                    "Rust name",
+                    "Type",
+                    "Default",
+                    "Description",
+                ],
+                fields,
+                true,
+            )?;
+        }
+        Definition::Enum(variants) | Definition::OpenEnum(variants) => {
+            writer.write_nl(".</p>")?;
+            emit_table(
+                writer,
+                &["Variant", "JSON tag", "Rust name", "Type", "Description"],
+                variants,
+                false,
+            )?;
+        }
+    }
+
+    writer.write_nl("<p>In JSON:</p>")?;
+    writer.write("<pre><code>")?;
+
+    for (index, line) in r#type.json.iter().enumerate() {
+        if index > 0 {
+            writer.nl()?;
+        }
+
+        for inline in line {
+            match inline {
+                Inline::Text(text) | Inline::Code(text) => writer.write(&escape(text))?,
+                Inline::Link { name, anchor } => {
+                    writer.write_fmt(format_args!("<a href=\"#{anchor}\">{}</a>", escape(name)))?
+                }
+            }
+        }
+    }
+
+    writer.write_nl("</code></pre>")
+}
+
+/// Emits the table of fields or variants.
+fn emit_table(
+    writer: &mut SourceWriter<impl Write>,
+    headings: &[&str],
+    members: &[MemberDoc],
+    default_column: bool,
+) -> Result<()> {
+    writer.write_nl("<table>")?;
+    writer.indent();
+    writer.write("<tr>")?;
+
+    for heading in headings {
+        writer.write_fmt(format_args!("<th>{heading}</th>"))?;
+    }
+
+    writer.write_nl("</tr>")?;
+
+    for member in members {
+        writer.write_fmt(format_args!(
+            "<tr><td><code>{}</code></td>",
+            escape(&member.name)
+        ))?;
+
+        match &member.json_name {
+            Some(json_name) => {
+                writer.write_fmt(format_args!("<td><code>{}</code></td>", escape(json_name)))?;
+            }
+            None => writer.write("<td>flattened</td>")?,
+        }
+
+        writer.write_fmt(format_args!(
+            "<td><code>{}</code></td><td>",
+            escape(&member.rust_name)
+        ))?;
+        write_inlines(writer, &member.r#type)?;
+        writer.write("</td>")?;
+
+        if default_column {
+            writer.write("<td>")?;
+
+            if let Some(default) = &member.default {
+                writer.write_fmt(format_args!("<code>{}</code>", escape(default)))?;
+            }
+
+            writer.write("</td>")?;
+        }
+
+        writer.write("<td>")?;
+
+        for (index, paragraph) in paragraphs(&member.docs).iter().enumerate() {
+            if index > 0 {
+                writer.write("<br><br>")?;
+            }
+
+            writer.write(&format_docs(paragraph).replace('\n', " "))?;
+        }
+
+        writer.write_nl("</td></tr>")?;
+    }
+
+    writer.dedent();
+    writer.write_nl("</table>")
+}
+
+/// Emits the paragraphs of a doc comment.
+///
+/// Of the Markdown in doc comments, only code spans are formatted.
+fn emit_docs(writer: &mut SourceWriter<impl Write>, docs: &[String]) -> Result<()> {
+    for paragraph in paragraphs(docs) {
+        writer.write("<p>")?;
+
+        for (index, line) in paragraph.lines().enumerate() {
+            if index > 0 {
+                writer.nl()?;
+            }
+
+            writer.write(&format_docs(line))?;
+        }
+
+        writer.write_nl("</p>")?;
+    }
+
+    Ok(())
+}
+
+/// Emits the changelog of a schema.
+pub fn emit_changelog(writer: &mut SourceWriter<impl Write>, changelog: &Changelog) -> Result<()> {
+    emit_header(writer, "Changelog")?;
+
+    for release in &changelog.releases {
+        writer.write_fmt_nl(format_args!("<h2>{}</h2>", escape(&release.new)))?;
+        writer.write_fmt_nl(format_args!(
+            "<p>Changes since {}:</p>",
+            escape(&release.old)
+        ))?;
+        writer.write_nl("<ul>")?;
+        writer.indent();
+
+        if release.changes.is_empty() {
+            writer.write_nl("<li>No changes to the types.</li>")?;
+        }
+
+        for change in &release.changes {
+            writer.write("<li>")?;
+            write_inlines(writer, change)?;
+            writer.write_nl("</li>")?;
+        }
+
+        writer.dedent();
+        writer.write_nl("</ul>")?;
+    }
+
+    emit_footer(writer)
+}
+
+/// Emits the start of a page, up to and including its heading.
+fn emit_header(writer: &mut SourceWriter<impl Write>, title: &str) -> Result<()> {
+    writer.write_nl("<!DOCTYPE html>")?;
+    writer.write_nl("<html lang=\"en\">")?;
+    writer.write_nl("<head>")?;
+    writer.indent();
+    writer.write_nl("<meta charset=\"utf-8\">")?;
+    writer.write_fmt_nl(format_args!("<title>{}</title>", escape(title)))?;
+    writer.write_fmt_nl(format_args!("<style>{STYLE}</style>"))?;
+    writer.dedent();
+    writer.write_nl("</head>")?;
+    writer.write_nl("<body>")?;
+    writer.write_fmt_nl(format_args!("<h1>{}</h1>", escape(title)))
+}
+
+/// Emits the end of a page.
+fn emit_footer(writer: &mut SourceWriter<impl Write>) -> Result<()> {
+    writer.write_nl("</body>")?;
+    writer.write_nl("</html>")
+}
+
+/// Writes a piece of text, formatting code and links.
+fn write_inlines(writer: &mut SourceWriter<impl Write>, inlines: &[Inline]) -> Result<()> {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => writer.write(&escape(text))?,
+            Inline::Code(code) => {
+                writer.write_fmt(format_args!("<code>{}</code>", escape(code)))?
+            }
+            Inline::Link { name, anchor } => writer.write_fmt(format_args!(
+                "<a href=\"#{anchor}\"><code>{}</code></a>",
+                escape(name)
+            ))?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Escapes text from a doc comment, formatting the code spans delimited by backticks.
+fn format_docs(text: &str) -> String {
+    let parts: Vec<&str> = text.split('`').collect();
+    let mut formatted = String::with_capacity(text.len());
+
+    for (index, part) in parts.iter().enumerate() {
+        if index % 2 == 0 {
+            formatted.push_str(&escape(part));
+        } else if index + 1 < parts.len() {
+            formatted.push_str(&format!("<code>{}</code>", escape(part)));
+        } else {
+            // An unterminated code span
+            formatted.push('`');
+            formatted.push_str(&escape(part));
+        }
+    }
+
+    formatted
+}
+
+/// Replaces the characters with a special meaning in HTML with character references.
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        match ch {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            ch => escaped.push(ch),
+        }
+    }
+
+    escaped
+}
@@ -0,0 +1,222 @@
+//! Emits documentation as Markdown.
+
+use std::io::{Result, Write};
+
+use crate::{
+    codegen::source_writer::SourceWriter,
+    docs::{Definition, Inline, MemberDoc, Reference, TypeDoc, changelog::Changelog, paragraphs},
+};
+
+/// Emits the documentation of a schema.
+pub fn emit_reference(writer: &mut SourceWriter<impl Write>, reference: &Reference) -> Result<()> {
+    writer.write_fmt_nl(format_args!("# Version {}", reference.version))?;
+    writer.blank_line();
+
+    if reference.changelog {
+        writer.write_nl("See the [changelog](changelog.md) for the changes between versions.")?;
+        writer.blank_line();
+    }
+
+    for r#type in &reference.types {
+        writer.write_fmt_nl(format_args!(
+            "- [`{}`](#{})",
+            escape_code(&r#type.name),
+            r#type.anchor
+        ))?;
+    }
+
+    for r#type in &reference.types {
+        writer.blank_line();
+        emit_type(writer, r#type)?;
+    }
+
+    Ok(())
+}
+
+/// Emits the documentation of a named type.
+fn emit_type(writer: &mut SourceWriter<impl Write>, r#type: &TypeDoc) -> Result<()> {
+    writer.write_fmt_nl(format_args!("<a id=\"{}\"></a>", r#type.anchor))?;
+    writer.blank_line();
+    writer.write_fmt_nl(format_args!("## `{}`", escape_code(&r#type.name)))?;
+    writer.blank_line();
+
+    emit_docs(writer, &r#type.docs)?;
+
+    let kind = match &r#type.definition {
+        Definition::Struct(_) => "A struct",
+        Definition::Enum(_) => "An enum",
+        Definition::OpenEnum(_) => "An open enum",
+        Definition::Alias(_) => "An alias",
+        Definition::Extern => "An extern type",
+    };
+
+    writer.write_fmt(format_args!(
+        "{kind}, named `{}` in Rust and `{}` in TypeScript",
+        escape_code(&r#type.rust_name),
+        escape_code(&r#type.typescript_name),
+    ))?;
+
+    match &r#type.definition {
+        Definition::Extern => {
+            writer.write_nl(", defined outside of the schema.")?;
+        }
+        Definition::Alias(aliased) => {
+            writer.write(", of ")?;
+            write_inlines(writer, aliased)?;
+            writer.write_nl(".")?;
+        }
+        Definition::Struct(members) | Definition::Enum(members) | Definition::OpenEnum(members)
+            if members.is_empty() =>
+        {
+            writer.write_nl(", without any members.")?;
+        }
+        Definition::Struct(fields) => {
+            writer.write_nl(".")?;
+            writer.blank_line();
+            writer.write_nl("| Field | JSON key | Rust name | Type | Default | Description |")?;
+            writer.write_nl("| ----- | -------- | --------- | ---- | ------- | ----------- |")?;
+
+            for field in fields {
+                emit_member(writer, field, true)?;
+            }
+        }
+        Definition::Enum(variants) | Definition::OpenEnum(variants) => {
+            writer.write_nl(".")?;
+            writer.blank_line();
+            writer.write_nl("| Variant | JSON tag | Rust name | Type | Description |")?;
+            writer.write_nl("| ------- | -------- | --------- | ---- | ----------- |")?;
+
+            for variant in variants {
+                emit_member(writer, variant, false)?;
+            }
+        }
+    }
+
+    writer.blank_line();
+    writer.write_nl("In JSON:")?;
+    writer.blank_line();
+    writer.write_nl("```")?;
+
+    for line in &r#type.json {
+        for inline in line {
+            match inline {
+                Inline::Text(text) | Inline::Code(text) => writer.write(text)?,
+                Inline::Link { name, .. } => writer.write(name)?,
+            }
+        }
+
+        writer.nl()?;
+    }
+
+    writer.write_nl("```")
+}
+
+/// Emits a row of the table of fields or variants.
+///
+/// The doc comment is put into the last column, with paragraphs separated by line breaks.
+fn emit_member(
+    writer: &mut SourceWriter<impl Write>,
+    member: &MemberDoc,
+    default_column: bool,
+) -> Result<()> {
+    writer.write_fmt(format_args!("| `{}` | ", escape_code(&member.name)))?;
+
+    match &member.json_name {
+        Some(json_name) => writer.write_fmt(format_args!("`{}` | ", escape_code(json_name)))?,
+        None => writer.write("flattened | ")?,
+    }
+
+    writer.write_fmt(format_args!("`{}` | ", escape_code(&member.rust_name)))?;
+    write_inlines(writer, &member.r#type)?;
+    writer.write(" | ")?;
+
+    if default_column {
+        if let Some(default) = &member.default {
+            writer.write_fmt(format_args!("`{}`", escape_code(default)))?;
+        }
+
+        writer.write(" | ")?;
+    }
+
+    let description = paragraphs(&member.docs).join("<br><br>");
+    writer.write(&description.replace('\n', " ").replace('|', "\\|"))?;
+    writer.write_nl(" |")
+}
+
+/// Emits the paragraphs of a doc comment, which are written as Markdown already.
+fn emit_docs(writer: &mut SourceWriter<impl Write>, docs: &[String]) -> Result<()> {
+    for paragraph in paragraphs(docs) {
+        for line in paragraph.lines() {
+            writer.write_nl(line)?;
+        }
+
+        writer.blank_line();
+    }
+
+    Ok(())
+}
+
+/// Emits the changelog of a schema.
+pub fn emit_changelog(writer: &mut SourceWriter<impl Write>, changelog: &Changelog) -> Result<()> {
+    writer.write_nl("# Changelog")?;
+
+    for release in &changelog.releases {
+        writer.blank_line();
+        writer.write_fmt_nl(format_args!("## {}", release.new))?;
+        writer.blank_line();
+        writer.write_fmt_nl(format_args!("Changes since {}:", release.old))?;
+        writer.blank_line();
+
+        if release.changes.is_empty() {
+            writer.write_nl("- No changes to the types.")?;
+        }
+
+        for change in &release.changes {
+            writer.write("- ")?;
+            write_inlines(writer, change)?;
+            writer.nl()?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Writes a piece of text, formatting code and links.
+fn write_inlines(writer: &mut SourceWriter<impl Write>, inlines: &[Inline]) -> Result<()> {
+    for inline in inlines {
+        match inline {
+            Inline::Text(text) => writer.write(&escape_text(text))?,
+            Inline::Code(code) => writer.write_fmt(format_args!("`{}`", escape_code(code)))?,
+            Inline::Link { name, anchor } => {
+                writer.write_fmt(format_args!("[`{}`](#{anchor})", escape_code(name)))?
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Escapes characters with a special meaning in Markdown text, including table cell separators.
+fn escape_text(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+
+    for ch in text.chars() {
+        if matches!(
+            ch,
+            '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '|' | '#'
+        ) {
+            escaped.push('\\');
+        }
+
+        escaped.push(ch);
+    }
+
+    escaped
+}
+
+/// Prepares text to be put in a code span, which may be inside a table cell.
+///
+/// Backticks can't be escaped inside code spans, so they're replaced by similar-looking quotes.
+fn escape_code(code: &str) -> String {
+    code.replace('`', "'").replace('|', "\\|")
+}
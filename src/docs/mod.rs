@@ -0,0 +1,691 @@
+//! Generates reference documentation for schemas, and changelogs from migration files.
+//!
+//! The documentation is first built as a [`Reference`] and optionally a [`Changelog`](changelog::Changelog),
+//! which are then emitted as Markdown by [`markdown`] or as HTML by [`html`].
+
+use std::{
+    collections::HashSet,
+    fs::{File, create_dir_all},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    ast::{Field, NamedType, Parameter, PrimitiveType, Type, TypeSet, TypeType},
+    codegen::{
+        idents::disambiguate,
+        naming_pass::{NameMetadata, name},
+        source_writer::SourceWriter,
+    },
+    composite,
+    error::{Error, ResultExt},
+    formats::IntEncoding,
+    graph::value_text,
+    loading::load_file_with_source,
+    mapper,
+    preprocessing::BasicMetadata,
+    rust::RustNamingRules,
+    syntax::Span,
+    typescript::TypeScriptNamingRules,
+};
+
+mod changelog;
+mod html;
+mod markdown;
+
+use changelog::build_changelog;
+
+/// The format of the generated documentation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DocsFormat {
+    /// Markdown files
+    #[default]
+    Markdown,
+    /// Standalone HTML files
+    Html,
+}
+
+impl DocsFormat {
+    /// Gets the file extension of the generated files.
+    fn extension(self) -> &'static str {
+        match self {
+            DocsFormat::Markdown => "md",
+            DocsFormat::Html => "html",
+        }
+    }
+}
+
+/// Configuration of the documentation generator.
+#[derive(Debug, Clone, Default)]
+pub struct DocsOptions {
+    format: DocsFormat,
+    migrations: Option<PathBuf>,
+    external_tag: bool,
+}
+
+impl DocsOptions {
+    /// Creates new [`DocsOptions`].
+    ///
+    /// If `migrations` is set, a changelog is generated from the migration files in that directory.
+    /// If `external_tag` is set, the JSON shapes of enums are described like with `--serde-external-tag`.
+    pub fn new(format: DocsFormat, migrations: Option<PathBuf>, external_tag: bool) -> Self {
+        Self {
+            format,
+            migrations,
+            external_tag,
+        }
+    }
+}
+
+/// A piece of text in the documentation.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Inline {
+    Text(String),
+    Code(String),
+    /// The name of a named type, linking to its documentation.
+    Link {
+        name: String,
+        anchor: String,
+    },
+}
+
+/// The documentation of all named types in a schema.
+#[derive(Debug, Clone)]
+struct Reference {
+    version: String,
+    types: Vec<TypeDoc>,
+    /// Whether a changelog is generated next to the reference.
+    changelog: bool,
+}
+
+/// The documentation of a named type.
+#[derive(Debug, Clone)]
+struct TypeDoc {
+    name: String,
+    /// A name that is unique among all named types, used to link to the documentation.
+    anchor: String,
+    rust_name: String,
+    typescript_name: String,
+    /// The lines of the doc comment.
+    docs: Vec<String>,
+    definition: Definition,
+    /// The lines of a description of the JSON representation.
+    json: Vec<Vec<Inline>>,
+}
+
+/// The contents of a named type.
+#[derive(Debug, Clone)]
+enum Definition {
+    Struct(Vec<MemberDoc>),
+    Enum(Vec<MemberDoc>),
+    /// An enum accepting variants added in later versions.
+    OpenEnum(Vec<MemberDoc>),
+    Alias(Vec<Inline>),
+    /// A type that's defined outside of the schema.
+    Extern,
+}
+
+/// The documentation of a field or variant.
+#[derive(Debug, Clone)]
+struct MemberDoc {
+    name: String,
+    /// The key of a field or the tag of a variant in JSON, which is also the name in TypeScript.
+    ///
+    /// It's `None` for spreads, whose fields are flattened into the object itself.
+    json_name: Option<String>,
+    rust_name: String,
+    r#type: Vec<Inline>,
+    default: Option<String>,
+    /// The lines of the doc comment.
+    docs: Vec<String>,
+}
+
+composite! {
+    struct (DocsRustPassInfo, DocsRustPassMetadata) {
+        basic: BasicMetadata | B,
+        rust_name: NameMetadata | R
+    }
+}
+
+mapper! {
+    fn AddRustName(basic: BasicMetadata, rust_name: NameMetadata) -> DocsRustPassMetadata {
+        DocsRustPassInfo { basic, rust_name }
+    }
+}
+
+composite! {
+    struct (DocsInfo, DocsMetadata) {
+        basic: BasicMetadata | B,
+        rust_name: NameMetadata | R,
+        name: NameMetadata | N
+    }
+}
+
+mapper! {
+    fn AddName(first: DocsRustPassMetadata, name: NameMetadata) -> DocsMetadata {
+        DocsInfo {
+            basic: first.basic,
+            rust_name: first.rust_name,
+            name,
+        }
+    }
+}
+
+/// Implements `versed docs`.
+pub fn generate_docs(path: &Path, output: &Path, options: &DocsOptions) -> Result<(), Error> {
+    let (types, src) = load_file_with_source(path)?;
+    let changelog = options
+        .migrations
+        .as_deref()
+        .map(build_changelog)
+        .transpose()?;
+
+    let types = name(types, RustNamingRules, AddRustName);
+    let types = name(types, TypeScriptNamingRules, AddName);
+    let reference = build_reference(&types, &src, changelog.is_some(), options);
+
+    create_dir_all(output).with_path(output)?;
+    let extension = options.format.extension();
+
+    write_file(
+        &output.join(format!("index.{extension}")),
+        |writer| match options.format {
+            DocsFormat::Markdown => markdown::emit_reference(writer, &reference),
+            DocsFormat::Html => html::emit_reference(writer, &reference),
+        },
+    )?;
+
+    if let Some(changelog) = changelog {
+        write_file(
+            &output.join(format!("changelog.{extension}")),
+            |writer| match options.format {
+                DocsFormat::Markdown => markdown::emit_changelog(writer, &changelog),
+                DocsFormat::Html => html::emit_changelog(writer, &changelog),
+            },
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Creates a file and writes to it using `emit`.
+fn write_file(
+    path: &Path,
+    emit: impl FnOnce(&mut SourceWriter<BufWriter<File>>) -> io::Result<()>,
+) -> Result<(), Error> {
+    let file = File::create(path).with_path(path)?;
+    let mut writer = SourceWriter::new(BufWriter::new(file));
+
+    emit(&mut writer)
+        .and_then(|()| writer.into_inner().flush())
+        .with_path(path)
+}
+
+/// Builds the documentation of a schema.
+fn build_reference(
+    types: &TypeSet<DocsMetadata>,
+    src: &str,
+    changelog: bool,
+    options: &DocsOptions,
+) -> Reference {
+    let mut used = HashSet::new();
+    let anchors: Vec<String> = types
+        .types
+        .iter()
+        .map(|named| {
+            let mut anchor: String = named
+                .name
+                .chars()
+                .map(|ch| {
+                    if ch.is_alphanumeric() {
+                        ch.to_ascii_lowercase()
+                    } else {
+                        '-'
+                    }
+                })
+                .collect();
+
+            disambiguate(&mut anchor, |anchor| used.contains(anchor));
+            used.insert(anchor.clone());
+            anchor
+        })
+        .collect();
+
+    let context = Context {
+        types,
+        anchors: &anchors,
+        external_tag: options.external_tag,
+        int_encoding: types.int_encoding,
+    };
+
+    let types = types
+        .types
+        .iter()
+        .zip(&anchors)
+        .map(|(named, anchor)| {
+            let r#type = &named.r#type;
+            let definition = match &r#type.r#type {
+                TypeType::Struct(r#struct) => Definition::Struct(
+                    r#struct
+                        .fields
+                        .iter()
+                        .map(|field| MemberDoc {
+                            name: if field.spread {
+                                format!("..{}", field.name)
+                            } else {
+                                field.name.clone()
+                            },
+                            json_name: (!field.spread).then(|| field.metadata.name.clone()),
+                            rust_name: field.metadata.rust_name.clone(),
+                            r#type: type_inlines(context, &field.r#type),
+                            default: field.default.as_ref().map(value_text),
+                            docs: doc_comment(src, field.metadata.basic.span.name),
+                        })
+                        .collect(),
+                ),
+                TypeType::Enum(r#enum) => {
+                    let variants = r#enum
+                        .variants
+                        .iter()
+                        .map(|variant| MemberDoc {
+                            name: variant.name.clone(),
+                            json_name: Some(variant.metadata.name.clone()),
+                            rust_name: variant.metadata.rust_name.clone(),
+                            r#type: type_inlines(context, &variant.r#type),
+                            default: None,
+                            docs: doc_comment(src, variant.metadata.basic.span.name),
+                        })
+                        .collect();
+
+                    if r#enum.open {
+                        Definition::OpenEnum(variants)
+                    } else {
+                        Definition::Enum(variants)
+                    }
+                }
+                TypeType::Extern(_) => Definition::Extern,
+                _ => Definition::Alias(type_inlines(context, r#type)),
+            };
+
+            let mut json = JsonShape::default();
+            json.write_type(context, r#type, Some(0), true);
+
+            TypeDoc {
+                name: generic_name(named, &named.name, |parameter| &parameter.name),
+                anchor: anchor.clone(),
+                rust_name: generic_name(named, &r#type.metadata.rust_name, |parameter| {
+                    &parameter.metadata.rust_name
+                }),
+                typescript_name: generic_name(named, &r#type.metadata.name, |parameter| {
+                    &parameter.metadata.name
+                }),
+                docs: doc_comment(src, named.metadata.basic.span.name),
+                definition,
+                json: json.lines,
+            }
+        })
+        .collect();
+
+    Reference {
+        version: context.types.version.clone(),
+        types,
+        changelog,
+    }
+}
+
+/// The context for building the documentation of types.
+#[derive(Clone, Copy)]
+struct Context<'a> {
+    types: &'a TypeSet<DocsMetadata>,
+    /// The anchor of every named type.
+    anchors: &'a [String],
+    external_tag: bool,
+    int_encoding: IntEncoding,
+}
+
+impl Context<'_> {
+    /// Creates a link to a named type.
+    fn link(self, index: usize) -> Inline {
+        Inline::Link {
+            name: self.types.types[index].name.clone(),
+            anchor: self.anchors[index].clone(),
+        }
+    }
+}
+
+/// Appends the type parameters of a named type to one of its names, like `Page<T>`.
+fn generic_name(
+    named: &NamedType<DocsMetadata>,
+    name: &str,
+    parameter_name: impl Fn(&Parameter<DocsMetadata>) -> &String,
+) -> String {
+    if named.parameters.is_empty() {
+        return name.to_owned();
+    }
+
+    let parameters: Vec<&str> = named
+        .parameters
+        .iter()
+        .map(|parameter| parameter_name(parameter).as_str())
+        .collect();
+    format!("{name}<{}>", parameters.join(", "))
+}
+
+/// Appends code to a piece of text, merging it with preceding code.
+fn push_code(inlines: &mut Vec<Inline>, code: &str) {
+    match inlines.last_mut() {
+        Some(Inline::Code(last)) => last.push_str(code),
+        _ => inlines.push(Inline::Code(code.to_owned())),
+    }
+}
+
+/// Writes a type in the syntax of the schema language, linking to named types.
+fn type_inlines(context: Context, r#type: &Type<DocsMetadata>) -> Vec<Inline> {
+    let mut inlines = vec![];
+    write_type_inlines(context, r#type, &mut inlines);
+    inlines
+}
+
+/// Writes a type in the syntax of the schema language recursively, see [`type_inlines`].
+fn write_type_inlines(context: Context, r#type: &Type<DocsMetadata>, inlines: &mut Vec<Inline>) {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) if r#struct.fields.is_empty() => push_code(inlines, "struct {}"),
+        TypeType::Struct(r#struct) => {
+            push_code(inlines, "struct { ");
+
+            for (index, field) in r#struct.fields.iter().enumerate() {
+                write_field_inlines(context, field, inlines);
+                push_code(
+                    inlines,
+                    if index + 1 < r#struct.fields.len() {
+                        ", "
+                    } else {
+                        " }"
+                    },
+                );
+            }
+        }
+        TypeType::Enum(r#enum) => {
+            if r#enum.open {
+                push_code(inlines, "open ");
+            }
+
+            if r#enum.variants.is_empty() {
+                push_code(inlines, "enum {}");
+                return;
+            }
+
+            push_code(inlines, "enum { ");
+
+            for (index, variant) in r#enum.variants.iter().enumerate() {
+                push_code(inlines, &variant.name);
+
+                if !variant.r#type.r#type.is_unit() {
+                    push_code(inlines, ": ");
+                    write_type_inlines(context, &variant.r#type, inlines);
+                }
+
+                push_code(
+                    inlines,
+                    if index + 1 < r#enum.variants.len() {
+                        ", "
+                    } else {
+                        " }"
+                    },
+                );
+            }
+        }
+        TypeType::List(list) => {
+            push_code(inlines, "[");
+            write_type_inlines(context, &list.r#type, inlines);
+            push_code(inlines, "]");
+        }
+        TypeType::Tuple(tuple) => {
+            for (index, element) in tuple.elements.iter().enumerate() {
+                push_code(inlines, if index == 0 { "(" } else { ", " });
+                write_type_inlines(context, element, inlines);
+            }
+
+            push_code(inlines, ")");
+        }
+        TypeType::Primitive(primitive) => push_code(inlines, &primitive.to_string()),
+        TypeType::Identifier(identifier) => {
+            inlines.push(context.link(identifier.metadata.basic.resolution));
+
+            for (index, argument) in identifier.arguments.iter().enumerate() {
+                push_code(inlines, if index == 0 { "<" } else { ", " });
+                write_type_inlines(context, argument, inlines);
+            }
+
+            if !identifier.arguments.is_empty() {
+                push_code(inlines, ">");
+            }
+        }
+        TypeType::Parameter(parameter) => push_code(inlines, &parameter.name),
+        TypeType::Extern(_) => push_code(inlines, "extern"),
+    }
+}
+
+/// Writes a field of a nested struct, including its default value, or a spread.
+fn write_field_inlines(context: Context, field: &Field<DocsMetadata>, inlines: &mut Vec<Inline>) {
+    if field.spread {
+        push_code(inlines, "..");
+        write_type_inlines(context, &field.r#type, inlines);
+        return;
+    }
+
+    push_code(inlines, &field.name);
+    push_code(inlines, ": ");
+    write_type_inlines(context, &field.r#type, inlines);
+
+    if let Some(default) = &field.default {
+        push_code(inlines, &format!(" = {}", value_text(default)));
+    }
+}
+
+/// A description of the JSON representation of a type, as produced by the Rust types with `--serde`.
+///
+/// Values are described by their JSON type, or by the name of a named type,
+/// and object keys and enum tags are written as JSON strings.
+#[derive(Debug, Default)]
+struct JsonShape {
+    lines: Vec<Vec<Inline>>,
+}
+
+impl JsonShape {
+    /// Appends code to the current line.
+    fn push(&mut self, code: &str) {
+        if self.lines.is_empty() {
+            self.lines.push(vec![]);
+        }
+
+        push_code(self.lines.last_mut().expect("a line to exist"), code);
+    }
+
+    /// Starts a new line with an indentation of `indent` levels.
+    fn new_line(&mut self, indent: usize) {
+        self.lines.push(vec![]);
+        self.push(&"  ".repeat(indent));
+    }
+
+    /// Appends a JSON string.
+    fn push_string(&mut self, value: &str) {
+        self.push(&serde_json::Value::from(value).to_string());
+    }
+
+    /// Describes a type.
+    ///
+    /// Structs are spread over multiple lines indented by `indent` levels,
+    /// unless it is `None`, in which case the type is described on a single line.
+    /// If `root` is set, the alternatives of an enum are written on separate lines.
+    fn write_type(
+        &mut self,
+        context: Context,
+        r#type: &Type<DocsMetadata>,
+        indent: Option<usize>,
+        root: bool,
+    ) {
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) if r#struct.fields.is_empty() => self.push("{}"),
+            TypeType::Struct(r#struct) => {
+                self.push("{");
+
+                for (index, field) in r#struct.fields.iter().enumerate() {
+                    match indent {
+                        Some(indent) => self.new_line(indent + 1),
+                        None => self.push(if index == 0 { " " } else { ", " }),
+                    }
+
+                    if field.spread {
+                        // The fields of spreads are members of the same object
+                        self.push("...");
+                    } else {
+                        self.push_string(&field.metadata.name);
+                        self.push(": ");
+                    }
+
+                    self.write_type(context, &field.r#type, indent.map(|i| i + 1), false);
+
+                    if indent.is_some() && index + 1 < r#struct.fields.len() {
+                        self.push(",");
+                    }
+                }
+
+                match indent {
+                    Some(indent) => self.new_line(indent),
+                    None => self.push(" "),
+                }
+
+                self.push("}");
+            }
+            TypeType::Enum(r#enum) if r#enum.variants.is_empty() && !r#enum.open => {
+                self.push("never");
+            }
+            TypeType::Enum(r#enum) => {
+                let separate = |shape: &mut Self, index: usize| {
+                    if index > 0 {
+                        match (root, indent) {
+                            (true, Some(indent)) => {
+                                shape.new_line(indent);
+                                shape.push("| ");
+                            }
+                            _ => shape.push(" | "),
+                        }
+                    }
+                };
+
+                for (index, variant) in r#enum.variants.iter().enumerate() {
+                    separate(self, index);
+
+                    if context.external_tag {
+                        self.push("{ ");
+                        self.push_string(&variant.metadata.name);
+                        self.push(": ");
+                    } else {
+                        self.push("{ \"type\": ");
+                        self.push_string(&variant.metadata.name);
+                        self.push(", \"value\": ");
+                    }
+
+                    self.write_type(context, &variant.r#type, None, false);
+                    self.push(" }");
+                }
+
+                // Variants added in later versions
+                if r#enum.open {
+                    separate(self, r#enum.variants.len());
+                    self.push(if context.external_tag {
+                        "{ string: any }"
+                    } else {
+                        "{ \"type\": string, \"value\": any }"
+                    });
+                }
+            }
+            TypeType::List(list) => {
+                self.push("[");
+                self.write_type(context, &list.r#type, None, false);
+                self.push("]");
+            }
+            TypeType::Tuple(tuple) => {
+                for (index, element) in tuple.elements.iter().enumerate() {
+                    self.push(if index == 0 { "[" } else { ", " });
+                    self.write_type(context, element, None, false);
+                }
+
+                self.push("]");
+            }
+            TypeType::Primitive(primitive) => match primitive.r#type {
+                PrimitiveType::String => self.push("string"),
+                PrimitiveType::Number => self.push(match context.int_encoding {
+                    IntEncoding::Number => "integer",
+                    IntEncoding::String => "string (integer)",
+                }),
+                PrimitiveType::Unit => self.push("null"),
+                // Built-in types are strings with a format, like `string (uuid)`
+                r#type => self.push(&format!("string ({})", r#type.name())),
+            },
+            TypeType::Identifier(identifier) => {
+                if self.lines.is_empty() {
+                    self.lines.push(vec![]);
+                }
+
+                let link = context.link(identifier.metadata.basic.resolution);
+                self.lines.last_mut().expect("a line to exist").push(link);
+
+                for (index, argument) in identifier.arguments.iter().enumerate() {
+                    self.push(if index == 0 { "<" } else { ", " });
+                    self.write_type(context, argument, None, false);
+                }
+
+                if !identifier.arguments.is_empty() {
+                    self.push(">");
+                }
+            }
+            TypeType::Parameter(parameter) => self.push(&parameter.name),
+            // The representation depends on the type it's mapped to
+            TypeType::Extern(_) => self.push("any"),
+        }
+    }
+}
+
+/// Finds the doc comment of the declaration whose name starts at `span`.
+///
+/// Doc comments are the lines starting with `///` directly above the line of the declaration,
+/// which must not contain anything else before the name,
+/// except for the `extern` keyword of an extern type and its migration marker.
+fn doc_comment(src: &str, span: Span) -> Vec<String> {
+    let before = &src[..span.start];
+    let line_start = before.rfind('\n').map_or(0, |index| index + 1);
+    let prefix = before[line_start..].trim();
+    let prefix = prefix.strip_prefix("extern").map_or(prefix, |rest| {
+        rest.trim_start_matches(|ch: char| ch == '#' || ch.is_ascii_digit() || ch.is_whitespace())
+    });
+
+    if !prefix.is_empty() {
+        return vec![];
+    }
+
+    let mut lines: Vec<String> = before[..line_start]
+        .lines()
+        .rev()
+        .map(str::trim)
+        .take_while(|line| line.starts_with("///") && !line.starts_with("////"))
+        .map(|line| {
+            let line = &line[3..];
+            line.strip_prefix(' ').unwrap_or(line).to_owned()
+        })
+        .collect();
+
+    lines.reverse();
+    lines
+}
+
+/// Splits the lines of a doc comment into paragraphs, separated by empty lines.
+fn paragraphs(docs: &[String]) -> Vec<String> {
+    docs.split(|line| line.trim().is_empty())
+        .filter(|paragraph| !paragraph.is_empty())
+        .map(|paragraph| paragraph.join("\n"))
+        .collect()
+}
@@ -0,0 +1,51 @@
+//! Prints the resolved AST of schema and migration files, for debugging and scripting.
+
+use std::{
+    io::{BufWriter, Write, stdout},
+    path::Path,
+};
+
+use clap::ValueEnum;
+use serde_json::Value as Json;
+
+use crate::{
+    error::{Error, ResultExt},
+    loading::{load_file, load_migration},
+    serialization::{BasicSerializer, serialize_migration, serialize_type_set},
+};
+
+/// The format of the printed AST.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum DumpFormat {
+    /// Pretty-printed JSON
+    #[default]
+    Json,
+}
+
+/// Implements `versed dump`.
+pub fn dump_schema(path: &Path, format: DumpFormat) -> Result<(), Error> {
+    let types = load_file(path)?;
+    print(&serialize_type_set(&types, &BasicSerializer, ""), format)
+}
+
+/// Implements `versed migration dump`.
+pub fn dump_migration(path: &Path, format: DumpFormat) -> Result<(), Error> {
+    let migration = load_migration(path)?;
+    print(
+        &serialize_migration(&migration, &BasicSerializer, ""),
+        format,
+    )
+}
+
+/// Prints a serialized AST to standard output.
+fn print(json: &Json, format: DumpFormat) -> Result<(), Error> {
+    let mut stream = BufWriter::new(stdout().lock());
+
+    match format {
+        DumpFormat::Json => serde_json::to_writer_pretty(&mut stream, json)
+            .map_err(Into::into)
+            .and_then(|()| writeln!(stream)),
+    }
+    .and_then(|()| stream.flush())
+    .with_stdout()
+}
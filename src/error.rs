@@ -18,6 +18,40 @@ pub enum Error {
     },
     #[error("The file is malformed")]
     MalformedFile,
+    #[error("The schema contains no type named '{name}'")]
+    UnknownType { name: String },
+    #[error("The extern type '{name}' isn't mapped to a type, pass --extern {name}=<TYPE>")]
+    UnmappedExtern { name: String },
+    #[error(
+        "The type '{name}' passes a type needing a serde_with adapter, like bytes, as a type argument to a generic struct, enum or newtype, where --serde can't give it its format"
+    )]
+    AdaptedTypeArgument { name: String },
+    #[error("The type '{name}' has no values of finite depth")]
+    InfiniteDepth { name: String },
+    #[error(
+        "The values of the type '{name}' always contain an extern type, so they can't be generated"
+    )]
+    ExternValue { name: String },
+    #[error("The type '{name}' is generic, so it needs type arguments")]
+    GenericType { name: String },
+    #[error("Failed to generate a string matching the pattern {pattern:?} of the required length")]
+    UnsatisfiablePattern { pattern: String },
+    #[error("Failed to parse {path} as JSON: {error}")]
+    Json {
+        #[source]
+        error: serde_json::Error,
+        path: PathBuf,
+    },
+    #[error("Failed to watch {path}: {error}")]
+    Watch {
+        #[source]
+        error: notify::Error,
+        path: PathBuf,
+    },
+    #[error("Failed to migrate the value at {path}: {reason}")]
+    DataMigration { path: String, reason: String },
+    #[error("The plugin '{name}' failed: {reason}")]
+    Plugin { name: String, reason: String },
 }
 
 /// Provides some extension methods on [`Result<T, E>`] where `E` = [`io::Error`].
@@ -30,6 +64,8 @@ pub trait ResultExt {
     fn with_stdout(self) -> Self::Result;
     /// Turns the error into [`Error::Io`] with the path set to "standard error".
     fn with_stderr(self) -> Self::Result;
+    /// Turns the error into [`Error::Io`] with the path set to "standard input".
+    fn with_stdin(self) -> Self::Result;
 }
 
 impl<T> ResultExt for Result<T, io::Error> {
@@ -55,4 +91,11 @@ impl<T> ResultExt for Result<T, io::Error> {
             path: "standard error".into(),
         })
     }
+
+    fn with_stdin(self) -> Self::Result {
+        self.map_err(|error| Error::Io {
+            error,
+            path: "standard input".into(),
+        })
+    }
 }
@@ -0,0 +1,175 @@
+//! The string formats of the built-in primitive types, like timestamps and UUIDs, and of ints encoded as strings.
+//!
+//! They're used by everything that reads or writes JSON values directly,
+//! and by the TypeScript validation functions, which check them with the same patterns.
+
+use std::{ops::Range, sync::LazyLock};
+
+use regex::Regex;
+
+use crate::ast::PrimitiveType;
+
+/// The pattern of an RFC 3339 timestamp.
+///
+/// Fractions of a second have at most nine digits, as no backend keeps more than nanoseconds.
+const TIMESTAMP_PATTERN: &str = concat!(
+    r"^[0-9]{4}-(?:0[1-9]|1[0-2])-(?:0[1-9]|[12][0-9]|3[01])",
+    r"T(?:[01][0-9]|2[0-3]):[0-5][0-9]:[0-5][0-9](?:\.[0-9]{1,9})?",
+    r"(?:Z|[+-](?:[01][0-9]|2[0-3]):[0-5][0-9])$"
+);
+
+/// The pattern of a calendar date.
+const DATE_PATTERN: &str = r"^[0-9]{4}-(?:0[1-9]|1[0-2])-(?:0[1-9]|[12][0-9]|3[01])$";
+
+/// The pattern of an ISO 8601 duration, restricted to a number of seconds.
+const DURATION_PATTERN: &str = r"^-?PT[0-9]+(?:\.[0-9]{1,9})?S$";
+
+/// The pattern of a hyphenated UUID.
+const UUID_PATTERN: &str =
+    r"^[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}$";
+
+/// The pattern of standard Base64 with padding.
+const BYTES_PATTERN: &str = r"^(?:[A-Za-z0-9+/]{4})*(?:[A-Za-z0-9+/]{2}==|[A-Za-z0-9+/]{3}=)?$";
+
+/// The pattern of an int encoded as a string.
+pub const INT_PATTERN: &str = r"^-?[0-9]+$";
+
+/// The characters of the standard Base64 alphabet.
+const BASE64_ALPHABET: &[u8] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+/// How ints are encoded in JSON.
+///
+/// Every backend has to use the same encoding, so it's part of the schema, see [`TypeSet::int_encoding`](crate::ast::TypeSet::int_encoding).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IntEncoding {
+    /// As numbers, which JavaScript only represents exactly up to 2^53.
+    #[default]
+    Number,
+    /// As strings of decimal digits, like `"9007199254740993"`.
+    String,
+}
+
+impl IntEncoding {
+    /// Gets the encoding with a name, as written in an `int_encoding` directive.
+    pub fn from_name(name: &str) -> Option<IntEncoding> {
+        match name {
+            "number" => Some(IntEncoding::Number),
+            "string" => Some(IntEncoding::String),
+            _ => None,
+        }
+    }
+
+    /// Gets the name of the encoding, as written in an `int_encoding` directive.
+    pub fn name(self) -> &'static str {
+        match self {
+            IntEncoding::Number => "number",
+            IntEncoding::String => "string",
+        }
+    }
+}
+
+/// Parses an int encoded as a string, returning `None` if it isn't one or doesn't fit into 64 bits.
+pub fn parse_int(value: &str) -> Option<i64> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+
+    if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+        return None;
+    }
+
+    value.parse().ok()
+}
+
+/// Gets the regular expression matching the values of a built-in type, or `None` for other primitive types.
+///
+/// The days of dates aren't checked against the length of the month, which [`is_valid`] does.
+pub fn pattern(r#type: PrimitiveType) -> Option<&'static str> {
+    match r#type {
+        PrimitiveType::Timestamp => Some(TIMESTAMP_PATTERN),
+        PrimitiveType::Date => Some(DATE_PATTERN),
+        PrimitiveType::Duration => Some(DURATION_PATTERN),
+        PrimitiveType::Uuid => Some(UUID_PATTERN),
+        PrimitiveType::Bytes => Some(BYTES_PATTERN),
+        PrimitiveType::String | PrimitiveType::Number | PrimitiveType::Unit => None,
+    }
+}
+
+/// Describes the values of a built-in type, for error messages.
+pub fn expected(r#type: PrimitiveType) -> &'static str {
+    match r#type {
+        PrimitiveType::Timestamp => "an RFC 3339 timestamp, like 2024-05-06T07:08:09Z",
+        PrimitiveType::Date => "a date, like 2024-05-06",
+        PrimitiveType::Duration => "an ISO 8601 duration in seconds, like PT1.5S",
+        PrimitiveType::Uuid => "a hyphenated UUID",
+        PrimitiveType::Bytes => "a Base64 string",
+        PrimitiveType::String | PrimitiveType::Number | PrimitiveType::Unit => {
+            unreachable!("only built-in types to have a format")
+        }
+    }
+}
+
+/// Checks whether a string is a valid value of a built-in type.
+pub fn is_valid(r#type: PrimitiveType, value: &str) -> bool {
+    static PATTERNS: LazyLock<Vec<(PrimitiveType, Regex)>> = LazyLock::new(|| {
+        PrimitiveType::BUILT_IN
+            .into_iter()
+            .map(|r#type| (r#type, Regex::new(pattern(r#type).unwrap()).unwrap()))
+            .collect()
+    });
+
+    let (_, regex) = PATTERNS
+        .iter()
+        .find(|(other, _)| *other == r#type)
+        .expect("only built-in types to have a format");
+
+    if !regex.is_match(value) {
+        return false;
+    }
+
+    match r#type {
+        PrimitiveType::Timestamp | PrimitiveType::Date => {
+            // The pattern makes sure these are numbers in range
+            let number = |range: Range<usize>| value[range].parse().unwrap();
+            number(8..10) <= days_in_month(number(0..4), number(5..7))
+        }
+        PrimitiveType::Duration => {
+            let seconds = value.trim_start_matches('-').trim_start_matches("PT");
+            let seconds = seconds.split(['.', 'S']).next().unwrap_or_default();
+            seconds.parse::<i64>().is_ok()
+        }
+        _ => true,
+    }
+}
+
+/// Gets the number of days in a month of a year.
+pub fn days_in_month(year: u32, month: u32) -> u32 {
+    let leap = year.is_multiple_of(4) && (!year.is_multiple_of(100) || year.is_multiple_of(400));
+
+    match month {
+        2 if leap => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Encodes bytes as standard Base64 with padding.
+pub fn encode_base64(bytes: &[u8]) -> String {
+    let mut result = String::new();
+
+    for chunk in bytes.chunks(3) {
+        let group = chunk.iter().enumerate().fold(0, |group, (index, &byte)| {
+            group | u32::from(byte) << (16 - 8 * index)
+        });
+
+        for index in 0..4 {
+            if index <= chunk.len() {
+                let sextet = (group >> (18 - 6 * index)) & 0x3f;
+                result.push(char::from(BASE64_ALPHABET[sextet as usize]));
+            } else {
+                result.push('=');
+            }
+        }
+    }
+
+    result
+}
@@ -0,0 +1,101 @@
+//! Emits diagrams as Graphviz DOT files.
+
+use std::io::{Result, Write};
+
+use crate::{
+    codegen::source_writer::SourceWriter,
+    graph::{Graph, Node, Status},
+};
+
+/// Emits a diagram.
+pub fn emit_graph(writer: &mut SourceWriter<impl Write>, graph: &Graph) -> Result<()> {
+    writer.write("digraph ")?;
+    write_string(writer, &graph.name)?;
+    writer.write_nl(" {")?;
+    writer.indent();
+
+    writer.write_nl("node [shape=box, fontname=monospace];")?;
+    writer.write_nl("edge [fontname=monospace];")?;
+    writer.blank_line();
+
+    for node in &graph.nodes {
+        emit_node(writer, node)?;
+    }
+
+    writer.blank_line();
+
+    for edge in &graph.edges {
+        write_string(writer, &graph.nodes[edge.from].id)?;
+        writer.write(" -> ")?;
+        write_string(writer, &graph.nodes[edge.to].id)?;
+        writer.write(" [label=")?;
+        write_string(writer, &edge.label)?;
+
+        if edge.many {
+            writer.write(", headlabel=\"*\"")?;
+        }
+
+        if edge.recursive {
+            writer.write(", color=red, fontcolor=red, penwidth=2")?;
+        }
+
+        writer.write_nl("];")?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")
+}
+
+/// Emits the declaration of a node.
+fn emit_node(writer: &mut SourceWriter<impl Write>, node: &Node) -> Result<()> {
+    write_string(writer, &node.id)?;
+    writer.write(" [label=\"")?;
+    write_escaped(writer, node.kind.keyword())?;
+    writer.write(" ")?;
+    write_escaped(writer, &node.name)?;
+
+    if !node.members.is_empty() {
+        writer.write("\\n\\n")?;
+    }
+
+    for member in &node.members {
+        write_escaped(writer, member)?;
+        writer.write("\\l")?;
+    }
+
+    writer.write("\"")?;
+
+    let color = match node.status {
+        Status::Unchanged => None,
+        Status::Added => Some("#c8e6c9"),
+        Status::Removed => Some("#ffcdd2"),
+        Status::Changed => Some("#fff9c4"),
+    };
+
+    if let Some(color) = color {
+        writer.write_fmt(format_args!(", style=filled, fillcolor=\"{color}\""))?;
+    }
+
+    writer.write_nl("];")
+}
+
+/// Writes a quoted string.
+fn write_string(writer: &mut SourceWriter<impl Write>, value: &str) -> Result<()> {
+    writer.write("\"")?;
+    write_escaped(writer, value)?;
+    writer.write("\"")
+}
+
+/// Writes the contents of a quoted string, escaping quotes, backslashes and newlines.
+fn write_escaped(writer: &mut SourceWriter<impl Write>, value: &str) -> Result<()> {
+    for ch in value.chars() {
+        match ch {
+            '"' => writer.write("\\\"")?,
+            '\\' => writer.write("\\\\")?,
+            '\n' => writer.write("\\n")?,
+            ch => writer.write_fmt(format_args!("{ch}"))?,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,133 @@
+//! Emits diagrams as Mermaid class diagrams.
+
+use std::{
+    collections::HashSet,
+    io::{Result, Write},
+};
+
+use crate::{
+    codegen::{idents::disambiguate, source_writer::SourceWriter},
+    graph::{Graph, Status},
+};
+
+/// The style classes of nodes that changed in a migration, with their names and styles.
+const STATUS_CLASSES: [(Status, &str, &str); 3] = [
+    (Status::Added, "added", "fill:#c8e6c9"),
+    (Status::Removed, "removed", "fill:#ffcdd2"),
+    (Status::Changed, "changed", "fill:#fff9c4"),
+];
+
+/// Emits a diagram.
+pub fn emit_graph(writer: &mut SourceWriter<impl Write>, graph: &Graph) -> Result<()> {
+    let ids = class_ids(graph);
+
+    writer.write_nl("---")?;
+    writer.write_fmt_nl(format_args!("title: {}", graph.name))?;
+    writer.write_nl("---")?;
+    writer.write_nl("classDiagram")?;
+    writer.indent();
+
+    for (node, id) in graph.nodes.iter().zip(&ids) {
+        if *id != node.name {
+            writer.write_fmt(format_args!("class {id}[\""))?;
+            write_escaped(writer, &node.name)?;
+            writer.write_nl("\"]")?;
+        }
+
+        writer.write_fmt_nl(format_args!("class {id} {{"))?;
+        writer.indent();
+        writer.write_fmt_nl(format_args!("<<{}>>", node.kind.keyword()))?;
+
+        for member in &node.members {
+            write_escaped(writer, member)?;
+            writer.write_nl("")?;
+        }
+
+        writer.dedent();
+        writer.write_nl("}")?;
+    }
+
+    writer.blank_line();
+
+    for edge in &graph.edges {
+        writer.write_fmt(format_args!("{} --> ", ids[edge.from]))?;
+
+        if edge.many {
+            writer.write("\"*\" ")?;
+        }
+
+        writer.write(&ids[edge.to])?;
+
+        let label = match (edge.label.as_str(), edge.recursive) {
+            ("", false) => None,
+            ("", true) => Some("(recursive)".to_owned()),
+            (label, false) => Some(label.to_owned()),
+            (label, true) => Some(format!("{label} (recursive)")),
+        };
+
+        if let Some(label) = label {
+            writer.write(" : ")?;
+            write_escaped(writer, &label)?;
+        }
+
+        writer.write_nl("")?;
+    }
+
+    for (status, class, style) in STATUS_CLASSES {
+        let members: Vec<&str> = graph
+            .nodes
+            .iter()
+            .zip(&ids)
+            .filter(|(node, _id)| node.status == status)
+            .map(|(_node, id)| id.as_str())
+            .collect();
+
+        if !members.is_empty() {
+            writer.blank_line();
+            writer.write_fmt_nl(format_args!("classDef {class} {style}"))?;
+            writer.write_fmt_nl(format_args!("cssClass \"{}\" {class}", members.join(",")))?;
+        }
+    }
+
+    writer.dedent();
+    Ok(())
+}
+
+/// Assigns every node an identifier that Mermaid accepts as a class name.
+fn class_ids(graph: &Graph) -> Vec<String> {
+    let mut used = HashSet::new();
+
+    graph
+        .nodes
+        .iter()
+        .map(|node| {
+            let mut id: String = node
+                .id
+                .chars()
+                .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+                .collect();
+
+            if !id.starts_with(|ch: char| ch.is_ascii_alphabetic()) {
+                id.insert(0, 'T');
+            }
+
+            disambiguate(&mut id, |id| used.contains(id));
+            used.insert(id.clone());
+            id
+        })
+        .collect()
+}
+
+/// Writes text, replacing characters with a special meaning with entity codes.
+fn write_escaped(writer: &mut SourceWriter<impl Write>, value: &str) -> Result<()> {
+    for ch in value.chars() {
+        match ch {
+            '"' | '{' | '}' | '<' | '>' | '#' | ';' | '~' => {
+                writer.write_fmt(format_args!("#{};", ch as u32))?
+            }
+            ch => writer.write_fmt(format_args!("{ch}"))?,
+        }
+    }
+
+    Ok(())
+}
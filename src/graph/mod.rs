@@ -0,0 +1,459 @@
+//! Exports schemas as diagrams, with named types as nodes and references between them as edges.
+//!
+//! The diagram is first built as a [`Graph`], which is then emitted
+//! as a Graphviz DOT file by [`dot`] or as a Mermaid class diagram by [`mermaid`].
+
+use std::{
+    collections::HashSet,
+    io::{BufWriter, Write, stdout},
+    path::Path,
+};
+
+use clap::ValueEnum;
+
+use crate::{
+    ast::{Field, NamedType, PrimitiveType, Type, TypeSet, TypeType, Value},
+    codegen::source_writer::SourceWriter,
+    error::{Error, ResultExt},
+    loading::{load_file, load_migration},
+    preprocessing::BasicMetadata,
+};
+
+mod dot;
+mod mermaid;
+
+/// The format of the emitted diagram.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum GraphFormat {
+    /// A Graphviz DOT file
+    #[default]
+    Dot,
+    /// A Mermaid class diagram
+    Mermaid,
+}
+
+/// A diagram of the named types in a schema.
+#[derive(Debug, Clone)]
+struct Graph {
+    /// The name of the graph, the version of the schema.
+    name: String,
+    nodes: Vec<Node>,
+    edges: Vec<Edge>,
+}
+
+/// A named type.
+#[derive(Debug, Clone)]
+struct Node {
+    /// A name that is unique among all nodes, used to refer to the node.
+    id: String,
+    name: String,
+    kind: Kind,
+    /// The fields of a struct, the variants of an enum or the aliased type.
+    members: Vec<String>,
+    status: Status,
+}
+
+/// The kind of a named type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Struct,
+    Enum,
+    OpenEnum,
+    Alias,
+    Extern,
+}
+
+impl Kind {
+    /// Gets the keyword used to display the kind.
+    fn keyword(self) -> &'static str {
+        match self {
+            Kind::Struct => "struct",
+            Kind::Enum => "enum",
+            Kind::OpenEnum => "open enum",
+            Kind::Alias => "alias",
+            Kind::Extern => "extern",
+        }
+    }
+}
+
+/// How a named type changed in a migration.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    /// The diagram isn't of a migration, or the type hasn't changed.
+    Unchanged,
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A reference from one named type to another.
+#[derive(Debug, Clone)]
+struct Edge {
+    from: usize,
+    to: usize,
+    /// The path to the reference through fields and variants.
+    label: String,
+    /// Whether the reference is inside a list.
+    many: bool,
+    /// Whether the edge is part of a cycle.
+    recursive: bool,
+}
+
+/// Implements `versed graph`.
+pub fn print_graph(path: &Path, format: GraphFormat, migration: bool) -> Result<(), Error> {
+    let graph = if migration {
+        let migration = load_migration(path)?;
+        build_migration_graph(&migration.old, &migration.new)
+    } else {
+        build_graph(&load_file(path)?)
+    };
+
+    let mut writer = SourceWriter::new(BufWriter::new(stdout().lock()));
+
+    match format {
+        GraphFormat::Dot => dot::emit_graph(&mut writer, &graph),
+        GraphFormat::Mermaid => mermaid::emit_graph(&mut writer, &graph),
+    }
+    .and_then(|()| writer.into_inner().flush())
+    .with_stdout()
+}
+
+/// Builds the diagram of a schema.
+fn build_graph(types: &TypeSet<BasicMetadata>) -> Graph {
+    let nodes = types
+        .types
+        .iter()
+        .map(|named| make_node(types, named))
+        .collect();
+
+    let mut edges = vec![];
+
+    for (index, named) in types.types.iter().enumerate() {
+        collect_edges(&named.r#type, index, &mut edges, &|to| to, None, false);
+    }
+
+    finish_graph(types.version.clone(), nodes, edges)
+}
+
+/// Builds the diagram of the new version of a migration, adding the removed types of the old version.
+fn build_migration_graph(old: &TypeSet<BasicMetadata>, new: &TypeSet<BasicMetadata>) -> Graph {
+    // The index of the linked type in the new version for every type in the old version
+    let counterparts: Vec<Option<usize>> = old
+        .types
+        .iter()
+        .map(|old_named| {
+            new.types.iter().position(|new_named| {
+                old_named
+                    .r#type
+                    .number
+                    .is_some_and(|number| new_named.r#type.number == Some(number))
+            })
+        })
+        .collect();
+
+    let mut nodes: Vec<Node> = new
+        .types
+        .iter()
+        .enumerate()
+        .map(|(index, named)| {
+            let node = make_node(new, named);
+
+            let status = match counterparts.iter().position(|&c| c == Some(index)) {
+                None => Status::Added,
+                Some(old_index) => {
+                    let old_node = make_node(old, &old.types[old_index]);
+
+                    if old_node.name == node.name
+                        && old_node.kind == node.kind
+                        && old_node.members == node.members
+                    {
+                        Status::Unchanged
+                    } else {
+                        Status::Changed
+                    }
+                }
+            };
+
+            Node { status, ..node }
+        })
+        .collect();
+
+    // The index of the node of every type in the old version
+    let mut old_nodes = vec![0; old.types.len()];
+
+    for (index, named) in old.types.iter().enumerate() {
+        old_nodes[index] = match counterparts[index] {
+            Some(new_index) => new_index,
+            None => {
+                let mut node = Node {
+                    status: Status::Removed,
+                    ..make_node(old, named)
+                };
+                let ids: HashSet<String> = nodes.iter().map(|node| node.id.clone()).collect();
+
+                if ids.contains(&node.id) {
+                    node.id = format!("{} ({})", node.id, old.version);
+                }
+
+                nodes.push(node);
+                nodes.len() - 1
+            }
+        };
+    }
+
+    let mut edges = vec![];
+
+    for (index, named) in new.types.iter().enumerate() {
+        collect_edges(&named.r#type, index, &mut edges, &|to| to, None, false);
+    }
+
+    for (index, named) in old.types.iter().enumerate() {
+        if counterparts[index].is_none() {
+            collect_edges(
+                &named.r#type,
+                old_nodes[index],
+                &mut edges,
+                &|to| old_nodes[to],
+                None,
+                false,
+            );
+        }
+    }
+
+    finish_graph(new.version.clone(), nodes, edges)
+}
+
+/// Creates the node for a named type, which is [unchanged](Status::Unchanged).
+fn make_node(types: &TypeSet<BasicMetadata>, named: &NamedType<BasicMetadata>) -> Node {
+    let r#type = &named.r#type;
+    let (kind, members) = match &r#type.r#type {
+        TypeType::Struct(r#struct) => (
+            Kind::Struct,
+            r#struct
+                .fields
+                .iter()
+                .map(|field| field_text(types, field))
+                .collect(),
+        ),
+        TypeType::Enum(r#enum) => (
+            if r#enum.open {
+                Kind::OpenEnum
+            } else {
+                Kind::Enum
+            },
+            r#enum
+                .variants
+                .iter()
+                .map(|variant| variant_text(types, &variant.name, &variant.r#type))
+                .collect(),
+        ),
+        TypeType::Extern(_) => (Kind::Extern, vec![]),
+        _ => (Kind::Alias, vec![type_text(types, r#type)]),
+    };
+
+    let name = if named.parameters.is_empty() {
+        named.name.clone()
+    } else {
+        let parameters: Vec<&str> = named
+            .parameters
+            .iter()
+            .map(|parameter| parameter.name.as_str())
+            .collect();
+        format!("{}<{}>", named.name, parameters.join(", "))
+    };
+
+    Node {
+        id: named.name.clone(),
+        name,
+        kind,
+        members,
+        status: Status::Unchanged,
+    }
+}
+
+/// Writes a type in the syntax of the schema language.
+pub fn type_text(types: &TypeSet<BasicMetadata>, r#type: &Type<BasicMetadata>) -> String {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) if r#struct.fields.is_empty() => "struct {}".into(),
+        TypeType::Struct(r#struct) => {
+            let fields: Vec<String> = r#struct
+                .fields
+                .iter()
+                .map(|field| field_text(types, field))
+                .collect();
+            format!("struct {{ {} }}", fields.join(", "))
+        }
+        TypeType::Enum(r#enum) => {
+            let keyword = if r#enum.open { "open enum" } else { "enum" };
+
+            if r#enum.variants.is_empty() {
+                return format!("{keyword} {{}}");
+            }
+
+            let variants: Vec<String> = r#enum
+                .variants
+                .iter()
+                .map(|variant| variant_text(types, &variant.name, &variant.r#type))
+                .collect();
+            format!("{keyword} {{ {} }}", variants.join(", "))
+        }
+        TypeType::List(list) => format!("[{}]", type_text(types, &list.r#type)),
+        TypeType::Tuple(tuple) => {
+            let elements: Vec<String> = tuple
+                .elements
+                .iter()
+                .map(|element| type_text(types, element))
+                .collect();
+            format!("({})", elements.join(", "))
+        }
+        TypeType::Primitive(primitive) => primitive.to_string(),
+        TypeType::Identifier(identifier) => {
+            let name = &types.types[identifier.metadata.resolution].name;
+
+            if identifier.arguments.is_empty() {
+                name.clone()
+            } else {
+                let arguments: Vec<String> = identifier
+                    .arguments
+                    .iter()
+                    .map(|argument| type_text(types, argument))
+                    .collect();
+                format!("{name}<{}>", arguments.join(", "))
+            }
+        }
+        TypeType::Parameter(parameter) => parameter.name.clone(),
+        TypeType::Extern(_) => "extern".to_owned(),
+    }
+}
+
+/// Writes a field, including its default value, or a spread like `..Timestamps`.
+pub fn field_text(types: &TypeSet<BasicMetadata>, field: &Field<BasicMetadata>) -> String {
+    let r#type = type_text(types, &field.r#type);
+
+    if field.spread {
+        return format!("..{type}");
+    }
+
+    match &field.default {
+        Some(default) => format!("{}: {type} = {}", field.name, value_text(default)),
+        None => format!("{}: {type}", field.name),
+    }
+}
+
+/// Writes a value in the syntax of the schema language.
+pub fn value_text(value: &Value) -> String {
+    match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(string) => format!("{string:?}"),
+        Value::List(elements) => {
+            let elements: Vec<String> = elements.iter().map(value_text).collect();
+            format!("[{}]", elements.join(", "))
+        }
+    }
+}
+
+/// Writes a variant, leaving out the type of unit variants.
+pub fn variant_text(
+    types: &TypeSet<BasicMetadata>,
+    name: &str,
+    r#type: &Type<BasicMetadata>,
+) -> String {
+    match &r#type.r#type {
+        TypeType::Primitive(primitive) if primitive.r#type == PrimitiveType::Unit => name.into(),
+        _ => format!("{name}: {}", type_text(types, r#type)),
+    }
+}
+
+/// Adds an edge for every reference in a type recursively.
+///
+/// `node` maps the indices of named types to the indices of their nodes,
+/// `label` is the path to the type and `many` is set inside lists.
+fn collect_edges(
+    r#type: &Type<BasicMetadata>,
+    from: usize,
+    edges: &mut Vec<Edge>,
+    node: &dyn Fn(usize) -> usize,
+    label: Option<&str>,
+    many: bool,
+) {
+    let join = |name: &str| match label {
+        Some(label) => format!("{label}.{name}"),
+        None => name.to_owned(),
+    };
+
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => {
+            for field in &r#struct.fields {
+                let label = if field.spread {
+                    join(&format!("..{}", field.name))
+                } else {
+                    join(&field.name)
+                };
+                collect_edges(&field.r#type, from, edges, node, Some(&label), many);
+            }
+        }
+        TypeType::Enum(r#enum) => {
+            for variant in &r#enum.variants {
+                let label = join(&variant.name);
+                collect_edges(&variant.r#type, from, edges, node, Some(&label), many);
+            }
+        }
+        TypeType::List(list) => collect_edges(&list.r#type, from, edges, node, label, true),
+        TypeType::Tuple(tuple) => {
+            for (index, element) in tuple.elements.iter().enumerate() {
+                let label = join(&index.to_string());
+                collect_edges(element, from, edges, node, Some(&label), many);
+            }
+        }
+        TypeType::Primitive(_primitive) => {}
+        TypeType::Identifier(identifier) => {
+            edges.push(Edge {
+                from,
+                to: node(identifier.metadata.resolution),
+                label: label.unwrap_or_default().to_owned(),
+                many,
+                recursive: false,
+            });
+
+            for argument in &identifier.arguments {
+                collect_edges(argument, from, edges, node, label, many);
+            }
+        }
+        TypeType::Parameter(_parameter) => {}
+        TypeType::Extern(_) => {}
+    }
+}
+
+/// Marks the edges that are part of a cycle and creates the [`Graph`].
+fn finish_graph(name: String, nodes: Vec<Node>, mut edges: Vec<Edge>) -> Graph {
+    let mut successors = vec![vec![]; nodes.len()];
+
+    for edge in &edges {
+        successors[edge.from].push(edge.to);
+    }
+
+    for edge in &mut edges {
+        edge.recursive = reaches(&successors, edge.to, edge.from);
+    }
+
+    Graph { name, nodes, edges }
+}
+
+/// Checks whether there is a path from one node to another.
+fn reaches(successors: &[Vec<usize>], from: usize, to: usize) -> bool {
+    let mut visited = vec![false; successors.len()];
+    let mut stack = vec![from];
+
+    while let Some(node) = stack.pop() {
+        if node == to {
+            return true;
+        }
+
+        if !visited[node] {
+            visited[node] = true;
+            stack.extend(&successors[node]);
+        }
+    }
+
+    false
+}
@@ -3,25 +3,58 @@
 //! Handles parsing through [`crate::syntax`] and name resolution and some checks through [`crate::preprocessing`].
 //! Also prints all generated [`ariadne::Report`]s, for lifetime reasons.
 
-use std::{fs, path::Path};
+use std::{
+    borrow::Cow,
+    collections::HashMap,
+    fs,
+    io::{Read, stdin},
+    path::Path,
+};
 
 use crate::{
-    ast::{Migration, TypeSet},
+    ast::{Migration, TypeSet, TypeType},
     error::{Error, ResultExt},
+    metadata::Metadata,
     preprocessing::{BasicMetadata, preprocess, preprocess_migration},
     reports::{Reports, handle_reports},
     syntax::{parse_migration, parse_schema},
 };
 
+/// The path that refers to standard input instead of a file.
+pub const STDIN_PATH: &str = "-";
+
+/// The name used for standard input in reports.
+const STDIN_FILENAME: &str = "<stdin>";
+
+/// Checks whether a path refers to standard input.
+pub fn is_stdin(path: &Path) -> bool {
+    path == Path::new(STDIN_PATH)
+}
+
+/// Reads a file, or standard input if the path is [`STDIN_PATH`].
+///
+/// Returns the source code and the filename to use in reports.
+fn read_source(file: &Path) -> Result<(String, Cow<'_, str>), Error> {
+    if is_stdin(file) {
+        let mut src = String::new();
+        stdin().read_to_string(&mut src).with_stdin()?;
+        Ok((src, Cow::Borrowed(STDIN_FILENAME)))
+    } else {
+        let src = fs::read_to_string(file).with_path(file)?;
+        Ok((src, file.to_string_lossy()))
+    }
+}
+
 /// Loads and parses a schema file, printing any errors.
 pub fn load_file(file: &Path) -> Result<TypeSet<BasicMetadata>, Error> {
     load_file_with_source(file).map(|(types, _)| types)
 }
 
 /// Loads and parses a schema file, printing any errors, returning the source code as well as the AST.
+///
+/// Reads standard input instead if the path is [`STDIN_PATH`].
 pub fn load_file_with_source(file: &Path) -> Result<(TypeSet<BasicMetadata>, String), Error> {
-    let filename = file.to_string_lossy();
-    let src = fs::read_to_string(file).with_path(file)?;
+    let (src, filename) = read_source(file)?;
     let mut reports = Reports::default();
 
     let ast = parse_schema(&src, &mut reports, &filename);
@@ -33,13 +66,48 @@ pub fn load_file_with_source(file: &Path) -> Result<(TypeSet<BasicMetadata>, Str
 
 /// Loads and parses a migration file, printing any errors.
 pub fn load_migration(file: &Path) -> Result<Migration<BasicMetadata>, Error> {
-    let filename = file.to_string_lossy();
-    let src = fs::read_to_string(file).with_path(file)?;
+    load_migration_with_source(file).map(|(migration, _)| migration)
+}
+
+/// Loads and parses a migration file, printing any errors, returning the source code as well as the AST.
+///
+/// Reads standard input instead if the path is [`STDIN_PATH`].
+pub fn load_migration_with_source(
+    file: &Path,
+) -> Result<(Migration<BasicMetadata>, String), Error> {
+    let (src, filename) = read_source(file)?;
     let mut reports = Reports::default();
 
     let migration = parse_migration(&src, &mut reports, &filename);
     let migration = migration.map(|m| preprocess_migration(m, &mut reports, &filename));
 
     handle_reports(&reports, &filename, &src)?;
-    migration.ok_or(Error::MalformedFile)
+    migration
+        .ok_or(Error::MalformedFile)
+        .map(|migration| (migration, src))
+}
+
+/// Checks that every name refers to a named type in the schema, returning an error if one doesn't.
+pub fn check_type_names<M: Metadata>(types: &TypeSet<M>, names: &[String]) -> Result<(), Error> {
+    match names.iter().find(|name| types.index_of(name).is_none()) {
+        Some(name) => Err(Error::UnknownType { name: name.clone() }),
+        None => Ok(()),
+    }
+}
+
+/// Checks that every extern type is mapped to a type of the target language, returning an error if one isn't.
+pub fn check_extern_mappings<M: Metadata>(
+    types: &TypeSet<M>,
+    externs: &HashMap<String, String>,
+) -> Result<(), Error> {
+    let unmapped = types.types.iter().find(|r#type| {
+        matches!(r#type.r#type.r#type, TypeType::Extern(_)) && !externs.contains_key(&r#type.name)
+    });
+
+    match unmapped {
+        Some(r#type) => Err(Error::UnmappedExtern {
+            name: r#type.name.clone(),
+        }),
+        None => Ok(()),
+    }
 }
@@ -1,6 +1,7 @@
 //! Versed is a tool for generating DTOs and their migrations from schema descriptions.
 
 use std::{
+    collections::HashMap,
     fmt::Display,
     io::{BufWriter, Write, stdout},
     path::{Path, PathBuf},
@@ -9,27 +10,44 @@ use std::{
 
 use anstream::stderr;
 use anstyle::{AnsiColor, Color, Style};
-use clap::{CommandFactory, Parser, Subcommand, ValueHint};
+use clap::{CommandFactory, Parser, Subcommand, ValueHint, error::ErrorKind};
 use clap_complete::{Generator, Shell};
 
 use crate::{
     ast::TypeSet,
+    codegen::output::Output,
+    docs::{DocsFormat, DocsOptions},
+    dump::DumpFormat,
     error::{Error, ResultExt},
-    loading::{load_file, load_migration},
-    rust::RustOptions,
+    graph::GraphFormat,
+    loading::{is_stdin, load_file, load_migration},
+    migrations::DataMigrationOptions,
+    plugin::{PluginNaming, PluginOptions},
+    rust::{RustMigrationOptions, RustOptions, TimeCrate},
+    sample::SampleOptions,
+    typescript::TypeScriptOptions,
+    watch::GeneratedFiles,
 };
 
 pub mod ast;
 pub mod codegen;
+pub mod docs;
+pub mod dump;
 pub mod error;
+pub mod formats;
+pub mod graph;
 pub mod loading;
 pub mod metadata;
 pub mod migrations;
+pub mod plugin;
 pub mod preprocessing;
 pub mod reports;
 pub mod rust;
+pub mod sample;
+pub mod serialization;
 pub mod syntax;
 pub mod typescript;
+pub mod watch;
 
 /// A tool for generating DTOs and their migrations from schema descriptions
 #[derive(Parser, Debug)]
@@ -71,6 +89,125 @@ enum Command {
         #[command(subcommand)]
         command: TypeScriptCommand,
     },
+    /// Generate reference documentation for a schema
+    ///
+    /// The documentation will be written to index.md or index.html inside the output directory.
+    /// It describes every named type with its fields or variants, its names in Rust and TypeScript,
+    /// the shape of its JSON representation and the doc comments (lines starting with ///) above it.
+    Docs {
+        /// The path to the schema file
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// The path to the directory in which to create the documentation
+        #[arg(value_hint = ValueHint::DirPath)]
+        output: PathBuf,
+        /// The format of the documentation
+        #[arg(short, long, value_enum, default_value_t)]
+        format: DocsFormat,
+        /// Also generate a changelog from the migration files (*.vsm) in this directory
+        #[arg(short, long, value_name = "DIR", value_hint = ValueHint::DirPath)]
+        migrations: Option<PathBuf>,
+        /// Describe enums like the Rust types generated with --serde-external-tag
+        #[arg(long)]
+        external_tag: bool,
+    },
+    /// Print the resolved syntax tree of a schema file
+    ///
+    /// Includes the spans of names and types as byte offsets, the migration markers of types
+    /// and, for every identifier, the index of the named type it refers to.
+    Dump {
+        /// The path to the schema file
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// The format of the syntax tree
+        #[arg(short, long, value_enum, default_value_t)]
+        format: DumpFormat,
+    },
+    /// Print a diagram of the named types in a schema
+    ///
+    /// Named types become nodes listing their fields or variants,
+    /// and references between them become edges.
+    /// References inside lists are marked with a *, and references forming a cycle are highlighted.
+    Graph {
+        /// The path to the schema file, or to the migration file with --migration
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// The format of the diagram
+        #[arg(short, long, value_enum, default_value_t)]
+        format: GraphFormat,
+        /// Read a migration file and colour the types added, removed and changed by it
+        #[arg(short, long)]
+        migration: bool,
+    },
+    /// Migrate a JSON document between the versions of a migration file
+    ///
+    /// Only structural changes are supported, such as renamed fields or new fields with a default value.
+    /// Where the generated migration functions would contain a todo!(),
+    /// the migration fails with the JSON path of the value that couldn't be migrated.
+    MigrateData {
+        /// The path to the migration file
+        #[arg(value_hint = ValueHint::FilePath)]
+        migration: PathBuf,
+        /// The path to the JSON document to migrate
+        #[arg(value_hint = ValueHint::FilePath)]
+        input: PathBuf,
+        /// The path to write the migrated document to
+        #[arg(value_hint = ValueHint::FilePath)]
+        output: PathBuf,
+        /// The name of the type of the document, in the version it's migrated from
+        #[arg(short, long = "type", value_name = "TYPE")]
+        type_name: String,
+        /// Migrate the document from the new version to the old version
+        #[arg(short, long)]
+        downgrade: bool,
+        /// Represent enums like the Rust types generated with --serde-external-tag
+        #[arg(long)]
+        external_tag: bool,
+    },
+    /// Print random values of a type as JSON
+    ///
+    /// The values use the same representation as the Rust types generated with --serde.
+    Sample {
+        /// The path to the schema file
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// The name of the type to generate values of
+        #[arg(value_name = "TYPE")]
+        type_name: String,
+        /// Print a JSON array with this many values instead of a single value
+        #[arg(short = 'n', long)]
+        count: Option<usize>,
+        /// The seed of the random number generator, to get the same values every time
+        #[arg(short, long)]
+        seed: Option<u64>,
+        /// Represent enums like the Rust types generated with --serde-external-tag
+        #[arg(long)]
+        external_tag: bool,
+    },
+    /// Generate code with an external generator, called a plugin
+    ///
+    /// Runs the executable versed-gen-<PLUGIN>, which receives the resolved schema as JSON
+    /// on its standard input and responds with the files to write into the output directory.
+    /// See the documentation for a description of the protocol.
+    Plugin {
+        /// The name of the plugin, without the versed-gen- prefix
+        plugin: String,
+        /// The path to the schema file, or to the migration file with --migration
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// The path to the directory in which to write the generated files
+        #[arg(value_hint = ValueHint::DirPath)]
+        output: PathBuf,
+        /// Read a migration file and send both versions and the pairs of linked types
+        #[arg(short, long)]
+        migration: bool,
+        /// Also send the names given by these naming rules
+        #[arg(short, long, value_enum, value_delimiter = ',')]
+        naming: Vec<PluginNaming>,
+        /// A parameter passed to the plugin as it is
+        #[arg(short, long = "parameter", value_name = "PARAMETER")]
+        parameters: Vec<String>,
+    },
     /// Generate a tab-completion script for your shell
     Completions {
         /// The shell to target
@@ -103,6 +240,17 @@ enum MigrationCommand {
         #[arg(value_hint = ValueHint::FilePath)]
         file: PathBuf,
     },
+    /// Print the resolved syntax trees of both versions in a migration file
+    ///
+    /// Also includes the pairs of types linked by migration markers.
+    Dump {
+        /// The path to the migration file
+        #[arg(value_hint = ValueHint::FilePath)]
+        file: PathBuf,
+        /// The format of the syntax trees
+        #[arg(short, long, value_enum, default_value_t)]
+        format: DumpFormat,
+    },
 }
 
 #[derive(Subcommand, Debug)]
@@ -113,12 +261,12 @@ enum RustCommand {
     /// the version of the schema.
     /// A corresponding module reference will be added to mod.rs.
     Types {
-        /// The path to the schema file
+        /// The path to the schema file, or - to read it from standard input
         #[arg(value_hint = ValueHint::FilePath)]
         file: PathBuf,
         /// The path to the directory in which to create a file with the generated types
-        #[arg(value_hint = ValueHint::AnyPath)]
-        output: PathBuf,
+        #[arg(value_hint = ValueHint::AnyPath, required_unless_present = "stdout")]
+        output: Option<PathBuf>,
         /// Interpret \<OUTPUT\> as a file instead of as a directory
         #[arg(
             short = 'f',
@@ -126,6 +274,13 @@ enum RustCommand {
             help = "Interpret <OUTPUT> as a file instead of as a directory"
         )]
         to_file: bool,
+        /// Write the generated code to standard output instead of to \<OUTPUT\>
+        #[arg(
+            long,
+            conflicts_with_all = ["output", "to_file"],
+            help = "Write the generated code to standard output instead of to <OUTPUT>"
+        )]
+        stdout: bool,
         /// Derive another trait
         #[arg(short = 'd', long)]
         derive: Vec<String>,
@@ -135,15 +290,43 @@ enum RustCommand {
         /// Use externally tagged representation for enums when serializing using serde
         #[arg(long)]
         serde_external_tag: bool,
+        /// Generate an envelope tagging a type with the schema version when serializing
+        ///
+        /// The envelope is an enum named after the type with an "Envelope" suffix,
+        /// which gets serialized as {"version": ..., "data": ...}.
+        /// May be specified multiple times.
+        #[arg(short = 'e', long, value_name = "TYPE", requires = "serde")]
+        envelope: Vec<String>,
+        /// Generate methods checking the refinements of primitive types, like `User::validate`
+        ///
+        /// Patterns are checked with the regex crate, which has to be a dependency then.
+        #[arg(long)]
+        validate: bool,
+        /// Map an extern type to a Rust type, like Timestamp=chrono::DateTime<chrono::Utc>
+        ///
+        /// Every extern type of the schema has to be mapped.
+        /// May be specified multiple times.
+        #[arg(long = "extern", value_name = "NAME=TYPE", value_parser = parse_extern)]
+        externs: Vec<(String, String)>,
+        /// The crate providing the types of timestamps, dates and durations
+        ///
+        /// With --serde, the built-in types need the serde_with crate for their formats.
+        #[arg(long, value_enum, default_value_t)]
+        time_crate: TimeCrate,
+        /// Keep running and regenerate the types whenever the schema file changes
+        ///
+        /// Files generated by an earlier run are rewritten in place.
+        #[arg(short, long)]
+        watch: bool,
     },
     /// Generate migration
     Migration {
-        /// The path to the schema file
+        /// The path to the schema file, or - to read it from standard input
         #[arg(value_hint = ValueHint::FilePath)]
         file: PathBuf,
         /// The path to the directory with the previously generated types
-        #[arg(value_hint = ValueHint::AnyPath)]
-        output: PathBuf,
+        #[arg(value_hint = ValueHint::AnyPath, required_unless_present = "stdout")]
+        output: Option<PathBuf>,
         /// Interpret \<OUTPUT\> as a file instead of as a directory
         #[arg(
             short = 'f',
@@ -151,6 +334,61 @@ enum RustCommand {
             help = "Interpret <OUTPUT> as a file instead of as a directory"
         )]
         to_file: bool,
+        /// Write the generated code to standard output instead of to \<OUTPUT\>
+        #[arg(
+            long,
+            conflicts_with_all = ["output", "to_file"],
+            help = "Write the generated code to standard output instead of to <OUTPUT>"
+        )]
+        stdout: bool,
+        /// Make downgrade functions return a Result instead of panicking on lossy changes
+        ///
+        /// Variants that don't exist in the old version are downgraded
+        /// to an Err containing a DowngradeError, which is propagated through all downgrades.
+        #[arg(long)]
+        fallible_downgrades: bool,
+        /// Emit proptest tests checking that upgrading and downgrading values is lossless
+        ///
+        /// Requires proptest as a dev-dependency and types generated with --derive PartialEq.
+        /// Types whose migration loses information are skipped, with a comment explaining why.
+        #[arg(long)]
+        property_tests: bool,
+        /// Map an extern type to a Rust type, like Timestamp=chrono::DateTime<chrono::Utc>
+        ///
+        /// Every extern type of both versions has to be mapped.
+        /// Linked extern types mapped to the same Rust type are migrated by passing values through.
+        /// May be specified multiple times.
+        #[arg(long = "extern", value_name = "NAME=TYPE", value_parser = parse_extern)]
+        externs: Vec<(String, String)>,
+        /// The crate providing the types of timestamps, dates and durations
+        #[arg(long, value_enum, default_value_t)]
+        time_crate: TimeCrate,
+    },
+    /// Generate a module that chains migrations across all versions
+    ///
+    /// The module will be written to migrations/mod.rs inside the output directory,
+    /// replacing its previous contents.
+    /// It declares the modules of all migrations and, for every named type present in all versions,
+    /// an AnyVersion enum that can be upgraded to the latest version or downgraded to any version.
+    /// Rerun this command whenever a new migration is added.
+    Chain {
+        /// The path to the directory with the previously generated types and migrations
+        #[arg(value_hint = ValueHint::AnyPath)]
+        output: PathBuf,
+        /// The paths to all migration files, ordered from oldest to newest
+        #[arg(required = true, value_hint = ValueHint::FilePath)]
+        migrations: Vec<PathBuf>,
+        /// Make AnyVersion (de)serializable as a version-tagged envelope using serde
+        ///
+        /// Also generates a deserialize_any_version function,
+        /// which reads an envelope of any version and upgrades it to the latest version.
+        #[arg(short = 's', long)]
+        serde: bool,
+        /// Chain migrations generated with --fallible-downgrades
+        ///
+        /// downgrade_to will return a Result, with an error that also contains the target version.
+        #[arg(long)]
+        fallible_downgrades: bool,
     },
 }
 
@@ -162,12 +400,12 @@ enum TypeScriptCommand {
     /// the version of the schema.
     /// A corresponding import statement will be added to index.ts.
     Types {
-        /// The path to the schema file
+        /// The path to the schema file, or - to read it from standard input
         #[arg(value_hint = ValueHint::FilePath)]
         file: PathBuf,
         /// The path to the directory in which to create a file with the generated types
-        #[arg(value_hint = ValueHint::AnyPath)]
-        output: PathBuf,
+        #[arg(value_hint = ValueHint::AnyPath, required_unless_present = "stdout")]
+        output: Option<PathBuf>,
         /// Interpret \<OUTPUT\> as a file instead of as a directory
         #[arg(
             short = 'f',
@@ -175,6 +413,59 @@ enum TypeScriptCommand {
             help = "Interpret <OUTPUT> as a file instead of as a directory"
         )]
         to_file: bool,
+        /// Write the generated code to standard output instead of to \<OUTPUT\>
+        #[arg(
+            long,
+            conflicts_with_all = ["output", "to_file"],
+            help = "Write the generated code to standard output instead of to <OUTPUT>"
+        )]
+        stdout: bool,
+        /// Generate an envelope type tagging a type with the schema version, and a function parsing it
+        ///
+        /// The envelope is named after the type with an "Envelope" suffix
+        /// and matches {"version": ..., "data": ...}.
+        /// May be specified multiple times.
+        #[arg(short = 'e', long, value_name = "TYPE")]
+        envelope: Vec<String>,
+        /// Make fields with a default value optional properties
+        #[arg(long)]
+        optional_defaults: bool,
+        /// Generate functions checking the refinements of primitive types, like `validateUser`
+        #[arg(long)]
+        validate: bool,
+        /// Generate functions checking that a value of unknown type, like parsed JSON, has a type
+        ///
+        /// Every named type gets a type guard like `isUser`, a function like `parseUser` throwing a ParseError,
+        /// and a function like `checkUser` returning the path to the first invalid value.
+        /// Refinements and the formats of built-in types are checked as well.
+        #[arg(long)]
+        guards: bool,
+        /// Map an extern type to a TypeScript type, like Timestamp=string
+        ///
+        /// Every extern type of the schema has to be mapped.
+        /// May be specified multiple times.
+        #[arg(long = "extern", value_name = "NAME=TYPE", value_parser = parse_extern)]
+        externs: Vec<(String, String)>,
+        /// Declare nested structs and enums as named types, like `UserAddress`, instead of inlining them
+        #[arg(long)]
+        hoist: bool,
+        /// Declare structs as interfaces instead of type aliases, extending the structs they spread
+        #[arg(long)]
+        interfaces: bool,
+        /// Mark properties readonly, and make arrays and tuples readonly
+        #[arg(long)]
+        readonly: bool,
+        /// Generate a namespace for every enum with functions constructing and matching its variants
+        ///
+        /// For example, `Contact.email(value)` constructs a variant, `Contact.isEmail(contact)` checks for it,
+        /// and `Contact.match(contact, { email: ..., phone: ... })` calls the function of the variant.
+        #[arg(long)]
+        enum_helpers: bool,
+        /// Keep running and regenerate the types whenever the schema file changes
+        ///
+        /// Files generated by an earlier run are rewritten in place.
+        #[arg(short, long)]
+        watch: bool,
     },
 }
 
@@ -185,9 +476,12 @@ mod exit_codes {
     /// The CLI arguments were invalid.
     ///
     /// Mirrors and documents the exit code used by clap internally.
-    pub const _USAGE: u8 = 2; // Used by clap
+    /// Also used when an argument refers to a type that doesn't exist or can't be sampled.
+    pub const USAGE: u8 = 2;
     /// A file or directory could not be read or written.
     pub const IO: u8 = 3;
+    /// A plugin failed, reported an error or returned an invalid response.
+    pub const PLUGIN: u8 = 4;
 }
 
 /// Converts a result into an exit code, printing errors if needed.
@@ -196,11 +490,31 @@ mod exit_codes {
 fn handle_result(result: Result<(), Error>) -> ExitCode {
     match result {
         Ok(()) => ExitCode::SUCCESS,
-        Err(error @ Error::Io { .. }) => {
+        Err(error @ (Error::Io { .. } | Error::Watch { .. })) => {
             print_error(&error);
             ExitCode::from(exit_codes::IO)
         }
         Err(Error::MalformedFile) => ExitCode::from(exit_codes::MALFORMED_FILE),
+        Err(error @ (Error::Json { .. } | Error::DataMigration { .. })) => {
+            print_error(&error);
+            ExitCode::from(exit_codes::MALFORMED_FILE)
+        }
+        Err(
+            error @ (Error::UnknownType { .. }
+            | Error::InfiniteDepth { .. }
+            | Error::UnmappedExtern { .. }
+            | Error::AdaptedTypeArgument { .. }
+            | Error::ExternValue { .. }
+            | Error::GenericType { .. }
+            | Error::UnsatisfiablePattern { .. }),
+        ) => {
+            print_error(&error);
+            ExitCode::from(exit_codes::USAGE)
+        }
+        Err(error @ Error::Plugin { .. }) => {
+            print_error(&error);
+            ExitCode::from(exit_codes::PLUGIN)
+        }
     }
 }
 
@@ -227,15 +541,30 @@ fn run_command(args: Args) -> Result<(), Error> {
         Command::Version { file } => print_version(&file)?,
         Command::Migration {
             command: MigrationCommand::Begin { file },
-        } => migrations::begin(&file)?,
+        } => {
+            reject_stdin(
+                &file,
+                "the schema file is modified, so it can't be standard input",
+            );
+            migrations::begin(&file)?
+        }
         Command::Migration {
             command: MigrationCommand::Finish { file, migration },
-        } => migrations::finish(&file, &migration)?,
+        } => {
+            reject_stdin(
+                &file,
+                "the schema file is modified, so it can't be standard input",
+            );
+            migrations::finish(&file, &migration)?
+        }
         Command::Migration {
             command: MigrationCommand::Check { file },
         } => {
             load_migration(&file)?;
         }
+        Command::Migration {
+            command: MigrationCommand::Dump { file, format },
+        } => dump::dump_migration(&file, format)?,
         Command::Rust {
             command:
                 RustCommand::Types {
@@ -245,35 +574,204 @@ fn run_command(args: Args) -> Result<(), Error> {
                     derive,
                     serde,
                     serde_external_tag,
+                    envelope,
+                    validate,
+                    externs,
+                    time_crate,
+                    watch,
+                    stdout: _,
                 },
-        } => rust::generate_types(
-            &file,
-            &output,
-            to_file,
-            &RustOptions::new(serde, derive, serde_external_tag),
-        )?,
+        } => {
+            let options = RustOptions::new(
+                serde,
+                derive,
+                serde_external_tag,
+                envelope,
+                validate,
+                HashMap::from_iter(externs),
+                time_crate,
+            );
+            run_generator(&file, watch, |generated| {
+                let output = output_target(output.as_deref(), to_file);
+                rust::generate_types(&file, output, &options, generated)
+            })?
+        }
         Command::Rust {
             command:
                 RustCommand::Migration {
                     file,
                     output,
                     to_file,
+                    fallible_downgrades,
+                    property_tests,
+                    externs,
+                    time_crate,
+                    stdout: _,
+                },
+        } => rust::generate_migration(
+            &file,
+            output_target(output.as_deref(), to_file),
+            &RustMigrationOptions::new(
+                fallible_downgrades,
+                property_tests,
+                HashMap::from_iter(externs),
+                time_crate,
+            ),
+        )?,
+        Command::Rust {
+            command:
+                RustCommand::Chain {
+                    output,
+                    migrations,
+                    serde,
+                    fallible_downgrades,
                 },
-        } => rust::generate_migration(&file, &output, to_file)?,
+        } => rust::generate_chain(&migrations, &output, serde, fallible_downgrades)?,
         Command::TypeScript {
             command:
                 TypeScriptCommand::Types {
                     file,
                     output,
                     to_file,
+                    envelope,
+                    optional_defaults,
+                    validate,
+                    guards,
+                    externs,
+                    hoist,
+                    interfaces,
+                    readonly,
+                    enum_helpers,
+                    watch,
+                    stdout: _,
                 },
-        } => typescript::generate_types(&file, &output, to_file)?,
+        } => {
+            let options = TypeScriptOptions::new(
+                envelope,
+                optional_defaults,
+                validate,
+                guards,
+                HashMap::from_iter(externs),
+                hoist,
+                interfaces,
+                readonly,
+                enum_helpers,
+            );
+            run_generator(&file, watch, |generated| {
+                let output = output_target(output.as_deref(), to_file);
+                typescript::generate_types(&file, output, &options, generated)
+            })?
+        }
+        Command::Docs {
+            file,
+            output,
+            format,
+            migrations,
+            external_tag,
+        } => docs::generate_docs(
+            &file,
+            &output,
+            &DocsOptions::new(format, migrations, external_tag),
+        )?,
+        Command::Dump { file, format } => dump::dump_schema(&file, format)?,
+        Command::Graph {
+            file,
+            format,
+            migration,
+        } => graph::print_graph(&file, format, migration)?,
+        Command::MigrateData {
+            migration,
+            input,
+            output,
+            type_name,
+            downgrade,
+            external_tag,
+        } => migrations::migrate_data(
+            &migration,
+            &input,
+            &output,
+            &DataMigrationOptions::new(type_name, downgrade, external_tag),
+        )?,
+        Command::Sample {
+            file,
+            type_name,
+            count,
+            seed,
+            external_tag,
+        } => sample::generate_sample(
+            &file,
+            &type_name,
+            &SampleOptions::new(count, seed, external_tag),
+        )?,
+        Command::Plugin {
+            plugin,
+            file,
+            output,
+            migration,
+            naming,
+            parameters,
+        } => plugin::run_plugin(
+            &plugin,
+            &file,
+            &output,
+            &PluginOptions::new(migration, naming, parameters),
+        )?,
         Command::Completions { shell } => print_completions(shell)?,
     }
 
     Ok(())
 }
 
+/// Determines where to write generated code from the CLI arguments.
+///
+/// A missing output path means --stdout was passed, as enforced by clap.
+fn output_target(output: Option<&Path>, to_file: bool) -> Output<'_> {
+    match output {
+        None => Output::Stdout,
+        Some(output) if to_file => Output::File(output),
+        Some(output) => Output::Directory(output),
+    }
+}
+
+/// Parses the mapping of an extern type, written as `NAME=TYPE`.
+fn parse_extern(argument: &str) -> Result<(String, String), String> {
+    match argument.split_once('=') {
+        Some((name, r#type)) if !name.is_empty() && !r#type.is_empty() => {
+            Ok((name.to_owned(), r#type.to_owned()))
+        }
+        _ => Err("expected a mapping of the form NAME=TYPE".to_owned()),
+    }
+}
+
+/// Exits with a usage error if a path refers to standard input.
+fn reject_stdin(path: &Path, reason: &str) {
+    if is_stdin(path) {
+        Args::command()
+            .error(ErrorKind::ArgumentConflict, reason)
+            .exit();
+    }
+}
+
+/// Runs a command generating files from a schema file, rerunning it on changes if `watch` is set.
+fn run_generator(
+    path: &Path,
+    watch: bool,
+    mut generate: impl FnMut(&mut GeneratedFiles) -> Result<(), Error>,
+) -> Result<(), Error> {
+    if watch {
+        reject_stdin(
+            path,
+            "--watch can't be used when reading from standard input",
+        );
+
+        watch::watch(path, generate, |error| {
+            handle_result(Err(error));
+        })
+    } else {
+        generate(&mut GeneratedFiles::default())
+    }
+}
+
 /// Implements `versed version`.
 fn print_version(path: &Path) -> Result<(), Error> {
     let TypeSet { version, .. } = load_file(path)?;
@@ -18,8 +18,11 @@ pub trait Metadata: Clone {
     type Struct: Debug + Clone;
     type Enum: Debug + Clone;
     type List: Debug + Clone;
+    type Tuple: Debug + Clone;
     type Primitive: Debug + Clone;
     type Identifier: Debug + Clone;
+    type Parameter: Debug + Clone;
+    type Extern: Debug + Clone;
 
     type Field: Debug + Clone;
     type Variant: Debug + Clone;
@@ -33,8 +36,11 @@ impl Metadata for () {
     type Struct = ();
     type Enum = ();
     type List = ();
+    type Tuple = ();
     type Primitive = ();
     type Identifier = ();
+    type Parameter = ();
+    type Extern = ();
 
     type Field = ();
     type Variant = ();
@@ -49,8 +55,11 @@ pub trait MapMetadata<A: Metadata, B: Metadata, R: Metadata> {
     fn map_struct(&self, left: A::Struct, right: B::Struct) -> R::Struct;
     fn map_enum(&self, left: A::Enum, right: B::Enum) -> R::Enum;
     fn map_list(&self, left: A::List, right: B::List) -> R::List;
+    fn map_tuple(&self, left: A::Tuple, right: B::Tuple) -> R::Tuple;
     fn map_primitive(&self, left: A::Primitive, right: B::Primitive) -> R::Primitive;
     fn map_identifier(&self, left: A::Identifier, right: B::Identifier) -> R::Identifier;
+    fn map_parameter(&self, left: A::Parameter, right: B::Parameter) -> R::Parameter;
+    fn map_extern(&self, left: A::Extern, right: B::Extern) -> R::Extern;
 
     fn map_field(&self, left: A::Field, right: B::Field) -> R::Field;
     fn map_variant(&self, left: A::Variant, right: B::Variant) -> R::Variant;
@@ -65,8 +74,11 @@ pub trait GetMetadata<A: Metadata, R: Metadata> {
     fn get_struct<'a>(&self, metadata: &'a A::Struct) -> &'a R::Struct;
     fn get_enum<'a>(&self, metadata: &'a A::Enum) -> &'a R::Enum;
     fn get_list<'a>(&self, metadata: &'a A::List) -> &'a R::List;
+    fn get_tuple<'a>(&self, metadata: &'a A::Tuple) -> &'a R::Tuple;
     fn get_primitive<'a>(&self, metadata: &'a A::Primitive) -> &'a R::Primitive;
     fn get_identifier<'a>(&self, metadata: &'a A::Identifier) -> &'a R::Identifier;
+    fn get_parameter<'a>(&self, metadata: &'a A::Parameter) -> &'a R::Parameter;
+    fn get_extern<'a>(&self, metadata: &'a A::Extern) -> &'a R::Extern;
 
     fn get_field<'a>(&self, metadata: &'a A::Field) -> &'a R::Field;
     fn get_variant<'a>(&self, metadata: &'a A::Variant) -> &'a R::Variant;
@@ -104,6 +116,10 @@ impl<A: Metadata> GetMetadata<A, A> for GetIdentity {
         metadata
     }
 
+    fn get_tuple<'a>(&self, metadata: &'a <A as Metadata>::Tuple) -> &'a <A as Metadata>::Tuple {
+        metadata
+    }
+
     fn get_primitive<'a>(
         &self,
         metadata: &'a <A as Metadata>::Primitive,
@@ -118,6 +134,17 @@ impl<A: Metadata> GetMetadata<A, A> for GetIdentity {
         metadata
     }
 
+    fn get_parameter<'a>(
+        &self,
+        metadata: &'a <A as Metadata>::Parameter,
+    ) -> &'a <A as Metadata>::Parameter {
+        metadata
+    }
+
+    fn get_extern<'a>(&self, metadata: &'a <A as Metadata>::Extern) -> &'a <A as Metadata>::Extern {
+        metadata
+    }
+
     fn get_field<'a>(&self, metadata: &'a <A as Metadata>::Field) -> &'a <A as Metadata>::Field {
         metadata
     }
@@ -152,8 +179,11 @@ macro_rules! mapper {
             mapper_func!(map_struct, Struct, $crate::metadata::Metadata);
             mapper_func!(map_enum, Enum, $crate::metadata::Metadata);
             mapper_func!(map_list, List, $crate::metadata::Metadata);
+            mapper_func!(map_tuple, Tuple, $crate::metadata::Metadata);
             mapper_func!(map_primitive, Primitive, $crate::metadata::Metadata);
             mapper_func!(map_identifier, Identifier, $crate::metadata::Metadata);
+            mapper_func!(map_parameter, Parameter, $crate::metadata::Metadata);
+            mapper_func!(map_extern, Extern, $crate::metadata::Metadata);
 
             mapper_func!(map_field, Field, $crate::metadata::Metadata);
             mapper_func!(map_variant, Variant, $crate::metadata::Metadata);
@@ -183,8 +213,11 @@ macro_rules! getter {
             getter_func!(get_struct, Struct, $crate::metadata::Metadata);
             getter_func!(get_enum, Enum, $crate::metadata::Metadata);
             getter_func!(get_list, List, $crate::metadata::Metadata);
+            getter_func!(get_tuple, Tuple, $crate::metadata::Metadata);
             getter_func!(get_primitive, Primitive, $crate::metadata::Metadata);
             getter_func!(get_identifier, Identifier, $crate::metadata::Metadata);
+            getter_func!(get_parameter, Parameter, $crate::metadata::Metadata);
+            getter_func!(get_extern, Extern, $crate::metadata::Metadata);
 
             getter_func!(get_field, Field, $crate::metadata::Metadata);
             getter_func!(get_variant, Variant, $crate::metadata::Metadata);
@@ -212,8 +245,11 @@ macro_rules! composite {
             type Struct = $element<$(<$type as $crate::metadata::Metadata>::Struct),*>;
             type Enum = $element<$(<$type as $crate::metadata::Metadata>::Enum),*>;
             type List = $element<$(<$type as $crate::metadata::Metadata>::List),*>;
+            type Tuple = $element<$(<$type as $crate::metadata::Metadata>::Tuple),*>;
             type Primitive = $element<$(<$type as $crate::metadata::Metadata>::Primitive),*>;
             type Identifier = $element<$(<$type as $crate::metadata::Metadata>::Identifier),*>;
+            type Parameter = $element<$(<$type as $crate::metadata::Metadata>::Parameter),*>;
+            type Extern = $element<$(<$type as $crate::metadata::Metadata>::Extern),*>;
 
             type Field = $element<$(<$type as $crate::metadata::Metadata>::Field),*>;
             type Variant = $element<$(<$type as $crate::metadata::Metadata>::Variant),*>;
@@ -72,8 +72,19 @@ fn annotate_type(r#type: &Type<BasicMetadata>, context: &mut AnnotationContext)
             }
         }
         TypeType::List(list) => annotate_type(&list.r#type, context),
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                annotate_type(element, context);
+            }
+        }
         TypeType::Primitive(_) => {}
-        TypeType::Identifier(_) => {}
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                annotate_type(argument, context);
+            }
+        }
+        TypeType::Parameter(_) => {}
+        TypeType::Extern(_) => {}
     }
 }
 
@@ -95,8 +106,19 @@ fn collect_used_numbers(r#type: &Type<BasicMetadata>, numbers: &mut HashSet<u64>
             }
         }
         TypeType::List(list) => collect_used_numbers(&list.r#type, numbers),
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                collect_used_numbers(element, numbers);
+            }
+        }
         TypeType::Primitive(_) => {}
-        TypeType::Identifier(_) => {}
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                collect_used_numbers(argument, numbers);
+            }
+        }
+        TypeType::Parameter(_) => {}
+        TypeType::Extern(_) => {}
     }
 }
 
@@ -133,8 +155,19 @@ fn strip_annotations_in_type(r#type: &Type<BasicMetadata>, edits: &mut Vec<Remov
             }
         }
         TypeType::List(list) => strip_annotations_in_type(&list.r#type, edits),
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                strip_annotations_in_type(element, edits);
+            }
+        }
         TypeType::Primitive(_) => {}
-        TypeType::Identifier(_) => {}
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                strip_annotations_in_type(argument, edits);
+            }
+        }
+        TypeType::Parameter(_) => {}
+        TypeType::Extern(_) => {}
     }
 }
 
@@ -0,0 +1,171 @@
+//! The catalogue of conversions between types which aren't linked by a migration marker.
+//!
+//! It's shared by the Rust backend, which fills in migration functions with them,
+//! and `versed migrate-data`, which applies them to JSON documents.
+//! Only conversions that are always correct are part of the catalogue,
+//! everything else is left to the user.
+
+use crate::{
+    ast::{Field, PrimitiveType, Struct, Type, TypeSet, TypeType},
+    metadata::{GetMetadata, Metadata},
+    preprocessing::ResolutionMetadata,
+};
+
+/// A conversion between two types which aren't linked by a migration marker.
+#[derive(Debug, Clone, Copy)]
+pub enum Conversion<'a, M: Metadata> {
+    /// `int` to `string`.
+    IntToString,
+    /// `T` to `[T]`, as a list with one element.
+    IntoList,
+    /// `T` to `struct { field: T }`.
+    WrapInStruct(&'a Field<M>),
+    /// `struct { field: T }` to `T`, the counterpart of [`Conversion::WrapInStruct`].
+    UnwrapStruct(&'a Field<M>),
+    /// `unit` to a struct whose fields all have default values,
+    /// like the payload of an enum variant that only gained fields.
+    UnitToStruct(&'a Struct<M>),
+    /// A struct whose fields all have default values to `unit`,
+    /// the counterpart of [`Conversion::UnitToStruct`].
+    StructToUnit,
+}
+
+impl<M: Metadata> Conversion<'_, M> {
+    /// Gets a short description of the conversion.
+    pub fn description(&self) -> &'static str {
+        match self {
+            Conversion::IntToString => "int to string",
+            Conversion::IntoList => "value to a list with one element",
+            Conversion::WrapInStruct(_) => "value to a struct with one field",
+            Conversion::UnwrapStruct(_) => "struct with one field to its field",
+            Conversion::UnitToStruct(_) => "unit to a struct of default values",
+            Conversion::StructToUnit => "struct of default values to unit",
+        }
+    }
+}
+
+/// The two versions of a migration, which the types passed to [`find_conversion`] belong to.
+#[derive(Debug)]
+pub struct Versions<'a, M: Metadata, GM> {
+    pub old: &'a TypeSet<M>,
+    pub new: &'a TypeSet<M>,
+    /// Gets the resolution of identifiers from their metadata.
+    pub get: GM,
+}
+
+impl<M: Metadata, GM: Copy> Clone for Versions<'_, M, GM> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: Metadata, GM: Copy> Copy for Versions<'_, M, GM> {}
+
+/// Finds a conversion from `old` to `new` in the catalogue.
+pub fn find_conversion<'a, M, GM>(
+    versions: Versions<M, GM>,
+    old: &'a Type<M>,
+    new: &'a Type<M>,
+) -> Option<Conversion<'a, M>>
+where
+    M: Metadata,
+    GM: GetMetadata<M, ResolutionMetadata> + Copy,
+{
+    match (&old.r#type, &new.r#type) {
+        (TypeType::Primitive(old), TypeType::Primitive(new))
+            if old.r#type == PrimitiveType::Number && new.r#type == PrimitiveType::String =>
+        {
+            Some(Conversion::IntToString)
+        }
+        (_, TypeType::List(list)) if is_direct(versions, old, &list.r#type) => {
+            Some(Conversion::IntoList)
+        }
+        (_, TypeType::Struct(r#struct))
+            if r#struct.fields.len() == 1
+                && is_direct(versions, old, &r#struct.fields[0].r#type) =>
+        {
+            Some(Conversion::WrapInStruct(&r#struct.fields[0]))
+        }
+        (TypeType::Struct(r#struct), _)
+            if r#struct.fields.len() == 1
+                && is_direct(versions, &r#struct.fields[0].r#type, new) =>
+        {
+            Some(Conversion::UnwrapStruct(&r#struct.fields[0]))
+        }
+        (TypeType::Primitive(old), TypeType::Struct(r#struct))
+            if old.r#type == PrimitiveType::Unit && has_defaults(r#struct) =>
+        {
+            Some(Conversion::UnitToStruct(r#struct))
+        }
+        (TypeType::Struct(r#struct), TypeType::Primitive(new))
+            if new.r#type == PrimitiveType::Unit && has_defaults(r#struct) =>
+        {
+            Some(Conversion::StructToUnit)
+        }
+        _ => None,
+    }
+}
+
+/// Checks whether all fields of a struct have default values.
+fn has_defaults<M: Metadata>(r#struct: &Struct<M>) -> bool {
+    r#struct.fields.iter().all(|field| field.default.is_some())
+}
+
+/// Checks whether values of `old` and `new` are the same, apart from migrating named types.
+///
+/// That's the case for equal primitive types and for references to linked named types.
+pub fn is_direct<M, GM>(versions: Versions<M, GM>, old: &Type<M>, new: &Type<M>) -> bool
+where
+    M: Metadata,
+    GM: GetMetadata<M, ResolutionMetadata> + Copy,
+{
+    match (&old.r#type, &new.r#type) {
+        (TypeType::Primitive(old), TypeType::Primitive(new)) => old.r#type == new.r#type,
+        (TypeType::Identifier(old), TypeType::Identifier(new))
+            if old.arguments.is_empty() && new.arguments.is_empty() =>
+        {
+            let old_ref = &versions.old.types[*versions.get.get_identifier(&old.metadata)];
+            let new_ref = &versions.new.types[*versions.get.get_identifier(&new.metadata)];
+
+            is_linked(&old_ref.r#type, &new_ref.r#type)
+        }
+        _ => false,
+    }
+}
+
+/// Checks whether two types are linked by a migration marker.
+pub fn is_linked<M: Metadata>(old: &Type<M>, new: &Type<M>) -> bool {
+    old.number.zip(new.number).is_some_and(|(o, n)| o == n)
+}
+
+/// Describes a type for use in a message, like `a list` or `a reference to 'User'`.
+///
+/// `types` is the version the type belongs to.
+pub fn describe<M, GM>(types: &TypeSet<M>, r#type: &Type<M>, get: GM) -> String
+where
+    M: Metadata,
+    GM: GetMetadata<M, ResolutionMetadata>,
+{
+    match &r#type.r#type {
+        TypeType::Struct(_) => "a struct".into(),
+        TypeType::Enum(_) => "an enum".into(),
+        TypeType::List(_) => "a list".into(),
+        TypeType::Tuple(_) => "a tuple".into(),
+        TypeType::Primitive(primitive) => match primitive.r#type {
+            PrimitiveType::String => "a string".into(),
+            PrimitiveType::Number => "an int".into(),
+            PrimitiveType::Unit => "unit".into(),
+            PrimitiveType::Timestamp => "a timestamp".into(),
+            PrimitiveType::Date => "a date".into(),
+            PrimitiveType::Duration => "a duration".into(),
+            PrimitiveType::Uuid => "a UUID".into(),
+            PrimitiveType::Bytes => "bytes".into(),
+        },
+        TypeType::Identifier(identifier) => {
+            let named = &types.types[*get.get_identifier(&identifier.metadata)];
+            format!("a reference to '{}'", named.name)
+        }
+        TypeType::Parameter(parameter) => format!("the type parameter '{}'", parameter.name),
+        TypeType::Extern(r#extern) => format!("the extern type '{}'", r#extern.name),
+    }
+}
@@ -0,0 +1,792 @@
+//! Migrates JSON documents by interpreting a migration, without generating any code.
+//!
+//! Only structural changes are supported, the same ones the Rust backend
+//! can generate complete migration functions for.
+//! Wherever it would leave a [`todo!`], the migration fails with the path to the offending value.
+//! Values of refined primitive types also have to satisfy the refinements of both versions.
+
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    fs,
+    path::Path,
+};
+
+use serde_json::{Map, Value as Json};
+
+use crate::{
+    ast::{Field, Migration, Primitive, PrimitiveType, Scope, Type, TypeSet, TypeType, Value},
+    codegen::naming_pass::{NameMetadata, name},
+    composite,
+    error::{Error, ResultExt},
+    formats::{self, IntEncoding},
+    getter,
+    loading::load_migration,
+    mapper,
+    migrations::conversions::{Conversion, Versions, describe, find_conversion, is_linked},
+    preprocessing::{BasicMetadata, ResolutionMetadata},
+    typescript::TypeScriptNamingRules,
+};
+
+/// Configuration of the data migration.
+#[derive(Debug, Clone)]
+pub struct DataMigrationOptions {
+    type_name: String,
+    downgrade: bool,
+    external_tag: bool,
+}
+
+impl DataMigrationOptions {
+    /// Creates new [`DataMigrationOptions`].
+    ///
+    /// `type_name` is the name of the type of the document in the version it's migrated from.
+    /// If `downgrade` is set, the document is migrated from the new version to the old one.
+    /// If `external_tag` is set, enums are represented like with `--serde-external-tag`.
+    pub fn new(type_name: String, downgrade: bool, external_tag: bool) -> Self {
+        Self {
+            type_name,
+            downgrade,
+            external_tag,
+        }
+    }
+}
+
+/// The context for the data migration, holding the version being migrated from and to.
+#[derive(Debug, Clone, Copy)]
+struct Context<'a> {
+    old: &'a TypeSet<DataMetadata>,
+    new: &'a TypeSet<DataMetadata>,
+    external_tag: bool,
+}
+
+/// The type arguments in scope in the version being migrated from and to, see [`Scope`].
+#[derive(Debug, Clone, Copy)]
+struct Scopes<'a> {
+    old: &'a Scope<'a, DataMetadata>,
+    new: &'a Scope<'a, DataMetadata>,
+}
+
+impl Scopes<'_> {
+    /// The scopes of two top-level types.
+    const ROOT: Scopes<'static> = Scopes {
+        old: &Scope::ROOT,
+        new: &Scope::ROOT,
+    };
+}
+
+/// A path to a value inside a JSON document, in the `$.field[0]` notation.
+#[derive(Debug, Clone, Copy)]
+enum JsonPath<'a> {
+    Root,
+    Key(&'a JsonPath<'a>, &'a str),
+    Index(&'a JsonPath<'a>, usize),
+}
+
+impl Display for JsonPath<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            JsonPath::Root => write!(f, "$"),
+            JsonPath::Key(parent, key) => {
+                let simple = key.starts_with(|ch: char| ch.is_ascii_alphabetic() || ch == '_')
+                    && key
+                        .chars()
+                        .all(|ch| ch.is_ascii_alphanumeric() || ch == '_');
+
+                if simple {
+                    write!(f, "{parent}.{key}")
+                } else {
+                    write!(f, "{parent}[{key:?}]")
+                }
+            }
+            JsonPath::Index(parent, index) => write!(f, "{parent}[{index}]"),
+        }
+    }
+}
+
+/// The result of migrating a value.
+type MigrationResult = Result<Json, Error>;
+
+/// Creates an [`Error::DataMigration`].
+fn error(path: &JsonPath, reason: impl Into<String>) -> Error {
+    Error::DataMigration {
+        path: path.to_string(),
+        reason: reason.into(),
+    }
+}
+
+/// Implements `versed migrate-data`.
+pub fn migrate_data(
+    migration_path: &Path,
+    input: &Path,
+    output: &Path,
+    options: &DataMigrationOptions,
+) -> Result<(), Error> {
+    let migration = load_migration(migration_path)?;
+    let Migration { old, new } = migration;
+    let old = name(old, TypeScriptNamingRules, AddName);
+    let new = name(new, TypeScriptNamingRules, AddName);
+
+    let (old, new) = if options.downgrade {
+        (&new, &old)
+    } else {
+        (&old, &new)
+    };
+
+    let context = Context {
+        old,
+        new,
+        external_tag: options.external_tag,
+    };
+
+    let Some(old_index) = old.index_of(&options.type_name) else {
+        return Err(Error::UnknownType {
+            name: options.type_name.clone(),
+        });
+    };
+
+    if !old.types[old_index].parameters.is_empty() {
+        return Err(Error::GenericType {
+            name: options.type_name.clone(),
+        });
+    }
+
+    let old_type = &old.types[old_index].r#type;
+    let Some(new_type) = new
+        .types
+        .iter()
+        .map(|named| &named.r#type)
+        .find(|new_type| is_linked(old_type, new_type))
+    else {
+        return Err(error(
+            &JsonPath::Root,
+            format!(
+                "the type '{}' has no counterpart in version {}",
+                options.type_name, new.version
+            ),
+        ));
+    };
+
+    let src = fs::read_to_string(input).with_path(input)?;
+    let document: Json = serde_json::from_str(&src).map_err(|error| Error::Json {
+        error,
+        path: input.to_path_buf(),
+    })?;
+
+    let result = migrate(
+        context,
+        Scopes::ROOT,
+        old_type,
+        new_type,
+        &document,
+        &JsonPath::Root,
+    )?;
+
+    let mut json = serde_json::to_string_pretty(&result).expect("JSON values to be serializable");
+    json.push('\n');
+    fs::write(output, json).with_path(output)?;
+
+    Ok(())
+}
+
+/// Migrates a value from `old` to `new`, two types linked by a migration marker.
+fn migrate(
+    context: Context,
+    scopes: Scopes,
+    old: &Type<DataMetadata>,
+    new: &Type<DataMetadata>,
+    value: &Json,
+    path: &JsonPath,
+) -> MigrationResult {
+    match (&old.r#type, &new.r#type) {
+        (TypeType::Struct(old_struct), TypeType::Struct(new_struct)) => {
+            let object = expect_object(value, path)?;
+
+            let by_type_number: HashMap<u64, &Field<DataMetadata>> = old_struct
+                .fields
+                .iter()
+                .filter_map(|field| field.r#type.number.map(|number| (number, field)))
+                .collect();
+
+            let mut result = Map::new();
+
+            for field in &new_struct.fields {
+                let old_field = field.r#type.number.and_then(|n| by_type_number.get(&n));
+
+                let field_value = if let Some(old_field) = old_field
+                    && old_field.spread
+                {
+                    // Spread fields are flattened into the object itself
+                    migrate(
+                        context,
+                        scopes,
+                        &old_field.r#type,
+                        &field.r#type,
+                        value,
+                        path,
+                    )?
+                } else if let Some(old_field) = old_field {
+                    let key = &old_field.metadata.name;
+                    let field_path = JsonPath::Key(path, key);
+                    let field_value = match (object.get(key), &old_field.default) {
+                        (Some(field_value), _) => field_value,
+                        (None, Some(default)) => &encode_default(context.old.int_encoding, default),
+                        (None, None) => return Err(error(&field_path, "the field is missing")),
+                    };
+
+                    migrate(
+                        context,
+                        scopes,
+                        &old_field.r#type,
+                        &field.r#type,
+                        field_value,
+                        &field_path,
+                    )?
+                } else if let Some(default) = &field.default {
+                    encode_default(context.new.int_encoding, default)
+                } else {
+                    return Err(error(
+                        &JsonPath::Key(path, &field.metadata.name),
+                        format!(
+                            "the field of version {} has no counterpart",
+                            context.new.version
+                        ),
+                    ));
+                };
+
+                match field_value {
+                    Json::Object(fields) if field.spread => result.extend(fields),
+                    field_value => {
+                        result.insert(field.metadata.name.clone(), field_value);
+                    }
+                }
+            }
+
+            Ok(Json::Object(result))
+        }
+        (TypeType::Enum(old_enum), TypeType::Enum(new_enum)) => {
+            let (tag, content, content_key) = read_variant(context, value, path)?;
+            let content_path = JsonPath::Key(path, content_key);
+
+            let Some(old_variant) = old_enum
+                .variants
+                .iter()
+                .find(|variant| variant.metadata.name == tag)
+            else {
+                return match (old_enum.open, new_enum.open) {
+                    // Unknown variants of open enums are passed through as they are
+                    (true, true) => Ok(value.clone()),
+                    (true, false) => Err(error(
+                        path,
+                        format!(
+                            "the unknown variant '{tag}' can't be passed through, \
+                             as the enum isn't open in version {}",
+                            context.new.version
+                        ),
+                    )),
+                    (false, _) => Err(error(path, format!("unknown variant '{tag}'"))),
+                };
+            };
+
+            let Some(new_variant) = new_enum
+                .variants
+                .iter()
+                .find(|variant| is_linked(&old_variant.r#type, &variant.r#type))
+            else {
+                return Err(error(
+                    path,
+                    format!(
+                        "the variant '{tag}' has no counterpart in version {}",
+                        context.new.version
+                    ),
+                ));
+            };
+
+            let content = migrate(
+                context,
+                scopes,
+                &old_variant.r#type,
+                &new_variant.r#type,
+                content,
+                &content_path,
+            )?;
+
+            Ok(write_variant(context, &new_variant.metadata.name, content))
+        }
+        (TypeType::List(old_list), TypeType::List(new_list)) => {
+            let Json::Array(elements) = value else {
+                return Err(error(path, "expected an array"));
+            };
+
+            if !is_linked(&old_list.r#type, &new_list.r#type) {
+                return Err(error(
+                    path,
+                    "the elements of the list aren't linked by a migration marker",
+                ));
+            }
+
+            elements
+                .iter()
+                .enumerate()
+                .map(|(index, element)| {
+                    migrate(
+                        context,
+                        scopes,
+                        &old_list.r#type,
+                        &new_list.r#type,
+                        element,
+                        &JsonPath::Index(path, index),
+                    )
+                })
+                .collect::<Result<_, _>>()
+                .map(Json::Array)
+        }
+        (TypeType::Tuple(old_tuple), TypeType::Tuple(new_tuple)) => {
+            let Json::Array(elements) = value else {
+                return Err(error(path, "expected an array"));
+            };
+
+            if elements.len() != old_tuple.elements.len() {
+                return Err(error(
+                    path,
+                    format!("expected an array of length {}", old_tuple.elements.len()),
+                ));
+            }
+
+            let mut result = Vec::with_capacity(new_tuple.elements.len());
+
+            for (index, element) in new_tuple.elements.iter().enumerate() {
+                let old_index = old_tuple
+                    .elements
+                    .iter()
+                    .position(|old_element| is_linked(old_element, element));
+
+                let element_value = if let Some(old_index) = old_index {
+                    migrate(
+                        context,
+                        scopes,
+                        &old_tuple.elements[old_index],
+                        element,
+                        &elements[old_index],
+                        &JsonPath::Index(path, old_index),
+                    )?
+                } else {
+                    return Err(error(
+                        path,
+                        format!(
+                            "the element {index} of the tuple in version {} has no counterpart",
+                            context.new.version
+                        ),
+                    ));
+                };
+
+                result.push(element_value);
+            }
+
+            Ok(Json::Array(result))
+        }
+        (TypeType::Primitive(old_primitive), TypeType::Primitive(new_primitive))
+            if old_primitive.r#type == new_primitive.r#type =>
+        {
+            migrate_primitive(context, old_primitive, new_primitive, value, path)
+        }
+        (TypeType::Identifier(_), TypeType::Identifier(_)) => {
+            migrate_identifier(context, scopes, old, new, value, path)
+        }
+        // Values of extern types are opaque, so they can only be kept as they are
+        (TypeType::Extern(old_extern), TypeType::Extern(new_extern))
+            if old_extern.name == new_extern.name =>
+        {
+            Ok(value.clone())
+        }
+        (TypeType::Parameter(old_parameter), TypeType::Parameter(new_parameter))
+            if old_parameter.metadata.resolution == new_parameter.metadata.resolution =>
+        {
+            let index = old_parameter.metadata.resolution;
+            let (Some((old, old_scope)), Some((new, new_scope))) =
+                (scopes.old.argument(index), scopes.new.argument(index))
+            else {
+                return Err(error(
+                    path,
+                    format!(
+                        "the type parameter '{}' has no type argument",
+                        old_parameter.name
+                    ),
+                ));
+            };
+
+            let scopes = Scopes {
+                old: old_scope,
+                new: new_scope,
+            };
+            migrate(context, scopes, old, new, value, path)
+        }
+        _ => convert(context, scopes, old, new, value, path),
+    }
+}
+
+/// Migrates a value between two identifiers, whose named types have to be linked.
+fn migrate_identifier(
+    context: Context,
+    scopes: Scopes,
+    old: &Type<DataMetadata>,
+    new: &Type<DataMetadata>,
+    value: &Json,
+    path: &JsonPath,
+) -> MigrationResult {
+    let (TypeType::Identifier(old), TypeType::Identifier(new)) = (&old.r#type, &new.r#type) else {
+        unreachable!("both types to be identifiers");
+    };
+
+    let old_ref = &context.old.types[old.metadata.resolution];
+    let new_ref = &context.new.types[new.metadata.resolution];
+
+    if old.arguments.len() != new.arguments.len() {
+        Err(error(
+            path,
+            format!(
+                "the types '{}' and '{}' have a different number of type arguments",
+                old_ref.name, new_ref.name
+            ),
+        ))
+    } else if is_linked(&old_ref.r#type, &new_ref.r#type) {
+        let old_scope = scopes.old.enter(&old.arguments);
+        let new_scope = scopes.new.enter(&new.arguments);
+        let scopes = Scopes {
+            old: &old_scope,
+            new: &new_scope,
+        };
+
+        migrate(
+            context,
+            scopes,
+            &old_ref.r#type,
+            &new_ref.r#type,
+            value,
+            path,
+        )
+    } else {
+        Err(error(
+            path,
+            format!(
+                "the types '{}' and '{}' aren't linked by a migration marker",
+                old_ref.name, new_ref.name
+            ),
+        ))
+    }
+}
+
+/// Converts a value between two types which aren't linked by a migration marker,
+/// using the same catalogue of conversions as the Rust backend.
+fn convert(
+    context: Context,
+    scopes: Scopes,
+    old: &Type<DataMetadata>,
+    new: &Type<DataMetadata>,
+    value: &Json,
+    path: &JsonPath,
+) -> MigrationResult {
+    let versions = Versions {
+        old: context.old,
+        new: context.new,
+        get: GetResolution,
+    };
+
+    match find_conversion(versions, old, new) {
+        Some(Conversion::IntToString) => {
+            let (TypeType::Primitive(old), TypeType::Primitive(new)) = (&old.r#type, &new.r#type)
+            else {
+                unreachable!("conversion to be found for primitive types");
+            };
+
+            check_primitive(context, old, value, path)?;
+            let int = read_int(context.old.int_encoding, value).expect("the value to be checked");
+            let value = Json::String(int.to_string());
+            check_new_refinements(context, new, &value, path)?;
+            Ok(value)
+        }
+        Some(Conversion::IntoList) => {
+            let TypeType::List(list) = &new.r#type else {
+                unreachable!("conversion to be found for a list");
+            };
+
+            Ok(Json::Array(vec![migrate_direct(
+                context,
+                scopes,
+                old,
+                &list.r#type,
+                value,
+                path,
+            )?]))
+        }
+        // Spread fields are flattened into the object, so their value is the object itself
+        Some(Conversion::WrapInStruct(field)) if field.spread => {
+            migrate_direct(context, scopes, old, &field.r#type, value, path)
+        }
+        Some(Conversion::WrapInStruct(field)) => {
+            let value = migrate_direct(context, scopes, old, &field.r#type, value, path)?;
+            Ok(Json::Object(Map::from_iter([(
+                field.metadata.name.clone(),
+                value,
+            )])))
+        }
+        Some(Conversion::UnwrapStruct(field)) if field.spread => {
+            migrate_direct(context, scopes, &field.r#type, new, value, path)
+        }
+        Some(Conversion::UnwrapStruct(field)) => {
+            let object = expect_object(value, path)?;
+            let field_path = JsonPath::Key(path, &field.metadata.name);
+            let Some(value) = object.get(&field.metadata.name) else {
+                return Err(error(&field_path, "the field is missing"));
+            };
+
+            migrate_direct(context, scopes, &field.r#type, new, value, &field_path)
+        }
+        Some(Conversion::UnitToStruct(r#struct)) => {
+            let TypeType::Primitive(old) = &old.r#type else {
+                unreachable!("conversion to be found for unit");
+            };
+
+            check_primitive(context, old, value, path)?;
+            Ok(Json::Object(
+                r#struct
+                    .fields
+                    .iter()
+                    .map(|field| {
+                        let default = field
+                            .default
+                            .as_ref()
+                            .expect("fields to have default values");
+                        (
+                            field.metadata.name.clone(),
+                            encode_default(context.new.int_encoding, default),
+                        )
+                    })
+                    .collect(),
+            ))
+        }
+        Some(Conversion::StructToUnit) => {
+            expect_object(value, path)?;
+            Ok(Json::Null)
+        }
+        None => Err(error(
+            path,
+            format!(
+                "{} can't be converted to {}",
+                describe(context.old, old, GetResolution),
+                describe(context.new, new, GetResolution)
+            ),
+        )),
+    }
+}
+
+/// Migrates a value from `old` to `new`, assuming [`is_direct`](super::conversions::is_direct) holds.
+fn migrate_direct(
+    context: Context,
+    scopes: Scopes,
+    old: &Type<DataMetadata>,
+    new: &Type<DataMetadata>,
+    value: &Json,
+    path: &JsonPath,
+) -> MigrationResult {
+    match (&old.r#type, &new.r#type) {
+        (TypeType::Identifier(_), TypeType::Identifier(_)) => {
+            migrate_identifier(context, scopes, old, new, value, path)
+        }
+        (TypeType::Primitive(old_primitive), TypeType::Primitive(new_primitive)) => {
+            migrate_primitive(context, old_primitive, new_primitive, value, path)
+        }
+        _ => unreachable!("types to be direct"),
+    }
+}
+
+/// Reads the tag and content of an enum value, along with the key of the content.
+fn read_variant<'a>(
+    context: Context,
+    value: &'a Json,
+    path: &JsonPath,
+) -> Result<(&'a str, &'a Json, &'a str), Error> {
+    let object = expect_object(value, path)?;
+
+    if context.external_tag {
+        let mut entries = object.iter();
+
+        match (entries.next(), entries.next()) {
+            (Some((tag, content)), None) => Ok((tag, content, tag)),
+            _ => Err(error(path, "expected an object with exactly one key")),
+        }
+    } else {
+        let Some(Json::String(tag)) = object.get("type") else {
+            return Err(error(path, "expected a string under the key 'type'"));
+        };
+        let content = object.get("value").unwrap_or(&Json::Null);
+
+        Ok((tag, content, "value"))
+    }
+}
+
+/// Writes an enum value.
+fn write_variant(context: Context, tag: &str, content: Json) -> Json {
+    if context.external_tag {
+        Json::Object(Map::from_iter([(tag.to_owned(), content)]))
+    } else {
+        Json::Object(Map::from_iter([
+            ("type".to_owned(), Json::String(tag.to_owned())),
+            ("value".to_owned(), content),
+        ]))
+    }
+}
+
+/// Checks that a value is an object.
+fn expect_object<'a>(value: &'a Json, path: &JsonPath) -> Result<&'a Map<String, Json>, Error> {
+    match value {
+        Json::Object(object) => Ok(object),
+        _ => Err(error(path, "expected an object")),
+    }
+}
+
+/// Checks that a value matches a primitive type, including its refinements.
+fn check_primitive(
+    context: Context,
+    primitive: &Primitive<DataMetadata>,
+    value: &Json,
+    path: &JsonPath,
+) -> Result<(), Error> {
+    let (matches, expected) = match primitive.r#type {
+        PrimitiveType::String => (value.is_string(), "expected a string".into()),
+        PrimitiveType::Number => (
+            read_int(context.old.int_encoding, value).is_some(),
+            match context.old.int_encoding {
+                IntEncoding::Number => "expected an integer".into(),
+                IntEncoding::String => "expected an integer encoded as a string".into(),
+            },
+        ),
+        PrimitiveType::Unit => (value.is_null(), "expected null".into()),
+        r#type => (
+            value
+                .as_str()
+                .is_some_and(|value| formats::is_valid(r#type, value)),
+            format!("expected {}", formats::expected(r#type)),
+        ),
+    };
+
+    if !matches {
+        return Err(error(path, expected));
+    }
+
+    check_refinements(context.old.int_encoding, primitive, value)
+        .map_err(|expected| error(path, expected))
+}
+
+/// Migrates a value of a primitive type to the same primitive type,
+/// which only changes the encoding of ints if the versions disagree on it.
+fn migrate_primitive(
+    context: Context,
+    old: &Primitive<DataMetadata>,
+    new: &Primitive<DataMetadata>,
+    value: &Json,
+    path: &JsonPath,
+) -> MigrationResult {
+    check_primitive(context, old, value, path)?;
+
+    let value = match read_int(context.old.int_encoding, value) {
+        Some(int) if old.r#type == PrimitiveType::Number => {
+            encode_int(context.new.int_encoding, int)
+        }
+        _ => value.clone(),
+    };
+
+    check_new_refinements(context, new, &value, path)?;
+    Ok(value)
+}
+
+/// Checks that a migrated value satisfies the refinements of a primitive type in the version migrated to.
+fn check_new_refinements(
+    context: Context,
+    primitive: &Primitive<DataMetadata>,
+    value: &Json,
+    path: &JsonPath,
+) -> Result<(), Error> {
+    check_refinements(context.new.int_encoding, primitive, value).map_err(|expected| {
+        error(
+            path,
+            format!("{expected} in version {}", context.new.version),
+        )
+    })
+}
+
+/// Checks whether a value of the right primitive type satisfies its refinements.
+fn check_refinements(
+    int_encoding: IntEncoding,
+    primitive: &Primitive<DataMetadata>,
+    value: &Json,
+) -> Result<(), String> {
+    match (primitive.r#type, value) {
+        (PrimitiveType::Number, _) => read_int(int_encoding, value)
+            .map_or(Ok(()), |number| primitive.refinements.check_int(number)),
+        (_, Json::String(string)) => primitive.refinements.check_string(string),
+        _ => Ok(()),
+    }
+}
+
+/// Reads an int in the given encoding, or returns `None` if the value isn't one.
+fn read_int(int_encoding: IntEncoding, value: &Json) -> Option<i64> {
+    match int_encoding {
+        IntEncoding::Number => value.as_i64(),
+        IntEncoding::String => value.as_str().and_then(formats::parse_int),
+    }
+}
+
+/// Writes an int in the given encoding.
+fn encode_int(int_encoding: IntEncoding, int: i64) -> Json {
+    match int_encoding {
+        IntEncoding::Number => Json::from(int),
+        IntEncoding::String => Json::String(int.to_string()),
+    }
+}
+
+/// Converts the default value of a field to JSON, encoding ints in the given encoding.
+///
+/// The encoding is the one of the version the field belongs to.
+fn encode_default(int_encoding: IntEncoding, value: &Value) -> Json {
+    match value {
+        Value::Number(number) => encode_int(int_encoding, *number),
+        Value::List(elements) => Json::Array(
+            elements
+                .iter()
+                .map(|element| encode_default(int_encoding, element))
+                .collect(),
+        ),
+        _ => default_value(value),
+    }
+}
+
+/// Converts the default value of a field to JSON.
+pub fn default_value(value: &Value) -> Json {
+    match value {
+        Value::Number(number) => Json::from(*number),
+        Value::String(string) => Json::String(string.clone()),
+        Value::List(elements) => Json::Array(elements.iter().map(default_value).collect()),
+    }
+}
+
+composite! {
+    struct (DataInfo, DataMetadata) {
+        name: NameMetadata | N,
+        resolution: ResolutionMetadata | R
+    }
+}
+
+mapper! {
+    fn AddName(basic: BasicMetadata, name: NameMetadata) -> DataMetadata {
+        DataInfo {
+            name,
+            resolution: basic.resolution,
+        }
+    }
+}
+
+getter! {
+    fn GetResolution(metadata: DataMetadata) -> ResolutionMetadata {
+        &metadata.resolution
+    }
+}
@@ -15,9 +15,12 @@ use crate::{
     reports::{Reports, handle_reports},
 };
 
+pub use data::{DataMigrationOptions, default_value, migrate_data};
 pub use pairing::{TypePair, pair_types};
 
 mod annotate;
+pub mod conversions;
+mod data;
 mod pairing;
 
 /// The extension of the temporary copy of the schema file.
@@ -73,7 +73,18 @@ where
             }
         }
         TypeType::List(list) => collect_type(&list.r#type, map, set),
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                collect_type(element, map, set);
+            }
+        }
         TypeType::Primitive(_primitive) => {}
-        TypeType::Identifier(_identifier) => {}
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                collect_type(argument, map, set);
+            }
+        }
+        TypeType::Parameter(_parameter) => {}
+        TypeType::Extern(_extern) => {}
     }
 }
@@ -0,0 +1,190 @@
+//! Runs external code generators, called plugins, for languages that Versed doesn't support itself.
+//!
+//! A plugin named `name` is an executable called `versed-gen-name`.
+//! It receives a request with the resolved schema or migration as JSON on its standard input,
+//! built by [`request`], and prints a response with the files to write on its standard output,
+//! which is read by [`response`].
+//! See `docs/plugins.md` for a description of the protocol.
+
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+    thread,
+};
+
+use clap::ValueEnum;
+use serde_json::{Map, Value as Json};
+
+use crate::{
+    ast::TypeSet,
+    codegen::naming_pass::{NameMetadata, name},
+    composite,
+    error::{Error, ResultExt},
+    loading::{load_file_with_source, load_migration_with_source},
+    mapper,
+    preprocessing::BasicMetadata,
+    rust::RustNamingRules,
+    typescript::TypeScriptNamingRules,
+};
+
+mod request;
+mod response;
+
+/// The version of the protocol, which is sent in every request and must be repeated in every response.
+///
+/// It is increased whenever a change could break existing plugins.
+pub const PROTOCOL_VERSION: u64 = 1;
+
+/// The naming rules whose names are sent to a plugin.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum PluginNaming {
+    /// The names used by `versed rust`
+    Rust,
+    /// The names used by `versed typescript`
+    #[value(name = "typescript")]
+    TypeScript,
+}
+
+/// Configuration of a plugin invocation.
+#[derive(Debug, Clone, Default)]
+pub struct PluginOptions {
+    migration: bool,
+    naming: Vec<PluginNaming>,
+    parameters: Vec<String>,
+}
+
+impl PluginOptions {
+    /// Creates new [`PluginOptions`].
+    ///
+    /// If `migration` is set, a migration file is sent instead of a schema file.
+    /// `naming` selects the naming rules whose names are sent along with the original names.
+    /// The `parameters` are passed to the plugin as they are.
+    pub fn new(migration: bool, naming: Vec<PluginNaming>, parameters: Vec<String>) -> Self {
+        Self {
+            migration,
+            naming,
+            parameters,
+        }
+    }
+}
+
+/// Implements `versed plugin`.
+pub fn run_plugin(
+    plugin: &str,
+    path: &Path,
+    output: &Path,
+    options: &PluginOptions,
+) -> Result<(), Error> {
+    let (kind, content, src) = if options.migration {
+        let (migration, src) = load_migration_with_source(path)?;
+        let migration = migration.map(convert_types);
+        let content = request::migration(&migration, &options.naming);
+        ("migration", content, src)
+    } else {
+        let (types, src) = load_file_with_source(path)?;
+        let content = request::schema(&convert_types(types), &options.naming);
+        ("schema", content, src)
+    };
+
+    let parameters = options.parameters.iter().map(String::as_str);
+
+    let request = Json::Object(Map::from_iter([
+        ("protocolVersion".to_owned(), Json::from(PROTOCOL_VERSION)),
+        (
+            "versedVersion".to_owned(),
+            Json::from(env!("CARGO_PKG_VERSION")),
+        ),
+        ("kind".to_owned(), Json::from(kind)),
+        ("file".to_owned(), Json::from(path.to_string_lossy())),
+        ("source".to_owned(), Json::from(src)),
+        (
+            "parameters".to_owned(),
+            Json::Array(parameters.map(Json::from).collect()),
+        ),
+        (kind.to_owned(), content),
+    ]));
+
+    let response = invoke(plugin, &request)?;
+    let response = response::parse(&response).map_err(|reason| Error::Plugin {
+        name: plugin.to_owned(),
+        reason,
+    })?;
+
+    response.apply(output)
+}
+
+/// Runs the executable of a plugin with a request, returning its raw response.
+///
+/// The standard error of the plugin is passed through, so that it can print diagnostics.
+fn invoke(plugin: &str, request: &Json) -> Result<Vec<u8>, Error> {
+    let executable = format!("versed-gen-{plugin}");
+    let request = serde_json::to_vec(request).expect("JSON values to be serializable");
+
+    let mut child = Command::new(&executable)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::inherit())
+        .spawn()
+        .with_path(&executable)?;
+
+    let mut stdin = child.stdin.take().expect("standard input to be piped");
+
+    // The request is written from another thread,
+    // so that a plugin writing its response early can't cause a deadlock
+    let output = thread::scope(|scope| {
+        scope.spawn(move || {
+            // A plugin may exit without reading the whole request, which is reported below if relevant
+            let _ = stdin.write_all(&request);
+        });
+
+        child.wait_with_output()
+    })
+    .with_path(&executable)?;
+
+    if !output.status.success() {
+        return Err(Error::Plugin {
+            name: plugin.to_owned(),
+            reason: format!("it exited with {}", output.status),
+        });
+    }
+
+    Ok(output.stdout)
+}
+
+/// Gives every entity its names in all supported languages.
+fn convert_types(types: TypeSet<BasicMetadata>) -> TypeSet<PluginMetadata> {
+    let types = name(types, RustNamingRules, AddRustName);
+    name(types, TypeScriptNamingRules, AddTypeScriptName)
+}
+
+composite! {
+    struct (PluginRustPassInfo, PluginRustPassMetadata) {
+        basic: BasicMetadata | B,
+        rust_name: NameMetadata | R
+    }
+}
+
+mapper! {
+    fn AddRustName(basic: BasicMetadata, rust_name: NameMetadata) -> PluginRustPassMetadata {
+        PluginRustPassInfo { basic, rust_name }
+    }
+}
+
+composite! {
+    pub struct (PluginInfo, PluginMetadata) {
+        basic: BasicMetadata | B,
+        rust_name: NameMetadata | R,
+        typescript_name: NameMetadata | T
+    }
+}
+
+mapper! {
+    fn AddTypeScriptName(first: PluginRustPassMetadata, typescript_name: NameMetadata) -> PluginMetadata {
+        PluginInfo {
+            basic: first.basic,
+            rust_name: first.rust_name,
+            typescript_name,
+        }
+    }
+}
@@ -0,0 +1,103 @@
+//! Converts schemas and migrations into the JSON sent to plugins.
+
+use serde_json::{Map, Value as Json};
+
+use crate::{
+    ast::{Migration, TypeSet},
+    metadata::Metadata,
+    plugin::{PluginMetadata, PluginNaming},
+    serialization::{
+        BasicSerializer, MetadataSerializer, insert, serialize_migration, serialize_type_set,
+    },
+};
+
+/// A [`MetadataSerializer`] adding the names given by the selected naming rules
+/// on top of what [`BasicSerializer`] adds.
+struct PluginSerializer<'a> {
+    naming: &'a [PluginNaming],
+}
+
+impl PluginSerializer<'_> {
+    /// Adds the names given to an entity by the selected naming rules.
+    fn names(&self, rust: &str, typescript: &str, object: &mut Map<String, Json>) {
+        let names = self.naming.iter().map(|naming| match naming {
+            PluginNaming::Rust => ("rust".to_owned(), Json::from(rust)),
+            PluginNaming::TypeScript => ("typescript".to_owned(), Json::from(typescript)),
+        });
+
+        insert(object, "names", Json::Object(names.collect()));
+    }
+}
+
+impl MetadataSerializer<PluginMetadata> for PluginSerializer<'_> {
+    fn type_set(
+        &self,
+        metadata: &<PluginMetadata as Metadata>::TypeSet,
+        object: &mut Map<String, Json>,
+    ) {
+        self.names(&metadata.rust_name, &metadata.typescript_name, object);
+        BasicSerializer.type_set(&metadata.basic, object);
+    }
+
+    fn named(
+        &self,
+        metadata: &<PluginMetadata as Metadata>::Named,
+        object: &mut Map<String, Json>,
+    ) {
+        BasicSerializer.named(&metadata.basic, object);
+    }
+
+    fn r#type(
+        &self,
+        metadata: &<PluginMetadata as Metadata>::Type,
+        object: &mut Map<String, Json>,
+    ) {
+        self.names(&metadata.rust_name, &metadata.typescript_name, object);
+        BasicSerializer.r#type(&metadata.basic, object);
+    }
+
+    fn identifier(
+        &self,
+        metadata: &<PluginMetadata as Metadata>::Identifier,
+        object: &mut Map<String, Json>,
+    ) {
+        BasicSerializer.identifier(&metadata.basic, object);
+    }
+
+    fn parameter(
+        &self,
+        metadata: &<PluginMetadata as Metadata>::Parameter,
+        object: &mut Map<String, Json>,
+    ) {
+        self.names(&metadata.rust_name, &metadata.typescript_name, object);
+        BasicSerializer.parameter(&metadata.basic, object);
+    }
+
+    fn field(
+        &self,
+        metadata: &<PluginMetadata as Metadata>::Field,
+        object: &mut Map<String, Json>,
+    ) {
+        self.names(&metadata.rust_name, &metadata.typescript_name, object);
+        BasicSerializer.field(&metadata.basic, object);
+    }
+
+    fn variant(
+        &self,
+        metadata: &<PluginMetadata as Metadata>::Variant,
+        object: &mut Map<String, Json>,
+    ) {
+        self.names(&metadata.rust_name, &metadata.typescript_name, object);
+        BasicSerializer.variant(&metadata.basic, object);
+    }
+}
+
+/// Converts a schema into the `schema` member of a request.
+pub fn schema(types: &TypeSet<PluginMetadata>, naming: &[PluginNaming]) -> Json {
+    serialize_type_set(types, &PluginSerializer { naming }, "/schema")
+}
+
+/// Converts a migration into the `migration` member of a request.
+pub fn migration(migration: &Migration<PluginMetadata>, naming: &[PluginNaming]) -> Json {
+    serialize_migration(migration, &PluginSerializer { naming }, "/migration")
+}
@@ -0,0 +1,196 @@
+//! Reads the JSON returned by plugins and applies it to the output directory.
+
+use std::{
+    fs::{self, create_dir_all},
+    io::{ErrorKind, Write},
+    path::{Component, Path, PathBuf},
+};
+
+use serde_json::{Map, Value as Json};
+
+use crate::{
+    codegen::file_patching::{WriteMode, add_line_to_file, write_file},
+    error::{Error, ResultExt},
+    plugin::PROTOCOL_VERSION,
+};
+
+/// The files a plugin asked to write, after they were validated.
+#[derive(Debug, Clone)]
+pub struct Response {
+    files: Vec<GeneratedFile>,
+    patches: Vec<Patch>,
+}
+
+/// A file to be written in its entirety.
+#[derive(Debug, Clone)]
+struct GeneratedFile {
+    /// The path relative to the output directory.
+    path: PathBuf,
+    content: String,
+    /// Whether an existing file may be replaced.
+    overwrite: bool,
+}
+
+/// A line to be appended to a file, such as a module declaration in an index file.
+#[derive(Debug, Clone)]
+struct Patch {
+    /// The path relative to the output directory.
+    path: PathBuf,
+    append_line: String,
+}
+
+/// Parses the response of a plugin.
+///
+/// Returns the reason why the plugin failed if the response is invalid or reports an error.
+pub fn parse(response: &[u8]) -> Result<Response, String> {
+    let response: Json = serde_json::from_slice(response)
+        .map_err(|error| format!("its response is not valid JSON: {error}"))?;
+    let response = expect_object(&response, "the response")?;
+
+    match response.get("protocolVersion").and_then(Json::as_u64) {
+        Some(PROTOCOL_VERSION) => {}
+        Some(version) => {
+            return Err(format!(
+                "it uses version {version} of the protocol instead of version {PROTOCOL_VERSION}"
+            ));
+        }
+        None => return Err("its response has no protocolVersion".to_owned()),
+    }
+
+    if let Some(error) = response.get("error") {
+        return match error.as_str() {
+            Some(message) => Err(message.to_owned()),
+            None => Err("its error is not a string".to_owned()),
+        };
+    }
+
+    let files = expect_array(response, "files")?
+        .iter()
+        .map(|file| {
+            let file = expect_object(file, "a file")?;
+
+            Ok(GeneratedFile {
+                path: expect_path(file)?,
+                content: expect_string(file, "content")?.to_owned(),
+                overwrite: match file.get("overwrite") {
+                    None => false,
+                    Some(overwrite) => overwrite
+                        .as_bool()
+                        .ok_or("the overwrite flag of a file is not a boolean")?,
+                },
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    let patches = expect_array(response, "patches")?
+        .iter()
+        .map(|patch| {
+            let patch = expect_object(patch, "a patch")?;
+            let append_line = expect_string(patch, "appendLine")?;
+
+            if append_line.contains(['\n', '\r']) {
+                return Err(format!("the line {append_line:?} contains a line break"));
+            }
+
+            Ok(Patch {
+                path: expect_path(patch)?,
+                append_line: append_line.to_owned(),
+            })
+        })
+        .collect::<Result<_, String>>()?;
+
+    Ok(Response { files, patches })
+}
+
+/// Gets a JSON value as an object.
+fn expect_object<'a>(value: &'a Json, what: &str) -> Result<&'a Map<String, Json>, String> {
+    value
+        .as_object()
+        .ok_or_else(|| format!("{what} is not an object"))
+}
+
+/// Gets a member of an object that is an array, treating a missing member as an empty array.
+fn expect_array<'a>(object: &'a Map<String, Json>, key: &str) -> Result<&'a [Json], String> {
+    match object.get(key) {
+        None => Ok(&[]),
+        Some(value) => value
+            .as_array()
+            .map(Vec::as_slice)
+            .ok_or_else(|| format!("{key} is not an array")),
+    }
+}
+
+/// Gets a member of an object that is a string.
+fn expect_string<'a>(object: &'a Map<String, Json>, key: &str) -> Result<&'a str, String> {
+    object
+        .get(key)
+        .and_then(Json::as_str)
+        .ok_or_else(|| format!("a {key} is missing or not a string"))
+}
+
+/// Gets the path of a file or patch, which must stay inside the output directory.
+fn expect_path(object: &Map<String, Json>) -> Result<PathBuf, String> {
+    let path = Path::new(expect_string(object, "path")?);
+
+    let is_inside = path.components().next().is_some()
+        && path
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+
+    if is_inside {
+        Ok(path.to_path_buf())
+    } else {
+        Err(format!(
+            "the path {} doesn't stay inside the output directory",
+            path.display()
+        ))
+    }
+}
+
+impl Response {
+    /// Writes all files and then applies all patches, relative to the `output` directory.
+    ///
+    /// A line is only appended if the file doesn't contain it yet.
+    pub fn apply(&self, output: &Path) -> Result<(), Error> {
+        for file in &self.files {
+            let path = output.join(&file.path);
+            create_parent(&path)?;
+
+            let mode = if file.overwrite {
+                WriteMode::Overwrite
+            } else {
+                WriteMode::New
+            };
+
+            write_file(&path, mode, |writer| {
+                writer.write_all(file.content.as_bytes())
+            })
+            .with_path(&path)?;
+        }
+
+        for patch in &self.patches {
+            let path = output.join(&patch.path);
+            create_parent(&path)?;
+
+            let is_present = match fs::read_to_string(&path) {
+                Ok(content) => content.lines().any(|line| line == patch.append_line),
+                Err(error) if error.kind() == ErrorKind::NotFound => false,
+                Err(error) => return Err(error).with_path(&path),
+            };
+
+            if !is_present {
+                add_line_to_file(&path, format_args!("{}", patch.append_line)).with_path(&path)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates the directory containing a file, if it doesn't exist.
+fn create_parent(path: &Path) -> Result<(), Error> {
+    match path.parent() {
+        Some(parent) => create_dir_all(parent).with_path(parent),
+        None => Ok(()),
+    }
+}
@@ -71,8 +71,19 @@ fn check_type<'types>(r#type: &'types Type<BasicMetadata>, context: &mut Context
             }
         }
         TypeType::List(list) => check_type(&list.r#type, context),
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                check_type(element, context);
+            }
+        }
         TypeType::Primitive(_primitive) => {}
-        TypeType::Identifier(_identifier) => {}
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                check_type(argument, context);
+            }
+        }
+        TypeType::Parameter(_parameter) => {}
+        TypeType::Extern(_extern) => {}
     }
 }
 
@@ -0,0 +1,192 @@
+//! Checks whether default values of fields match their types, including their refinements.
+
+use std::ops::Range;
+
+use ariadne::{Color, Config, IndexType, Label, Report, ReportKind};
+
+use crate::{
+    ast::{PrimitiveType, Scope, Type, TypeSet, TypeType, Value},
+    preprocessing::{BasicMetadata, name_resolution::INVALID_INDEX},
+    reports::Reports,
+    syntax::Span,
+};
+
+/// The context for the default value check pass.
+struct Context<'types, 'filename, 'reports> {
+    types: &'types TypeSet<BasicMetadata>,
+    /// The report collection to add to.
+    reports: &'reports mut Reports<'filename>,
+    /// The name of the schema file (for building reports).
+    filename: &'filename str,
+}
+
+/// The reason why a value doesn't match a type.
+enum Mismatch {
+    /// The type can't have default values.
+    Unsupported,
+    /// The value has a different type, described by the string.
+    Expected(&'static str),
+    /// The type is a type parameter, which could stand for any type.
+    Parameter,
+    /// The type is an extern type, whose values Versed doesn't know.
+    Extern,
+    /// The value doesn't satisfy the refinements of the type, described by the string.
+    Refinement(String),
+}
+
+/// Runs the default value check pass.
+pub fn check_defaults<'filename>(
+    types: &TypeSet<BasicMetadata>,
+    reports: &mut Reports<'filename>,
+    filename: &'filename str,
+) {
+    let mut context = Context {
+        types,
+        reports,
+        filename,
+    };
+
+    for r#type in &types.types {
+        check_type(&r#type.r#type, &mut context);
+    }
+}
+
+/// Visits a type and checks the default values of all fields recursively.
+fn check_type(r#type: &Type<BasicMetadata>, context: &mut Context) {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => {
+            for field in &r#struct.fields {
+                if let Some((default, span)) =
+                    field.default.as_ref().zip(field.metadata.span.default)
+                    && let Err(mismatch) =
+                        check_value(&field.r#type, default, context.types, &Scope::ROOT, 0)
+                {
+                    let label = match mismatch {
+                        Mismatch::Unsupported => {
+                            "only ints, strings and lists can have default values".to_string()
+                        }
+                        Mismatch::Expected(expected) => format!("expected {expected}"),
+                        Mismatch::Parameter => {
+                            "type parameters can't have default values".to_string()
+                        }
+                        Mismatch::Extern => "extern types can't have default values".to_string(),
+                        Mismatch::Refinement(expected) => expected,
+                    };
+
+                    context.reports.add_fatal(make_report(
+                        format!(
+                            "the default value of the field '{}' doesn't match its type",
+                            field.name
+                        ),
+                        label,
+                        span,
+                        context.filename,
+                    ));
+                }
+
+                check_type(&field.r#type, context);
+            }
+        }
+        TypeType::Enum(r#enum) => {
+            for variant in &r#enum.variants {
+                check_type(&variant.r#type, context);
+            }
+        }
+        TypeType::List(list) => check_type(&list.r#type, context),
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                check_type(element, context);
+            }
+        }
+        TypeType::Primitive(_primitive) => {}
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                check_type(argument, context);
+            }
+        }
+        TypeType::Parameter(_parameter) => {}
+        TypeType::Extern(_extern) => {}
+    }
+}
+
+/// Checks whether a value matches a type.
+///
+/// `scope` holds the type arguments of the followed generic types,
+/// and `depth` counts the followed identifiers, to stop at cycles of type aliases.
+fn check_value(
+    r#type: &Type<BasicMetadata>,
+    value: &Value,
+    types: &TypeSet<BasicMetadata>,
+    scope: &Scope<BasicMetadata>,
+    depth: usize,
+) -> Result<(), Mismatch> {
+    match (&r#type.r#type, value) {
+        (TypeType::Primitive(primitive), Value::Number(number)) => match primitive.r#type {
+            PrimitiveType::Number => primitive
+                .refinements
+                .check_int(*number)
+                .map_err(Mismatch::Refinement),
+            PrimitiveType::String => Err(Mismatch::Expected("a string")),
+            // `unit` and the built-in types have no literals
+            _ => Err(Mismatch::Unsupported),
+        },
+        (TypeType::Primitive(primitive), Value::String(string)) => match primitive.r#type {
+            PrimitiveType::String => primitive
+                .refinements
+                .check_string(string)
+                .map_err(Mismatch::Refinement),
+            PrimitiveType::Number => Err(Mismatch::Expected("an int")),
+            _ => Err(Mismatch::Unsupported),
+        },
+        (TypeType::Primitive(primitive), Value::List(_)) => match primitive.r#type {
+            PrimitiveType::Number => Err(Mismatch::Expected("an int")),
+            PrimitiveType::String => Err(Mismatch::Expected("a string")),
+            _ => Err(Mismatch::Unsupported),
+        },
+        (TypeType::List(list), Value::List(elements)) => elements
+            .iter()
+            .try_for_each(|element| check_value(&list.r#type, element, types, scope, depth)),
+        (TypeType::List(_list), _) => Err(Mismatch::Expected("a list")),
+        (TypeType::Identifier(identifier), _) => {
+            let index = identifier.metadata.resolution;
+
+            if index == INVALID_INDEX {
+                // Already reported by name resolution
+                Ok(())
+            } else if depth > types.types.len() {
+                Err(Mismatch::Unsupported)
+            } else {
+                let scope = scope.enter(&identifier.arguments);
+                check_value(&types.types[index].r#type, value, types, &scope, depth + 1)
+            }
+        }
+        (TypeType::Parameter(parameter), _) => {
+            match scope.argument(parameter.metadata.resolution) {
+                Some((argument, scope)) => check_value(argument, value, types, scope, depth),
+                None => Err(Mismatch::Parameter),
+            }
+        }
+        (TypeType::Extern(_), _) => Err(Mismatch::Extern),
+        (TypeType::Struct(_) | TypeType::Enum(_) | TypeType::Tuple(_), _) => {
+            Err(Mismatch::Unsupported)
+        }
+    }
+}
+
+/// Creates a report.
+fn make_report(
+    message: String,
+    label: String,
+    span: Span,
+    filename: &str,
+) -> Report<'static, (&str, Range<usize>)> {
+    Report::build(ReportKind::Error, (filename, span.into_range()))
+        .with_config(Config::new().with_index_type(IndexType::Byte))
+        .with_message(message)
+        .with_label(
+            Label::new((filename, span.into_range()))
+                .with_message(label)
+                .with_color(Color::Red),
+        )
+        .finish()
+}
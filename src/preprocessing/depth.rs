@@ -0,0 +1,178 @@
+//! Computes the minimal depth of types, which the generators of random values start from.
+//!
+//! The depth of a value is the largest number of references to named types
+//! on a path from the value to any of its parts.
+//! Types that the recursion check warns about have no values with finite depth.
+
+use std::collections::HashSet;
+
+use crate::{
+    ast::{Scope, Type, TypeSet, TypeType},
+    metadata::{GetMetadata, Metadata},
+    preprocessing::ResolutionMetadata,
+};
+
+/// The depth of generated values, counted in references to named types,
+/// on top of the depth needed to generate a value at all.
+pub const EXTRA_DEPTH: u32 = 3;
+
+/// Computes the minimal depth of all named types.
+///
+/// It is `None` if the type has no values with finite depth,
+/// which covers the types that the recursion check warns about, as well as uninhabited types.
+/// Generic types depend on their type arguments, so they are left at `None`
+/// and followed with the arguments of every use instead.
+pub fn min_depths<M, GM>(types: &TypeSet<M>, get: GM) -> Vec<Option<u32>>
+where
+    M: Metadata,
+    GM: GetMetadata<M, ResolutionMetadata> + Copy,
+{
+    min_depths_with(types, get, None)
+}
+
+/// Checks whether the values of the named type at `index` have no finite depth only because
+/// they always contain an extern type, that is whether they would if extern types were leaves.
+pub fn needs_extern<M, GM>(types: &TypeSet<M>, get: GM, index: usize) -> bool
+where
+    M: Metadata,
+    GM: GetMetadata<M, ResolutionMetadata> + Copy,
+{
+    min_depths(types, get)[index].is_none() && min_depths_with(types, get, Some(0))[index].is_some()
+}
+
+/// Computes the minimal depth of all named types, see [`min_depths`],
+/// where extern types have the depth `extern_depth`.
+fn min_depths_with<M, GM>(
+    types: &TypeSet<M>,
+    get: GM,
+    extern_depth: Option<u32>,
+) -> Vec<Option<u32>>
+where
+    M: Metadata,
+    GM: GetMetadata<M, ResolutionMetadata> + Copy,
+{
+    let mut depths = vec![None; types.types.len()];
+    let mut changed = true;
+
+    while changed {
+        changed = false;
+
+        for (index, named) in types.types.iter().enumerate() {
+            if !named.parameters.is_empty() {
+                continue;
+            }
+
+            let depth = min_depth_in_path(
+                &named.r#type,
+                types,
+                &depths,
+                &Scope::ROOT,
+                get,
+                extern_depth,
+                &mut HashSet::new(),
+            );
+
+            if depth != depths[index] {
+                depths[index] = depth;
+                changed = true;
+            }
+        }
+    }
+
+    depths
+}
+
+/// Computes the minimal depth of a type, given the minimal depths of all non-generic named types.
+///
+/// The type arguments of the generic types it's inside of are taken from `scope`.
+pub fn min_depth<M, GM>(
+    r#type: &Type<M>,
+    types: &TypeSet<M>,
+    depths: &[Option<u32>],
+    scope: &Scope<M>,
+    get: GM,
+) -> Option<u32>
+where
+    M: Metadata,
+    GM: GetMetadata<M, ResolutionMetadata> + Copy,
+{
+    min_depth_in_path(r#type, types, depths, scope, get, None, &mut HashSet::new())
+}
+
+/// Computes the minimal depth of a type, see [`min_depth`].
+///
+/// Extern types have the depth `extern_depth`, which is `None` as they have no values to generate.
+/// `path` contains the uses of generic types that are being followed,
+/// by their index and type arguments, which are identified by their address.
+/// Following one of them again can't lead to a smaller depth, so it counts as infinitely deep.
+fn min_depth_in_path<M, GM>(
+    r#type: &Type<M>,
+    types: &TypeSet<M>,
+    depths: &[Option<u32>],
+    scope: &Scope<M>,
+    get: GM,
+    extern_depth: Option<u32>,
+    path: &mut HashSet<(usize, *const Type<M>)>,
+) -> Option<u32>
+where
+    M: Metadata,
+    GM: GetMetadata<M, ResolutionMetadata> + Copy,
+{
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => r#struct
+            .fields
+            .iter()
+            .map(|field| {
+                min_depth_in_path(&field.r#type, types, depths, scope, get, extern_depth, path)
+            })
+            .try_fold(0, |max, depth| depth.map(|depth| max.max(depth))),
+        TypeType::Enum(r#enum) => r#enum
+            .variants
+            .iter()
+            .filter_map(|variant| {
+                min_depth_in_path(
+                    &variant.r#type,
+                    types,
+                    depths,
+                    scope,
+                    get,
+                    extern_depth,
+                    path,
+                )
+            })
+            .min(),
+        TypeType::List(_list) => Some(0),
+        TypeType::Tuple(tuple) => tuple
+            .elements
+            .iter()
+            .map(|element| {
+                min_depth_in_path(element, types, depths, scope, get, extern_depth, path)
+            })
+            .try_fold(0, |max, depth| depth.map(|depth| max.max(depth))),
+        TypeType::Primitive(_primitive) => Some(0),
+        TypeType::Identifier(identifier) => {
+            let index = *get.get_identifier(&identifier.metadata);
+
+            if identifier.arguments.is_empty() {
+                return depths[index].map(|depth| depth + 1);
+            }
+
+            let key = (index, identifier.arguments.as_ptr());
+
+            if !path.insert(key) {
+                return None;
+            }
+
+            let r#type = &types.types[index].r#type;
+            let scope = scope.enter(&identifier.arguments);
+            let depth = min_depth_in_path(r#type, types, depths, &scope, get, extern_depth, path);
+            path.remove(&key);
+            depth.map(|depth| depth + 1)
+        }
+        TypeType::Parameter(parameter) => {
+            let (argument, scope) = scope.argument(*get.get_parameter(&parameter.metadata))?;
+            min_depth_in_path(argument, types, depths, scope, get, extern_depth, path)
+        }
+        TypeType::Extern(_) => extern_depth,
+    }
+}
@@ -3,21 +3,28 @@
 //! It resolves names and checks for
 //! duplicate migration markers (which are errors)
 //! and unbounded recursion (which is a warning).
-//! It also checks whether both versions in a schema file have the same name (which is an error).
+//! It also checks whether both versions in a schema file have the same name (which is an error)
+//! and whether default values match the types of their fields (which is also an error).
 //!
 use ariadne::{Color, Label, Report, ReportKind};
 use name_resolution::resolve_names;
 
 mod annotation_check;
+mod default_check;
+mod depth;
 mod name_resolution;
 mod recursion_check;
 
+pub use depth::{EXTRA_DEPTH, min_depth, min_depths, needs_extern};
 pub use name_resolution::ResolutionMetadata;
 
 use crate::{
     ast::{Migration, TypeSet},
     composite,
-    preprocessing::{annotation_check::check_annotations, recursion_check::check_recursion},
+    preprocessing::{
+        annotation_check::check_annotations, default_check::check_defaults,
+        recursion_check::check_recursion,
+    },
     reports::Reports,
     syntax::SpanMetadata,
 };
@@ -30,6 +37,7 @@ pub fn preprocess<'filename>(
 ) -> TypeSet<BasicMetadata> {
     let types = resolve_names(types, reports, filename);
     check_annotations(&types, reports, filename);
+    check_defaults(&types, reports, filename);
     check_recursion(&types, reports, filename);
 
     types
@@ -74,6 +82,37 @@ pub fn check_migration_versions<'filename>(
     }
 }
 
+/// Checks whether a migration starts at the version the previous migration ended with,
+/// producing a report if it doesn't.
+pub fn check_migration_continuity<'filename>(
+    previous: &TypeSet<BasicMetadata>,
+    next: &TypeSet<BasicMetadata>,
+    reports: &mut Reports<'filename>,
+    filename: &'filename str,
+) {
+    if previous.version != next.version {
+        let message = format!(
+            "the migration should start at version '{}', as the previous migration ends with it",
+            previous.version
+        );
+
+        let report = Report::build(
+            ReportKind::Error,
+            (filename, next.metadata.span.version.into_range()),
+        )
+        .with_config(ariadne::Config::new().with_index_type(ariadne::IndexType::Byte))
+        .with_message(&message)
+        .with_label(
+            Label::new((filename, next.metadata.span.version.into_range()))
+                .with_message(message)
+                .with_color(Color::Red),
+        )
+        .finish();
+
+        reports.add_fatal(report);
+    }
+}
+
 composite! {
     pub struct (BasicInfo, BasicMetadata) {
         resolution: ResolutionMetadata | R,
@@ -1,6 +1,10 @@
 //! Attaches indices into [`TypeSet::types`] to identifiers.
 //!
-//! It also checks if any name is defined more than once.
+//! Identifiers referring to type parameters of the enclosing named type are turned into [`Parameter`]s.
+//! It also checks if any name is defined more than once, whether tuples have too many elements,
+//! whether generic types get the right number of type arguments and whether every type parameter is used.
+//! Spreads are checked to refer to named structs that don't include themselves,
+//! and the fields they include count towards the duplicate field names.
 
 use std::{
     collections::{HashMap, hash_map::Entry},
@@ -11,8 +15,8 @@ use ariadne::{Color, Config, IndexType, Label, Report, ReportKind};
 
 use crate::{
     ast::{
-        Enum, Field, Identifier, List, NamedType, Primitive, Struct, Type, TypeSet, TypeType,
-        Variant,
+        Enum, Extern, Field, Identifier, List, NamedType, Parameter, Primitive, Struct, Tuple,
+        Type, TypeSet, TypeType, Variant,
     },
     metadata::Metadata,
     preprocessing::{BasicInfo, BasicMetadata},
@@ -25,6 +29,9 @@ use crate::{
 /// It does need to be checked inside preprocessing.
 pub const INVALID_INDEX: usize = usize::MAX;
 
+/// The maximum number of elements in a tuple.
+const MAX_TUPLE_ELEMENTS: usize = 12;
+
 /// Info about the name of a type.
 #[derive(Debug)]
 struct NameInfo {
@@ -32,6 +39,19 @@ struct NameInfo {
     index: usize,
     /// The span where the name is defined.
     span: Span,
+    /// The number of type parameters.
+    parameters: usize,
+    /// The fields, if the type is a struct.
+    fields: Option<Vec<FieldInfo>>,
+}
+
+/// Info about a field of a named struct, used to check spreads.
+#[derive(Debug)]
+struct FieldInfo {
+    /// The name of the field, or of the struct for spreads.
+    name: String,
+    /// Whether the field is a spread.
+    spread: bool,
 }
 
 /// The context for the name resolution pass.
@@ -39,6 +59,12 @@ struct NameInfo {
 struct ResolutionContext<'a, 'filename> {
     /// Information about each named type.
     names: HashMap<String, NameInfo>,
+    /// The type parameters of the named type being resolved.
+    parameters: Vec<String>,
+    /// Whether each of [`ResolutionContext::parameters`] has been used.
+    used_parameters: Vec<bool>,
+    /// The name of the named type being resolved, until its outermost type has been visited.
+    owner: Option<String>,
     /// The report collection to add to.
     reports: &'a mut Reports<'filename>,
     /// The name of the schema file (for building reports).
@@ -49,6 +75,7 @@ struct ResolutionContext<'a, 'filename> {
 pub fn resolve_names<'filename>(
     TypeSet {
         version,
+        int_encoding,
         types,
         metadata,
     }: TypeSet<SpanMetadata>,
@@ -73,6 +100,20 @@ pub fn resolve_names<'filename>(
                 entry.insert(NameInfo {
                     index,
                     span: r#type.metadata.name,
+                    parameters: r#type.parameters.len(),
+                    fields: match &r#type.r#type.r#type {
+                        TypeType::Struct(r#struct) => Some(
+                            r#struct
+                                .fields
+                                .iter()
+                                .map(|field| FieldInfo {
+                                    name: field.name.clone(),
+                                    spread: field.spread,
+                                })
+                                .collect(),
+                        ),
+                        _ => None,
+                    },
                 });
             }
         }
@@ -80,30 +121,21 @@ pub fn resolve_names<'filename>(
 
     let mut context = ResolutionContext {
         names,
+        parameters: Vec::new(),
+        used_parameters: Vec::new(),
+        owner: None,
         filename,
         reports,
     };
 
     let types = types
         .into_iter()
-        .map(
-            |NamedType {
-                 name,
-                 r#type,
-                 metadata,
-             }| NamedType {
-                name,
-                r#type: resolve_type(r#type, &mut context),
-                metadata: BasicInfo {
-                    resolution: (),
-                    span: metadata,
-                },
-            },
-        )
+        .map(|r#type| resolve_named(r#type, &mut context))
         .collect();
 
     TypeSet {
         version,
+        int_encoding,
         types,
         metadata: BasicInfo {
             resolution: (),
@@ -112,6 +144,88 @@ pub fn resolve_names<'filename>(
     }
 }
 
+/// Resolves a named type, checking its type parameters.
+fn resolve_named(
+    NamedType {
+        name,
+        parameters,
+        r#type,
+        metadata,
+    }: NamedType<SpanMetadata>,
+    context: &mut ResolutionContext,
+) -> NamedType<BasicMetadata> {
+    check_unique(
+        parameters
+            .iter()
+            .map(|parameter| (parameter.name.as_str(), parameter.metadata)),
+        "type parameter",
+        context.filename,
+        context.reports,
+    );
+
+    for parameter in &parameters {
+        if let Some(info) = context.names.get(&parameter.name) {
+            context.reports.add_fatal(make_double_label_report(
+                format!(
+                    "the type parameter '{}' has the same name as a type",
+                    parameter.name
+                ),
+                format!("the type parameter '{}' is declared here", parameter.name),
+                parameter.metadata,
+                format!("the type '{}' is declared here", parameter.name),
+                info.span,
+                context.filename,
+            ));
+        }
+    }
+
+    context.parameters = parameters
+        .iter()
+        .map(|parameter| parameter.name.clone())
+        .collect();
+    context.used_parameters = vec![false; parameters.len()];
+    context.owner = Some(name.clone());
+
+    let r#type = resolve_type(r#type, context);
+
+    for (index, (parameter, used)) in parameters.iter().zip(&context.used_parameters).enumerate() {
+        // Later duplicates are never resolved to, but they were already reported
+        let is_duplicate = parameters[..index]
+            .iter()
+            .any(|other| other.name == parameter.name);
+
+        if !used && !is_duplicate {
+            context.reports.add_fatal(make_simple_report(
+                format!("the type parameter '{}' is never used", parameter.name),
+                parameter.metadata,
+                context.filename,
+            ));
+        }
+    }
+
+    let parameters = parameters
+        .into_iter()
+        .enumerate()
+        .map(|(index, Parameter { name, metadata })| Parameter {
+            name,
+            metadata: BasicInfo {
+                resolution: index,
+                span: metadata,
+            },
+        })
+        .collect();
+
+    NamedType {
+        name,
+        parameters,
+        r#type,
+        metadata: BasicInfo {
+            resolution: (),
+            span: metadata,
+        },
+    }
+}
+
 /// Visits and resolves a type recursively.
 fn resolve_type(
     r#type: Type<SpanMetadata>,
@@ -123,16 +237,27 @@ fn resolve_type(
         metadata,
     } = r#type;
 
+    let owner = context.owner.take();
+
     let r#type = match r#type {
         TypeType::Struct(Struct { fields, metadata }) => {
-            check_unique(
-                fields
-                    .iter()
-                    .map(|Field { name, metadata, .. }| (name.as_str(), metadata.name)),
-                "field",
-                context.filename,
-                context.reports,
-            );
+            check_spreads(&fields, owner.as_deref(), context);
+
+            let mut stack = owner.into_iter().collect();
+            let mut expanded = Vec::new();
+            for field in &fields {
+                let span = field.metadata.name;
+
+                if field.spread {
+                    let mut included = Vec::new();
+                    expand_spread(&context.names, &field.name, &mut stack, &mut included);
+                    expanded.extend(included.into_iter().map(|name| (name, span, true)));
+                } else {
+                    expanded.push((field.name.clone(), span, false));
+                }
+            }
+
+            check_unique_fields(&expanded, context.filename, context.reports);
 
             let fields = fields
                 .into_iter()
@@ -140,10 +265,14 @@ fn resolve_type(
                     |Field {
                          name,
                          r#type,
+                         default,
+                         spread,
                          metadata,
                      }| Field {
                         name,
                         r#type: resolve_type(r#type, context),
+                        default,
+                        spread,
                         metadata: BasicInfo {
                             resolution: (),
                             span: metadata,
@@ -160,7 +289,11 @@ fn resolve_type(
                 },
             })
         }
-        TypeType::Enum(Enum { variants, metadata }) => {
+        TypeType::Enum(Enum {
+            variants,
+            open,
+            metadata,
+        }) => {
             check_unique(
                 variants
                     .iter()
@@ -191,6 +324,7 @@ fn resolve_type(
 
             TypeType::Enum(Enum {
                 variants,
+                open,
 
                 metadata: BasicInfo {
                     resolution: (),
@@ -206,8 +340,37 @@ fn resolve_type(
                 span: metadata,
             },
         }),
-        TypeType::Primitive(Primitive { r#type, metadata }) => TypeType::Primitive(Primitive {
+        TypeType::Tuple(Tuple {
+            elements,
+            metadata: tuple_metadata,
+        }) => {
+            // Rust only implements common traits for tuples of up to 12 elements
+            if elements.len() > MAX_TUPLE_ELEMENTS {
+                context.reports.add_fatal(make_simple_report(
+                    format!("tuples can have at most {MAX_TUPLE_ELEMENTS} elements"),
+                    metadata.r#type,
+                    context.filename,
+                ));
+            }
+
+            TypeType::Tuple(Tuple {
+                elements: elements
+                    .into_iter()
+                    .map(|element| resolve_type(element, context))
+                    .collect(),
+                metadata: BasicInfo {
+                    resolution: (),
+                    span: tuple_metadata,
+                },
+            })
+        }
+        TypeType::Primitive(Primitive {
             r#type,
+            refinements,
+            metadata,
+        }) => TypeType::Primitive(Primitive {
+            r#type,
+            refinements,
             metadata: BasicInfo {
                 resolution: (),
                 span: metadata,
@@ -215,28 +378,80 @@ fn resolve_type(
         }),
         TypeType::Identifier(Identifier {
             ident,
+            arguments,
             metadata: span_metadata,
         }) => {
-            let index = if let Some(&NameInfo { index, .. }) = context.names.get(&ident) {
-                index
+            if let Some(index) = context.parameters.iter().position(|name| *name == ident) {
+                context.used_parameters[index] = true;
+
+                if !arguments.is_empty() {
+                    context.reports.add_fatal(make_simple_report(
+                        format!("the type parameter '{ident}' can't have type arguments"),
+                        metadata.r#type,
+                        context.filename,
+                    ));
+                }
+
+                TypeType::Parameter(Parameter {
+                    name: ident,
+                    metadata: BasicInfo {
+                        resolution: index,
+                        span: metadata.r#type,
+                    },
+                })
             } else {
-                context.reports.add_fatal(make_simple_report(
-                    format!("unknown type '{ident}'"),
-                    metadata.r#type,
-                    context.filename,
-                ));
-
-                INVALID_INDEX
-            };
-
-            TypeType::Identifier(Identifier {
-                ident,
-                metadata: BasicInfo {
-                    resolution: index,
-                    span: span_metadata,
-                },
-            })
+                let arguments: Vec<_> = arguments
+                    .into_iter()
+                    .map(|argument| resolve_type(argument, context))
+                    .collect();
+
+                let index = if let Some(info) = context.names.get(&ident) {
+                    if info.parameters != arguments.len() {
+                        context.reports.add_fatal(make_simple_report(
+                            format!(
+                                "the type '{ident}' expects {}, but {} given",
+                                count(info.parameters, "type argument"),
+                                match arguments.len() {
+                                    0 => "none were".to_owned(),
+                                    1 => "1 was".to_owned(),
+                                    given => format!("{given} were"),
+                                }
+                            ),
+                            metadata.r#type,
+                            context.filename,
+                        ));
+                    }
+
+                    info.index
+                } else {
+                    context.reports.add_fatal(make_simple_report(
+                        format!("unknown type '{ident}'"),
+                        metadata.r#type,
+                        context.filename,
+                    ));
+
+                    INVALID_INDEX
+                };
+
+                TypeType::Identifier(Identifier {
+                    ident,
+                    arguments,
+                    metadata: BasicInfo {
+                        resolution: index,
+                        span: span_metadata,
+                    },
+                })
+            }
         }
+        TypeType::Parameter(_) => unreachable!("the parser to only produce identifiers"),
+        // Extern types are leaves, with nothing to resolve
+        TypeType::Extern(Extern { name, metadata }) => TypeType::Extern(Extern {
+            name,
+            metadata: BasicInfo {
+                resolution: (),
+                span: metadata,
+            },
+        }),
     };
 
     Type {
@@ -249,7 +464,121 @@ fn resolve_type(
     }
 }
 
-/// Checks if all values, meant to be field or variant names, are unique.
+/// Checks if every spread of a struct refers to a named struct, which doesn't include the struct itself.
+///
+/// `owner` is the named type the struct belongs to, if it isn't nested in another type.
+fn check_spreads(
+    fields: &[Field<SpanMetadata>],
+    owner: Option<&str>,
+    context: &mut ResolutionContext,
+) {
+    for field in fields.iter().filter(|field| field.spread) {
+        let span = field.metadata.name;
+
+        let error = match &field.r#type.r#type {
+            TypeType::Identifier(Identifier { ident, .. }) => {
+                if context.parameters.contains(ident) {
+                    format!("the type parameter '{ident}' can't be spread")
+                } else if let Some(info) = context.names.get(ident) {
+                    if info.fields.is_none() {
+                        format!("the type '{ident}' can't be spread, as it isn't a struct")
+                    } else if let Some(owner) = owner
+                        && spreads(&context.names, ident, owner, &mut vec![])
+                    {
+                        format!(
+                            "the struct '{owner}' includes itself through the spread of '{ident}'"
+                        )
+                    } else {
+                        continue;
+                    }
+                } else {
+                    // Reported as an unknown type
+                    continue;
+                }
+            }
+            _ => "only named structs can be spread".to_owned(),
+        };
+
+        context
+            .reports
+            .add_fatal(make_simple_report(error, span, context.filename));
+    }
+}
+
+/// Checks if the named struct `name` includes `target` through spreads, directly or indirectly.
+fn spreads<'a>(
+    names: &'a HashMap<String, NameInfo>,
+    name: &'a str,
+    target: &str,
+    visited: &mut Vec<&'a str>,
+) -> bool {
+    if name == target {
+        return true;
+    }
+
+    if visited.contains(&name) {
+        return false;
+    }
+
+    visited.push(name);
+
+    let Some(NameInfo {
+        fields: Some(fields),
+        ..
+    }) = names.get(name)
+    else {
+        return false;
+    };
+
+    fields
+        .iter()
+        .filter(|field| field.spread)
+        .any(|field| spreads(names, &field.name, target, visited))
+}
+
+/// Collects the names of the fields a spread of the named struct `name` includes,
+/// replacing nested spreads with the fields of the spread structs.
+///
+/// `stack` contains the named structs being expanded, which are skipped when they're spread again.
+fn expand_spread(
+    names: &HashMap<String, NameInfo>,
+    name: &str,
+    stack: &mut Vec<String>,
+    expanded: &mut Vec<String>,
+) {
+    let Some(NameInfo {
+        fields: Some(fields),
+        ..
+    }) = names.get(name)
+    else {
+        return;
+    };
+
+    if stack.iter().any(|other| other == name) {
+        return;
+    }
+
+    stack.push(name.to_owned());
+    for field in fields {
+        if field.spread {
+            expand_spread(names, &field.name, stack, expanded);
+        } else {
+            expanded.push(field.name.clone());
+        }
+    }
+    stack.pop();
+}
+
+/// Formats a number of things, like `1 type argument` or `2 type arguments`.
+fn count(number: usize, noun: &str) -> String {
+    if number == 1 {
+        format!("1 {noun}")
+    } else {
+        format!("{number} {noun}s")
+    }
+}
+
+/// Checks if all values, meant to be field, variant or type parameter names, are unique.
 fn check_unique<'a, 'filename>(
     iter: impl Iterator<Item = (&'a str, Span)>,
     type_name: &'a str,
@@ -275,7 +604,50 @@ fn check_unique<'a, 'filename>(
     }
 }
 
-/// Metadata containing indices into [`TypeSet::types`] for every identifier.
+/// Checks if the names of the fields of a struct, including the ones of spread structs, are unique.
+///
+/// The fields are given with the span of their declaration or, if they're included by a spread,
+/// the span of the spread, so that both labels point into the struct.
+fn check_unique_fields<'filename>(
+    fields: &[(String, Span, bool)],
+    filename: &'filename str,
+    reports: &mut Reports<'filename>,
+) {
+    let mut names: HashMap<&str, (Span, bool)> = HashMap::new();
+
+    for (name, span, spread) in fields {
+        match names.entry(name) {
+            Entry::Occupied(entry) => {
+                let (first_span, first_spread) = *entry.get();
+                let again = if *spread {
+                    format!("the field '{name}' is included again by this spread")
+                } else {
+                    format!("the field '{name}' was declared again here")
+                };
+                let first = if first_spread {
+                    format!("the field '{name}' was first included by this spread")
+                } else {
+                    format!("the field '{name}' was first declared here")
+                };
+
+                reports.add_fatal(make_double_label_report(
+                    format!("the field '{name}' was declared multiple times"),
+                    again,
+                    *span,
+                    first,
+                    first_span,
+                    filename,
+                ));
+            }
+            Entry::Vacant(entry) => {
+                entry.insert((*span, *spread));
+            }
+        }
+    }
+}
+
+/// Metadata containing indices into [`TypeSet::types`] for every identifier,
+/// and indices into [`NamedType::parameters`] of the enclosing named type for every type parameter.
 #[derive(Debug, Clone, Copy)]
 pub struct ResolutionMetadata;
 
@@ -287,8 +659,11 @@ impl Metadata for ResolutionMetadata {
     type Struct = ();
     type Enum = ();
     type List = ();
+    type Tuple = ();
     type Primitive = ();
     type Identifier = usize;
+    type Parameter = usize;
+    type Extern = ();
 
     type Field = ();
     type Variant = ();
@@ -320,18 +695,24 @@ fn make_double_label_report(
     secondary_span: Span,
     filename: &str,
 ) -> Report<'static, (&str, Range<usize>)> {
+    // Labels out of source order would be split into groups, each headed by the primary span
+    let primary_order =
+        i32::from(primary_span.into_range().start > secondary_span.into_range().start);
+
     Report::build(ReportKind::Error, (filename, primary_span.into_range()))
         .with_config(Config::new().with_index_type(IndexType::Byte))
         .with_message(error)
         .with_label(
             Label::new((filename, primary_span.into_range()))
                 .with_message(primary_label)
-                .with_color(Color::Red),
+                .with_color(Color::Red)
+                .with_order(primary_order),
         )
         .with_label(
             Label::new((filename, secondary_span.into_range()))
                 .with_message(secondary_label)
-                .with_color(Color::Yellow),
+                .with_color(Color::Yellow)
+                .with_order(1 - primary_order),
         )
         .finish()
 }
@@ -4,13 +4,17 @@
 //! This means there is a dependency cycle that does not involve a list or an enum,
 //! not counting enums where all variants contain the original type.
 //! It will also not trigger for uninhabited types.
+//! Generic types are checked with the type arguments they're used with.
 
-use std::{collections::HashMap, ops::Range};
+use std::{
+    collections::{HashMap, HashSet},
+    ops::Range,
+};
 
 use ariadne::{Color, Config, IndexType, Label, Report, ReportKind};
 
 use crate::{
-    ast::{Type, TypeSet, TypeType},
+    ast::{Scope, Type, TypeSet, TypeType},
     preprocessing::{BasicMetadata, name_resolution::INVALID_INDEX},
     reports::Reports,
     syntax::Span,
@@ -20,6 +24,12 @@ use crate::{
 struct RecursionContext<'types> {
     types: &'types TypeSet<BasicMetadata>,
     cache: HashMap<usize, CheckResult>,
+    /// The uses of generic types currently being checked, by their index and type arguments.
+    ///
+    /// Results for generic types can't be cached, since they depend on the type arguments,
+    /// so this is used to stop at cycles instead.
+    /// Type arguments are identified by their address in the AST.
+    generic_path: HashSet<(usize, *const Type<BasicMetadata>)>,
 }
 
 /// The result of checking a type .
@@ -41,9 +51,10 @@ pub fn check_recursion<'filename>(
         let mut context = RecursionContext {
             types,
             cache: HashMap::new(),
+            generic_path: HashSet::new(),
         };
 
-        if check_type(&r#type.r#type, &mut context) == CheckResult::InfiniteDepth {
+        if check_type(&r#type.r#type, &Scope::ROOT, &mut context) == CheckResult::InfiniteDepth {
             reports.add_nonfatal(make_report(
                 format!(
                     "the type '{name}' will unavoidably have infinite depth",
@@ -67,29 +78,72 @@ fn check_named(index: usize, context: &mut RecursionContext) -> CheckResult {
     }
 
     context.cache.insert(index, CheckResult::InfiniteDepth);
-    let result = check_type(&context.types.types[index].r#type, context);
+    let result = check_type(&context.types.types[index].r#type, &Scope::ROOT, context);
     context.cache.insert(index, result);
     result
 }
 
+/// Runs the check for a use of a generic top-level type, with the type arguments in `scope`.
+fn check_generic(
+    index: usize,
+    scope: &Scope<BasicMetadata>,
+    context: &mut RecursionContext,
+) -> CheckResult {
+    if index == INVALID_INDEX {
+        return CheckResult::None;
+    }
+
+    let key = (index, scope.arguments.as_ptr());
+
+    if !context.generic_path.insert(key) {
+        return CheckResult::InfiniteDepth;
+    }
+
+    let result = check_type(&context.types.types[index].r#type, scope, context);
+    context.generic_path.remove(&key);
+    result
+}
+
 /// Visits and checks a type recursively.
-fn check_type(r#type: &Type<BasicMetadata>, context: &mut RecursionContext) -> CheckResult {
+fn check_type(
+    r#type: &Type<BasicMetadata>,
+    scope: &Scope<BasicMetadata>,
+    context: &mut RecursionContext,
+) -> CheckResult {
     match &r#type.r#type {
         TypeType::Struct(r#struct) => r#struct
             .fields
             .iter()
-            .map(|field| check_type(&field.r#type, context))
+            .map(|field| check_type(&field.r#type, scope, context))
             .max()
             .unwrap_or(CheckResult::None),
         TypeType::Enum(r#enum) => r#enum
             .variants
             .iter()
-            .map(|variant| check_type(&variant.r#type, context))
+            .map(|variant| check_type(&variant.r#type, scope, context))
             .min()
             .unwrap_or(CheckResult::ContainsNever),
         TypeType::List(_list) => CheckResult::None,
+        TypeType::Tuple(tuple) => tuple
+            .elements
+            .iter()
+            .map(|element| check_type(element, scope, context))
+            .max()
+            .unwrap_or(CheckResult::None),
         TypeType::Primitive(_primitive) => CheckResult::None,
-        TypeType::Identifier(identifier) => check_named(identifier.metadata.resolution, context),
+        TypeType::Extern(_extern) => CheckResult::None,
+        TypeType::Identifier(identifier) if identifier.arguments.is_empty() => {
+            check_named(identifier.metadata.resolution, context)
+        }
+        TypeType::Identifier(identifier) => check_generic(
+            identifier.metadata.resolution,
+            &scope.enter(&identifier.arguments),
+            context,
+        ),
+        TypeType::Parameter(parameter) => match scope.argument(parameter.metadata.resolution) {
+            Some((argument, scope)) => check_type(argument, scope, context),
+            None => CheckResult::None,
+        },
     }
 }
 
@@ -0,0 +1,392 @@
+//! The backend for chaining Rust migrations across multiple versions.
+
+use std::{
+    collections::HashSet,
+    io::{Result, Write},
+};
+
+use crate::{
+    ast::{Migration, NamedType},
+    codegen::{idents::disambiguate, source_writer::SourceWriter},
+    rust::{
+        RustMigrationMetadata,
+        codegen::version_variant_name,
+        migrations::{self, function_to},
+    },
+};
+
+/// A named type that exists in every version of a migration chain.
+#[derive(Debug)]
+struct TypeChain<'a> {
+    /// The name of the module containing the `AnyVersion` enum for this type.
+    module: String,
+    /// The Rust name of the type in every version.
+    names: Vec<&'a str>,
+    /// The names of the upgrade functions for every migration.
+    upgrades: Vec<String>,
+    /// The names of the downgrade functions for every migration.
+    downgrades: Vec<String>,
+}
+
+/// A version of the schema.
+#[derive(Debug)]
+struct Version<'a> {
+    /// The name of the version in the schema.
+    name: &'a str,
+    /// The name of the module with the type declarations.
+    module: &'a str,
+    /// The name of the corresponding variant of the `Version` and `AnyVersion` enums.
+    variant: String,
+}
+
+/// Emits the `migrations` module, which declares all migration modules and chains them.
+///
+/// The migrations must be ordered, such that every migration starts with the version
+/// the previous one ended with.
+/// If `serde` is set, the `AnyVersion` enums will be serializable as version-tagged envelopes.
+/// If `fallible` is set, the migrations are expected to have fallible downgrades.
+pub fn emit_chain(
+    writer: &mut SourceWriter<impl Write>,
+    migrations: &[Migration<RustMigrationMetadata>],
+    serde: bool,
+    fallible: bool,
+) -> Result<()> {
+    let versions = collect_versions(migrations);
+    let chains = collect_chains(migrations);
+
+    for migration in migrations {
+        writer.write_fmt_nl(format_args!(
+            "pub mod {};",
+            migration.new.metadata.base.name
+        ))?;
+    }
+    writer.blank_line();
+
+    writer.write_nl("#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]")?;
+    writer.write_nl("pub enum Version {")?;
+    writer.indent();
+
+    for version in &versions {
+        writer.write_fmt_nl(format_args!("{},", version.variant))?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    if fallible {
+        emit_downgrade_error(writer, &versions)?;
+    }
+
+    for chain in &chains {
+        emit_type_chain(writer, &versions, chain, serde, fallible)?;
+    }
+
+    Ok(())
+}
+
+/// Emits the error type of fallible downgrades across multiple versions.
+///
+/// It can be created from the error type of any migration.
+fn emit_downgrade_error(writer: &mut SourceWriter<impl Write>, versions: &[Version]) -> Result<()> {
+    migrations::emit_downgrade_error(writer, Some("Version"))?;
+    writer.blank_line();
+
+    for (old, new) in versions.iter().zip(&versions[1..]) {
+        writer.write_fmt_nl(format_args!(
+            "impl From<{}::DowngradeError> for DowngradeError {{",
+            new.module
+        ))?;
+        writer.indent();
+        writer.write_fmt_nl(format_args!(
+            "fn from(error: {}::DowngradeError) -> Self {{",
+            new.module
+        ))?;
+        writer.indent();
+        writer.write_nl("Self {")?;
+        writer.indent();
+        writer.write_fmt_nl(format_args!("version: Version::{},", old.variant))?;
+        writer.write_nl("type_name: error.type_name,")?;
+        writer.write_nl("variant: error.variant,")?;
+        writer.dedent();
+        writer.write_nl("}")?;
+        writer.dedent();
+        writer.write_nl("}")?;
+        writer.dedent();
+        writer.write_nl("}")?;
+        writer.blank_line();
+    }
+
+    Ok(())
+}
+
+/// Emits the module with the `AnyVersion` enum for one type.
+fn emit_type_chain(
+    writer: &mut SourceWriter<impl Write>,
+    versions: &[Version],
+    chain: &TypeChain,
+    serde: bool,
+    fallible: bool,
+) -> Result<()> {
+    writer.write_fmt_nl(format_args!("pub mod {} {{", chain.module))?;
+    writer.indent();
+
+    if serde {
+        writer.write_nl("#[derive(Debug, Clone, ::serde::Serialize, ::serde::Deserialize)]")?;
+        writer.write_nl(r#"#[serde(tag = "version", content = "data")]"#)?;
+    } else {
+        writer.write_nl("#[derive(Debug, Clone)]")?;
+    }
+    writer.write_nl("pub enum AnyVersion {")?;
+    writer.indent();
+
+    for (version, name) in versions.iter().zip(&chain.names) {
+        if serde {
+            writer.write_fmt_nl(format_args!("#[serde(rename = {:?})]", version.name))?;
+        }
+
+        writer.write_fmt_nl(format_args!(
+            "{}(super::super::{}::{name}),",
+            version.variant, version.module
+        ))?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_nl("impl AnyVersion {")?;
+    writer.indent();
+
+    writer.write_nl("pub fn version(&self) -> super::Version {")?;
+    writer.indent();
+    writer.write_nl("match self {")?;
+    writer.indent();
+
+    for version in versions {
+        let variant = &version.variant;
+        writer.write_fmt_nl(format_args!(
+            "AnyVersion::{variant}(_) => super::Version::{variant},"
+        ))?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    let (latest, steps) = versions.split_last().expect("chain to have a version");
+
+    writer.write_fmt_nl(format_args!(
+        "pub fn upgrade_to_latest(self) -> super::super::{}::{} {{",
+        latest.module,
+        chain.names.last().expect("chain to have a version")
+    ))?;
+    writer.indent();
+    writer.write_nl("let mut value = self;")?;
+    writer.blank_line();
+    writer.write_nl("loop {")?;
+    writer.indent();
+    writer.write_nl("value = match value {")?;
+    writer.indent();
+
+    for (index, (old, new)) in steps.iter().zip(&versions[1..]).enumerate() {
+        writer.write_fmt_nl(format_args!(
+            "AnyVersion::{}(value) => AnyVersion::{}(super::{}::upgrade::{}(value)),",
+            old.variant, new.variant, new.module, chain.upgrades[index]
+        ))?;
+    }
+
+    writer.write_fmt_nl(format_args!(
+        "AnyVersion::{}(value) => return value,",
+        latest.variant
+    ))?;
+
+    writer.dedent();
+    writer.write_nl("};")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    if fallible {
+        writer.write_nl("pub fn downgrade_to(")?;
+        writer.indent();
+        writer.write_nl("self,")?;
+        writer.write_nl("version: super::Version,")?;
+        writer.dedent();
+        writer.write_nl(") -> ::std::result::Result<Self, super::DowngradeError> {")?;
+    } else {
+        writer.write_nl("pub fn downgrade_to(self, version: super::Version) -> Self {")?;
+    }
+
+    writer.indent();
+    writer.write_nl("let mut value = self;")?;
+    writer.blank_line();
+    writer.write_nl("loop {")?;
+    writer.indent();
+    writer.write_nl("value = match value {")?;
+    writer.indent();
+
+    for (index, (old, new)) in steps.iter().zip(&versions[1..]).enumerate() {
+        writer.write_fmt_nl(format_args!(
+            "AnyVersion::{new_variant}(value) if version < super::Version::{new_variant} => {{",
+            new_variant = new.variant
+        ))?;
+        writer.indent();
+        writer.write_fmt_nl(format_args!(
+            "AnyVersion::{}(super::{}::downgrade::{}(value){})",
+            old.variant,
+            new.module,
+            chain.downgrades[index],
+            if fallible { "?" } else { "" }
+        ))?;
+        writer.dedent();
+        writer.write_nl("}")?;
+    }
+
+    if fallible {
+        writer.write_nl("value => return Ok(value),")?;
+    } else {
+        writer.write_nl("value => return value,")?;
+    }
+
+    writer.dedent();
+    writer.write_nl("};")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    if serde {
+        writer.write_nl("pub fn deserialize_any_version<'de, D: ::serde::Deserializer<'de>>(")?;
+        writer.indent();
+        writer.write_nl("deserializer: D,")?;
+        writer.dedent();
+        writer.write_fmt_nl(format_args!(
+            ") -> ::std::result::Result<super::super::{}::{}, D::Error> {{",
+            latest.module,
+            chain.names.last().expect("chain to have a version")
+        ))?;
+        writer.indent();
+        writer.write_nl("<AnyVersion as ::serde::Deserialize>::deserialize(deserializer)")?;
+        writer.indent();
+        writer.write_nl(".map(AnyVersion::upgrade_to_latest)")?;
+        writer.dedent();
+        writer.dedent();
+        writer.write_nl("}")?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Lists all versions in the chain, in order.
+fn collect_versions(migrations: &[Migration<RustMigrationMetadata>]) -> Vec<Version<'_>> {
+    let modules = migrations
+        .first()
+        .map(|migration| &migration.old)
+        .into_iter()
+        .chain(migrations.iter().map(|migration| &migration.new));
+
+    let mut used_variants = HashSet::new();
+
+    modules
+        .map(|types| {
+            let mut variant = version_variant_name(&types.version);
+            disambiguate(&mut variant, |name| used_variants.contains(name));
+            used_variants.insert(variant.clone());
+
+            Version {
+                name: &types.version,
+                module: &types.metadata.base.name,
+                variant,
+            }
+        })
+        .collect()
+}
+
+/// Finds all named types that can be migrated through every version of the chain.
+///
+/// A type is followed through a migration using its migration marker,
+/// and from one migration to the next using its name,
+/// as the new half of a migration and the old half of the next one describe the same schema.
+fn collect_chains(migrations: &[Migration<RustMigrationMetadata>]) -> Vec<TypeChain<'_>> {
+    let Some(first) = migrations.first() else {
+        return vec![];
+    };
+
+    let mut used_modules: HashSet<String> = migrations
+        .iter()
+        .map(|migration| migration.new.metadata.base.name.clone())
+        .collect();
+    let mut chains = vec![];
+
+    for named in &first.old.types {
+        // Generic types can't be migrated on their own, only through the types using them
+        if !named.parameters.is_empty() {
+            continue;
+        }
+
+        let Some((chain, latest)) = follow_type(named, migrations) else {
+            continue;
+        };
+
+        let mut module = latest.r#type.metadata.migration_name.clone();
+        disambiguate(&mut module, |name| used_modules.contains(name));
+        used_modules.insert(module.clone());
+
+        chains.push(TypeChain { module, ..chain });
+    }
+
+    chains
+}
+
+/// Follows a named type through all migrations.
+///
+/// Returns `None` if the type doesn't exist in some version.
+/// Otherwise returns the chain, with an empty module name, and the type in the latest version.
+fn follow_type<'a>(
+    mut named: &'a NamedType<RustMigrationMetadata>,
+    migrations: &'a [Migration<RustMigrationMetadata>],
+) -> Option<(TypeChain<'a>, &'a NamedType<RustMigrationMetadata>)> {
+    let mut chain = TypeChain {
+        module: String::new(),
+        names: vec![&named.r#type.metadata.base.name],
+        upgrades: vec![],
+        downgrades: vec![],
+    };
+
+    for (index, migration) in migrations.iter().enumerate() {
+        let number = named.r#type.number?;
+        let new = migration
+            .new
+            .types
+            .iter()
+            .find(|new| new.r#type.number == Some(number))?;
+
+        chain
+            .upgrades
+            .push(function_to("upgrade", &new.r#type).to_string());
+        chain
+            .downgrades
+            .push(function_to("downgrade", &named.r#type).to_string());
+        chain.names.push(&new.r#type.metadata.base.name);
+
+        named = match migrations.get(index + 1) {
+            Some(next) => next.old.types.iter().find(|next| next.name == new.name)?,
+            None => new,
+        };
+    }
+
+    Some((chain, named))
+}
@@ -1,25 +1,39 @@
 //! Helpers shared by the type declaration and migration backends.
 
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     fmt,
     io::{Result, Write},
 };
 
 use crate::{
-    ast::{PrimitiveType, Type, TypeSet, TypeType},
-    codegen::source_writer::SourceWriter,
+    ast::{Enum, Parameter, PrimitiveType, Type, TypeSet, TypeType, Value},
+    codegen::{
+        idents::{PascalCase, convert_case, disambiguate},
+        source_writer::SourceWriter,
+    },
     metadata::{GetMetadata, Metadata},
-    rust::RustMetadata,
+    rust::{RustMetadata, TimeCrate, idents::RustIdentRules},
 };
 
 /// The information needed by [`write_type_name`].
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug)]
 pub struct NamingContext<'a, M: Metadata> {
     pub types: &'a TypeSet<M>,
     pub used_type_names: &'a HashSet<&'a str>,
+    /// Appended to the names of type parameters.
+    pub parameter_suffix: &'a str,
+    pub time_crate: TimeCrate,
 }
 
+impl<M: Metadata> Clone for NamingContext<'_, M> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<M: Metadata> Copy for NamingContext<'_, M> {}
+
 impl<M: Metadata> NamingContext<'_, M> {
     /// Returns `name`, unless a user-defined type conflicts with it, then it returns `fallback`.
     pub fn rust_type<'b>(&self, name: &'b str, fallback: &'b str) -> &'b str {
@@ -43,14 +57,14 @@ pub fn write_type_name<M, GM, W>(
 where
     M: Metadata,
     W: Write,
-    GM: GetMetadata<M, RustMetadata>,
+    GM: GetMetadata<M, RustMetadata> + Copy,
 {
     let metadata = get.get_type(&r#type.metadata);
 
     if metadata.newtype && !inspect_newtypes {
         writer.write_fmt(self_path)?;
         writer.write(&metadata.name)?;
-        return Ok(());
+        return write_parameters(writer, context, &used_parameters(r#type, get), get);
     }
 
     if metadata.r#box {
@@ -62,6 +76,12 @@ where
         TypeType::Struct(_) | TypeType::Enum(_) => {
             writer.write_fmt(self_path)?;
             writer.write(&metadata.name)?;
+            write_parameters(writer, context, &used_parameters(r#type, get), get)?;
+        }
+        // The type alias of the extern type, which refers to the type it's mapped to
+        TypeType::Extern(_) => {
+            writer.write_fmt(self_path)?;
+            writer.write(&metadata.name)?;
         }
         TypeType::List(list) => {
             writer.write(context.rust_type("Vec", "::std::vec::Vec"))?;
@@ -76,18 +96,54 @@ where
             )?;
             writer.write(">")?;
         }
-        TypeType::Primitive(primitive) => {
-            writer.write(match primitive.r#type {
-                PrimitiveType::String => context.rust_type("String", "::std::string::String"),
-                PrimitiveType::Number => context.rust_type("i64", "::std::primitive::i64"),
-                PrimitiveType::Unit => "()",
-            })?;
+        TypeType::Tuple(tuple) => {
+            writer.write("(")?;
+
+            for (index, element) in tuple.elements.iter().enumerate() {
+                if index > 0 {
+                    writer.write(", ")?;
+                }
+
+                write_type_name(writer, context, element, self_path, inspect_newtypes, get)?;
+            }
+
+            writer.write(")")?;
         }
+        TypeType::Primitive(primitive) => match primitive.r#type {
+            PrimitiveType::String => {
+                writer.write(context.rust_type("String", "::std::string::String"))?
+            }
+            PrimitiveType::Number => {
+                writer.write(context.rust_type("i64", "::std::primitive::i64"))?
+            }
+            PrimitiveType::Unit => writer.write("()")?,
+            PrimitiveType::Uuid => writer.write("::uuid::Uuid")?,
+            PrimitiveType::Bytes => {
+                writer.write(context.rust_type("Vec", "::std::vec::Vec"))?;
+                writer.write("<")?;
+                writer.write(context.rust_type("u8", "::std::primitive::u8"))?;
+                writer.write(">")?;
+            }
+            r#type => writer.write(context.time_crate.rust_type(r#type))?,
+        },
         TypeType::Identifier(identifier) => {
             let index = get.get_identifier(&identifier.metadata).resolution;
             let r#type = &context.types.types[index].r#type;
             writer.write_fmt(self_path)?;
             writer.write(&get.get_type(&r#type.metadata).name)?;
+
+            for (index, argument) in identifier.arguments.iter().enumerate() {
+                writer.write(if index == 0 { "<" } else { ", " })?;
+                write_type_name(writer, context, argument, self_path, inspect_newtypes, get)?;
+            }
+
+            if !identifier.arguments.is_empty() {
+                writer.write(">")?;
+            }
+        }
+        TypeType::Parameter(parameter) => {
+            writer.write(&get.get_parameter(&parameter.metadata).name)?;
+            writer.write(context.parameter_suffix)?;
         }
     }
 
@@ -98,6 +154,167 @@ where
     Ok(())
 }
 
+/// Writes a list of type parameters, like `<T, U>`, or nothing if it's empty.
+pub fn write_parameters<M, GM, W>(
+    writer: &mut SourceWriter<W>,
+    context: NamingContext<M>,
+    parameters: &[&Parameter<M>],
+    get: GM,
+) -> Result<()>
+where
+    M: Metadata,
+    W: Write,
+    GM: GetMetadata<M, RustMetadata>,
+{
+    for (index, parameter) in parameters.iter().enumerate() {
+        writer.write(if index == 0 { "<" } else { ", " })?;
+        writer.write(&get.get_parameter(&parameter.metadata).name)?;
+        writer.write(context.parameter_suffix)?;
+    }
+
+    if !parameters.is_empty() {
+        writer.write(">")?;
+    }
+
+    Ok(())
+}
+
+/// Collects the type parameters used inside a type, ordered like in the declaration of the named type.
+///
+/// Structs and enums are generic over exactly the parameters they use,
+/// so nested types of generic named types only get the parameters they need.
+pub fn used_parameters<M, GM>(r#type: &Type<M>, get: GM) -> Vec<&Parameter<M>>
+where
+    M: Metadata,
+    GM: GetMetadata<M, RustMetadata>,
+{
+    let mut parameters = BTreeMap::new();
+    let mut stack = vec![r#type];
+
+    while let Some(r#type) = stack.pop() {
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) => {
+                stack.extend(r#struct.fields.iter().map(|field| &field.r#type));
+            }
+            TypeType::Enum(r#enum) => {
+                stack.extend(r#enum.variants.iter().map(|variant| &variant.r#type));
+            }
+            TypeType::List(list) => stack.push(&list.r#type),
+            TypeType::Tuple(tuple) => stack.extend(&tuple.elements),
+            TypeType::Primitive(_) | TypeType::Extern(_) => {}
+            TypeType::Identifier(identifier) => stack.extend(&identifier.arguments),
+            TypeType::Parameter(parameter) => {
+                let index = get.get_parameter(&parameter.metadata).resolution;
+                parameters.entry(index).or_insert(parameter);
+            }
+        }
+    }
+
+    parameters.into_values().collect()
+}
+
+/// Gets the name of the catch-all variant of an open enum,
+/// which is `Unknown`, unless another variant already has that name.
+pub fn unknown_variant_name<M, GM>(r#enum: &Enum<M>, get: GM) -> String
+where
+    M: Metadata,
+    GM: GetMetadata<M, RustMetadata>,
+{
+    let mut name = "Unknown".to_owned();
+    disambiguate(&mut name, |name| {
+        r#enum
+            .variants
+            .iter()
+            .any(|variant| get.get_variant(&variant.metadata).name == name)
+    });
+    name
+}
+
+/// Writes a Rust expression evaluating to a literal value of a type.
+///
+/// The value is wrapped in the constructors of the newtypes and boxes it goes through, like `Tree(vec![])`.
+pub fn write_value<M, GM>(
+    writer: &mut SourceWriter<impl Write>,
+    context: NamingContext<M>,
+    r#type: &Type<M>,
+    value: &Value,
+    self_path: fmt::Arguments,
+    get: GM,
+) -> Result<()>
+where
+    M: Metadata,
+    GM: GetMetadata<M, RustMetadata> + Copy,
+{
+    let metadata = get.get_type(&r#type.metadata);
+    if metadata.r#box {
+        writer.write(context.rust_type("Box", "::std::boxed::Box"))?;
+        writer.write("::new(")?;
+    }
+
+    match (&r#type.r#type, value) {
+        (TypeType::Identifier(identifier), value) => {
+            let index = get.get_identifier(&identifier.metadata).resolution;
+            let aliased = &context.types.types[index].r#type;
+            let aliased_metadata = get.get_type(&aliased.metadata);
+
+            if aliased_metadata.newtype {
+                writer.write_fmt(self_path)?;
+                writer.write(&aliased_metadata.name)?;
+                writer.write("(")?;
+                write_value(writer, context, aliased, value, self_path, get)?;
+                writer.write(")")?;
+            } else {
+                write_value(writer, context, aliased, value, self_path, get)?;
+            }
+        }
+        (TypeType::List(list), Value::List(elements)) => {
+            writer.write("vec![")?;
+
+            for (index, element) in elements.iter().enumerate() {
+                if index > 0 {
+                    writer.write(", ")?;
+                }
+
+                write_value(writer, context, &list.r#type, element, self_path, get)?;
+            }
+
+            writer.write("]")?;
+        }
+        (_, Value::Number(number)) => writer.write_fmt(format_args!("{number}"))?,
+        (_, Value::String(string)) => writer.write_fmt(format_args!("{string:?}.to_owned()"))?,
+        (_, Value::List(_)) => unreachable!("lists to only be the values of list types"),
+    }
+
+    if metadata.r#box {
+        writer.write(")")?;
+    }
+
+    Ok(())
+}
+
+/// Checks whether a literal value is the same as the [`Default`] value of its Rust type.
+///
+/// Newtypes, possibly behind type aliases, don't implement [`Default`], so their values never are.
+pub fn is_default_value<M, GM>(types: &TypeSet<M>, r#type: &Type<M>, value: &Value, get: GM) -> bool
+where
+    M: Metadata,
+    GM: GetMetadata<M, RustMetadata> + Copy,
+{
+    if let TypeType::Identifier(identifier) = &r#type.r#type {
+        let index = get.get_identifier(&identifier.metadata).resolution;
+        let aliased = &types.types[index].r#type;
+
+        return !get.get_type(&aliased.metadata).newtype
+            && is_default_value(types, aliased, value, get);
+    }
+
+    match value {
+        Value::Number(number) => *number == 0,
+        Value::String(string) => string.is_empty(),
+        Value::List(elements) => elements.is_empty(),
+    }
+}
+
 /// Computes the set of all Rust type names used by a schema.
 pub fn all_rust_type_names<M: Metadata>(
     types: &TypeSet<M>,
@@ -136,7 +353,23 @@ fn add_all_rust_type_names_for_type<'a, M: Metadata>(
             }
         }
         TypeType::List(list) => add_all_rust_type_names_for_type(&list.r#type, set, get_metadata),
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                add_all_rust_type_names_for_type(element, set, get_metadata);
+            }
+        }
         TypeType::Primitive(_primitive) => {}
-        TypeType::Identifier(_identifier) => {}
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                add_all_rust_type_names_for_type(argument, set, get_metadata);
+            }
+        }
+        TypeType::Parameter(_parameter) => {}
+        TypeType::Extern(_extern) => {}
     }
 }
+
+/// Computes the name of the enum variant that represents a given version of the schema.
+pub fn version_variant_name(version: &str) -> String {
+    convert_case([version], PascalCase, RustIdentRules)
+}
@@ -8,21 +8,28 @@ use std::{
 
 use crate::{
     ast::{
-        Enum, Field, Identifier, List, Migration, Primitive, Struct, Type, TypeSet, TypeType,
-        Variant,
+        Enum, Field, Identifier, List, Migration, Parameter, Primitive, Struct, Tuple, Type,
+        TypeSet, TypeType, Variant,
     },
     codegen::{
-        idents::{IdentRules, disambiguate},
+        idents::{IdentRules, SnakeCase, convert_case, disambiguate},
         source_writer::SourceWriter,
     },
-    migrations::TypePair,
+    metadata::GetMetadata,
+    migrations::{
+        TypePair,
+        conversions::{Versions, find_conversion, is_linked},
+    },
     rust::{
-        GetBase, RustMigrationMetadata,
-        codegen::{self, NamingContext},
+        GetBase, GetBaseResolution, RustMigrationMetadata, RustMigrationOptions,
+        codegen::{self, NamingContext, used_parameters},
         idents::RustIdentRules,
     },
 };
 
+mod conversions;
+mod property_tests;
+
 /// The context for the Rust migration backend.
 #[derive(Debug, Clone, Copy)]
 pub struct Context<'a> {
@@ -30,6 +37,12 @@ pub struct Context<'a> {
     new: NamingContext<'a, RustMigrationMetadata>,
     direction: &'static str,
     function_names: &'a HashSet<String>,
+    /// Whether the migration functions return a [`Result`].
+    fallible: bool,
+    /// The type parameters the current migration function is generic over.
+    closures: &'a [ParameterClosure],
+    /// The Rust types that extern types are mapped to.
+    externs: &'a HashMap<String, String>,
 }
 
 impl<'a> Context<'a> {
@@ -39,28 +52,88 @@ impl<'a> Context<'a> {
         old: &'a Type<RustMigrationMetadata>,
         new: &'a Type<RustMigrationMetadata>,
     ) -> Option<impl Display> {
-        if old.number.zip(new.number).is_some_and(|(o, n)| o == n) {
+        if is_linked(old, new) {
             Some(function_to(self.direction, new))
         } else {
             None
         }
     }
 
+    /// Gets the versions to look up conversions between.
+    fn versions(&self) -> Versions<'a, RustMigrationMetadata, GetBaseResolution> {
+        Versions {
+            old: self.old.types,
+            new: self.new.types,
+            get: GetBaseResolution,
+        }
+    }
+
     /// Gets the name of the migration function to `new`, assuming it exists.
     fn function_to(&'a self, new: &'a Type<RustMigrationMetadata>) -> impl Display {
         function_to(self.direction, new)
     }
 
+    /// Gets the operator propagating errors of migration function calls, if they can fail.
+    fn try_operator(&self) -> &'static str {
+        if self.fallible { "?" } else { "" }
+    }
+
     /// Gets a free name for a local variable.
     fn free_name<'b>(&'a self, base: &'b str) -> String {
         let mut result = base.to_string();
         disambiguate(&mut result, |name| self.function_names.contains(name));
         result
     }
+
+    /// Gets the closures to pass to the migration function between `old` and `new`,
+    /// each preceded by a comma.
+    fn closure_arguments(
+        &self,
+        old: &Type<RustMigrationMetadata>,
+        new: &Type<RustMigrationMetadata>,
+    ) -> String {
+        parameter_indices(old, new)
+            .into_iter()
+            .filter_map(|index| self.closures.iter().find(|closure| closure.index == index))
+            .map(|closure| format!(", {}", closure.closure))
+            .collect()
+    }
+}
+
+/// A type parameter that a migration function is generic over.
+///
+/// The function takes a closure migrating values of the parameter from `input` to `output`,
+/// which are the names of its generic types.
+#[derive(Debug, Clone)]
+struct ParameterClosure {
+    /// The index of the parameter in the named type.
+    index: usize,
+    input: String,
+    output: String,
+    /// The name of the argument holding the closure.
+    closure: String,
+}
+
+/// Gets the indices of the type parameters that the migration function between `old` and `new` is generic over.
+///
+/// These are the parameters used by either version, which are linked by their position.
+fn parameter_indices(
+    old: &Type<RustMigrationMetadata>,
+    new: &Type<RustMigrationMetadata>,
+) -> Vec<usize> {
+    let mut indices: Vec<usize> = used_parameters(old, GetBase)
+        .into_iter()
+        .chain(used_parameters(new, GetBase))
+        .map(|parameter| GetBase.get_parameter(&parameter.metadata).resolution)
+        .collect();
+
+    indices.sort_unstable();
+    indices.dedup();
+    indices
 }
 
 /// Gets the name of the migration function to `new`, assuming it exists.
-fn function_to<'a>(direction: &'a str, new: &'a Type<RustMigrationMetadata>) -> impl Display {
+pub fn function_to<'a>(direction: &'a str, new: &'a Type<RustMigrationMetadata>) -> impl Display {
     let name = &new.metadata.migration_name;
     let name = name
         .strip_prefix(RustIdentRules.reserved_prefix())
@@ -116,18 +189,41 @@ impl<'a, T> GenericPair<'a, T> {
 /// An invocation of the [`todo!`] macro.
 const TODO: &str = "todo!()";
 
+/// The name of the error type of fallible downgrades.
+const DOWNGRADE_ERROR: &str = "DowngradeError";
+
+/// Appended to type parameters of the version migrated from, like `TIn`.
+const INPUT_SUFFIX: &str = "In";
+
+/// Appended to type parameters of the version migrated to, like `TOut`.
+const OUTPUT_SUFFIX: &str = "Out";
+
 /// Emits all migration functions.
 pub fn emit_migrations(
     writer: &mut SourceWriter<impl Write>,
     migration: &Migration<RustMigrationMetadata>,
     pairs: &[TypePair<RustMigrationMetadata>],
+    options: &RustMigrationOptions,
 ) -> Result<()> {
     let swaped_pairs: Vec<TypePair<RustMigrationMetadata>> = pairs
         .iter()
         .map(|TypePair { old, new }| TypePair { old: new, new: old })
         .collect();
 
-    emit_directional_migration(writer, &migration.old, &migration.new, pairs, "upgrade")?;
+    if options.fallible_downgrades {
+        emit_downgrade_error(writer, None)?;
+        writer.blank_line();
+    }
+
+    emit_directional_migration(
+        writer,
+        &migration.old,
+        &migration.new,
+        pairs,
+        "upgrade",
+        false,
+        options,
+    )?;
     writer.blank_line();
     emit_directional_migration(
         writer,
@@ -135,11 +231,87 @@ pub fn emit_migrations(
         &migration.old,
         &swaped_pairs,
         "downgrade",
+        options.fallible_downgrades,
+        options,
     )?;
 
+    if options.property_tests {
+        let context = Context {
+            old: codegen::NamingContext {
+                types: &migration.old,
+                used_type_names: &HashSet::new(),
+                parameter_suffix: INPUT_SUFFIX,
+                time_crate: options.time_crate,
+            },
+            new: codegen::NamingContext {
+                types: &migration.new,
+                used_type_names: &HashSet::new(),
+                parameter_suffix: OUTPUT_SUFFIX,
+                time_crate: options.time_crate,
+            },
+            direction: "upgrade",
+            function_names: &HashSet::new(),
+            fallible: false,
+            closures: &[],
+            externs: &options.externs,
+        };
+
+        writer.blank_line();
+        property_tests::emit_property_tests(writer, context, pairs, options.fallible_downgrades)?;
+    }
+
     Ok(())
 }
 
+/// Emits the error type of fallible downgrades.
+///
+/// With `version_type`, the name of an enum of versions, the error has a `version` field
+/// naming the version the variant doesn't exist in, which is the old version otherwise.
+pub fn emit_downgrade_error(
+    writer: &mut SourceWriter<impl Write>,
+    version_type: Option<&str>,
+) -> Result<()> {
+    writer.write_nl("#[derive(Debug, Clone, Copy, PartialEq, Eq)]")?;
+    writer.write_fmt_nl(format_args!("pub struct {DOWNGRADE_ERROR} {{"))?;
+    writer.indent();
+    if let Some(version_type) = version_type {
+        writer.write_fmt_nl(format_args!("pub version: {version_type},"))?;
+    }
+    writer.write_nl("pub type_name: &'static str,")?;
+    writer.write_nl("pub variant: &'static str,")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_fmt_nl(format_args!(
+        "impl ::std::fmt::Display for {DOWNGRADE_ERROR} {{"
+    ))?;
+    writer.indent();
+    writer.write_nl("fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {")?;
+    writer.indent();
+    writer.write_nl("write!(")?;
+    writer.indent();
+    writer.write_nl("f,")?;
+    if version_type.is_some() {
+        writer.write_nl(r#""the variant {} of {} doesn't exist in version {:?}","#)?;
+        writer.write_nl("self.variant, self.type_name, self.version")?;
+    } else {
+        writer.write_nl(r#""the variant {} of {} doesn't exist in the old version","#)?;
+        writer.write_nl("self.variant, self.type_name")?;
+    }
+    writer.dedent();
+    writer.write_nl(")")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_fmt_nl(format_args!(
+        "impl ::std::error::Error for {DOWNGRADE_ERROR} {{}}"
+    ))
+}
+
 /// Emits all migration functions in a given direction.
 fn emit_directional_migration(
     writer: &mut SourceWriter<impl Write>,
@@ -147,6 +319,8 @@ fn emit_directional_migration(
     new: &TypeSet<RustMigrationMetadata>,
     pairs: &[TypePair<RustMigrationMetadata>],
     direction: &'static str,
+    fallible: bool,
+    options: &RustMigrationOptions,
 ) -> Result<()> {
     let mut function_names = HashSet::new();
 
@@ -159,13 +333,20 @@ fn emit_directional_migration(
         old: codegen::NamingContext {
             types: old,
             used_type_names: &HashSet::new(),
+            parameter_suffix: INPUT_SUFFIX,
+            time_crate: options.time_crate,
         },
         new: codegen::NamingContext {
             types: new,
             used_type_names: &HashSet::new(),
+            parameter_suffix: OUTPUT_SUFFIX,
+            time_crate: options.time_crate,
         },
         direction,
         function_names: &function_names,
+        fallible,
+        closures: &[],
+        externs: &options.externs,
     };
 
     writer.write_fmt_nl(format_args!("pub mod {direction} {{"))?;
@@ -188,24 +369,79 @@ fn emit_directional_migration(
 }
 
 /// Emits one migration function.
+///
+/// Functions for types using type parameters are generic,
+/// and take a closure migrating the values of each parameter.
 fn emit_function(
     writer: &mut SourceWriter<impl Write>,
     context: Context,
     pair: TypePair<RustMigrationMetadata>,
 ) -> Result<()> {
-    writer.write_fmt(format_args!("pub fn {}(", context.function_to(pair.new)))?;
+    let closures = parameter_closures(context, pair);
+    let context = Context {
+        closures: &closures,
+        ..context
+    };
+
+    writer.write_fmt(format_args!("pub fn {}", context.function_to(pair.new)))?;
+
+    for (index, closure) in closures.iter().enumerate() {
+        let separator = if index == 0 { "<" } else { ", " };
+        writer.write_fmt(format_args!(
+            "{separator}{}, {}",
+            closure.input, closure.output
+        ))?;
+    }
+
+    if !closures.is_empty() {
+        writer.write(">")?;
+    }
 
     let expr = context.free_name(&pair.old.metadata.migration_name);
-    writer.write_fmt(format_args!("{expr}: "))?;
+    writer.write_fmt(format_args!("({expr}: "))?;
     write_type_name(writer, context.old, pair.old)?;
 
+    for closure in &closures {
+        writer.write_fmt(format_args!(
+            ", {}: &dyn Fn({}) -> ",
+            closure.closure, closure.input
+        ))?;
+
+        if context.fallible {
+            writer.write_fmt(format_args!(
+                "Result<{}, super::{DOWNGRADE_ERROR}>",
+                closure.output
+            ))?;
+        } else {
+            writer.write(&closure.output)?;
+        }
+    }
+
     writer.write(") -> ")?;
-    write_type_name(writer, context.new, pair.new)?;
+
+    if context.fallible {
+        writer.write("Result<")?;
+        write_type_name(writer, context.new, pair.new)?;
+        writer.write_fmt(format_args!(", super::{DOWNGRADE_ERROR}>"))?;
+    } else {
+        write_type_name(writer, context.new, pair.new)?;
+    }
+
     writer.write_nl(" {")?;
     writer.indent();
 
+    if context.fallible {
+        writer.write_nl("Ok(")?;
+        writer.indent();
+    }
+
     emit_body(writer, context, pair, format_args!("{expr}"))?;
 
+    if context.fallible {
+        writer.dedent();
+        writer.write_nl(")")?;
+    }
+
     writer.dedent();
     writer.write_nl("}")?;
     writer.blank_line();
@@ -213,6 +449,47 @@ fn emit_function(
     Ok(())
 }
 
+/// Creates the closures taken by the migration function of a pair, see [`ParameterClosure`].
+fn parameter_closures(
+    context: Context,
+    pair: TypePair<RustMigrationMetadata>,
+) -> Vec<ParameterClosure> {
+    let old_parameters = used_parameters(pair.old, GetBase);
+    let new_parameters = used_parameters(pair.new, GetBase);
+    let mut used_names = HashSet::new();
+
+    parameter_indices(pair.old, pair.new)
+        .into_iter()
+        .map(|index| {
+            let find = |parameters: &[&Parameter<RustMigrationMetadata>]| {
+                parameters
+                    .iter()
+                    .map(|parameter| GetBase.get_parameter(&parameter.metadata))
+                    .find(|parameter| parameter.resolution == index)
+                    .map(|parameter| parameter.name.clone())
+            };
+
+            let old_name = find(&old_parameters);
+            let new_name = find(&new_parameters);
+            let input = old_name.clone().or(new_name.clone());
+            let input = input.expect("the parameter to be used");
+            let output = new_name.or(old_name).expect("the parameter to be used");
+
+            let base = convert_case([output.as_str()], SnakeCase, RustIdentRules);
+            let mut closure = context.free_name(&format!("{}_{base}", context.direction));
+            disambiguate(&mut closure, |name| used_names.contains(name));
+            used_names.insert(closure.clone());
+
+            ParameterClosure {
+                index,
+                input: format!("{input}{}", context.old.parameter_suffix),
+                output: format!("{output}{}", context.new.parameter_suffix),
+                closure,
+            }
+        })
+        .collect()
+}
+
 /// Emits the body (without any boxing or newtype wrapping) of one migration function.
 fn emit_body(
     writer: &mut SourceWriter<impl Write>,
@@ -221,7 +498,7 @@ fn emit_body(
     expr: fmt::Arguments,
 ) -> Result<()> {
     if pair.new.metadata.base.newtype {
-        write_type_name(writer, context.new, pair.new)?;
+        write_path(writer, context.new, pair.new)?;
         writer.write_nl("(")?;
         writer.indent();
     }
@@ -262,6 +539,12 @@ fn emit_body(
             GenericPair::new(old, new, pair.old, pair.new),
             expr,
         )?,
+        (TypeType::Tuple(old), TypeType::Tuple(new)) => emit_tuple(
+            writer,
+            context,
+            GenericPair::new(old, new, pair.old, pair.new),
+            expr,
+        )?,
         (TypeType::Primitive(old), TypeType::Primitive(new)) => emit_primitive(
             writer,
             context,
@@ -274,7 +557,19 @@ fn emit_body(
             GenericPair::new(old, new, pair.old, pair.new),
             expr,
         )?,
-        (_old, _new) => write_todo(writer)?,
+        (TypeType::Parameter(old), TypeType::Parameter(new)) => emit_parameter(
+            writer,
+            context,
+            GenericPair::new(old, new, pair.old, pair.new),
+            expr,
+        )?,
+        // Values are passed through if both extern types stand for the same Rust type
+        (TypeType::Extern(old), TypeType::Extern(new))
+            if context.externs[&old.name] == context.externs[&new.name] =>
+        {
+            writer.write_fmt_nl(expr)?
+        }
+        (_old, _new) => write_conversion_or_todo(writer, context, pair.old, pair.new, expr)?,
     }
 
     if pair.new.metadata.base.r#box {
@@ -297,7 +592,7 @@ fn emit_struct(
     GenericPair { old, new }: GenericPair<Struct<RustMigrationMetadata>>,
     expr: fmt::Arguments,
 ) -> Result<()> {
-    write_type_name(writer, context.new, new.full)?;
+    write_path(writer, context.new, new.full)?;
     writer.write_nl(" {")?;
     writer.indent();
 
@@ -309,16 +604,28 @@ fn emit_struct(
         .collect();
 
     for field in &new.r#type.fields {
+        let old_field = field.r#type.number.and_then(|n| by_type_number.get(&n));
+
         writer.write_fmt(format_args!("{}: ", field.metadata.base.name))?;
 
-        if let Some(&old_field) = field.r#type.number.and_then(|n| by_type_number.get(&n)) {
+        if let Some(&old_field) = old_field {
             let field_name = &old_field.metadata.base.name;
 
             write_upgrade(
                 writer,
                 context,
                 format_args!("{expr}.{field_name}"),
+                &old_field.r#type,
+                &field.r#type,
+            )?;
+        } else if let Some(default) = &field.default {
+            codegen::write_value(
+                writer,
+                context.new,
                 &field.r#type,
+                default,
+                format_args!("{}::", context.new.types.metadata.base.name),
+                GetBase,
             )?;
         } else {
             writer.write(TODO)?;
@@ -351,23 +658,32 @@ fn emit_enum(
         .collect();
 
     for variant in &old.r#type.variants {
+        let new_variant = variant.r#type.number.and_then(|n| by_type_number.get(&n));
+
+        if new_variant.is_none() && context.fallible {
+            write_path(writer, context.old, old.full)?;
+            write_downgrade_error(writer, old.full, &variant.metadata.base.name)?;
+            continue;
+        }
+
         let binding = &variant.metadata.migration_name;
 
-        write_type_name(writer, context.old, old.full)?;
+        write_path(writer, context.old, old.full)?;
         writer.write_fmt(format_args!(
             "::{}({binding}) => ",
             variant.metadata.base.name
         ))?;
 
-        if let Some(&new_variant) = variant.r#type.number.and_then(|n| by_type_number.get(&n)) {
+        if let Some(&new_variant) = new_variant {
             let variant_name = &new_variant.metadata.base.name;
 
-            write_type_name(writer, context.new, new.full)?;
+            write_path(writer, context.new, new.full)?;
             writer.write_fmt(format_args!("::{variant_name}("))?;
             write_upgrade(
                 writer,
                 context,
                 format_args!("{binding}"),
+                &variant.r#type,
                 &new_variant.r#type,
             )?;
             writer.write(")")?;
@@ -378,12 +694,46 @@ fn emit_enum(
         writer.write_nl(",")?;
     }
 
+    // Values of unknown variants are passed through as they are
+    if old.r#type.open {
+        let old_name = codegen::unknown_variant_name(old.r#type, GetBase);
+        write_path(writer, context.old, old.full)?;
+
+        if new.r#type.open {
+            let new_name = codegen::unknown_variant_name(new.r#type, GetBase);
+            writer.write_fmt(format_args!("::{old_name}(value) => "))?;
+            write_path(writer, context.new, new.full)?;
+            writer.write_fmt_nl(format_args!("::{new_name}(value),"))?;
+        } else if context.fallible {
+            write_downgrade_error(writer, old.full, &old_name)?;
+        } else {
+            writer.write_fmt_nl(format_args!("::{old_name}(_) => {TODO},"))?;
+        }
+    }
+
     writer.dedent();
     writer.write_nl("}")?;
 
     Ok(())
 }
 
+/// Emits the rest of a match arm for a variant of `r#type` that can't be downgraded,
+/// after the path to the enum.
+fn write_downgrade_error(
+    writer: &mut SourceWriter<impl Write>,
+    r#type: &Type<RustMigrationMetadata>,
+    variant: &str,
+) -> Result<()> {
+    writer.write_fmt_nl(format_args!(
+        "::{variant}(_) => return Err(super::{DOWNGRADE_ERROR} {{"
+    ))?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!("type_name: {:?},", r#type.metadata.base.name))?;
+    writer.write_fmt_nl(format_args!("variant: {variant:?},"))?;
+    writer.dedent();
+    writer.write_nl("}),")
+}
+
 /// Emits the body of a migration function for a list.
 fn emit_list(
     writer: &mut SourceWriter<impl Write>,
@@ -391,24 +741,74 @@ fn emit_list(
     GenericPair { old, new }: GenericPair<List<RustMigrationMetadata>>,
     expr: fmt::Arguments,
 ) -> Result<()> {
-    if let Some(func) = context.function_between(&old.r#type.r#type, &new.r#type.r#type) {
-        writer.write_fmt_nl(format_args!("{expr}.into_iter().map({func}).collect()"))
+    let (old_element, new_element) = (&old.r#type.r#type, &new.r#type.r#type);
+
+    if let Some(func) = context.function_between(old_element, new_element) {
+        let closures = context.closure_arguments(old_element, new_element);
+        let func = if closures.is_empty() {
+            func.to_string()
+        } else {
+            format!("|element| {func}(element{closures})")
+        };
+
+        if context.fallible {
+            writer.write_fmt_nl(format_args!(
+                "{expr}.into_iter().map({func}).collect::<Result<_, super::{DOWNGRADE_ERROR}>>()?"
+            ))
+        } else {
+            writer.write_fmt_nl(format_args!("{expr}.into_iter().map({func}).collect()"))
+        }
     } else {
         write_todo(writer)
     }
 }
 
+/// Emits the body of a migration function for a tuple, migrating it element by element.
+fn emit_tuple(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    GenericPair { old, new }: GenericPair<Tuple<RustMigrationMetadata>>,
+    expr: fmt::Arguments,
+) -> Result<()> {
+    writer.write_nl("(")?;
+    writer.indent();
+
+    for element in &new.r#type.elements {
+        let old_index = old.r#type.elements.iter().position(|old_element| {
+            element.number.is_some() && old_element.number == element.number
+        });
+        if let Some(old_index) = old_index {
+            write_upgrade(
+                writer,
+                context,
+                format_args!("{expr}.{old_index}"),
+                &old.r#type.elements[old_index],
+                element,
+            )?;
+        } else {
+            writer.write(TODO)?;
+        }
+
+        writer.write_nl(",")?;
+    }
+
+    writer.dedent();
+    writer.write_nl(")")?;
+
+    Ok(())
+}
+
 /// Emits the body of a migration function for a primitive.
 fn emit_primitive(
     writer: &mut SourceWriter<impl Write>,
-    _context: Context,
+    context: Context,
     GenericPair { old, new }: GenericPair<Primitive<RustMigrationMetadata>>,
     expr: fmt::Arguments,
 ) -> Result<()> {
     if old.r#type.r#type == new.r#type.r#type {
         writer.write_fmt_nl(expr)
     } else {
-        write_todo(writer)
+        write_conversion_or_todo(writer, context, old.full, new.full, expr)
     }
 }
 
@@ -422,10 +822,68 @@ fn emit_identifier(
     let old_ref = &context.old.types.types[old.r#type.metadata.base.resolution];
     let new_ref = &context.new.types.types[new.r#type.metadata.base.resolution];
 
-    if let Some(func) = context.function_between(&old_ref.r#type, &new_ref.r#type) {
-        writer.write_fmt_nl(format_args!("{func}({expr})"))
-    } else {
-        write_todo(writer)
+    let Some(func) = context.function_between(&old_ref.r#type, &new_ref.r#type) else {
+        return write_todo(writer);
+    };
+
+    if old.r#type.arguments.len() != new.r#type.arguments.len() {
+        return write_todo(writer);
+    }
+
+    // Type arguments are migrated by closures calling the functions between them
+    let closures: Option<Vec<String>> = old
+        .r#type
+        .arguments
+        .iter()
+        .zip(&new.r#type.arguments)
+        .map(|(old, new)| {
+            context.function_between(old, new).map(|func| {
+                let closures = context.closure_arguments(old, new);
+
+                if closures.is_empty() {
+                    format!("&{func}")
+                } else {
+                    format!("&|argument| {func}(argument{closures})")
+                }
+            })
+        })
+        .collect();
+
+    match closures {
+        Some(closures) => {
+            writer.write_fmt(format_args!("{func}({expr}"))?;
+
+            for closure in closures {
+                writer.write_fmt(format_args!(", {closure}"))?;
+            }
+
+            writer.write_fmt_nl(format_args!("){}", context.try_operator()))
+        }
+        None => write_todo(writer),
+    }
+}
+
+/// Emits the body of a migration function for a type parameter, which calls its closure.
+fn emit_parameter(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    GenericPair { old, new }: GenericPair<Parameter<RustMigrationMetadata>>,
+    expr: fmt::Arguments,
+) -> Result<()> {
+    let index = GetBase.get_parameter(&old.r#type.metadata).resolution;
+    let closure = context
+        .closures
+        .iter()
+        .find(|closure| closure.index == index);
+
+    match closure {
+        Some(closure) if GetBase.get_parameter(&new.r#type.metadata).resolution == index => writer
+            .write_fmt_nl(format_args!(
+                "{}({expr}){}",
+                closure.closure,
+                context.try_operator()
+            )),
+        _ => write_todo(writer),
     }
 }
 
@@ -434,22 +892,55 @@ fn write_upgrade(
     writer: &mut SourceWriter<impl Write>,
     context: Context,
     binding: Arguments,
+    old: &Type<RustMigrationMetadata>,
     new: &Type<RustMigrationMetadata>,
 ) -> Result<()> {
     let func = context.function_to(new);
 
     writer.write_fmt(format_args!("{func}("))?;
     writer.write_fmt(binding)?;
+    writer.write(&context.closure_arguments(old, new))?;
     writer.write(")")?;
+    writer.write(context.try_operator())?;
 
     Ok(())
 }
 
+/// Writes a conversion from the catalogue, or an invocation of the [`todo!`] macro if there is none.
+fn write_conversion_or_todo(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    old: &Type<RustMigrationMetadata>,
+    new: &Type<RustMigrationMetadata>,
+    expr: fmt::Arguments,
+) -> Result<()> {
+    match find_conversion(context.versions(), old, new) {
+        Some(conversion) => {
+            conversions::write_conversion(writer, context, conversion, old, new, expr)
+        }
+        None => write_todo(writer),
+    }
+}
+
 /// Writes an invocation of the [`todo!`] macro.
 fn write_todo(writer: &mut SourceWriter<impl Write>) -> Result<()> {
     writer.write_nl(TODO)
 }
 
+/// Writes the path of the Rust type corresponding to a struct, enum or newtype, without type arguments.
+///
+/// Unlike [`write_type_name`], it can be used in expressions and patterns.
+fn write_path(
+    writer: &mut SourceWriter<impl Write>,
+    context: codegen::NamingContext<RustMigrationMetadata>,
+    r#type: &Type<RustMigrationMetadata>,
+) -> Result<()> {
+    writer.write_fmt(format_args!(
+        "{}::{}",
+        context.types.metadata.base.name, r#type.metadata.base.name
+    ))
+}
+
 /// Writes the name of the Rust type corresponding to a Versed type.
 fn write_type_name(
     writer: &mut SourceWriter<impl Write>,
@@ -0,0 +1,129 @@
+//! Writes the conversions of the shared catalogue, which migration functions are filled in with automatically.
+//!
+//! Everything else is left to the user as a [`todo!`].
+
+use std::{
+    fmt::Arguments,
+    io::{Result, Write},
+};
+
+use crate::{
+    ast::{Type, TypeType},
+    codegen::source_writer::SourceWriter,
+    migrations::conversions::Conversion,
+    rust::{GetBase, RustMigrationMetadata, codegen},
+};
+
+use super::{Context, write_path};
+
+/// Writes the comment marking an auto-filled value.
+pub fn write_comment(writer: &mut SourceWriter<impl Write>, description: &str) -> Result<()> {
+    writer.write_fmt_nl(format_args!("// Auto-filled: {description}"))
+}
+
+/// Writes the conversion of `expr` from `old` to `new`, preceded by a comment.
+pub fn write_conversion(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    conversion: Conversion<RustMigrationMetadata>,
+    old: &Type<RustMigrationMetadata>,
+    new: &Type<RustMigrationMetadata>,
+    expr: Arguments,
+) -> Result<()> {
+    write_comment(writer, conversion.description())?;
+
+    match conversion {
+        Conversion::IntToString => writer.write_fmt(format_args!("{expr}.to_string()"))?,
+        Conversion::IntoList => {
+            let TypeType::List(list) = &new.r#type else {
+                unreachable!("conversion to be found for a list");
+            };
+
+            writer.write("vec![")?;
+            write_direct(writer, context, old, &list.r#type, expr)?;
+            writer.write("]")?;
+        }
+        Conversion::WrapInStruct(field) => {
+            write_path(writer, context.new, new)?;
+            writer.write_fmt(format_args!(" {{ {}: ", field.metadata.base.name))?;
+
+            if field.r#type.metadata.base.r#box {
+                writer.write("Box::new(")?;
+                write_direct(writer, context, old, &field.r#type, expr)?;
+                writer.write(")")?;
+            } else {
+                write_direct(writer, context, old, &field.r#type, expr)?;
+            }
+
+            writer.write(" }")?;
+        }
+        Conversion::UnwrapStruct(field) => {
+            let name = &field.metadata.base.name;
+
+            if field.r#type.metadata.base.r#box {
+                write_direct(
+                    writer,
+                    context,
+                    &field.r#type,
+                    new,
+                    format_args!("*{expr}.{name}"),
+                )?;
+            } else {
+                write_direct(
+                    writer,
+                    context,
+                    &field.r#type,
+                    new,
+                    format_args!("{expr}.{name}"),
+                )?;
+            }
+        }
+        Conversion::UnitToStruct(r#struct) => {
+            write_path(writer, context.new, new)?;
+            writer.write(" {")?;
+
+            for (index, field) in r#struct.fields.iter().enumerate() {
+                let separator = if index == 0 { " " } else { ", " };
+                writer.write_fmt(format_args!("{separator}{}: ", field.metadata.base.name))?;
+                codegen::write_value(
+                    writer,
+                    context.new,
+                    &field.r#type,
+                    field
+                        .default
+                        .as_ref()
+                        .expect("fields to have default values"),
+                    format_args!("{}::", context.new.types.metadata.base.name),
+                    GetBase,
+                )?;
+            }
+
+            writer.write(" }")?;
+        }
+        Conversion::StructToUnit => writer.write("()")?,
+    }
+
+    writer.write_nl("")
+}
+
+/// Writes `expr`, migrated from `old` to `new`, assuming [`is_direct`](crate::migrations::conversions::is_direct) holds.
+fn write_direct(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    old: &Type<RustMigrationMetadata>,
+    new: &Type<RustMigrationMetadata>,
+    expr: Arguments,
+) -> Result<()> {
+    match (&old.r#type, &new.r#type) {
+        (TypeType::Identifier(old), TypeType::Identifier(new)) => {
+            let old_ref = &context.old.types.types[old.metadata.base.resolution];
+            let new_ref = &context.new.types.types[new.metadata.base.resolution];
+            let func = context
+                .function_between(&old_ref.r#type, &new_ref.r#type)
+                .expect("types to be linked");
+
+            writer.write_fmt(format_args!("{func}({expr}){}", context.try_operator()))
+        }
+        _ => writer.write_fmt(expr),
+    }
+}
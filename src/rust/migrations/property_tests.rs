@@ -0,0 +1,682 @@
+//! Generates property tests checking that upgrading and then downgrading a value returns it unchanged.
+//!
+//! The tests use [proptest](https://docs.rs/proptest),
+//! with strategies generated for all types of the old version.
+
+use std::{
+    collections::HashSet,
+    io::{Result, Write},
+    ptr,
+};
+
+use crate::{
+    ast::{NamedType, PrimitiveType, Scope, Type, TypeType},
+    codegen::source_writer::SourceWriter,
+    migrations::{
+        TypePair,
+        conversions::{Conversion, describe, find_conversion},
+    },
+    preprocessing::{EXTRA_DEPTH, min_depth, min_depths},
+    rust::{GetBaseResolution, RustMigrationMetadata, TimeCrate},
+};
+
+use super::{Context, function_to, write_type_name};
+
+/// The maximum length of generated lists.
+const MAX_LIST_LENGTH: usize = 4;
+
+/// A named type pair that gets a round-trip test.
+struct Test<'a> {
+    named: &'a NamedType<RustMigrationMetadata>,
+    pair: TypePair<'a, RustMigrationMetadata>,
+    depth: u32,
+}
+
+/// Emits the test module, with a round-trip test for every pair of named types that can round-trip.
+///
+/// `context` has to be the context of the upgrade direction.
+pub fn emit_property_tests(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    pairs: &[TypePair<RustMigrationMetadata>],
+    fallible_downgrades: bool,
+) -> Result<()> {
+    let depths = min_depths(context.old.types, GetBaseResolution);
+
+    let mut tests = vec![];
+    let mut skipped = vec![];
+
+    for (index, named) in context.old.types.types.iter().enumerate() {
+        let Some(&pair) = pairs.iter().find(|pair| {
+            ptr::eq(pair.old, &named.r#type)
+                && context
+                    .new
+                    .types
+                    .types
+                    .iter()
+                    .any(|new| ptr::eq(pair.new, &new.r#type))
+        }) else {
+            continue;
+        };
+
+        let issue = missing_strategy(context, index).or_else(|| match depths[index] {
+            None => Some("its values can't be generated, as they would be infinitely deep".into()),
+            Some(_) => round_trip_issue(context, pair.old, pair.new, &mut HashSet::new()),
+        });
+
+        match (issue, depths[index]) {
+            (None, Some(depth)) => tests.push(Test {
+                named,
+                pair,
+                depth: depth + EXTRA_DEPTH,
+            }),
+            (issue, _) => skipped.push((named, issue.unwrap_or_default())),
+        }
+    }
+
+    writer.write_nl("#[cfg(test)]")?;
+    writer.write_nl("mod tests {")?;
+    writer.indent();
+
+    writer.write_nl("use proptest::prelude::*;")?;
+    writer.blank_line();
+    writer.write_fmt_nl(format_args!(
+        "use super::super::super::{};",
+        context.old.types.metadata.base.name
+    ))?;
+    writer.blank_line();
+
+    for index in used_types(context, &tests) {
+        emit_strategy_function(writer, context, &context.old.types.types[index], &depths)?;
+    }
+
+    writer.write_nl("proptest! {")?;
+    writer.indent();
+
+    for (index, test) in tests.iter().enumerate() {
+        if index > 0 {
+            writer.blank_line();
+        }
+
+        emit_test(writer, context, test, fallible_downgrades)?;
+    }
+
+    for (index, (named, issue)) in skipped.iter().enumerate() {
+        if index > 0 || !tests.is_empty() {
+            writer.blank_line();
+        }
+
+        writer.write_fmt_nl(format_args!(
+            "// {} can't round-trip, because {issue}.",
+            named.name
+        ))?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+
+    writer.dedent();
+    writer.write_nl("}")?;
+
+    Ok(())
+}
+
+/// Emits one round-trip test.
+fn emit_test(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    test: &Test,
+    fallible_downgrades: bool,
+) -> Result<()> {
+    writer.write_nl("#[test]")?;
+    writer.write_fmt_nl(format_args!(
+        "fn {}(value in {}({})) {{",
+        function_to("round_trip", test.pair.old),
+        function_to("arb", &test.named.r#type),
+        test.depth
+    ))?;
+    writer.indent();
+
+    writer.write_fmt_nl(format_args!(
+        "let upgraded = super::upgrade::{}(value.clone());",
+        context.function_to(test.pair.new)
+    ))?;
+    writer.write_fmt(format_args!(
+        "prop_assert_eq!(super::downgrade::{}(upgraded), ",
+        function_to("downgrade", test.pair.old)
+    ))?;
+
+    if fallible_downgrades {
+        writer.write_nl("Ok(value));")?;
+    } else {
+        writer.write_nl("value);")?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+
+    Ok(())
+}
+
+/// Emits the function creating the strategy for a named type of the old version.
+fn emit_strategy_function(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    named: &NamedType<RustMigrationMetadata>,
+    depths: &[Option<u32>],
+) -> Result<()> {
+    let r#type = &named.r#type;
+    let depth = if contains_identifier(r#type) {
+        "depth"
+    } else {
+        "_depth"
+    };
+
+    writer.write_fmt(format_args!(
+        "fn {}({depth}: u32) -> BoxedStrategy<",
+        function_to("arb", r#type)
+    ))?;
+    write_type_name(writer, context.old, r#type)?;
+    writer.write_nl("> {")?;
+    writer.indent();
+
+    write_strategy(writer, context, r#type, depths)?;
+
+    if r#type.metadata.base.newtype {
+        writer.write(".prop_map(")?;
+        write_type_name(writer, context.old, r#type)?;
+        writer.write(")")?;
+    }
+
+    writer.write_nl(".boxed()")?;
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Writes an expression creating the strategy for a type, without newtype wrapping.
+///
+/// Assumes that a local variable named `depth` contains at least the [minimal depth](min_depth).
+fn write_strategy(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    r#type: &Type<RustMigrationMetadata>,
+    depths: &[Option<u32>],
+) -> Result<()> {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => {
+            if r#struct.fields.is_empty() {
+                writer.write("Just(")?;
+                write_path(writer, context, r#type)?;
+                writer.write(" {})")?;
+            } else {
+                for field in &r#struct.fields {
+                    writer.write("(")?;
+                    write_strategy(writer, context, &field.r#type, depths)?;
+                    writer.write(", ")?;
+                }
+
+                writer.write("Just(())")?;
+
+                for _ in &r#struct.fields {
+                    writer.write(")")?;
+                }
+
+                writer.write(".prop_map(|")?;
+
+                for index in 0..r#struct.fields.len() {
+                    writer.write_fmt(format_args!("(field_{index}, "))?;
+                }
+
+                writer.write("()")?;
+
+                for _ in &r#struct.fields {
+                    writer.write(")")?;
+                }
+
+                writer.write("| ")?;
+                write_path(writer, context, r#type)?;
+                writer.write(" {")?;
+
+                for (index, field) in r#struct.fields.iter().enumerate() {
+                    let separator = if index == 0 { " " } else { ", " };
+                    writer.write_fmt(format_args!(
+                        "{separator}{}: field_{index}",
+                        field.metadata.base.name
+                    ))?;
+                }
+
+                writer.write(" })")?;
+            }
+        }
+        TypeType::Enum(r#enum) => {
+            writer.write_nl("{")?;
+            writer.indent();
+            writer.write_nl("let mut variants = Vec::new();")?;
+
+            for variant in &r#enum.variants {
+                let Some(depth) = min_depth(
+                    &variant.r#type,
+                    context.old.types,
+                    depths,
+                    &Scope::ROOT,
+                    GetBaseResolution,
+                ) else {
+                    continue;
+                };
+
+                if depth > 0 {
+                    writer.write_fmt_nl(format_args!("if depth >= {depth} {{"))?;
+                    writer.indent();
+                }
+
+                writer.write("variants.push(")?;
+                write_strategy(writer, context, &variant.r#type, depths)?;
+                writer.write(".prop_map(")?;
+                write_path(writer, context, r#type)?;
+                writer.write_fmt_nl(format_args!("::{}).boxed());", variant.metadata.base.name))?;
+
+                if depth > 0 {
+                    writer.dedent();
+                    writer.write_nl("}")?;
+                }
+            }
+
+            writer.write_nl("prop::strategy::Union::new(variants)")?;
+            writer.dedent();
+            writer.write("}")?;
+        }
+        TypeType::List(list) => {
+            let depth = min_depth(
+                &list.r#type,
+                context.old.types,
+                depths,
+                &Scope::ROOT,
+                GetBaseResolution,
+            );
+
+            if depth.is_none_or(|depth| depth > 0) {
+                writer.write("(")?;
+
+                if let Some(depth) = depth {
+                    writer.write_fmt(format_args!("if depth >= {depth} {{ "))?;
+                    write_list_strategy(writer, context, &list.r#type, depths)?;
+                    writer.write(".boxed() } else { ")?;
+                } else {
+                    writer.write("{ ")?;
+                }
+
+                writer.write("Just(Vec::new()).boxed() })")?;
+            } else {
+                write_list_strategy(writer, context, &list.r#type, depths)?;
+            }
+        }
+        TypeType::Tuple(tuple) => {
+            writer.write("(")?;
+
+            for (index, element) in tuple.elements.iter().enumerate() {
+                if index > 0 {
+                    writer.write(", ")?;
+                }
+
+                write_strategy(writer, context, element, depths)?;
+            }
+
+            writer.write(")")?;
+        }
+        TypeType::Primitive(primitive) => {
+            writer.write(primitive_strategy(context.old.time_crate, primitive.r#type))?
+        }
+        TypeType::Identifier(identifier) => {
+            let named = &context.old.types.types[identifier.metadata.base.resolution];
+            writer.write_fmt(format_args!(
+                "{}(depth - 1)",
+                function_to("arb", &named.r#type)
+            ))?;
+        }
+        TypeType::Parameter(_parameter) => unreachable!("generic types to be skipped"),
+        TypeType::Extern(_) => unreachable!("extern types to be skipped"),
+    }
+
+    if r#type.metadata.base.r#box {
+        writer.write(".prop_map(Box::new)")?;
+    }
+
+    Ok(())
+}
+
+/// Writes the path of a struct or enum of the old version, without a [`Box`].
+fn write_path(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    r#type: &Type<RustMigrationMetadata>,
+) -> Result<()> {
+    writer.write_fmt(format_args!(
+        "{}::{}",
+        context.old.types.metadata.base.name, r#type.metadata.base.name
+    ))
+}
+
+/// Writes an expression creating the strategy for a list, ignoring the depth.
+fn write_list_strategy(
+    writer: &mut SourceWriter<impl Write>,
+    context: Context,
+    element: &Type<RustMigrationMetadata>,
+    depths: &[Option<u32>],
+) -> Result<()> {
+    writer.write("prop::collection::vec(")?;
+    write_strategy(writer, context, element, depths)?;
+    writer.write_fmt(format_args!(", 0..{MAX_LIST_LENGTH})"))
+}
+
+/// Checks whether a named type of the old version is or refers to a type without a strategy,
+/// which are generic types and extern types, returning the reason it gets skipped if it does.
+fn missing_strategy(context: Context, index: usize) -> Option<String> {
+    let types = &context.old.types.types;
+    let mut visited = vec![false; types.len()];
+    let mut stack = vec![&types[index].r#type];
+    visited[index] = true;
+
+    while let Some(r#type) = stack.pop() {
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) => {
+                stack.extend(r#struct.fields.iter().map(|field| &field.r#type));
+            }
+            TypeType::Enum(r#enum) => {
+                stack.extend(r#enum.variants.iter().map(|variant| &variant.r#type));
+            }
+            TypeType::List(list) => stack.push(&list.r#type),
+            TypeType::Tuple(tuple) => stack.extend(&tuple.elements),
+            TypeType::Primitive(_primitive) => {}
+            TypeType::Identifier(identifier) => {
+                if !identifier.arguments.is_empty() {
+                    return Some(GENERIC_ISSUE.into());
+                }
+
+                let index = identifier.metadata.base.resolution;
+
+                if !visited[index] {
+                    visited[index] = true;
+                    stack.push(&types[index].r#type);
+                }
+            }
+            TypeType::Parameter(_parameter) => return Some(GENERIC_ISSUE.into()),
+            TypeType::Extern(r#extern) => {
+                return Some(format!(
+                    "it uses the extern type '{}', which doesn't get a strategy",
+                    r#extern.name
+                ));
+            }
+        }
+    }
+
+    None
+}
+
+/// The reason why a type using generic types gets skipped.
+const GENERIC_ISSUE: &str = "it uses generic types, which don't get strategies";
+
+/// Lists the indices of all named types needing a strategy function, in order.
+fn used_types(context: Context, tests: &[Test]) -> Vec<usize> {
+    let mut used = vec![false; context.old.types.types.len()];
+    let mut stack: Vec<&Type<RustMigrationMetadata>> =
+        tests.iter().map(|test| &test.named.r#type).collect();
+
+    for test in tests {
+        if let Some(index) = context
+            .old
+            .types
+            .types
+            .iter()
+            .position(|named| ptr::eq(named, test.named))
+        {
+            used[index] = true;
+        }
+    }
+
+    while let Some(r#type) = stack.pop() {
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) => {
+                stack.extend(r#struct.fields.iter().map(|field| &field.r#type));
+            }
+            TypeType::Enum(r#enum) => {
+                stack.extend(r#enum.variants.iter().map(|variant| &variant.r#type));
+            }
+            TypeType::List(list) => stack.push(&list.r#type),
+            TypeType::Tuple(tuple) => stack.extend(&tuple.elements),
+            TypeType::Primitive(_primitive) => {}
+            TypeType::Identifier(identifier) => {
+                let index = identifier.metadata.base.resolution;
+
+                if !used[index] {
+                    used[index] = true;
+                    stack.push(&context.old.types.types[index].r#type);
+                }
+            }
+            TypeType::Parameter(_parameter) => {}
+            TypeType::Extern(_extern) => {}
+        }
+    }
+
+    (0..used.len()).filter(|&index| used[index]).collect()
+}
+
+/// Checks whether a type refers to a named type, without following the reference.
+fn contains_identifier(r#type: &Type<RustMigrationMetadata>) -> bool {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => r#struct
+            .fields
+            .iter()
+            .any(|field| contains_identifier(&field.r#type)),
+        TypeType::Enum(r#enum) => r#enum
+            .variants
+            .iter()
+            .any(|variant| contains_identifier(&variant.r#type)),
+        TypeType::List(list) => contains_identifier(&list.r#type),
+        TypeType::Tuple(tuple) => tuple.elements.iter().any(contains_identifier),
+        TypeType::Primitive(_primitive) => false,
+        TypeType::Identifier(_identifier) => true,
+        TypeType::Parameter(_parameter) => false,
+        TypeType::Extern(_extern) => false,
+    }
+}
+
+/// Finds a reason why upgrading and then downgrading a value might not return the original value.
+///
+/// `visited` contains the pairs that are already being checked, to stop at recursive types.
+fn round_trip_issue<'a>(
+    context: Context<'a>,
+    old: &'a Type<RustMigrationMetadata>,
+    new: &'a Type<RustMigrationMetadata>,
+    visited: &mut HashSet<(
+        *const Type<RustMigrationMetadata>,
+        *const Type<RustMigrationMetadata>,
+    )>,
+) -> Option<String> {
+    if !visited.insert((old, new)) {
+        return None;
+    }
+
+    match (&old.r#type, &new.r#type) {
+        (TypeType::Struct(old), TypeType::Struct(new)) => {
+            for old_field in &old.fields {
+                let Some(new_field) = new.fields.iter().find(|new_field| {
+                    old_field.r#type.number.is_some()
+                        && new_field.r#type.number == old_field.r#type.number
+                }) else {
+                    return Some(format!(
+                        "the field '{}' doesn't exist in the new version",
+                        old_field.name
+                    ));
+                };
+
+                if let Some(issue) =
+                    round_trip_issue(context, &old_field.r#type, &new_field.r#type, visited)
+                {
+                    return Some(issue);
+                }
+            }
+
+            new.fields
+                .iter()
+                .find(|new_field| {
+                    new_field.default.is_none()
+                        && !old.fields.iter().any(|old_field| {
+                            new_field.r#type.number.is_some()
+                                && old_field.r#type.number == new_field.r#type.number
+                        })
+                })
+                .map(|new_field| format!("the new field '{}' has no default value", new_field.name))
+        }
+        (TypeType::Enum(old), TypeType::Enum(new)) => {
+            for old_variant in &old.variants {
+                let Some(new_variant) = new.variants.iter().find(|new_variant| {
+                    old_variant.r#type.number.is_some()
+                        && new_variant.r#type.number == old_variant.r#type.number
+                }) else {
+                    return Some(format!(
+                        "the variant '{}' doesn't exist in the new version",
+                        old_variant.name
+                    ));
+                };
+
+                if let Some(issue) =
+                    round_trip_issue(context, &old_variant.r#type, &new_variant.r#type, visited)
+                {
+                    return Some(issue);
+                }
+            }
+
+            None
+        }
+        (TypeType::List(old_list), TypeType::List(new_list)) => {
+            if context
+                .function_between(&old_list.r#type, &new_list.r#type)
+                .is_some()
+            {
+                round_trip_issue(context, &old_list.r#type, &new_list.r#type, visited)
+            } else {
+                Some("the elements of a list aren't linked by a migration marker".into())
+            }
+        }
+        (TypeType::Tuple(old), TypeType::Tuple(new)) => {
+            for (index, old_element) in old.elements.iter().enumerate() {
+                let Some(new_element) = new.elements.iter().find(|new_element| {
+                    old_element.number.is_some() && new_element.number == old_element.number
+                }) else {
+                    return Some(format!(
+                        "the element {index} of a tuple doesn't exist in the new version"
+                    ));
+                };
+
+                if let Some(issue) = round_trip_issue(context, old_element, new_element, visited) {
+                    return Some(issue);
+                }
+            }
+
+            new.elements
+                .iter()
+                .position(|new_element| {
+                    !old.elements.iter().any(|old_element| {
+                        new_element.number.is_some() && old_element.number == new_element.number
+                    })
+                })
+                .map(|index| format!("the new element {index} of a tuple has no value"))
+        }
+        (TypeType::Primitive(old_primitive), TypeType::Primitive(new_primitive))
+            if old_primitive.r#type == new_primitive.r#type =>
+        {
+            None
+        }
+        (TypeType::Identifier(_), TypeType::Identifier(_)) => {
+            identifier_issue(context, old, new, visited)
+        }
+        _ => match find_conversion(context.versions(), old, new) {
+            Some(Conversion::WrapInStruct(field)) => {
+                identifier_issue(context, old, &field.r#type, visited)
+            }
+            Some(Conversion::UnwrapStruct(field)) => {
+                identifier_issue(context, &field.r#type, new, visited)
+            }
+            // The default values are dropped again when downgrading
+            Some(Conversion::UnitToStruct(_)) => None,
+            _ => Some(format!(
+                "{} can't be converted to {} and back",
+                describe(context.old.types, old, GetBaseResolution),
+                describe(context.new.types, new, GetBaseResolution)
+            )),
+        },
+    }
+}
+
+/// Finds a round-trip issue between two types that can be converted directly,
+/// which is only possible for identifiers.
+fn identifier_issue<'a>(
+    context: Context<'a>,
+    old: &'a Type<RustMigrationMetadata>,
+    new: &'a Type<RustMigrationMetadata>,
+    visited: &mut HashSet<(
+        *const Type<RustMigrationMetadata>,
+        *const Type<RustMigrationMetadata>,
+    )>,
+) -> Option<String> {
+    let (TypeType::Identifier(old), TypeType::Identifier(new)) = (&old.r#type, &new.r#type) else {
+        return None;
+    };
+
+    let old_ref = &context.old.types.types[old.metadata.base.resolution];
+    let new_ref = &context.new.types.types[new.metadata.base.resolution];
+
+    if context
+        .function_between(&old_ref.r#type, &new_ref.r#type)
+        .is_some()
+    {
+        round_trip_issue(context, &old_ref.r#type, &new_ref.r#type, visited)
+    } else {
+        Some(format!(
+            "the references to '{}' and '{}' aren't linked by a migration marker",
+            old_ref.name, new_ref.name
+        ))
+    }
+}
+
+/// Gets the strategy generating the values of a primitive type.
+///
+/// Timestamps and dates are kept between 1970 and 2100, to stay in the range of every time crate.
+fn primitive_strategy(time_crate: TimeCrate, r#type: PrimitiveType) -> &'static str {
+    match (r#type, time_crate) {
+        (PrimitiveType::String, _) => "any::<String>()",
+        (PrimitiveType::Number, _) => "any::<i64>()",
+        (PrimitiveType::Unit, _) => "Just(())",
+        (PrimitiveType::Uuid, _) => "any::<u128>().prop_map(::uuid::Uuid::from_u128)",
+        (PrimitiveType::Bytes, _) => "any::<Vec<u8>>()",
+        (PrimitiveType::Timestamp, TimeCrate::Chrono) => concat!(
+            "(0..4_102_444_800i64, 0..1_000_000_000u32).prop_map(|(seconds, nanos)| ",
+            "::chrono::DateTime::from_timestamp(seconds, nanos).unwrap())"
+        ),
+        (PrimitiveType::Date, TimeCrate::Chrono) => concat!(
+            "(1970..2100i32, 1..=365u32).prop_map(|(year, day)| ",
+            "::chrono::NaiveDate::from_yo_opt(year, day).unwrap())"
+        ),
+        (PrimitiveType::Duration, TimeCrate::Chrono) => concat!(
+            "(any::<i32>(), 0..1_000_000_000u32).prop_map(|(seconds, nanos)| ",
+            "::chrono::TimeDelta::new(seconds.into(), nanos).unwrap())"
+        ),
+        (PrimitiveType::Timestamp, TimeCrate::Time) => concat!(
+            "(0..4_102_444_800i64, 0..1_000_000_000u32).prop_map(|(seconds, nanos)| ",
+            "::time::OffsetDateTime::from_unix_timestamp(seconds).unwrap()",
+            ".replace_nanosecond(nanos).unwrap())"
+        ),
+        (PrimitiveType::Date, TimeCrate::Time) => concat!(
+            "(1970..2100i32, 1..=365u16).prop_map(|(year, day)| ",
+            "::time::Date::from_ordinal_date(year, day).unwrap())"
+        ),
+        (PrimitiveType::Duration, TimeCrate::Time) => concat!(
+            "(any::<i32>(), 0..1_000_000_000i32).prop_map(|(seconds, nanos)| ",
+            "::time::Duration::new(seconds.into(), nanos))"
+        ),
+    }
+}
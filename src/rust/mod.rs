@@ -2,39 +2,57 @@
 
 use std::{
     borrow::Cow,
+    collections::HashMap,
     fs::{File, create_dir_all, exists},
     io::{BufWriter, Write},
-    path::Path,
+    path::{Path, PathBuf},
 };
 
+use clap::ValueEnum;
+
 use crate::{
-    ast::{Migration, TypeSet},
+    ast::{Migration, PrimitiveType, TypeSet},
     codegen::{
-        file_patching::add_line_to_file,
+        file_patching::{WriteMode, add_line_to_file, write_file},
         naming_pass::{NameMetadata, name},
+        output::{Output, write_to_stdout},
         source_writer::SourceWriter,
     },
     composite,
     error::{Error, ResultExt},
     getter,
-    loading::{load_file, load_migration},
+    loading::{
+        check_extern_mappings, check_type_names, load_file, load_migration,
+        load_migration_with_source,
+    },
     mapper,
+    metadata::GetIdentity,
     migrations::{TypePair, pair_types},
-    preprocessing::{BasicMetadata, ResolutionMetadata},
+    preprocessing::{BasicMetadata, ResolutionMetadata, check_migration_continuity},
+    reports::{Reports, handle_reports},
     rust::{
-        idents::{RustMigrationSuffixNamingRules, RustNamingRules},
+        chain::emit_chain,
+        codegen::{NamingContext, all_rust_type_names},
+        idents::RustMigrationSuffixNamingRules,
         migrations::emit_migrations,
         recursive::{BoxMetadata, NewtypeMetadata, mark_boxes, mark_newtypes},
+        serde_as::{AdapterContext, find_adapted_argument},
         types::emit_types,
     },
     typescript::TypeScriptNamingRules,
+    watch::GeneratedFiles,
 };
 
+mod chain;
 mod codegen;
 mod idents;
 mod migrations;
 mod recursive;
+mod serde_as;
 mod types;
+mod validation;
+
+pub use idents::RustNamingRules;
 
 /// Configuration of the Rust type declaration backend.
 #[derive(Debug, Clone)]
@@ -42,11 +60,29 @@ pub struct RustOptions {
     serde: bool,
     derives: Vec<Cow<'static, str>>,
     serde_external_tag: bool,
+    envelopes: Vec<String>,
+    validate: bool,
+    externs: HashMap<String, String>,
+    time_crate: TimeCrate,
 }
 
 impl RustOptions {
     /// Creates new [`RustOptions`].
-    pub fn new(serde: bool, extra_derives: Vec<String>, serde_external_tag: bool) -> Self {
+    ///
+    /// `envelopes` contains the names of top-level types that should get a version-tagged envelope.
+    /// If `validate` is set, every named type gets a function checking the refinements of the primitive types inside it.
+    /// `externs` maps the names of extern types to the Rust types they stand for.
+    /// `time_crate` provides the types of timestamps, dates and durations.
+    #[allow(clippy::too_many_arguments)] // One for every flag of `versed rust types`
+    pub fn new(
+        serde: bool,
+        extra_derives: Vec<String>,
+        serde_external_tag: bool,
+        envelopes: Vec<String>,
+        validate: bool,
+        externs: HashMap<String, String>,
+        time_crate: TimeCrate,
+    ) -> Self {
         let mut derives = vec![Cow::Borrowed("Debug"), Cow::Borrowed("Clone")];
 
         if serde {
@@ -62,13 +98,84 @@ impl RustOptions {
             serde,
             derives,
             serde_external_tag,
+            envelopes,
+            validate,
+            externs,
+            time_crate,
         }
     }
 }
 
 impl Default for RustOptions {
     fn default() -> Self {
-        Self::new(false, vec![], false)
+        Self::new(
+            false,
+            vec![],
+            false,
+            vec![],
+            false,
+            HashMap::new(),
+            TimeCrate::default(),
+        )
+    }
+}
+
+/// Configuration of the Rust migration backend.
+#[derive(Debug, Clone, Default)]
+pub struct RustMigrationOptions {
+    fallible_downgrades: bool,
+    property_tests: bool,
+    externs: HashMap<String, String>,
+    time_crate: TimeCrate,
+}
+
+impl RustMigrationOptions {
+    /// Creates new [`RustMigrationOptions`].
+    ///
+    /// If `fallible_downgrades` is set, downgrade functions return a [`Result`],
+    /// with an error for variants that don't exist in the old version.
+    /// If `property_tests` is set, a test module checks that upgrading and downgrading
+    /// values of named types returns them unchanged, wherever no information is lost.
+    /// `externs` maps the names of extern types in both versions to the Rust types they stand for,
+    /// values of linked extern types are passed through if they stand for the same type.
+    /// `time_crate` provides the types of timestamps, dates and durations.
+    pub fn new(
+        fallible_downgrades: bool,
+        property_tests: bool,
+        externs: HashMap<String, String>,
+        time_crate: TimeCrate,
+    ) -> Self {
+        Self {
+            fallible_downgrades,
+            property_tests,
+            externs,
+            time_crate,
+        }
+    }
+}
+
+/// The crate providing the Rust types of timestamps, dates and durations.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, ValueEnum)]
+pub enum TimeCrate {
+    /// The chrono crate, with `DateTime<Utc>`, `NaiveDate` and `TimeDelta`
+    #[default]
+    Chrono,
+    /// The time crate, with `OffsetDateTime`, `Date` and `Duration`
+    Time,
+}
+
+impl TimeCrate {
+    /// Gets the path of the Rust type of a timestamp, date or duration.
+    pub fn rust_type(self, r#type: PrimitiveType) -> &'static str {
+        match (self, r#type) {
+            (TimeCrate::Chrono, PrimitiveType::Timestamp) => "::chrono::DateTime<::chrono::Utc>",
+            (TimeCrate::Chrono, PrimitiveType::Date) => "::chrono::NaiveDate",
+            (TimeCrate::Chrono, PrimitiveType::Duration) => "::chrono::TimeDelta",
+            (TimeCrate::Time, PrimitiveType::Timestamp) => "::time::OffsetDateTime",
+            (TimeCrate::Time, PrimitiveType::Date) => "::time::Date",
+            (TimeCrate::Time, PrimitiveType::Duration) => "::time::Duration",
+            _ => unreachable!("only times to come from the time crate"),
+        }
     }
 }
 
@@ -92,34 +199,77 @@ fn convert_types_for_migration(types: TypeSet<BasicMetadata>) -> TypeSet<RustMig
 /// Implements `versed rust types`.
 pub fn generate_types(
     path: &Path,
-    output: &Path,
-    to_file: bool,
+    output: Output,
     options: &RustOptions,
+    generated: &mut GeneratedFiles,
 ) -> Result<(), Error> {
     let types = load_file(path)?;
+    check_type_names(&types, &options.envelopes)?;
+    check_extern_mappings(&types, &options.externs)?;
     let types = convert_types(types);
+    check_serde_adapters(&types, options)?;
 
-    if to_file {
-        write_to_file(&types, options, output, false)
-    } else {
-        write_to_directory(&types, options, output)
+    match output {
+        Output::Directory(output) => write_to_directory(&types, options, output, generated),
+        Output::File(output) => write_to_file(&types, options, output, WriteMode::Overwrite),
+        Output::Stdout => write_to_stdout(|writer| emit_types(writer, &types, options)),
+    }
+}
+
+/// Checks that every type needing a `serde_as` adapter with `--serde` can get one.
+fn check_serde_adapters(types: &TypeSet<RustMetadata>, options: &RustOptions) -> Result<(), Error> {
+    if !options.serde {
+        return Ok(());
+    }
+
+    let used_type_names = all_rust_type_names(types, GetIdentity);
+    let context = AdapterContext {
+        naming: NamingContext {
+            types,
+            used_type_names: &used_type_names,
+            parameter_suffix: "",
+            time_crate: options.time_crate,
+        },
+        // Only whether there's an adapter matters, not its name
+        duration: "",
+        int_encoding: types.int_encoding,
+    };
+
+    match find_adapted_argument(context) {
+        Some(name) => Err(Error::AdaptedTypeArgument {
+            name: name.to_owned(),
+        }),
+        None => Ok(()),
     }
 }
 
 /// Saves type declarations into a specific directory and adds the new module to `mod.rs`.
+///
+/// Files written by an earlier run in watch mode are rewritten in place instead.
 fn write_to_directory(
     types: &TypeSet<RustMetadata>,
     options: &RustOptions,
     path: &Path,
+    generated: &mut GeneratedFiles,
 ) -> Result<(), Error> {
     create_dir_all(path).with_path(path)?;
     let mod_name = &types.metadata.name;
 
     let type_path = path.join(format!("{mod_name}.rs"));
-    write_to_file(types, options, &type_path, true)?;
+    let rewrite = generated.contains(&type_path);
+    let mode = if rewrite {
+        WriteMode::Replace
+    } else {
+        WriteMode::New
+    };
+    write_to_file(types, options, &type_path, mode)?;
 
-    let mod_path = path.join("mod.rs");
-    add_mod_to_file(mod_name, &mod_path)?;
+    if !rewrite {
+        let mod_path = path.join("mod.rs");
+        add_mod_to_file(mod_name, &mod_path)?;
+    }
+
+    generated.insert(type_path);
 
     Ok(())
 }
@@ -129,32 +279,54 @@ fn write_to_file(
     types: &TypeSet<RustMetadata>,
     options: &RustOptions,
     path: &Path,
-    must_be_new: bool,
+    mode: WriteMode,
 ) -> Result<(), Error> {
-    let file = if must_be_new {
-        File::create_new(path).with_path(path)?
-    } else {
-        File::create(path).with_path(path)?
-    };
-
-    let mut writer = SourceWriter::new(BufWriter::new(file));
-    emit_types(&mut writer, types, options).with_path(path)?;
-    writer.into_inner().flush().with_path(path)?;
-
-    Ok(())
+    write_file(path, mode, |file| {
+        emit_types(&mut SourceWriter::new(file), types, options)
+    })
+    .with_path(path)
 }
 
 /// Implements `versed rust migration`.
-pub fn generate_migration(path: &Path, output: &Path, to_file: bool) -> Result<(), Error> {
+pub fn generate_migration(
+    path: &Path,
+    output: Output,
+    options: &RustMigrationOptions,
+) -> Result<(), Error> {
     let migration = load_migration(path)?;
+    check_extern_mappings(&migration.old, &options.externs)?;
+    check_extern_mappings(&migration.new, &options.externs)?;
     let migration = migration.map(convert_types_for_migration);
     let pairs = pair_types(&migration);
 
-    if to_file {
-        write_migration_to_file(&migration, &pairs, output, false)
-    } else {
-        write_migration_to_directory(&migration, &pairs, output)
+    match output {
+        Output::Directory(output) => {
+            write_migration_to_directory(&migration, &pairs, output, options)
+        }
+        Output::File(output) => write_migration_to_file(&migration, &pairs, output, false, options),
+        Output::Stdout => {
+            write_to_stdout(|writer| emit_migrations(writer, &migration, &pairs, options))
+        }
+    }
+}
+
+/// The name of the module containing all migrations.
+const MIGRATION_MOD: &str = "migrations";
+
+/// Creates the `migrations` directory inside a specific directory, if it doesn't exist yet.
+///
+/// Adds the new module to `mod.rs` when creating the directory.
+/// Returns the path to the `migrations` directory.
+fn create_migrations_directory(path: &Path) -> Result<PathBuf, Error> {
+    let migrations_dir = path.join(MIGRATION_MOD);
+    let is_directory_new = !exists(&migrations_dir).with_path(&migrations_dir)?;
+    create_dir_all(&migrations_dir).with_path(&migrations_dir)?;
+
+    if is_directory_new {
+        add_mod_to_file(MIGRATION_MOD, &path.join("mod.rs"))?;
     }
+
+    Ok(migrations_dir)
 }
 
 /// Saves migrations into a specific directory and adds the new module to `migrations/mod.rs`.
@@ -162,25 +334,18 @@ fn write_migration_to_directory(
     migration: &Migration<RustMigrationMetadata>,
     pairs: &[TypePair<RustMigrationMetadata>],
     path: &Path,
+    options: &RustMigrationOptions,
 ) -> Result<(), Error> {
-    const MIGRATION_MOD: &str = "migrations";
-
-    let migrations_dir = path.join(MIGRATION_MOD);
-    let is_directory_new = !exists(&migrations_dir).with_path(&migrations_dir)?;
-    create_dir_all(&migrations_dir).with_path(&migrations_dir)?;
+    let migrations_dir = create_migrations_directory(path)?;
 
     let mod_name = &migration.new.metadata.base.name;
     let type_path = migrations_dir.join(format!("{mod_name}.rs"));
 
-    write_migration_to_file(migration, pairs, &type_path, true)?;
+    write_migration_to_file(migration, pairs, &type_path, true, options)?;
 
     let mod_path = migrations_dir.join("mod.rs");
     add_mod_to_file(mod_name, &mod_path)?;
 
-    if is_directory_new {
-        add_mod_to_file(MIGRATION_MOD, &path.join("mod.rs"))?;
-    }
-
     Ok(())
 }
 
@@ -190,6 +355,7 @@ fn write_migration_to_file(
     pairs: &[TypePair<RustMigrationMetadata>],
     path: &Path,
     must_be_new: bool,
+    options: &RustMigrationOptions,
 ) -> Result<(), Error> {
     let file = if must_be_new {
         File::create_new(path).with_path(path)?
@@ -198,12 +364,52 @@ fn write_migration_to_file(
     };
 
     let mut writer = SourceWriter::new(BufWriter::new(file));
-    emit_migrations(&mut writer, migration, pairs).with_path(path)?;
+    emit_migrations(&mut writer, migration, pairs, options).with_path(path)?;
     writer.into_inner().flush().with_path(path)?;
 
     Ok(())
 }
 
+/// Implements `versed rust chain`.
+///
+/// `fallible_downgrades` must match the option the migrations were generated with.
+pub fn generate_chain(
+    paths: &[PathBuf],
+    output: &Path,
+    serde: bool,
+    fallible_downgrades: bool,
+) -> Result<(), Error> {
+    let mut migrations: Vec<Migration<BasicMetadata>> = Vec::with_capacity(paths.len());
+
+    for path in paths {
+        let (migration, src) = load_migration_with_source(path)?;
+
+        if let Some(previous) = migrations.last() {
+            let filename = path.to_string_lossy();
+            let mut reports = Reports::default();
+            check_migration_continuity(&previous.new, &migration.old, &mut reports, &filename);
+            handle_reports(&reports, &filename, &src)?;
+        }
+
+        migrations.push(migration);
+    }
+
+    let migrations: Vec<_> = migrations
+        .into_iter()
+        .map(|migration| migration.map(convert_types_for_migration))
+        .collect();
+
+    let migrations_dir = create_migrations_directory(output)?;
+    let mod_path = migrations_dir.join("mod.rs");
+
+    let file = File::create(&mod_path).with_path(&mod_path)?;
+    let mut writer = SourceWriter::new(BufWriter::new(file));
+    emit_chain(&mut writer, &migrations, serde, fallible_downgrades).with_path(&mod_path)?;
+    writer.into_inner().flush().with_path(&mod_path)?;
+
+    Ok(())
+}
+
 /// Appends `pub mod {name};` to a file.
 fn add_mod_to_file(mod_name: &str, path: &Path) -> Result<(), Error> {
     add_line_to_file(path, format_args!("pub mod {mod_name};")).with_path(path)
@@ -265,8 +471,20 @@ mapper! {
     }
 }
 
+getter! {
+    fn GetResolution(metadata: RustMetadata) -> ResolutionMetadata {
+        &metadata.resolution
+    }
+}
+
 getter! {
     fn GetBase(metadata: RustMigrationMetadata) -> RustMetadata {
         &metadata.base
     }
 }
+
+getter! {
+    fn GetBaseResolution(metadata: RustMigrationMetadata) -> ResolutionMetadata {
+        &metadata.base.resolution
+    }
+}
@@ -70,8 +70,25 @@ fn process_type(r#type: &mut Type<RustMetadata>, context: &mut BoxContext) -> bo
             false
         }
         TypeType::List(_list) => false,
+        TypeType::Tuple(tuple) => {
+            for r#type in &mut tuple.elements {
+                if !r#type.metadata.r#box {
+                    r#type.metadata.r#box |= process_type(r#type, context);
+                }
+            }
+
+            false
+        }
         TypeType::Primitive(_primitive) => false,
+        TypeType::Extern(_extern) => false,
         TypeType::Identifier(identifier) => {
+            // Type arguments get boxed themselves, since the generic type might contain them directly
+            for r#type in &mut identifier.arguments {
+                if !r#type.metadata.r#box {
+                    r#type.metadata.r#box |= process_type(r#type, context);
+                }
+            }
+
             let idx = identifier.metadata.resolution;
 
             if idx == context.source {
@@ -81,6 +98,7 @@ fn process_type(r#type: &mut Type<RustMetadata>, context: &mut BoxContext) -> bo
                 false
             }
         }
+        TypeType::Parameter(_parameter) => false,
     }
 }
 
@@ -115,8 +133,22 @@ fn has_type_reference_through_alias(
         TypeType::Struct(_struct) => false,
         TypeType::Enum(_enum) => false,
         TypeType::List(list) => has_type_reference_through_alias(&list.r#type, context),
+        TypeType::Tuple(tuple) => tuple
+            .elements
+            .iter()
+            .any(|element| has_type_reference_through_alias(element, context)),
         TypeType::Primitive(_primitive) => false,
+        TypeType::Extern(_extern) => false,
         TypeType::Identifier(identifier) => {
+            // Type aliases can't refer to themselves through type arguments either
+            if identifier
+                .arguments
+                .iter()
+                .any(|argument| has_type_reference_through_alias(argument, context))
+            {
+                return true;
+            }
+
             let index = identifier.metadata.resolution;
 
             if index == context.source {
@@ -131,6 +163,7 @@ fn has_type_reference_through_alias(
                 }
             }
         }
+        TypeType::Parameter(_parameter) => false,
     }
 }
 
@@ -146,8 +179,11 @@ impl Metadata for BoxMetadata {
     type Struct = ();
     type Enum = ();
     type List = ();
+    type Tuple = ();
     type Primitive = ();
     type Identifier = ();
+    type Parameter = ();
+    type Extern = ();
 
     type Field = ();
     type Variant = ();
@@ -165,8 +201,11 @@ impl Metadata for NewtypeMetadata {
     type Struct = ();
     type Enum = ();
     type List = ();
+    type Tuple = ();
     type Primitive = ();
     type Identifier = ();
+    type Parameter = ();
+    type Extern = ();
 
     type Field = ();
     type Variant = ();
@@ -0,0 +1,269 @@
+//! Finds the `serde_with` adapters that give built-in types their string formats with `--serde`.
+//!
+//! The Rust types of some built-in types don't serialize to the formats Versed defines,
+//! like `Vec<u8>`, which serde turns into an array instead of Base64,
+//! and neither do ints with `int_encoding string`.
+//! Fields of these types get a `#[serde_as(as = "...")]` attribute with an adapter,
+//! which mirrors the structure of the field's type, like `Vec<Base64>` for a list of bytes.
+
+use std::io::{Result, Write};
+
+use crate::{
+    ast::{PrimitiveType, Scope, Type, TypeSet, TypeType},
+    codegen::source_writer::SourceWriter,
+    formats::IntEncoding,
+    rust::{RustMetadata, TimeCrate, codegen::NamingContext},
+};
+
+/// The information needed by [`adapter`].
+#[derive(Debug, Clone, Copy)]
+pub struct AdapterContext<'a> {
+    pub naming: NamingContext<'a, RustMetadata>,
+    /// The name of the generated adapter for durations.
+    pub duration: &'a str,
+    pub int_encoding: IntEncoding,
+}
+
+/// Gets the `serde_as` adapter of a type, or `None` if serde's own implementation has the right format.
+///
+/// Identifiers are followed into type aliases, but not into structs, enums and newtypes,
+/// which serialize their fields themselves.
+pub fn adapter(
+    context: AdapterContext,
+    r#type: &Type<RustMetadata>,
+    scope: &Scope<RustMetadata>,
+) -> Option<String> {
+    let naming = context.naming;
+    let adapter = match &r#type.r#type {
+        TypeType::List(list) => adapter(context, &list.r#type, scope)
+            .map(|element| format!("{}<{element}>", naming.rust_type("Vec", "::std::vec::Vec"))),
+        TypeType::Tuple(tuple) => {
+            let elements: Vec<_> = tuple
+                .elements
+                .iter()
+                .map(|element| adapter(context, element, scope))
+                .collect();
+
+            elements.iter().any(Option::is_some).then(|| {
+                let elements: Vec<_> = elements
+                    .iter()
+                    .map(|element| element.as_deref().unwrap_or("_"))
+                    .collect();
+                format!("({})", elements.join(", "))
+            })
+        }
+        TypeType::Primitive(primitive) => match (primitive.r#type, naming.time_crate) {
+            (PrimitiveType::Number, _) if context.int_encoding == IntEncoding::String => {
+                Some("::serde_with::DisplayFromStr".to_owned())
+            }
+            (PrimitiveType::Bytes, _) => Some("::serde_with::base64::Base64".to_owned()),
+            (PrimitiveType::Duration, _) => Some(context.duration.to_owned()),
+            (PrimitiveType::Timestamp, TimeCrate::Time) => {
+                Some("::time::format_description::well_known::Rfc3339".to_owned())
+            }
+            _ => None,
+        },
+        TypeType::Identifier(identifier) => {
+            let target = &context.naming.types.types[identifier.metadata.resolution].r#type;
+
+            if is_alias(target) {
+                adapter(context, target, &scope.enter(&identifier.arguments))
+            } else {
+                None
+            }
+        }
+        TypeType::Parameter(parameter) => scope
+            .argument(parameter.metadata.resolution)
+            .and_then(|(argument, scope)| adapter(context, argument, scope)),
+        TypeType::Struct(_) | TypeType::Enum(_) | TypeType::Extern(_) => None,
+    }?;
+
+    Some(if r#type.metadata.r#box {
+        format!(
+            "{}<{adapter}>",
+            naming.rust_type("Box", "::std::boxed::Box")
+        )
+    } else {
+        adapter
+    })
+}
+
+/// Finds a named type that passes a type needing an adapter as a type argument to a struct, enum or newtype.
+///
+/// The fields of generic types can't get an adapter for their type arguments,
+/// so their values would be serialized in the wrong format.
+pub fn find_adapted_argument<'a>(context: AdapterContext<'a>) -> Option<&'a str> {
+    context
+        .naming
+        .types
+        .types
+        .iter()
+        .find(|r#type| has_adapted_argument(context, &r#type.r#type))
+        .map(|r#type| r#type.name.as_str())
+}
+
+/// Checks whether a type passes a type needing an adapter as a type argument to a struct, enum or newtype.
+fn has_adapted_argument(context: AdapterContext, r#type: &Type<RustMetadata>) -> bool {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => r#struct
+            .fields
+            .iter()
+            .any(|field| has_adapted_argument(context, &field.r#type)),
+        TypeType::Enum(r#enum) => r#enum
+            .variants
+            .iter()
+            .any(|variant| has_adapted_argument(context, &variant.r#type)),
+        TypeType::List(list) => has_adapted_argument(context, &list.r#type),
+        TypeType::Tuple(tuple) => tuple
+            .elements
+            .iter()
+            .any(|element| has_adapted_argument(context, element)),
+        TypeType::Identifier(identifier) => {
+            let target = &context.naming.types.types[identifier.metadata.resolution].r#type;
+
+            identifier.arguments.iter().any(|argument| {
+                has_adapted_argument(context, argument)
+                    || !is_alias(target) && adapter(context, argument, &Scope::ROOT).is_some()
+            })
+        }
+        TypeType::Primitive(_) | TypeType::Parameter(_) | TypeType::Extern(_) => false,
+    }
+}
+
+/// Checks whether a named type becomes a type alias, which adapters are followed into.
+fn is_alias(r#type: &Type<RustMetadata>) -> bool {
+    !r#type.metadata.newtype
+        && !matches!(
+            r#type.r#type,
+            TypeType::Struct(_) | TypeType::Enum(_) | TypeType::Extern(_)
+        )
+}
+
+/// Checks whether any named type contains a duration, which needs the generated adapter.
+pub fn uses_durations(types: &TypeSet<RustMetadata>) -> bool {
+    fn contains_duration(r#type: &Type<RustMetadata>) -> bool {
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) => r#struct
+                .fields
+                .iter()
+                .any(|field| contains_duration(&field.r#type)),
+            TypeType::Enum(r#enum) => r#enum
+                .variants
+                .iter()
+                .any(|variant| contains_duration(&variant.r#type)),
+            TypeType::List(list) => contains_duration(&list.r#type),
+            TypeType::Tuple(tuple) => tuple.elements.iter().any(contains_duration),
+            TypeType::Primitive(primitive) => primitive.r#type == PrimitiveType::Duration,
+            TypeType::Identifier(identifier) => identifier.arguments.iter().any(contains_duration),
+            TypeType::Parameter(_) | TypeType::Extern(_) => false,
+        }
+    }
+
+    types
+        .types
+        .iter()
+        .any(|r#type| contains_duration(&r#type.r#type))
+}
+
+/// Emits the adapter serializing durations as a number of seconds, like `PT1.5S` or `-PT30S`.
+///
+/// Neither time crate has this format, so it's implemented in the generated code.
+pub fn emit_duration_adapter(
+    writer: &mut SourceWriter<impl Write>,
+    time_crate: TimeCrate,
+    name: &str,
+) -> Result<()> {
+    let duration = time_crate.rust_type(PrimitiveType::Duration);
+    // The zero duration, the magnitude as a `std::time::Duration`,
+    // and the conversion back from seconds and nanoseconds
+    let (zero, magnitude, from_parts) = match time_crate {
+        TimeCrate::Chrono => (
+            "::chrono::TimeDelta::zero()",
+            "source.abs().to_std().expect(\"the magnitude to be positive\")",
+            "::chrono::TimeDelta::new(seconds, nanos).ok_or_else(|| Error::custom(\"duration out of range\"))?",
+        ),
+        TimeCrate::Time => (
+            "::time::Duration::ZERO",
+            "source.unsigned_abs()",
+            "::time::Duration::new(seconds, nanos as i32)",
+        ),
+    };
+
+    writer.write_nl("/// Serializes durations as a number of seconds, like `PT1.5S`.")?;
+    writer.write_fmt_nl(format_args!("pub struct {name};"))?;
+    writer.blank_line();
+
+    writer.write_fmt_nl(format_args!(
+        "impl ::serde_with::SerializeAs<{duration}> for {name} {{"
+    ))?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!(
+        "fn serialize_as<S: ::serde::Serializer>(source: &{duration}, serializer: S) -> ::std::result::Result<S::Ok, S::Error> {{"
+    ))?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!(
+        "let sign = if *source < {zero} {{ \"-\" }} else {{ \"\" }};"
+    ))?;
+    writer.write_fmt_nl(format_args!("let magnitude = {magnitude};"))?;
+    writer.write_nl("let mut string = format!(\"{sign}PT{}\", magnitude.as_secs());")?;
+    writer.blank_line();
+    writer.write_nl("if magnitude.subsec_nanos() > 0 {")?;
+    writer.indent();
+    writer.write_nl("let fraction = format!(\"{:09}\", magnitude.subsec_nanos());")?;
+    writer.write_nl("string.push('.');")?;
+    writer.write_nl("string.push_str(fraction.trim_end_matches('0'));")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+    writer.write_nl("string.push('S');")?;
+    writer.write_nl("serializer.serialize_str(&string)")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_fmt_nl(format_args!(
+        "impl<'de> ::serde_with::DeserializeAs<'de, {duration}> for {name} {{"
+    ))?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!(
+        "fn deserialize_as<D: ::serde::Deserializer<'de>>(deserializer: D) -> ::std::result::Result<{duration}, D::Error> {{"
+    ))?;
+    writer.indent();
+    writer.write_nl("use ::serde::de::Error;")?;
+    writer.blank_line();
+    writer.write_nl(
+        "let string = <::std::string::String as ::serde::Deserialize>::deserialize(deserializer)?;",
+    )?;
+    writer.write_nl("let invalid = || Error::custom(format!(\"invalid duration {string:?}, expected one like PT1.5S\"));")?;
+    writer.write_nl("let (negative, rest) = match string.strip_prefix('-') {")?;
+    writer.indent();
+    writer.write_nl("Some(rest) => (true, rest),")?;
+    writer.write_nl("None => (false, string.as_str()),")?;
+    writer.dedent();
+    writer.write_nl("};")?;
+    writer.write_nl("let rest = rest.strip_prefix(\"PT\").and_then(|rest| rest.strip_suffix('S')).ok_or_else(invalid)?;")?;
+    writer.write_nl("let (seconds, fraction) = rest.split_once('.').unwrap_or((rest, \"\"));")?;
+    writer.blank_line();
+    writer.write_nl("let digits = |digits: &str| !digits.is_empty() && digits.bytes().all(|digit| digit.is_ascii_digit());")?;
+    writer.write_nl(
+        "if !digits(seconds) || rest.contains('.') && (!digits(fraction) || fraction.len() > 9) {",
+    )?;
+    writer.indent();
+    writer.write_nl("return Err(invalid());")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+    writer.write_nl("let seconds: i64 = seconds.parse().map_err(|_| invalid())?;")?;
+    writer.write_nl("let nanos: u32 = if fraction.is_empty() { 0 } else { format!(\"{fraction:0<9}\").parse().map_err(|_| invalid())? };")?;
+    writer.write_fmt_nl(format_args!("let duration = {from_parts};"))?;
+    writer.write_nl("Ok(if negative { -duration } else { duration })")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
@@ -1,14 +1,26 @@
 //! The backend for Rust type declarations.
 
-use std::io::{Result, Write};
+use std::{
+    collections::HashSet,
+    io::{Result, Write},
+};
 
 use crate::{
-    ast::{Enum, NamedType, Struct, Type, TypeSet, TypeType},
-    codegen::source_writer::SourceWriter,
+    ast::{Enum, Field, NamedType, Parameter, Scope, Struct, Type, TypeSet, TypeType},
+    codegen::{
+        idents::{IdentRules, disambiguate},
+        source_writer::SourceWriter,
+    },
     metadata::GetIdentity,
     rust::{
         RustMetadata, RustOptions,
-        codegen::{self, NamingContext, all_rust_type_names},
+        codegen::{
+            self, NamingContext, all_rust_type_names, is_default_value, unknown_variant_name,
+            used_parameters, version_variant_name, write_parameters, write_value,
+        },
+        idents::RustIdentRules,
+        serde_as::{self, AdapterContext, emit_duration_adapter, uses_durations},
+        validation::emit_validation,
     },
 };
 
@@ -17,6 +29,8 @@ use crate::{
 struct TypeCodegenContext<'a> {
     pub naming: NamingContext<'a, RustMetadata>,
     pub options: &'a RustOptions,
+    /// The name of the `serde_as` adapter of durations.
+    pub duration_adapter: &'a str,
 }
 
 /// Emits all type declarations.
@@ -27,12 +41,18 @@ pub fn emit_types(
 ) -> Result<()> {
     let used_type_names = all_rust_type_names(types, GetIdentity);
 
+    let mut duration_adapter = "Iso8601Duration".to_owned();
+    disambiguate(&mut duration_adapter, |name| used_type_names.contains(name));
+
     let context = TypeCodegenContext {
         naming: NamingContext {
             types,
             used_type_names: &used_type_names,
+            parameter_suffix: "",
+            time_crate: options.time_crate,
         },
         options,
+        duration_adapter: &duration_adapter,
     };
 
     for r#type in &types.types {
@@ -46,6 +66,34 @@ pub fn emit_types(
         emit_type_recursive(writer, context, &r#type.r#type)?;
     }
 
+    // The names of the duration adapter, of the error type of validation functions and of envelopes
+    let mut generated_type_names = HashSet::new();
+
+    if context.options.serde && uses_durations(types) {
+        emit_duration_adapter(writer, context.options.time_crate, &duration_adapter)?;
+        generated_type_names.insert(duration_adapter.clone());
+    }
+
+    if context.options.validate {
+        generated_type_names.insert(emit_validation(
+            writer,
+            context.naming,
+            context.options.serde_external_tag,
+        )?);
+    }
+
+    for r#type in &types.types {
+        if context.options.envelopes.contains(&r#type.name) {
+            let mut name = format!("{}Envelope", r#type.r#type.metadata.name);
+            disambiguate(&mut name, |name| {
+                used_type_names.contains(name) || generated_type_names.contains(name)
+            });
+
+            emit_envelope(writer, context, r#type, &name)?;
+            generated_type_names.insert(name);
+        }
+    }
+
     Ok(())
 }
 
@@ -57,47 +105,91 @@ fn emit_type_recursive(
 ) -> Result<()> {
     match &r#type.r#type {
         TypeType::Struct(r#struct) => {
-            emit_struct(writer, context, r#struct, &r#type.metadata.name)?;
+            emit_struct(writer, context, r#struct, r#type)?;
 
             for field in &r#struct.fields {
                 emit_type_recursive(writer, context, &field.r#type)?;
             }
         }
         TypeType::Enum(r#enum) => {
-            emit_enum(writer, context, r#enum, &r#type.metadata.name)?;
+            emit_enum(writer, context, r#enum, r#type)?;
 
             for variant in &r#enum.variants {
                 emit_type_recursive(writer, context, &variant.r#type)?;
             }
         }
         TypeType::List(list) => emit_type_recursive(writer, context, &list.r#type)?,
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                emit_type_recursive(writer, context, element)?;
+            }
+        }
         TypeType::Primitive(_) => {}
-        TypeType::Identifier(_) => {}
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                emit_type_recursive(writer, context, argument)?;
+            }
+        }
+        TypeType::Parameter(_) => {}
+        TypeType::Extern(_) => {}
     }
 
     Ok(())
 }
 
-/// Emits the type declaration for a struct.
+/// Emits the type declaration for a struct, which is generic over the type parameters it uses.
 fn emit_struct(
     writer: &mut SourceWriter<impl Write>,
     context: TypeCodegenContext,
     r#struct: &Struct<RustMetadata>,
-    name: &str,
+    r#type: &Type<RustMetadata>,
 ) -> Result<()> {
+    let name = &r#type.metadata.name;
+    let parameters = used_parameters(r#type, GetIdentity);
+    let adapters: Vec<_> = r#struct
+        .fields
+        .iter()
+        .map(|field| field_adapter(context, &field.r#type))
+        .collect();
+
+    write_serde_as(writer, &adapters)?;
     write_derive(writer, context)?;
     writer.write("pub struct ")?;
     writer.write(name)?;
+    write_parameters(writer, context.naming, &parameters, GetIdentity)?;
     writer.write_nl(" {")?;
     writer.indent();
 
-    for field in &r#struct.fields {
-        if context.options.serde && field.metadata.serde_name != field.metadata.name {
+    for (field, adapter) in r#struct.fields.iter().zip(&adapters) {
+        if context.options.serde && field.spread {
+            writer.write_nl("#[serde(flatten)]")?;
+        } else if context.options.serde && field.metadata.serde_name != field.metadata.name {
             writer.write(r#"#[serde(rename = ""#)?;
             writer.write(&field.metadata.serde_name)?;
             writer.write_nl(r#"")]"#)?;
         }
 
+        if context.options.serde
+            && let Some(default) = &field.default
+        {
+            if is_default_value(context.naming.types, &field.r#type, default, GetIdentity) {
+                writer.write_nl("#[serde(default)]")?;
+            } else {
+                writer.write_fmt(format_args!("#[serde(default = \"{name}"))?;
+
+                if !parameters.is_empty() {
+                    writer.write("::")?;
+                    write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+                }
+
+                writer.write_fmt_nl(format_args!("::{}\")]", default_function_name(field)))?;
+            }
+        }
+
+        if let Some(adapter) = adapter {
+            writer.write_fmt_nl(format_args!("#[serde_as(as = \"{adapter}\")]"))?;
+        }
+
         writer.write("pub ")?;
         writer.write(&field.metadata.name)?;
         writer.write(": ")?;
@@ -109,27 +201,119 @@ fn emit_struct(
     writer.write_nl("}")?;
     writer.blank_line();
 
+    emit_default_functions(writer, context, r#struct, name, &parameters)?;
+
     Ok(())
 }
 
-/// Emits the type declaration for an enum.
+/// Emits an `impl` block with functions returning the default values of fields,
+/// for the ones that `#[serde(default = "...")]` attributes refer to, if there are any.
+fn emit_default_functions(
+    writer: &mut SourceWriter<impl Write>,
+    context: TypeCodegenContext,
+    r#struct: &Struct<RustMetadata>,
+    name: &str,
+    parameters: &[&Parameter<RustMetadata>],
+) -> Result<()> {
+    if !context.options.serde {
+        return Ok(());
+    }
+
+    let mut fields = r#struct
+        .fields
+        .iter()
+        .filter_map(|field| field.default.as_ref().map(|default| (field, default)))
+        .filter(|(field, default)| {
+            !is_default_value(context.naming.types, &field.r#type, default, GetIdentity)
+        })
+        .peekable();
+
+    if fields.peek().is_none() {
+        return Ok(());
+    }
+
+    writer.write("impl")?;
+    write_parameters(writer, context.naming, parameters, GetIdentity)?;
+    writer.write_fmt(format_args!(" {name}"))?;
+    write_parameters(writer, context.naming, parameters, GetIdentity)?;
+    writer.write_nl(" {")?;
+    writer.indent();
+
+    for (index, (field, default)) in fields.enumerate() {
+        if index > 0 {
+            writer.blank_line();
+        }
+
+        writer.write_fmt(format_args!(
+            "pub fn {}() -> ",
+            default_function_name(field)
+        ))?;
+        write_type_name(writer, context, &field.r#type)?;
+        writer.write_nl(" {")?;
+        writer.indent();
+        write_value(
+            writer,
+            context.naming,
+            &field.r#type,
+            default,
+            format_args!(""),
+            GetIdentity,
+        )?;
+        writer.write_nl("")?;
+        writer.dedent();
+        writer.write_nl("}")?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Gets the name of the function returning the default value of a field.
+fn default_function_name(field: &Field<RustMetadata>) -> String {
+    let name = &field.metadata.name;
+    let name = name
+        .strip_prefix(RustIdentRules.reserved_prefix())
+        .unwrap_or(name);
+    format!("default_{name}")
+}
+
+/// Emits the type declaration for an enum, which is generic over the type parameters it uses.
 fn emit_enum(
     writer: &mut SourceWriter<impl Write>,
     context: TypeCodegenContext,
     r#enum: &Enum<RustMetadata>,
-    name: &str,
+    r#type: &Type<RustMetadata>,
 ) -> Result<()> {
+    let adapters: Vec<_> = r#enum
+        .variants
+        .iter()
+        .map(|variant| field_adapter(context, &variant.r#type))
+        .collect();
+
+    write_serde_as(writer, &adapters)?;
     write_derive(writer, context)?;
     if context.options.serde && !context.options.serde_external_tag {
         writer.write_nl(r#"#[serde(tag = "type", content = "value")]"#)?;
     }
+    if r#enum.open {
+        writer.write_nl("#[non_exhaustive]")?;
+    }
+
+    let name = &r#type.metadata.name;
+    let parameters = used_parameters(r#type, GetIdentity);
+    // Values with the tag of a known variant are rejected by the catch-all variant, see `emit_unknown_check`
+    let checks_unknown = r#enum.open && context.options.serde && !r#enum.variants.is_empty();
 
     writer.write("pub enum ")?;
     writer.write(name)?;
+    write_parameters(writer, context.naming, &parameters, GetIdentity)?;
     writer.write_nl(" {")?;
     writer.indent();
 
-    for variant in &r#enum.variants {
+    for (variant, adapter) in r#enum.variants.iter().zip(&adapters) {
         if context.options.serde && variant.metadata.serde_name != variant.metadata.name {
             writer.write(r#"#[serde(rename = ""#)?;
             writer.write(&variant.metadata.serde_name)?;
@@ -138,26 +322,127 @@ fn emit_enum(
 
         writer.write(&variant.metadata.name)?;
         writer.write("(")?;
+        write_adapter(writer, adapter)?;
         write_type_name(writer, context, &variant.r#type)?;
         writer.write_nl("),")?;
     }
 
+    // Holds the whole value of a variant added in a later version, as it was deserialized
+    if r#enum.open {
+        let unknown = unknown_variant_name(r#enum, GetIdentity);
+
+        if context.options.serde {
+            writer.write_nl("#[serde(untagged)]")?;
+
+            if checks_unknown {
+                writer.write_fmt(format_args!("#[serde(deserialize_with = \"{name}"))?;
+
+                if !parameters.is_empty() {
+                    writer.write("::")?;
+                    write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+                }
+
+                writer.write_fmt_nl(format_args!("::{DESERIALIZE_UNKNOWN}\")]"))?;
+            }
+
+            writer.write_fmt_nl(format_args!("{unknown}(::serde_json::Value),"))?;
+        } else {
+            writer.write_fmt_nl(format_args!("{unknown}(()),"))?;
+        }
+    }
+
     writer.dedent();
     writer.write_nl("}")?;
     writer.blank_line();
 
+    if checks_unknown {
+        emit_unknown_check(writer, context, r#enum, name, &parameters)?;
+    }
+
     Ok(())
 }
 
-/// Emits a type alias.
+/// The name of the function deserializing the catch-all variant of an open enum.
+const DESERIALIZE_UNKNOWN: &str = "deserialize_unknown";
+
+/// Emits an `impl` block with the function deserializing the catch-all variant of an open enum,
+/// which rejects values with the tag of a known variant.
+///
+/// Serde tries the catch-all variant when a value doesn't match any known variant,
+/// so without it, invalid values of known variants would be deserialized as unknown ones.
+fn emit_unknown_check(
+    writer: &mut SourceWriter<impl Write>,
+    context: TypeCodegenContext,
+    r#enum: &Enum<RustMetadata>,
+    name: &str,
+    parameters: &[&Parameter<RustMetadata>],
+) -> Result<()> {
+    let result = context.naming.rust_type("Result", "::std::result::Result");
+
+    writer.write("impl")?;
+    write_parameters(writer, context.naming, parameters, GetIdentity)?;
+    writer.write_fmt(format_args!(" {name}"))?;
+    write_parameters(writer, context.naming, parameters, GetIdentity)?;
+    writer.write_nl(" {")?;
+    writer.indent();
+
+    writer.write_fmt_nl(format_args!(
+        "fn {DESERIALIZE_UNKNOWN}<'de, D: ::serde::Deserializer<'de>>(deserializer: D) -> {result}<::serde_json::Value, D::Error> {{"
+    ))?;
+    writer.indent();
+    writer.write_nl(
+        "let value = <::serde_json::Value as ::serde::Deserialize>::deserialize(deserializer)?;",
+    )?;
+
+    if context.options.serde_external_tag {
+        writer.write_nl("let tag = value.as_object().filter(|object| object.len() == 1).and_then(|object| object.keys().next()).map(|tag| tag.as_str());")?;
+    } else {
+        writer.write_nl("let tag = value.get(\"type\").and_then(::serde_json::Value::as_str);")?;
+    }
+
+    writer.write("if let Some(")?;
+    for (index, variant) in r#enum.variants.iter().enumerate() {
+        if index > 0 {
+            writer.write(" | ")?;
+        }
+        writer.write_fmt(format_args!("{:?}", variant.metadata.serde_name))?;
+    }
+    writer.write_nl(") = tag {")?;
+    writer.indent();
+    writer.write_nl(
+        "return Err(::serde::de::Error::custom(\"invalid value of a known variant\"));",
+    )?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.write_nl("Ok(value)")?;
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Emits a type alias, which for an extern type refers to the Rust type it's mapped to.
 fn emit_type_alias(
     writer: &mut SourceWriter<impl Write>,
     context: TypeCodegenContext,
     r#type: &NamedType<RustMetadata>,
 ) -> Result<()> {
     let r#type = &r#type.r#type;
+    let parameters = used_parameters(r#type, GetIdentity);
 
-    if r#type.metadata.newtype {
+    if let TypeType::Extern(r#extern) = &r#type.r#type {
+        writer.write_fmt_nl(format_args!(
+            "pub type {} = {};",
+            r#type.metadata.name, context.options.externs[&r#extern.name]
+        ))?;
+    } else if r#type.metadata.newtype {
+        let adapter = field_adapter(context, r#type);
+
+        write_serde_as(writer, std::slice::from_ref(&adapter))?;
         write_derive(writer, context)?;
         if context.options.serde {
             writer.write_nl("#[serde(transparent)]")?;
@@ -165,12 +450,16 @@ fn emit_type_alias(
 
         writer.write("pub struct ")?;
         writer.write(&r#type.metadata.name)?;
-        writer.write("(pub ")?;
+        write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+        writer.write("(")?;
+        write_adapter(writer, &adapter)?;
+        writer.write("pub ")?;
         write_type_name(writer, context, r#type)?;
         writer.write_nl(");")?;
     } else {
         writer.write("pub type ")?;
         writer.write(&r#type.metadata.name)?;
+        write_parameters(writer, context.naming, &parameters, GetIdentity)?;
         writer.write(" = ")?;
         write_type_name(writer, context, r#type)?;
         writer.write_nl(";")?;
@@ -179,6 +468,42 @@ fn emit_type_alias(
     Ok(())
 }
 
+/// Emits an envelope, an enum that tags a top-level type with the version of the schema.
+///
+/// With adjacent tagging, it gets serialized as `{"version": ..., "data": ...}`.
+fn emit_envelope(
+    writer: &mut SourceWriter<impl Write>,
+    context: TypeCodegenContext,
+    r#type: &NamedType<RustMetadata>,
+    name: &str,
+) -> Result<()> {
+    let version = &context.naming.types.version;
+    let parameters = used_parameters(&r#type.r#type, GetIdentity);
+
+    write_derive(writer, context)?;
+    writer.write_nl(r#"#[serde(tag = "version", content = "data")]"#)?;
+    writer.write("pub enum ")?;
+    writer.write(name)?;
+    write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+    writer.write_nl(" {")?;
+    writer.indent();
+
+    writer.write_fmt_nl(format_args!("#[serde(rename = {version:?})]"))?;
+    writer.write_fmt(format_args!(
+        "{}({}",
+        version_variant_name(version),
+        r#type.r#type.metadata.name
+    ))?;
+    write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+    writer.write_nl("),")?;
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
 /// Writes the name of the Rust type corresponding to a Versed type.
 fn write_type_name(
     writer: &mut SourceWriter<impl Write>,
@@ -195,6 +520,42 @@ fn write_type_name(
     )
 }
 
+/// Gets the `serde_as` adapter of the type of a field, variant or newtype, if it needs one with `--serde`.
+fn field_adapter(context: TypeCodegenContext, r#type: &Type<RustMetadata>) -> Option<String> {
+    if !context.options.serde {
+        return None;
+    }
+
+    let context = AdapterContext {
+        naming: context.naming,
+        duration: context.duration_adapter,
+        int_encoding: context.naming.types.int_encoding,
+    };
+
+    serde_as::adapter(context, r#type, &Scope::ROOT)
+}
+
+/// Writes the `serde_as` attribute macro if any field, variant or newtype has an adapter.
+fn write_serde_as(
+    writer: &mut SourceWriter<impl Write>,
+    adapters: &[Option<String>],
+) -> Result<()> {
+    if adapters.iter().any(Option::is_some) {
+        writer.write_nl("#[::serde_with::serde_as]")?;
+    }
+
+    Ok(())
+}
+
+/// Writes the inline attribute applying an adapter to a variant or newtype, if there is one.
+fn write_adapter(writer: &mut SourceWriter<impl Write>, adapter: &Option<String>) -> Result<()> {
+    if let Some(adapter) = adapter {
+        writer.write_fmt(format_args!("#[serde_as(as = \"{adapter}\")] "))?;
+    }
+
+    Ok(())
+}
+
 /// Writes a [`derive`] macro.
 fn write_derive(writer: &mut SourceWriter<impl Write>, context: TypeCodegenContext) -> Result<()> {
     writer.write("#[derive(")?;
@@ -0,0 +1,623 @@
+//! The backend for Rust validation functions, which check the refinements of primitive types.
+//!
+//! Every struct, enum and newtype gets a `validate` method, which returns the first invalid value it finds,
+//! with a JSON path like `$.users[0].name` pointing to it.
+//! Type aliases can't have methods, so they get a function like `validate_score` instead.
+//! The methods and functions of generic types take a function validating each type parameter they check.
+
+use std::{
+    fmt::Display,
+    io::{Result, Write},
+};
+
+use crate::{
+    ast::{
+        Enum, Field, Identifier, NamedType, Parameter, Primitive, PrimitiveType, Type, TypeType,
+    },
+    codegen::{
+        idents::{SnakeCase, convert_case, disambiguate},
+        source_writer::SourceWriter,
+        validation::{Body, CheckEmitter, Checks, bounds_condition, emit_checks, key_segment},
+    },
+    metadata::GetIdentity,
+    rust::{
+        GetResolution, RustMetadata,
+        codegen::{NamingContext, used_parameters, write_parameters, write_type_name},
+        idents::RustIdentRules,
+    },
+};
+
+/// The context for emitting validation functions.
+#[derive(Debug, Clone, Copy)]
+struct ValidationContext<'a> {
+    naming: NamingContext<'a, RustMetadata>,
+    checks: &'a Checks,
+    /// The paths of the validation methods or functions of all named types, like `User::validate`.
+    function_names: &'a [String],
+    /// The name of the error type.
+    error: &'a str,
+    external_tag: bool,
+}
+
+/// An expression evaluating to a value that is validated.
+#[derive(Debug, Clone)]
+enum Value {
+    /// A variable holding a reference to the value.
+    Reference(String),
+    /// A place expression, like `value.name`.
+    Place(String),
+}
+
+impl Value {
+    /// An expression evaluating to the value or a reference to it, which can be used with the `.` operator.
+    fn place(&self) -> &str {
+        match self {
+            Value::Reference(name) | Value::Place(name) => name,
+        }
+    }
+
+    /// An expression evaluating to a reference to the value.
+    fn reference(&self) -> String {
+        match self {
+            Value::Reference(name) => name.clone(),
+            Value::Place(place) => format!("&{place}"),
+        }
+    }
+
+    /// An expression evaluating to a copy of the value.
+    fn copy(&self) -> String {
+        match self {
+            Value::Reference(name) => format!("*{name}"),
+            Value::Place(place) => place.clone(),
+        }
+    }
+
+    /// The value inside a [`Box`].
+    fn unbox(&self) -> Value {
+        match self {
+            Value::Reference(name) => Value::Place(format!("(**{name})")),
+            Value::Place(place) => Value::Place(format!("(*{place})")),
+        }
+    }
+
+    /// A field or tuple element of the value.
+    fn member(&self, name: impl Display) -> Value {
+        Value::Place(format!("{}.{name}", self.place()))
+    }
+}
+
+/// Emits the error type and the validation functions of all named types.
+///
+/// Returns the name of the error type, which is `ValidationError` unless a user-defined type has that name.
+pub fn emit_validation(
+    writer: &mut SourceWriter<impl Write>,
+    naming: NamingContext<RustMetadata>,
+    external_tag: bool,
+) -> Result<String> {
+    let types = naming.types;
+    let checks = Checks::new(types, GetResolution, Vec::new());
+
+    let mut error = "ValidationError".to_owned();
+    disambiguate(&mut error, |name| naming.used_type_names.contains(name));
+
+    let mut function_names = Vec::<String>::with_capacity(types.types.len());
+
+    for r#type in &types.types {
+        if has_method(r#type) {
+            function_names.push(format!("{}::validate", r#type.r#type.metadata.name));
+            continue;
+        }
+
+        let mut name = convert_case(
+            ["validate", &r#type.r#type.metadata.name],
+            SnakeCase,
+            RustIdentRules,
+        );
+        disambiguate(&mut name, |name| {
+            naming.used_type_names.contains(name)
+                || function_names.iter().any(|function| function == name)
+        });
+        function_names.push(name);
+    }
+
+    let context = ValidationContext {
+        naming,
+        checks: &checks,
+        function_names: &function_names,
+        error: &error,
+        external_tag,
+    };
+
+    emit_error(writer, context)?;
+
+    for (index, r#type) in types.types.iter().enumerate() {
+        emit_function(writer, context, index, r#type)?;
+    }
+
+    Ok(error)
+}
+
+/// Emits the error type, which holds the path to the invalid value and describes what was expected.
+fn emit_error(writer: &mut SourceWriter<impl Write>, context: ValidationContext) -> Result<()> {
+    let error = context.error;
+    let string = context.naming.rust_type("String", "::std::string::String");
+
+    writer.write_nl("#[derive(Debug, Clone, PartialEq, Eq)]")?;
+    writer.write_fmt_nl(format_args!("pub struct {error} {{"))?;
+    writer.indent();
+    writer.write_nl("/// The JSON path to the invalid value, like `$.users[0].name`.")?;
+    writer.write_fmt_nl(format_args!("pub path: {string},"))?;
+    writer.write_nl("/// What was expected instead of the invalid value.")?;
+    writer.write_fmt_nl(format_args!("pub message: {string},"))?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_fmt_nl(format_args!("impl {error} {{"))?;
+    writer.indent();
+    writer.write_nl("fn new(message: &str) -> Self {")?;
+    writer.indent();
+    writer.write_nl("Self {")?;
+    writer.indent();
+    writer.write_nl("path: \"$\".to_owned(),")?;
+    writer.write_nl("message: message.to_owned(),")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+    writer.write_nl("fn at(mut self, segment: ::std::fmt::Arguments) -> Self {")?;
+    writer.indent();
+    writer.write_nl("self.path.insert_str(1, &segment.to_string());")?;
+    writer.write_nl("self")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_fmt_nl(format_args!("impl ::std::fmt::Display for {error} {{"))?;
+    writer.indent();
+    writer.write_nl("fn fmt(&self, f: &mut ::std::fmt::Formatter<'_>) -> ::std::fmt::Result {")?;
+    writer.indent();
+    writer.write_nl("write!(f, \"{}: {}\", self.path, self.message)")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_fmt_nl(format_args!("impl ::std::error::Error for {error} {{}}"))?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Checks whether a named type is declared as a Rust struct or enum, which can have a `validate` method,
+/// rather than as a type alias.
+fn has_method(r#type: &NamedType<RustMetadata>) -> bool {
+    r#type.r#type.metadata.newtype
+        || matches!(
+            r#type.r#type.r#type,
+            TypeType::Struct(_) | TypeType::Enum(_)
+        )
+}
+
+/// Emits the validation method or function of a named type.
+fn emit_function(
+    writer: &mut SourceWriter<impl Write>,
+    context: ValidationContext,
+    index: usize,
+    r#type: &NamedType<RustMetadata>,
+) -> Result<()> {
+    let result = context.naming.rust_type("Result", "::std::result::Result");
+    let parameters = used_parameters(&r#type.r#type, GetIdentity);
+    let needed = context.checks.is_needed(index);
+
+    let mut validators: Vec<(usize, String)> = Vec::new();
+
+    for &parameter in context.checks.parameters(index) {
+        let mut name = convert_case(
+            ["validate", &r#type.parameters[parameter].metadata.name],
+            SnakeCase,
+            RustIdentRules,
+        );
+        disambiguate(&mut name, |name| {
+            context
+                .function_names
+                .iter()
+                .any(|function| function == name)
+                || validators.iter().any(|(_, validator)| validator == name)
+        });
+        validators.push((parameter, name));
+    }
+
+    let method = has_method(r#type);
+
+    if method {
+        writer.write("impl")?;
+        write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+        writer.write_fmt(format_args!(" {}", r#type.r#type.metadata.name))?;
+        write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+        writer.write_nl(" {")?;
+        writer.indent();
+        writer.write("pub fn validate(&self")?;
+    } else {
+        writer.write_fmt(format_args!("pub fn {}", context.function_names[index]))?;
+        write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+        writer.write(if needed { "(value: &" } else { "(_value: &" })?;
+        writer.write(&r#type.r#type.metadata.name)?;
+        write_parameters(writer, context.naming, &parameters, GetIdentity)?;
+    }
+
+    for (parameter, name) in &validators {
+        writer.write_fmt(format_args!(
+            ", {name}: &dyn Fn(&{}) -> {result}<(), {}>",
+            r#type.parameters[*parameter].metadata.name, context.error
+        ))?;
+    }
+
+    writer.write_fmt_nl(format_args!(") -> {result}<(), {}> {{", context.error))?;
+    writer.indent();
+
+    if needed {
+        let mut validator = Validator {
+            context,
+            body: Body::new(validators),
+        };
+        let mut value = Value::Reference(if method { "self" } else { "value" }.to_owned());
+
+        if r#type.r#type.metadata.newtype {
+            value = value.member(0);
+        }
+
+        emit_checks(
+            &mut validator,
+            writer,
+            &r#type.r#type,
+            &value,
+            &String::new(),
+        )?;
+    }
+
+    writer.write_nl("Ok(())")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+
+    if method {
+        writer.dedent();
+        writer.write_nl("}")?;
+    }
+
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Emits the statements of a validation function, see [`CheckEmitter`].
+struct Validator<'a> {
+    context: ValidationContext<'a>,
+    body: Body,
+}
+
+impl CheckEmitter<RustMetadata> for Validator<'_> {
+    type Value = Value;
+    /// The format string of the JSON path from the value of the function or closure.
+    type Path = String;
+
+    fn body(&mut self) -> &mut Body {
+        &mut self.body
+    }
+
+    fn has_checks(&self, r#type: &Type<RustMetadata>) -> bool {
+        self.context.checks.has_checks(r#type, GetResolution)
+    }
+
+    fn is_boxed(&self, r#type: &Type<RustMetadata>) -> bool {
+        r#type.metadata.r#box
+    }
+
+    fn unbox(&self, r#type: &Type<RustMetadata>, value: &Value) -> Value {
+        if r#type.metadata.r#box {
+            value.unbox()
+        } else {
+            value.clone()
+        }
+    }
+
+    fn field(
+        &mut self,
+        _writer: &mut SourceWriter<impl Write>,
+        object: &Value,
+        field: &Field<RustMetadata>,
+        path: &String,
+    ) -> Result<(Value, String)> {
+        let path = if field.spread {
+            path.to_owned()
+        } else {
+            format!("{path}{}", escaped_key_segment(&field.metadata.serde_name))
+        };
+
+        Ok((object.member(&field.metadata.name), path))
+    }
+
+    fn emit_enum(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        r#type: &Type<RustMetadata>,
+        r#enum: &Enum<RustMetadata>,
+        value: &Value,
+        path: &String,
+    ) -> Result<()> {
+        let checked: Vec<_> = r#enum
+            .variants
+            .iter()
+            .filter(|variant| self.has_checks(&variant.r#type))
+            .collect();
+
+        let catch_all = r#enum.open || checked.len() < r#enum.variants.len();
+        // A single checked variant is matched with `if let` instead
+        let single = catch_all && checked.len() == 1;
+
+        if !single {
+            writer.write_fmt_nl(format_args!("match {} {{", value.reference()))?;
+            writer.indent();
+        }
+
+        for variant in &checked {
+            let variable = self.body.variable("value");
+            let pattern = format!(
+                "{}::{}({variable})",
+                r#type.metadata.name, variant.metadata.name
+            );
+            let path = if self.context.external_tag {
+                format!(
+                    "{path}{}",
+                    escaped_key_segment(&variant.metadata.serde_name)
+                )
+            } else {
+                format!("{path}.value")
+            };
+
+            if single {
+                writer.write_fmt_nl(format_args!("if let {pattern} = {} {{", value.reference()))?;
+            } else {
+                writer.write_fmt_nl(format_args!("{pattern} => {{"))?;
+            }
+
+            writer.indent();
+            let variant_value = Value::Reference(variable);
+            emit_checks(self, writer, &variant.r#type, &variant_value, &path)?;
+            writer.dedent();
+            writer.write_nl("}")?;
+        }
+
+        if !single {
+            if catch_all {
+                writer.write_nl("_ => {}")?;
+            }
+
+            writer.dedent();
+            writer.write_nl("}")?;
+        }
+
+        Ok(())
+    }
+
+    fn begin_loop(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        value: &Value,
+        path: &String,
+    ) -> Result<(Value, String)> {
+        let index = self.body.variable("index");
+        let element = self.body.variable("element");
+
+        writer.write_fmt_nl(format_args!(
+            "for ({index}, {element}) in {}.iter().enumerate() {{",
+            value.place()
+        ))?;
+        writer.indent();
+
+        Ok((Value::Reference(element), format!("{path}[{{{index}}}]")))
+    }
+
+    fn element(
+        &mut self,
+        _writer: &mut SourceWriter<impl Write>,
+        value: &Value,
+        index: usize,
+        path: &String,
+    ) -> Result<(Value, String)> {
+        Ok((value.member(index), format!("{path}[{index}]")))
+    }
+
+    fn emit_primitive(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        primitive: &Primitive<RustMetadata>,
+        value: &Value,
+        path: &String,
+    ) -> Result<()> {
+        emit_primitive_checks(writer, self.context, primitive, value, path)
+    }
+
+    fn function_name(&self, identifier: &Identifier<RustMetadata>) -> String {
+        self.context.function_names[identifier.metadata.resolution].clone()
+    }
+
+    fn checked_arguments<'t>(
+        &self,
+        identifier: &'t Identifier<RustMetadata>,
+    ) -> Vec<&'t Type<RustMetadata>> {
+        self.context
+            .checks
+            .parameters(identifier.metadata.resolution)
+            .iter()
+            .map(|&parameter| &identifier.arguments[parameter])
+            .collect()
+    }
+
+    fn parameter_index(&self, parameter: &Parameter<RustMetadata>) -> usize {
+        parameter.metadata.resolution
+    }
+
+    fn begin_call(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        function: &str,
+        value: &Value,
+    ) -> Result<String> {
+        writer.write_fmt(format_args!("{function}({}", value.reference()))?;
+        Ok(String::new())
+    }
+
+    fn end_call(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        _result: &str,
+        path: &String,
+    ) -> Result<()> {
+        writer.write(")")?;
+        write_error_path(writer, path)?;
+        writer.write_nl("?;")
+    }
+
+    fn write_no_checks(&mut self, writer: &mut SourceWriter<impl Write>) -> Result<()> {
+        writer.write("&|_| Ok(())")
+    }
+
+    fn write_function(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        function: &str,
+    ) -> Result<()> {
+        writer.write_fmt(format_args!("&{function}"))
+    }
+
+    fn begin_closure(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        r#type: &Type<RustMetadata>,
+    ) -> Result<(Value, String)> {
+        let variable = self.body.variable("value");
+        writer.write_fmt(format_args!("&|{variable}: &"))?;
+        write_type_name(
+            writer,
+            self.context.naming,
+            r#type,
+            format_args!(""),
+            true,
+            GetIdentity,
+        )?;
+        writer.write_nl("| {")?;
+        writer.indent();
+
+        Ok((Value::Reference(variable), String::new()))
+    }
+
+    fn end_closure(&mut self, writer: &mut SourceWriter<impl Write>) -> Result<()> {
+        writer.write_nl("Ok(())")?;
+        writer.dedent();
+        writer.write("}")
+    }
+}
+
+/// Emits the checks of the refinements of a primitive type.
+fn emit_primitive_checks(
+    writer: &mut SourceWriter<impl Write>,
+    context: ValidationContext,
+    primitive: &Primitive<RustMetadata>,
+    value: &Value,
+    path: &str,
+) -> Result<()> {
+    let refinements = &primitive.refinements;
+
+    match primitive.r#type {
+        PrimitiveType::Number => {
+            if let Some(expected) = refinements.expected_int() {
+                let condition = bounds_condition(
+                    refinements.min,
+                    refinements.max,
+                    &value.copy(),
+                    "",
+                    Some(&value.reference()),
+                );
+                emit_check(writer, context, &condition, &expected, path)?;
+            }
+        }
+        PrimitiveType::String => {
+            if let Some(expected) = refinements.expected_length() {
+                let length = format!("{}.chars().count()", value.place());
+                let condition = bounds_condition(
+                    refinements.min,
+                    refinements.max,
+                    &length,
+                    "",
+                    Some(&format!("&{length}")),
+                );
+                emit_check(writer, context, &condition, &expected, path)?;
+            }
+
+            if let (Some(pattern), Some(expected)) =
+                (&refinements.pattern, refinements.expected_pattern())
+            {
+                writer.write_nl("{")?;
+                writer.indent();
+                writer.write_fmt_nl(format_args!(
+                    "static PATTERN: ::std::sync::LazyLock<::regex::Regex> = \
+                    ::std::sync::LazyLock::new(|| ::regex::Regex::new({:?}).unwrap());",
+                    pattern.as_str()
+                ))?;
+                let condition = format!("!PATTERN.is_match({})", value.reference());
+                emit_check(writer, context, &condition, &expected, path)?;
+                writer.dedent();
+                writer.write_nl("}")?;
+            }
+        }
+        // The Rust types of built-in types only hold valid values
+        _ => {}
+    }
+
+    Ok(())
+}
+
+/// Emits an `if` returning an error if a condition is true.
+fn emit_check(
+    writer: &mut SourceWriter<impl Write>,
+    context: ValidationContext,
+    condition: &str,
+    expected: &str,
+    path: &str,
+) -> Result<()> {
+    writer.write_fmt_nl(format_args!("if {condition} {{"))?;
+    writer.indent();
+    writer.write_fmt(format_args!(
+        "return Err({}::new({expected:?})",
+        context.error
+    ))?;
+
+    if !path.is_empty() {
+        writer.write_fmt(format_args!(".at(format_args!({path:?}))"))?;
+    }
+
+    writer.write_nl(");")?;
+    writer.dedent();
+    writer.write_nl("}")
+}
+
+/// Writes a call adding the path to the value to the error returned by a validation function, if it's not empty.
+fn write_error_path(writer: &mut SourceWriter<impl Write>, path: &str) -> Result<()> {
+    if path.is_empty() {
+        Ok(())
+    } else {
+        writer.write_fmt(format_args!(
+            ".map_err(|error| error.at(format_args!({path:?})))"
+        ))
+    }
+}
+
+/// Formats a segment of a JSON path accessing a key, escaped for a format string.
+fn escaped_key_segment(key: &str) -> String {
+    key_segment(key).replace('{', "{{").replace('}', "}}")
+}
@@ -0,0 +1,479 @@
+//! Generates random sample values of a type, serialized as JSON.
+//!
+//! The values use the same representation as the Rust types generated with `--serde`,
+//! which is also the one described by the TypeScript types.
+
+use std::{
+    io::{BufWriter, Write, stdout},
+    path::Path,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use regex_syntax::hir::{Class, Hir, HirKind};
+use serde_json::{Map, Value as Json};
+
+use crate::{
+    ast::{PrimitiveType, Refinements, Scope, Struct, Type, TypeSet, TypeType},
+    codegen::naming_pass::{NameMetadata, name},
+    composite,
+    error::{Error, ResultExt},
+    formats::{self, IntEncoding},
+    getter,
+    loading::{check_type_names, load_file},
+    mapper,
+    preprocessing::{
+        BasicMetadata, EXTRA_DEPTH, ResolutionMetadata, min_depth, min_depths, needs_extern,
+    },
+    typescript::TypeScriptNamingRules,
+};
+
+/// The maximum length of generated lists.
+const MAX_LIST_LENGTH: u64 = 3;
+
+/// The maximum length of generated strings.
+const MAX_STRING_LENGTH: u64 = 8;
+
+/// The characters generated strings consist of.
+const STRING_CHARACTERS: &[u8] = b"abcdefghijklmnopqrstuvwxyz";
+
+/// The largest absolute value of generated ints.
+const MAX_INT: u64 = 1000;
+
+/// The maximum number of repetitions generated for `*` and `+` in patterns, on top of the minimum.
+const MAX_EXTRA_REPETITIONS: u32 = 3;
+
+/// The number of strings generated from a pattern before giving up on finding one of the required length.
+const PATTERN_ATTEMPTS: usize = 100;
+
+/// Configuration of the sample generator.
+#[derive(Debug, Clone, Default)]
+pub struct SampleOptions {
+    count: Option<usize>,
+    seed: Option<u64>,
+    external_tag: bool,
+}
+
+impl SampleOptions {
+    /// Creates new [`SampleOptions`].
+    ///
+    /// If `count` is set, a JSON array with that many values is generated instead of a single value.
+    /// Without a `seed`, one is derived from the current time.
+    /// If `external_tag` is set, enums are represented like with `--serde-external-tag`.
+    pub fn new(count: Option<usize>, seed: Option<u64>, external_tag: bool) -> Self {
+        Self {
+            count,
+            seed,
+            external_tag,
+        }
+    }
+}
+
+/// The context for the sample generator.
+struct Context<'a> {
+    types: &'a TypeSet<SampleMetadata>,
+    /// The minimal depth of every named type, see [`min_depths`].
+    depths: &'a [Option<u32>],
+    rng: Rng,
+    external_tag: bool,
+    int_encoding: IntEncoding,
+    /// A pattern that no string of the required length could be generated for.
+    unsatisfiable: Option<String>,
+}
+
+/// Implements `versed sample`.
+pub fn generate_sample(path: &Path, type_name: &str, options: &SampleOptions) -> Result<(), Error> {
+    let types = load_file(path)?;
+    check_type_names(&types, &[type_name.to_owned()])?;
+    let types = name(types, TypeScriptNamingRules, AddName);
+
+    let index = types.index_of(type_name).expect("name to be checked");
+
+    if !types.types[index].parameters.is_empty() {
+        return Err(Error::GenericType {
+            name: type_name.to_owned(),
+        });
+    }
+
+    let depths = min_depths(&types, GetResolution);
+    let Some(depth) = depths[index] else {
+        // Extern types have no values that could be generated, so they count as infinitely deep
+        return Err(if needs_extern(&types, GetResolution, index) {
+            Error::ExternValue {
+                name: type_name.to_owned(),
+            }
+        } else {
+            Error::InfiniteDepth {
+                name: type_name.to_owned(),
+            }
+        });
+    };
+
+    let seed = options.seed.unwrap_or_else(|| {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |duration| duration.as_nanos() as u64)
+    });
+
+    let mut context = Context {
+        types: &types,
+        depths: &depths,
+        rng: Rng::new(seed),
+        external_tag: options.external_tag,
+        int_encoding: types.int_encoding,
+        unsatisfiable: None,
+    };
+
+    let r#type = &types.types[index].r#type;
+    let depth = depth + EXTRA_DEPTH;
+    let sample = match options.count {
+        None => sample_value(&mut context, r#type, &Scope::ROOT, depth),
+        Some(count) => Json::Array(
+            (0..count)
+                .map(|_| sample_value(&mut context, r#type, &Scope::ROOT, depth))
+                .collect(),
+        ),
+    };
+
+    // Nothing gets printed if the generation fails
+    if let Some(pattern) = context.unsatisfiable {
+        return Err(Error::UnsatisfiablePattern { pattern });
+    }
+
+    let mut stream = BufWriter::new(stdout().lock());
+    serde_json::to_writer_pretty(&mut stream, &sample)
+        .map_err(Into::into)
+        .and_then(|()| writeln!(stream))
+        .and_then(|()| stream.flush())
+        .with_stdout()
+}
+
+/// Generates a random value of a type, with the type arguments of the generic types it's inside of in `scope`.
+///
+/// `depth` has to be at least the [minimal depth](min_depth) of the type.
+fn sample_value(
+    context: &mut Context,
+    r#type: &Type<SampleMetadata>,
+    scope: &Scope<SampleMetadata>,
+    depth: u32,
+) -> Json {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => {
+            let mut object = Map::new();
+            sample_fields(context, r#struct, scope, depth, &mut object);
+            Json::Object(object)
+        }
+        TypeType::Enum(r#enum) => {
+            let variants: Vec<_> = r#enum
+                .variants
+                .iter()
+                .filter(|variant| {
+                    min_depth(
+                        &variant.r#type,
+                        context.types,
+                        context.depths,
+                        scope,
+                        GetResolution,
+                    )
+                    .is_some_and(|min| min <= depth)
+                })
+                .collect();
+            let variant = variants[context.rng.below(variants.len() as u64) as usize];
+            let value = sample_value(context, &variant.r#type, scope, depth);
+
+            let mut object = Map::new();
+            if context.external_tag {
+                object.insert(variant.metadata.name.clone(), value);
+            } else {
+                object.insert(
+                    "type".to_owned(),
+                    Json::String(variant.metadata.name.clone()),
+                );
+                object.insert("value".to_owned(), value);
+            }
+
+            Json::Object(object)
+        }
+        TypeType::List(list) => {
+            let length = match min_depth(
+                &list.r#type,
+                context.types,
+                context.depths,
+                scope,
+                GetResolution,
+            ) {
+                Some(min) if min <= depth => context.rng.below(MAX_LIST_LENGTH + 1),
+                _ => 0,
+            };
+
+            Json::Array(
+                (0..length)
+                    .map(|_| sample_value(context, &list.r#type, scope, depth))
+                    .collect(),
+            )
+        }
+        TypeType::Tuple(tuple) => Json::Array(
+            tuple
+                .elements
+                .iter()
+                .map(|element| sample_value(context, element, scope, depth))
+                .collect(),
+        ),
+        TypeType::Primitive(primitive) => match primitive.r#type {
+            PrimitiveType::String => Json::String(random_string(context, &primitive.refinements)),
+            PrimitiveType::Number => {
+                let value = random_int(&mut context.rng, &primitive.refinements);
+
+                match context.int_encoding {
+                    IntEncoding::Number => Json::from(value),
+                    IntEncoding::String => Json::String(value.to_string()),
+                }
+            }
+            PrimitiveType::Unit => Json::Null,
+            r#type => Json::String(random_formatted(&mut context.rng, r#type)),
+        },
+        TypeType::Identifier(identifier) => {
+            let types = context.types;
+            let r#type = &types.types[identifier.metadata.resolution].r#type;
+            let scope = scope.enter(&identifier.arguments);
+            sample_value(context, r#type, &scope, depth - 1)
+        }
+        TypeType::Parameter(parameter) => {
+            let (argument, scope) = scope
+                .argument(parameter.metadata.resolution)
+                .expect("type arguments to be checked");
+            sample_value(context, argument, scope, depth)
+        }
+        TypeType::Extern(_) => unreachable!("extern types to have no minimal depth"),
+    }
+}
+
+/// Generates the members of the object for a struct.
+///
+/// The fields of spread structs are members of the same object.
+fn sample_fields(
+    context: &mut Context,
+    r#struct: &Struct<SampleMetadata>,
+    scope: &Scope<SampleMetadata>,
+    depth: u32,
+    object: &mut Map<String, Json>,
+) {
+    for field in &r#struct.fields {
+        if field.spread {
+            let TypeType::Identifier(identifier) = &field.r#type.r#type else {
+                unreachable!("spreads to be checked");
+            };
+            let types = context.types;
+            let TypeType::Struct(r#struct) =
+                &types.types[identifier.metadata.resolution].r#type.r#type
+            else {
+                unreachable!("spreads to be checked");
+            };
+            let scope = scope.enter(&identifier.arguments);
+            sample_fields(context, r#struct, &scope, depth - 1, object);
+            continue;
+        }
+
+        let value = sample_value(context, &field.r#type, scope, depth);
+        object.insert(field.metadata.name.clone(), value);
+    }
+}
+
+/// Generates a random int satisfying the refinements.
+///
+/// The values are taken from around the allowed value closest to zero,
+/// so that they're as small as without refinements.
+fn random_int(rng: &mut Rng, refinements: &Refinements) -> i64 {
+    let min = refinements.min.unwrap_or(i64::MIN);
+    let max = refinements.max.unwrap_or(i64::MAX);
+
+    let center = 0.clamp(min, max);
+    let low = center.saturating_sub(MAX_INT as i64).max(min);
+    let high = center.saturating_add(MAX_INT as i64).min(max);
+
+    low + rng.below(high.abs_diff(low) + 1) as i64
+}
+
+/// Generates a random string satisfying the refinements.
+///
+/// Strings with a pattern are generated from the pattern until one has the required length.
+/// If none does, the pattern is recorded in the context, so that an error can be reported.
+fn random_string(context: &mut Context, refinements: &Refinements) -> String {
+    let Some(pattern) = &refinements.pattern else {
+        let min = refinements.min.unwrap_or(0) as u64;
+        let max = refinements.max.map_or(u64::MAX, |max| max as u64);
+        let length = min
+            + context
+                .rng
+                .below(MAX_STRING_LENGTH.max(min).min(max) - min + 1);
+
+        return (0..length)
+            .map(|_| {
+                let index = context.rng.below(STRING_CHARACTERS.len() as u64);
+                char::from(STRING_CHARACTERS[index as usize])
+            })
+            .collect();
+    };
+
+    let hir = regex_syntax::parse(pattern.as_str()).expect("patterns to be checked");
+
+    for _ in 0..PATTERN_ATTEMPTS {
+        let mut value = String::new();
+        write_match(&mut context.rng, &hir, &mut value);
+
+        if refinements.check_string(&value).is_ok() {
+            return value;
+        }
+    }
+
+    context.unsatisfiable = Some(pattern.as_str().to_owned());
+    String::new()
+}
+
+/// Generates a random value of a built-in type, in its string format.
+///
+/// Timestamps and dates are between 1970 and 2100, and durations are whole seconds.
+fn random_formatted(rng: &mut Rng, r#type: PrimitiveType) -> String {
+    match r#type {
+        PrimitiveType::Timestamp | PrimitiveType::Date => {
+            let year = 1970 + rng.below(130) as u32;
+            let month = 1 + rng.below(12) as u32;
+            let day = 1 + rng.below(u64::from(formats::days_in_month(year, month))) as u32;
+            let date = format!("{year:04}-{month:02}-{day:02}");
+
+            if r#type == PrimitiveType::Date {
+                return date;
+            }
+
+            let (hour, minute, second) = (rng.below(24), rng.below(60), rng.below(60));
+            format!("{date}T{hour:02}:{minute:02}:{second:02}Z")
+        }
+        PrimitiveType::Duration => format!("PT{}S", rng.below(MAX_INT + 1)),
+        PrimitiveType::Uuid => {
+            let digits = format!("{:016x}{:016x}", rng.next(), rng.next());
+            format!(
+                "{}-{}-{}-{}-{}",
+                &digits[0..8],
+                &digits[8..12],
+                &digits[12..16],
+                &digits[16..20],
+                &digits[20..32]
+            )
+        }
+        PrimitiveType::Bytes => {
+            let length = rng.below(MAX_STRING_LENGTH + 1);
+            let bytes: Vec<u8> = (0..length).map(|_| rng.next() as u8).collect();
+            formats::encode_base64(&bytes)
+        }
+        PrimitiveType::String | PrimitiveType::Number | PrimitiveType::Unit => {
+            unreachable!("only built-in types to have a format")
+        }
+    }
+}
+
+/// Appends a random string matching a regular expression to `value`.
+///
+/// Assertions like `^` or `\b` are ignored, so the string has to be checked afterwards.
+fn write_match(rng: &mut Rng, hir: &Hir, value: &mut String) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => value.push_str(&String::from_utf8_lossy(&literal.0)),
+        HirKind::Class(Class::Unicode(class)) => {
+            // Printable ASCII characters are preferred, as they make for readable samples
+            let ranges = class.ranges();
+            let printable: Vec<char> = ranges
+                .iter()
+                .flat_map(|range| range.start().max(' ')..=range.end().min('~'))
+                .collect();
+
+            if !printable.is_empty() {
+                value.push(printable[rng.below(printable.len() as u64) as usize]);
+            } else if !ranges.is_empty() {
+                let range = ranges[rng.below(ranges.len() as u64) as usize];
+                let (start, end) = (u32::from(range.start()), u32::from(range.end()));
+                let code = start + rng.below(u64::from(end - start) + 1) as u32;
+                value.push(char::from_u32(code).unwrap_or(range.start()));
+            }
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let ascii: Vec<u8> = class
+                .ranges()
+                .iter()
+                .flat_map(|range| range.start()..=range.end().min(0x7f))
+                .collect();
+
+            if !ascii.is_empty() {
+                value.push(char::from(ascii[rng.below(ascii.len() as u64) as usize]));
+            }
+        }
+        HirKind::Repetition(repetition) => {
+            let max = repetition
+                .max
+                .unwrap_or(u32::MAX)
+                .min(repetition.min.saturating_add(MAX_EXTRA_REPETITIONS));
+            let count = repetition.min + rng.below(u64::from(max - repetition.min) + 1) as u32;
+
+            for _ in 0..count {
+                write_match(rng, &repetition.sub, value);
+            }
+        }
+        HirKind::Capture(capture) => write_match(rng, &capture.sub, value),
+        HirKind::Concat(parts) => {
+            for part in parts {
+                write_match(rng, part, value);
+            }
+        }
+        HirKind::Alternation(alternatives) => {
+            let index = rng.below(alternatives.len() as u64) as usize;
+            write_match(rng, &alternatives[index], value);
+        }
+    }
+}
+
+/// A small pseudorandom number generator, implementing SplitMix64.
+///
+/// The output only has to look random and be reproducible from the seed.
+struct Rng(u64);
+
+impl Rng {
+    /// Creates a new [`Rng`] from a seed.
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    /// Generates the next random number.
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d049bb133111eb);
+        z ^ (z >> 31)
+    }
+
+    /// Generates a random number less than `bound`, which must not be zero.
+    fn below(&mut self, bound: u64) -> u64 {
+        self.next() % bound
+    }
+}
+
+composite! {
+    struct (SampleInfo, SampleMetadata) {
+        name: NameMetadata | N,
+        resolution: ResolutionMetadata | R
+    }
+}
+
+mapper! {
+    fn AddName(basic: BasicMetadata, name: NameMetadata) -> SampleMetadata {
+        SampleInfo {
+            name,
+            resolution: basic.resolution,
+        }
+    }
+}
+
+getter! {
+    fn GetResolution(metadata: SampleMetadata) -> ResolutionMetadata {
+        &metadata.resolution
+    }
+}
@@ -0,0 +1,396 @@
+//! Serializes ASTs as JSON, for `versed dump` and for plugins.
+//!
+//! The structure of the AST is serialized by [`serialize_type_set`] and [`serialize_migration`],
+//! while the metadata is added to the JSON objects by a [`MetadataSerializer`],
+//! so that tools with different metadata can share the format.
+
+use std::collections::HashMap;
+
+use serde_json::{Map, Value as Json};
+
+use crate::{
+    ast::{Field, Migration, NamedType, Parameter, Refinements, Type, TypeSet, TypeType, Variant},
+    metadata::Metadata,
+    migrations::{default_value, pair_types},
+    preprocessing::BasicMetadata,
+    syntax::Span,
+};
+
+/// Adds the metadata of AST nodes to their JSON objects.
+///
+/// Every method adds members to the object of one kind of node and does nothing by default.
+pub trait MetadataSerializer<M: Metadata> {
+    fn type_set(&self, _metadata: &M::TypeSet, _object: &mut Map<String, Json>) {}
+    fn named(&self, _metadata: &M::Named, _object: &mut Map<String, Json>) {}
+    fn r#type(&self, _metadata: &M::Type, _object: &mut Map<String, Json>) {}
+
+    fn r#struct(&self, _metadata: &M::Struct, _object: &mut Map<String, Json>) {}
+    fn r#enum(&self, _metadata: &M::Enum, _object: &mut Map<String, Json>) {}
+    fn list(&self, _metadata: &M::List, _object: &mut Map<String, Json>) {}
+    fn tuple(&self, _metadata: &M::Tuple, _object: &mut Map<String, Json>) {}
+    fn primitive(&self, _metadata: &M::Primitive, _object: &mut Map<String, Json>) {}
+    fn identifier(&self, _metadata: &M::Identifier, _object: &mut Map<String, Json>) {}
+    fn parameter(&self, _metadata: &M::Parameter, _object: &mut Map<String, Json>) {}
+    fn r#extern(&self, _metadata: &M::Extern, _object: &mut Map<String, Json>) {}
+
+    fn field(&self, _metadata: &M::Field, _object: &mut Map<String, Json>) {}
+    fn variant(&self, _metadata: &M::Variant, _object: &mut Map<String, Json>) {}
+}
+
+/// A [`MetadataSerializer`] for [`BasicMetadata`], adding spans and the indices of resolved identifiers.
+///
+/// Spans are objects with the byte offsets of their `start` and `end`.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BasicSerializer;
+
+impl MetadataSerializer<BasicMetadata> for BasicSerializer {
+    fn type_set(
+        &self,
+        metadata: &<BasicMetadata as Metadata>::TypeSet,
+        object: &mut Map<String, Json>,
+    ) {
+        insert(object, "versionSpan", span(metadata.span.version));
+    }
+
+    fn named(&self, metadata: &<BasicMetadata as Metadata>::Named, object: &mut Map<String, Json>) {
+        insert(object, "span", span(metadata.span.name));
+    }
+
+    fn r#type(&self, metadata: &<BasicMetadata as Metadata>::Type, object: &mut Map<String, Json>) {
+        insert(object, "span", span(metadata.span.r#type));
+        insert(
+            object,
+            "numberSpan",
+            metadata.span.number.map_or(Json::Null, span),
+        );
+    }
+
+    fn identifier(
+        &self,
+        metadata: &<BasicMetadata as Metadata>::Identifier,
+        object: &mut Map<String, Json>,
+    ) {
+        insert(object, "index", Json::from(metadata.resolution));
+    }
+
+    fn parameter(
+        &self,
+        metadata: &<BasicMetadata as Metadata>::Parameter,
+        object: &mut Map<String, Json>,
+    ) {
+        insert(object, "index", Json::from(metadata.resolution));
+        insert(object, "span", span(metadata.span));
+    }
+
+    fn field(&self, metadata: &<BasicMetadata as Metadata>::Field, object: &mut Map<String, Json>) {
+        insert(object, "span", span(metadata.span.name));
+        insert(
+            object,
+            "defaultSpan",
+            metadata.span.default.map_or(Json::Null, span),
+        );
+    }
+
+    fn variant(
+        &self,
+        metadata: &<BasicMetadata as Metadata>::Variant,
+        object: &mut Map<String, Json>,
+    ) {
+        insert(object, "span", span(metadata.span.name));
+    }
+}
+
+/// Converts the nodes of an AST into JSON, recording where the numbered types end up.
+struct Serializer<'a, S> {
+    metadata: &'a S,
+    /// The JSON pointers to all types with a migration marker, by their number.
+    numbered: HashMap<u64, String>,
+}
+
+impl<S> Serializer<'_, S> {
+    /// Converts a schema, which is found at `pointer` in the document.
+    fn type_set<M>(&mut self, types: &TypeSet<M>, pointer: &str) -> Json
+    where
+        M: Metadata,
+        S: MetadataSerializer<M>,
+    {
+        let mut object = Map::new();
+        insert(&mut object, "version", Json::from(types.version.as_str()));
+        insert(
+            &mut object,
+            "intEncoding",
+            Json::from(types.int_encoding.name()),
+        );
+        self.metadata.type_set(&types.metadata, &mut object);
+
+        let named_types = types
+            .types
+            .iter()
+            .enumerate()
+            .map(|(index, r#type)| self.named_type(r#type, &format!("{pointer}/types/{index}")))
+            .collect();
+        insert(&mut object, "types", Json::Array(named_types));
+
+        Json::Object(object)
+    }
+
+    /// Converts a named type, which is found at `pointer` in the document.
+    fn named_type<M>(&mut self, r#type: &NamedType<M>, pointer: &str) -> Json
+    where
+        M: Metadata,
+        S: MetadataSerializer<M>,
+    {
+        let mut object = Map::new();
+        insert(&mut object, "name", Json::from(r#type.name.as_str()));
+        self.metadata.named(&r#type.metadata, &mut object);
+
+        let parameters = r#type
+            .parameters
+            .iter()
+            .map(|parameter| self.parameter(parameter))
+            .collect();
+        insert(&mut object, "parameters", Json::Array(parameters));
+
+        let r#type = self.r#type(&r#type.r#type, &format!("{pointer}/type"));
+        insert(&mut object, "type", r#type);
+
+        Json::Object(object)
+    }
+
+    /// Converts a type recursively, which is found at `pointer` in the document.
+    fn r#type<M>(&mut self, r#type: &Type<M>, pointer: &str) -> Json
+    where
+        M: Metadata,
+        S: MetadataSerializer<M>,
+    {
+        if let Some(number) = r#type.number {
+            self.numbered.insert(number, pointer.to_owned());
+        }
+
+        let mut object = Map::new();
+        insert(&mut object, "kind", Json::from(kind(&r#type.r#type)));
+        insert(&mut object, "number", Json::from(r#type.number));
+        self.metadata.r#type(&r#type.metadata, &mut object);
+
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) => {
+                self.metadata.r#struct(&r#struct.metadata, &mut object);
+
+                let fields = r#struct
+                    .fields
+                    .iter()
+                    .enumerate()
+                    .map(|(index, field)| self.field(field, &format!("{pointer}/fields/{index}")))
+                    .collect();
+                insert(&mut object, "fields", Json::Array(fields));
+            }
+            TypeType::Enum(r#enum) => {
+                self.metadata.r#enum(&r#enum.metadata, &mut object);
+
+                let variants = r#enum
+                    .variants
+                    .iter()
+                    .enumerate()
+                    .map(|(index, variant)| {
+                        self.variant(variant, &format!("{pointer}/variants/{index}"))
+                    })
+                    .collect();
+                insert(&mut object, "variants", Json::Array(variants));
+                insert(&mut object, "open", Json::from(r#enum.open));
+            }
+            TypeType::List(list) => {
+                self.metadata.list(&list.metadata, &mut object);
+
+                let element = self.r#type(&list.r#type, &format!("{pointer}/element"));
+                insert(&mut object, "element", element);
+            }
+            TypeType::Tuple(tuple) => {
+                self.metadata.tuple(&tuple.metadata, &mut object);
+
+                let elements = tuple
+                    .elements
+                    .iter()
+                    .enumerate()
+                    .map(|(index, element)| {
+                        self.r#type(element, &format!("{pointer}/elements/{index}"))
+                    })
+                    .collect();
+                insert(&mut object, "elements", Json::Array(elements));
+            }
+            TypeType::Primitive(primitive) => {
+                let name = primitive.r#type.name();
+                insert(&mut object, "primitive", Json::from(name));
+
+                let Refinements { min, max, pattern } = &primitive.refinements;
+                let mut refinements = Map::new();
+                insert(&mut refinements, "min", Json::from(*min));
+                insert(&mut refinements, "max", Json::from(*max));
+                let pattern = pattern.as_ref().map(|pattern| pattern.as_str());
+                insert(&mut refinements, "pattern", Json::from(pattern));
+                insert(&mut object, "refinements", Json::Object(refinements));
+
+                self.metadata.primitive(&primitive.metadata, &mut object);
+            }
+            TypeType::Identifier(identifier) => {
+                insert(&mut object, "name", Json::from(identifier.ident.as_str()));
+                self.metadata.identifier(&identifier.metadata, &mut object);
+
+                let arguments = identifier
+                    .arguments
+                    .iter()
+                    .enumerate()
+                    .map(|(index, argument)| {
+                        self.r#type(argument, &format!("{pointer}/arguments/{index}"))
+                    })
+                    .collect();
+                insert(&mut object, "arguments", Json::Array(arguments));
+            }
+            TypeType::Parameter(parameter) => {
+                insert(&mut object, "name", Json::from(parameter.name.as_str()));
+                self.metadata.parameter(&parameter.metadata, &mut object);
+            }
+            TypeType::Extern(r#extern) => {
+                insert(&mut object, "name", Json::from(r#extern.name.as_str()));
+                self.metadata.r#extern(&r#extern.metadata, &mut object);
+            }
+        }
+
+        Json::Object(object)
+    }
+
+    /// Converts a type parameter, either where it's declared or where it's used.
+    fn parameter<M>(&self, parameter: &Parameter<M>) -> Json
+    where
+        M: Metadata,
+        S: MetadataSerializer<M>,
+    {
+        let mut object = Map::new();
+        insert(&mut object, "name", Json::from(parameter.name.as_str()));
+        self.metadata.parameter(&parameter.metadata, &mut object);
+        Json::Object(object)
+    }
+
+    /// Converts a field, which is found at `pointer` in the document.
+    fn field<M>(&mut self, field: &Field<M>, pointer: &str) -> Json
+    where
+        M: Metadata,
+        S: MetadataSerializer<M>,
+    {
+        let default = field.default.as_ref().map_or(Json::Null, default_value);
+
+        let mut object = Map::new();
+        insert(&mut object, "name", Json::from(field.name.as_str()));
+        insert(&mut object, "default", default);
+        insert(&mut object, "spread", Json::from(field.spread));
+        self.metadata.field(&field.metadata, &mut object);
+
+        let r#type = self.r#type(&field.r#type, &format!("{pointer}/type"));
+        insert(&mut object, "type", r#type);
+
+        Json::Object(object)
+    }
+
+    /// Converts a variant, which is found at `pointer` in the document.
+    fn variant<M>(&mut self, variant: &Variant<M>, pointer: &str) -> Json
+    where
+        M: Metadata,
+        S: MetadataSerializer<M>,
+    {
+        let mut object = Map::new();
+        insert(&mut object, "name", Json::from(variant.name.as_str()));
+        self.metadata.variant(&variant.metadata, &mut object);
+
+        let r#type = self.r#type(&variant.r#type, &format!("{pointer}/type"));
+        insert(&mut object, "type", r#type);
+
+        Json::Object(object)
+    }
+}
+
+/// Converts a schema into JSON, to be placed at `pointer` in the document.
+pub fn serialize_type_set<M, S>(types: &TypeSet<M>, metadata: &S, pointer: &str) -> Json
+where
+    M: Metadata,
+    S: MetadataSerializer<M>,
+{
+    Serializer {
+        metadata,
+        numbered: HashMap::new(),
+    }
+    .type_set(types, pointer)
+}
+
+/// Converts a migration into JSON, to be placed at `pointer` in the document.
+///
+/// Besides both versions, the result contains the pairs of types linked by a migration marker,
+/// as JSON pointers to both types.
+pub fn serialize_migration<M, S>(migration: &Migration<M>, metadata: &S, pointer: &str) -> Json
+where
+    M: Metadata,
+    S: MetadataSerializer<M>,
+{
+    let mut old_serializer = Serializer {
+        metadata,
+        numbered: HashMap::new(),
+    };
+    let mut new_serializer = Serializer {
+        metadata,
+        numbered: HashMap::new(),
+    };
+
+    let old = old_serializer.type_set(&migration.old, &format!("{pointer}/old"));
+    let new = new_serializer.type_set(&migration.new, &format!("{pointer}/new"));
+
+    let pairs = pair_types(migration)
+        .into_iter()
+        .filter_map(|pair| pair.old.number)
+        .map(|number| {
+            let mut object = Map::new();
+            insert(&mut object, "number", Json::from(number));
+            insert(
+                &mut object,
+                "old",
+                Json::from(old_serializer.numbered[&number].as_str()),
+            );
+            insert(
+                &mut object,
+                "new",
+                Json::from(new_serializer.numbered[&number].as_str()),
+            );
+            Json::Object(object)
+        })
+        .collect();
+
+    let mut object = Map::new();
+    insert(&mut object, "old", old);
+    insert(&mut object, "new", new);
+    insert(&mut object, "pairs", Json::Array(pairs));
+    Json::Object(object)
+}
+
+/// Gets the `kind` of a type.
+fn kind<M: Metadata>(r#type: &TypeType<M>) -> &'static str {
+    match r#type {
+        TypeType::Struct(_) => "struct",
+        TypeType::Enum(_) => "enum",
+        TypeType::List(_) => "list",
+        TypeType::Tuple(_) => "tuple",
+        TypeType::Primitive(_) => "primitive",
+        TypeType::Identifier(_) => "identifier",
+        TypeType::Parameter(_) => "parameter",
+        TypeType::Extern(_) => "extern",
+    }
+}
+
+/// Converts a span into an object with the byte offsets of its start and end.
+fn span(span: Span) -> Json {
+    let mut object = Map::new();
+    insert(&mut object, "start", Json::from(span.start));
+    insert(&mut object, "end", Json::from(span.end));
+    Json::Object(object)
+}
+
+/// Adds a member to a JSON object.
+pub fn insert(object: &mut Map<String, Json>, key: &str, value: Json) {
+    object.insert(key.to_owned(), value);
+}
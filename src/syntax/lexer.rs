@@ -138,15 +138,20 @@ pub fn lexer<'src>() -> impl Parser<'src, &'src str, Vec<Spanned<Token>>, extra:
         just(',').to(Token::Punct(Punct::Comma)),
         just(';').to(Token::Punct(Punct::Semicolon)),
         just('#').to(Token::Punct(Punct::Pound)),
+        just('-').to(Token::Punct(Punct::Minus)),
         just('(').to(Token::GroupLeft(Group::Paren)),
         just(')').to(Token::GroupRight(Group::Paren)),
         just('[').to(Token::GroupLeft(Group::Bracket)),
         just(']').to(Token::GroupRight(Group::Bracket)),
         just('{').to(Token::GroupLeft(Group::Brace)),
         just('}').to(Token::GroupRight(Group::Brace)),
+        just('<').to(Token::GroupLeft(Group::Angle)),
+        just('>').to(Token::GroupRight(Group::Angle)),
     ]);
 
-    let token = choice((ident_like, quoted_ident, number, punct_or_group));
+    let dot_dot = just("..").to(Token::Punct(Punct::DotDot));
+
+    let token = choice((ident_like, quoted_ident, number, punct_or_group, dot_dot));
 
     let comment = just("//").ignore_then(none_of("\r\n").repeated());
 
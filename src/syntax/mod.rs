@@ -135,12 +135,19 @@ impl TypeSpanInfo {
     }
 }
 
-/// A collection of spans tied to a field or variant.
+/// A collection of spans tied to a variant or named type.
 #[derive(Debug, Clone, Copy)]
 pub struct MemberSpanInfo {
     pub name: Span,
 }
 
+/// A collection of spans tied to a field.
+#[derive(Debug, Clone, Copy)]
+pub struct FieldSpanInfo {
+    pub name: Span,
+    pub default: Option<Span>,
+}
+
 /// A collection of spans tied to a schema file.
 #[derive(Debug, Clone, Copy)]
 pub struct TypeSetSpanInfo {
@@ -159,10 +166,13 @@ impl Metadata for SpanMetadata {
     type Struct = ();
     type Enum = ();
     type List = ();
+    type Tuple = ();
     type Primitive = ();
     type Identifier = ();
+    type Parameter = Span;
+    type Extern = ();
 
-    type Field = MemberSpanInfo;
+    type Field = FieldSpanInfo;
     type Variant = MemberSpanInfo;
 }
 
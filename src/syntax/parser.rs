@@ -12,14 +12,17 @@ use chumsky::{
 };
 use either::Either;
 use icu_normalizer::ComposingNormalizerBorrowed;
+use regex::Regex;
 
 use crate::{
     ast::{
-        Enum, Field, Identifier, List, Migration, NamedType, Primitive, PrimitiveType, Struct,
-        Type, TypeSet, TypeType, Variant,
+        Enum, Extern, Field, Identifier, List, Migration, NamedType, Parameter, Primitive,
+        PrimitiveType, Refinements, Struct, Tuple, Type, TypeSet, TypeType, Value, Variant,
     },
+    formats::IntEncoding,
     syntax::{
-        FromIterFlatten, MemberSpanInfo, Span, SpanMetadata, TypeSetSpanInfo, TypeSpanInfo,
+        FieldSpanInfo, FromIterFlatten, MemberSpanInfo, Span, SpanMetadata, TypeSetSpanInfo,
+        TypeSpanInfo,
         tokens::{Group, Keyword, Punct, Token},
     },
 };
@@ -104,6 +107,170 @@ fn type_number<'tokens, I: Input<'tokens>>() -> Parser![Option<(u64, Span)>] {
         .map_with(|n, e| n.map(|n| (n, e.span())))
 }
 
+/// A parser for a number with an optional minus sign.
+fn signed_number<'tokens, I: Input<'tokens>>() -> Parser![i64] {
+    punct(Punct::Minus)
+        .or_not()
+        .then(select! {
+            Token::Number(string) => string,
+        })
+        .validate(|(minus, digits), e, emitter| {
+            let digits = if minus.is_some() {
+                format!("-{digits}")
+            } else {
+                digits
+            };
+
+            match i64::from_str(&digits) {
+                Ok(n) => n,
+                Err(_error) => {
+                    emitter.emit(Rich::custom(
+                        e.span(),
+                        format!("numbers must be between {} and {}", i64::MIN, i64::MAX),
+                    ));
+
+                    0
+                }
+            }
+        })
+        .labelled("number")
+}
+
+/// A parser for a literal value.
+fn value<'tokens, I: Input<'tokens>>() -> Parser![Value] {
+    recursive(|value| {
+        let number = signed_number().map(Value::Number);
+
+        let string = select! {
+            Token::QuotedIdent(string) => Value::String(string),
+        };
+
+        let list = value
+            .separated_by(punct(Punct::Comma))
+            .allow_trailing()
+            .collect()
+            .delimited_by(left(Group::Bracket), right(Group::Bracket))
+            .map(Value::List);
+
+        choice((number, string, list))
+    })
+    .labelled("value")
+}
+
+/// A single refinement of a primitive type, as it's written.
+#[derive(Debug, Clone)]
+enum Refinement {
+    /// A range of allowed values or lengths, like `0..150`.
+    Range(Option<i64>, Option<i64>),
+    /// A named refinement, like `max = 64`.
+    Named(String, Value),
+}
+
+/// A parser for the parenthesized refinements of a primitive type, like `(max = 64)`.
+fn refinements<'tokens, I: Input<'tokens>>() -> Parser![Vec<(Refinement, Span)>] {
+    let range = signed_number()
+        .or_not()
+        .then_ignore(punct(Punct::DotDot))
+        .then(signed_number().or_not())
+        .map(|(min, max)| Refinement::Range(min, max));
+
+    let named = ident()
+        .then_ignore(punct(Punct::Equals))
+        .then(value())
+        .map(|(name, value)| Refinement::Named(name, value));
+
+    choice((range, named))
+        .map_with(|refinement, e| (refinement, e.span()))
+        .separated_by(punct(Punct::Comma))
+        .allow_trailing()
+        .collect()
+        .delimited_by(left(Group::Paren), right(Group::Paren))
+        .labelled("refinements")
+}
+
+/// Checks the refinements of a primitive type and combines them,
+/// calling `report` with the span and message of every error.
+fn combine_refinements(
+    r#type: PrimitiveType,
+    refinements: Vec<(Refinement, Span)>,
+    mut report: impl FnMut(Span, String),
+) -> Refinements {
+    let mut result = Refinements::NONE;
+
+    // Bounds of strings are lengths, which can't be negative
+    let set_bound = |bound: &mut Option<i64>, value: i64, name: &str| {
+        if bound.is_some() {
+            Err(format!("the {name} is set more than once"))
+        } else if r#type == PrimitiveType::String && value < 0 {
+            Err("the length of a string can't be negative".to_owned())
+        } else {
+            *bound = Some(value);
+            Ok(())
+        }
+    };
+
+    let mut min_span = None;
+
+    for (refinement, span) in refinements {
+        match refinement {
+            Refinement::Range(min, max) => {
+                if let Some(min) = min {
+                    set_bound(&mut result.min, min, "minimum")
+                        .unwrap_or_else(|message| report(span, message));
+                    min_span = Some(span);
+                }
+                if let Some(max) = max {
+                    set_bound(&mut result.max, max, "maximum")
+                        .unwrap_or_else(|message| report(span, message));
+                }
+            }
+            Refinement::Named(name, Value::Number(value)) if name == "min" => {
+                set_bound(&mut result.min, value, "minimum")
+                    .unwrap_or_else(|message| report(span, message));
+                min_span = Some(span);
+            }
+            Refinement::Named(name, Value::Number(value)) if name == "max" => {
+                set_bound(&mut result.max, value, "maximum")
+                    .unwrap_or_else(|message| report(span, message));
+            }
+            Refinement::Named(name, _) if name == "min" || name == "max" => {
+                report(span, format!("the {name}imum has to be an int"));
+            }
+            Refinement::Named(name, value) if name == "pattern" => match value {
+                _ if r#type != PrimitiveType::String => {
+                    report(span, "only strings can have a pattern".to_owned());
+                }
+                _ if result.pattern.is_some() => {
+                    report(span, "the pattern is set more than once".to_owned());
+                }
+                Value::String(pattern) => match Regex::new(&pattern) {
+                    Ok(regex) => result.pattern = Some(regex),
+                    Err(error) => {
+                        // The message of syntax errors ends with a line like `error: unclosed group`
+                        let error = error.to_string();
+                        let reason = error.lines().last().unwrap_or_default();
+                        let reason = reason.strip_prefix("error: ").unwrap_or(reason);
+                        report(span, format!("the pattern is invalid: {reason}"));
+                    }
+                },
+                _ => report(span, "the pattern has to be a string".to_owned()),
+            },
+            Refinement::Named(name, _) => report(
+                span,
+                format!("unknown refinement '{name}', expected 'min', 'max' or 'pattern'"),
+            ),
+        }
+    }
+
+    if let (Some(min), Some(max), Some(span)) = (result.min, result.max, min_span)
+        && min > max
+    {
+        report(span, "the minimum is larger than the maximum".to_owned());
+    }
+
+    result
+}
+
 /// A parser (for error recovery) that matches any token or a bracketed expression (without semicolons),
 /// but doesn't allow for semicolons, as those cannot appear within types
 /// and are used to synchronize broken named types.
@@ -133,11 +300,16 @@ fn single_or_group<'tokens, I: Input<'tokens>>() -> Parser![()] {
 }
 
 /// A parser that parses a configurable composite type (an enum or a struct).
-fn composite<'tokens, I: Input<'tokens>, F, T>(
+///
+/// `suffix` parses anything following the type of a field, like a default value.
+/// `spread` parses the members written as `..Type`, which is only valid in structs.
+fn composite<'tokens, I: Input<'tokens>, F, T, S>(
     leading_keyword: Keyword,
-    map_field: impl Fn(String, Type<SpanMetadata>, Span) -> F + Clone,
+    map_field: impl Fn(String, Type<SpanMetadata>, S, Span) -> F + Clone,
     map_type: impl Fn(Vec<F>) -> T + Clone,
     r#type: Parser![Type<SpanMetadata>],
+    suffix: Parser![S],
+    spread: Parser![F],
 ) -> Parser![T] {
     let field = ident()
         .map_with(|ident, e| (ident, e.span()))
@@ -161,7 +333,8 @@ fn composite<'tokens, I: Input<'tokens>, F, T>(
                         },
                     })),
         )
-        .map(move |((ident, span), r#type)| map_field(ident, r#type, span));
+        .then(suffix)
+        .map(move |(((ident, span), r#type), suffix)| map_field(ident, r#type, suffix, span));
 
     let skip_to_comma = skip_until(
         single_or_group(),
@@ -176,7 +349,8 @@ fn composite<'tokens, I: Input<'tokens>, F, T>(
             .map(|()| None),
     );
 
-    let body = field
+    let body = spread
+        .or(field)
         .map(Some)
         .recover_with(skip_to_comma)
         .recover_with(skip_to_brace)
@@ -196,6 +370,7 @@ fn composite<'tokens, I: Input<'tokens>, F, T>(
 /// The unit type, to replace unparsable types during error recover.
 const UNIT: Primitive<SpanMetadata> = Primitive {
     r#type: PrimitiveType::Unit,
+    refinements: Refinements::NONE,
     metadata: (),
 };
 
@@ -220,26 +395,84 @@ fn schema_parser<'tokens, I: Input<'tokens>>(
         )
         .recover_with(via_parser(empty().map(to_default_version)));
 
+    // Like `extern`, `int_encoding` is only special at the start of a line of its own,
+    // so `int_encoding = int;` still declares a type.
+    let int_encoding = select! {
+        Token::Ident(ident) if ident == "int_encoding" => (),
+    }
+    .ignore_then(
+        keyword(Keyword::String)
+            .to(String::from("string"))
+            .or(ident()),
+    )
+    .then_ignore(punct(Punct::Semicolon))
+    .validate(|name, e, emitter| {
+        IntEncoding::from_name(&name).unwrap_or_else(|| {
+            emitter.emit(Rich::custom(
+                e.span(),
+                format!("unknown int encoding '{name}', expected 'number' or 'string'"),
+            ));
+            IntEncoding::default()
+        })
+    })
+    .or_not()
+    .map(Option::unwrap_or_default);
+
     let r#type = recursive(|r#type| {
         let parens = r#type
             .clone()
             .delimited_by(left(Group::Paren), right(Group::Paren));
 
-        let primitive = choice([
-            keyword(Keyword::Unit).to(PrimitiveType::Unit),
+        let refinable = choice([
             keyword(Keyword::Int).to(PrimitiveType::Number),
             keyword(Keyword::String).to(PrimitiveType::String),
         ])
-        .map(|r#type| {
-            TypeType::Primitive(Primitive {
-                r#type,
-                metadata: (),
-            })
+        .then(refinements().or_not())
+        .validate(|(r#type, refinements), _e, emitter| {
+            let refinements =
+                combine_refinements(r#type, refinements.unwrap_or_default(), |span, message| {
+                    emitter.emit(Rich::custom(span, message))
+                });
+
+            (r#type, refinements)
+        });
+
+        // Built-in types are only special where a type is expected, so they can still be used as names,
+        // and named types called like them can be referred to with quotes
+        let built_in = select! {
+            Token::Ident(ident) if PrimitiveType::built_in(&ident).is_some() => ident,
+        }
+        .map(|ident| {
+            let r#type = PrimitiveType::built_in(&ident).expect("the name to be checked");
+            (r#type, Refinements::NONE)
         });
 
-        let identifier = ident().map(|ident| {
+        let primitive = keyword(Keyword::Unit)
+            .to((PrimitiveType::Unit, Refinements::NONE))
+            .or(refinable)
+            .or(built_in)
+            .map(|(r#type, refinements)| {
+                TypeType::Primitive(Primitive {
+                    r#type,
+                    refinements,
+                    metadata: (),
+                })
+            });
+
+        let arguments = r#type
+            .clone()
+            .separated_by(punct(Punct::Comma))
+            .allow_trailing()
+            .at_least(1)
+            .collect()
+            .delimited_by(left(Group::Angle), right(Group::Angle))
+            .or_not()
+            .map(Option::unwrap_or_default);
+
+        let identifier = ident().then(arguments).map(|(ident, arguments)| {
             TypeType::Identifier(Identifier {
                 ident,
+                arguments,
                 metadata: (),
             })
         });
@@ -254,12 +487,39 @@ fn schema_parser<'tokens, I: Input<'tokens>>(
                 })
             });
 
+        let tuple = r#type
+            .clone()
+            .separated_by(punct(Punct::Comma))
+            .allow_trailing()
+            .at_least(2)
+            .collect()
+            .delimited_by(left(Group::Paren), right(Group::Paren))
+            .map(|elements| {
+                TypeType::Tuple(Tuple {
+                    elements,
+                    metadata: (),
+                })
+            });
+
+        let default = punct(Punct::Equals)
+            .ignore_then(value().map_with(|value, e| (value, e.span())))
+            .or_not();
+
         let r#struct = composite(
             Keyword::Struct,
-            |name, r#type, span| Field {
-                name,
-                r#type,
-                metadata: MemberSpanInfo { name: span },
+            |name, r#type, default: Option<(Value, Span)>, span| {
+                let (default, default_span) = default.unzip();
+
+                Field {
+                    name,
+                    r#type,
+                    default,
+                    spread: false,
+                    metadata: FieldSpanInfo {
+                        name: span,
+                        default: default_span,
+                    },
+                }
             },
             |fields| {
                 TypeType::Struct(Struct {
@@ -268,27 +528,57 @@ fn schema_parser<'tokens, I: Input<'tokens>>(
                 })
             },
             r#type.clone(),
+            default,
+            punct(Punct::DotDot)
+                .ignore_then(r#type.clone())
+                .map_with(|r#type, e| Field {
+                    name: match &r#type.r#type {
+                        TypeType::Identifier(identifier) => identifier.ident.clone(),
+                        _ => String::new(),
+                    },
+                    r#type,
+                    default: None,
+                    spread: true,
+                    metadata: FieldSpanInfo {
+                        name: e.span(),
+                        default: None,
+                    },
+                }),
         );
 
         let r#enum = composite(
             Keyword::Enum,
-            |name, r#type, span| Variant {
+            |name, r#type, (), span| Variant {
                 name,
                 r#type,
                 metadata: MemberSpanInfo { name: span },
             },
-            |variants| {
-                TypeType::Enum(Enum {
-                    variants,
-                    metadata: (),
-                })
+            |variants| Enum {
+                variants,
+                open: false,
+                metadata: (),
             },
             r#type.clone(),
+            empty(),
+            punct(Punct::DotDot)
+                .try_map(|_, span| Err(Rich::custom(span, "only structs can contain spreads"))),
         );
 
+        // `open` is only special in front of `enum`, so it can still be used as a name
+        let open = select! {
+            Token::Ident(ident) if ident == "open" => (),
+        };
+
+        let r#enum = open.or_not().then(r#enum).map(|(open, r#enum)| {
+            TypeType::Enum(Enum {
+                open: open.is_some(),
+                ..r#enum
+            })
+        });
+
         let real_type = type_number()
             .then(
-                choice((list, r#struct, r#enum, primitive, identifier))
+                choice((list, tuple, r#struct, r#enum, primitive, identifier))
                     .map_with(|r#type, e| (r#type, e.span())),
             )
             .map(|(number, (r#type, span))| Type {
@@ -303,8 +593,22 @@ fn schema_parser<'tokens, I: Input<'tokens>>(
         choice((parens, real_type))
     });
 
+    let parameters = ident()
+        .map_with(|name, e| Parameter {
+            name,
+            metadata: e.span(),
+        })
+        .separated_by(punct(Punct::Comma))
+        .allow_trailing()
+        .at_least(1)
+        .collect()
+        .delimited_by(left(Group::Angle), right(Group::Angle))
+        .or_not()
+        .map(Option::unwrap_or_default);
+
     let named_type = ident()
         .map_with(|ident, e| (ident, e.span()))
+        .then(parameters)
         .then_ignore(punct(Punct::Equals))
         .then(r#type.clone().recover_with(skip_until(
             any().ignored(),
@@ -323,19 +627,43 @@ fn schema_parser<'tokens, I: Input<'tokens>>(
                 .ignored()
                 .recover_with(via_parser(empty())),
         )
-        .map(|((name, span), r#type)| NamedType {
+        .map(|(((name, span), parameters), r#type)| NamedType {
             name,
+            parameters,
             r#type,
             metadata: MemberSpanInfo { name: span },
         });
 
+    // Like `open`, `extern` is only special in front of a name, so `extern = int;` still works.
+    // The migration marker goes between the two, and the name doubles as the span of the type.
+    let extern_type = select! {
+        Token::Ident(ident) if ident == "extern" => (),
+    }
+    .ignore_then(type_number())
+    .then(ident().map_with(|ident, e| (ident, e.span())))
+    .then_ignore(punct(Punct::Semicolon))
+    .map(|(number, (name, span))| NamedType {
+        name: name.clone(),
+        parameters: vec![],
+        r#type: Type {
+            r#type: TypeType::Extern(Extern { name, metadata: () }),
+            number: number.map(|(number, _)| number),
+            metadata: TypeSpanInfo {
+                r#type: span,
+                number: number.map(|(_, span)| span),
+            },
+        },
+        metadata: MemberSpanInfo { name: span },
+    });
+
     let type_recovery_start = if stop_at_version {
         Either::Left(keyword(Keyword::Version).not())
     } else {
         Either::Right(empty())
     };
 
-    let types = named_type
+    let types = extern_type
+        .or(named_type)
         .map(Some)
         .recover_with(via_parser(
             type_recovery_start
@@ -355,15 +683,16 @@ fn schema_parser<'tokens, I: Input<'tokens>>(
         .collect()
         .map(|FromIterFlatten(inner)| inner);
 
-    version
-        .then(types)
-        .map(|((version, version_span), types)| TypeSet {
+    version.then(int_encoding).then(types).map(
+        |(((version, version_span), int_encoding), types)| TypeSet {
             version,
+            int_encoding,
             types,
             metadata: TypeSetSpanInfo {
                 version: version_span,
             },
-        })
+        },
+    )
 }
 
 /// A parser that parses a schema file.
@@ -22,6 +22,7 @@ pub enum Group {
     Paren,
     Bracket,
     Brace,
+    Angle,
 }
 
 /// A specific punctuation character.
@@ -32,6 +33,8 @@ pub enum Punct {
     Comma,
     Semicolon,
     Pound,
+    Minus,
+    DotDot,
 }
 
 /// A specific keyword.
@@ -53,14 +56,18 @@ impl Display for Token {
             Token::GroupLeft(Group::Paren) => f.write_char('('),
             Token::GroupLeft(Group::Bracket) => f.write_char('['),
             Token::GroupLeft(Group::Brace) => f.write_char('{'),
+            Token::GroupLeft(Group::Angle) => f.write_char('<'),
             Token::GroupRight(Group::Paren) => f.write_char(')'),
             Token::GroupRight(Group::Bracket) => f.write_char(']'),
             Token::GroupRight(Group::Brace) => f.write_char('}'),
+            Token::GroupRight(Group::Angle) => f.write_char('>'),
             Token::Punct(Punct::Equals) => f.write_char('='),
             Token::Punct(Punct::Colon) => f.write_char(':'),
             Token::Punct(Punct::Comma) => f.write_char(','),
             Token::Punct(Punct::Semicolon) => f.write_char(';'),
             Token::Punct(Punct::Pound) => f.write_char('#'),
+            Token::Punct(Punct::Minus) => f.write_char('-'),
+            Token::Punct(Punct::DotDot) => f.write_str(".."),
             Token::Keyword(Keyword::Version) => f.write_str("version"),
             Token::Keyword(Keyword::Struct) => f.write_str("struct"),
             Token::Keyword(Keyword::Enum) => f.write_str("enum"),
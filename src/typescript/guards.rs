@@ -0,0 +1,521 @@
+//! The backend for TypeScript type guards, which check that a value of unknown type, like parsed JSON, has a type.
+//!
+//! Every named type gets a function like `checkUser`, which returns the first value not matching the type,
+//! with a JSON path like `$.users[0].name` pointing to it, or `undefined` if the whole value matches.
+//! Refinements and the formats of built-in types are checked as well.
+//! The type guard `isUser` and the function `parseUser`, which throws a `ParseError`, are built on it.
+//! The functions of generic types take a function checking each type parameter.
+//! Named types are checked by calling their functions, so recursive types need no special treatment.
+
+use std::io::{Result, Write};
+
+use crate::{
+    ast::{
+        Enum, Field, Identifier, NamedType, Parameter, Primitive, PrimitiveType, Struct, Tuple,
+        Type, TypeSet, TypeType,
+    },
+    codegen::{
+        idents::disambiguate,
+        source_writer::SourceWriter,
+        validation::{Body, CheckEmitter, emit_checks, key_segment},
+    },
+    formats::{self, IntEncoding},
+    typescript::{
+        TypeScriptMetadata, TypeScriptOptions,
+        types::{is_anomalously_recursive, is_type_name, write_parameters, write_string_literal},
+        validation::{
+            PathPart, Patterns, begin_call, begin_loop, begin_optional, emit_check,
+            emit_primitive_checks, end_call, end_closure, function_name, parameter_function_names,
+            with_literal, write_path,
+        },
+    },
+};
+
+/// The prefix of the names of check functions, like `checkUser`.
+pub const CHECK: &str = "check";
+
+/// The error message for types without values.
+const NO_VALUE: &str = "expected no value, as the type has none";
+
+/// The context for emitting type guards.
+#[derive(Debug, Clone, Copy)]
+struct GuardContext<'a> {
+    types: &'a TypeSet<TypeScriptMetadata>,
+    options: &'a TypeScriptOptions,
+    /// The name of the error type.
+    error: &'a str,
+}
+
+/// Emits the exception thrown by parse functions and the type guards of all named types,
+/// which return the error type named `error`.
+///
+/// Returns the name of the exception, which is `ParseError` unless a user-defined type has that name.
+pub fn emit_guards(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    patterns: &mut Patterns,
+    error: &str,
+) -> Result<String> {
+    let mut parse_error = "ParseError".to_owned();
+    disambiguate(&mut parse_error, |name| {
+        name == error || is_type_name(types, options, name)
+    });
+
+    writer.write_nl(
+        "/** Thrown by the parse functions if a value doesn't have the expected type. */",
+    )?;
+    writer.write_fmt_nl(format_args!("export class {parse_error} extends Error {{"))?;
+    writer.indent();
+    writer.write_nl("/** The JSON path to the invalid value, like `$.users[0].name`. */")?;
+    writer.write_nl("readonly path: string;")?;
+    writer.blank_line();
+    writer.write_fmt_nl(format_args!("constructor(error: {error}) {{"))?;
+    writer.indent();
+    writer.write_nl("super(`${error.message} at ${error.path}`);")?;
+    writer.write("this.name = ")?;
+    write_string_literal(writer, &parse_error)?;
+    writer.write_nl(";")?;
+    writer.write_nl("this.path = error.path;")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    let context = GuardContext {
+        types,
+        options,
+        error,
+    };
+
+    for (index, r#type) in types.types.iter().enumerate() {
+        emit_functions(writer, context, patterns, index, r#type, &parse_error)?;
+    }
+
+    Ok(parse_error)
+}
+
+/// Gets the names of the functions checking the type parameters of a named type,
+/// which its check function takes after the value, like `checkT`.
+pub fn checker_names(
+    types: &TypeSet<TypeScriptMetadata>,
+    r#type: &NamedType<TypeScriptMetadata>,
+) -> Vec<(usize, String)> {
+    parameter_function_names(types, r#type, CHECK, 0..r#type.parameters.len())
+}
+
+/// Emits the check function, the type guard and the parse function of a named type.
+fn emit_functions(
+    writer: &mut SourceWriter<impl Write>,
+    context: GuardContext,
+    patterns: &mut Patterns,
+    index: usize,
+    r#type: &NamedType<TypeScriptMetadata>,
+    parse_error: &str,
+) -> Result<()> {
+    let error = context.error;
+    let name = &r#type.r#type.metadata.name;
+    let checkers = checker_names(context.types, r#type);
+
+    let mut checker_parameters = String::new();
+    let mut checker_arguments = String::new();
+
+    for (_, checker) in &checkers {
+        checker_parameters.push_str(&format!(
+            ", {checker}: (value: unknown, path: string) => {error} | undefined"
+        ));
+        checker_arguments.push_str(&format!(", {checker}"));
+    }
+
+    writer.write_fmt_nl(format_args!(
+        "export function {}(value: unknown{checker_parameters}, path = \"$\"): {error} | undefined {{",
+        function_name(CHECK, r#type)
+    ))?;
+    writer.indent();
+
+    if is_anomalously_recursive(context.types, index) || is_empty_enum(&r#type.r#type) {
+        // The type has no values, as it's declared as `never`
+        emit_return(writer, NO_VALUE, &[PathPart::Expression("path".to_owned())])?;
+    } else {
+        let mut guard = Guard {
+            context,
+            body: Body::new(checkers),
+            patterns,
+        };
+        let value = "value".to_owned();
+        let path = vec![PathPart::Expression("path".to_owned())];
+        emit_checks(&mut guard, writer, &r#type.r#type, &value, &path)?;
+        writer.write_nl("return undefined;")?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_fmt(format_args!("export function is{name}"))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_fmt(format_args!(
+        "(value: unknown{checker_parameters}): value is {name}"
+    ))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_nl(" {")?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!(
+        "return {}(value{checker_arguments}) === undefined;",
+        function_name(CHECK, r#type)
+    ))?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    writer.write_fmt(format_args!("export function parse{name}"))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_fmt(format_args!("(value: unknown{checker_parameters}): {name}"))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_nl(" {")?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!(
+        "const error = {}(value{checker_arguments});",
+        function_name(CHECK, r#type)
+    ))?;
+    writer.blank_line();
+    writer.write_nl("if (error !== undefined) {")?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!("throw new {parse_error}(error);"))?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+    writer.write_fmt(format_args!("return value as {name}"))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_nl(";")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Checks whether a type is a closed enum without variants, which has no values.
+fn is_empty_enum(r#type: &Type<TypeScriptMetadata>) -> bool {
+    matches!(&r#type.r#type, TypeType::Enum(r#enum) if !r#enum.open && r#enum.variants.is_empty())
+}
+
+/// Emits the statements of a check function, see [`CheckEmitter`].
+///
+/// Values are variables of unknown type, and parts of them are assigned to variables of their own,
+/// so that TypeScript narrows their types.
+struct Guard<'a, 'b, 'c> {
+    context: GuardContext<'a>,
+    body: Body,
+    patterns: &'b mut Patterns<'c>,
+}
+
+impl CheckEmitter<TypeScriptMetadata> for Guard<'_, '_, '_> {
+    type Value = String;
+    type Path = Vec<PathPart>;
+
+    fn body(&mut self) -> &mut Body {
+        &mut self.body
+    }
+
+    /// Values of extern types are opaque, so they're accepted as they are.
+    fn has_checks(&self, r#type: &Type<TypeScriptMetadata>) -> bool {
+        !matches!(r#type.r#type, TypeType::Extern(_))
+    }
+
+    fn begin_struct(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        r#struct: &Struct<TypeScriptMetadata>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<String> {
+        emit_object_check(writer, value, path)?;
+
+        // Spreads are flattened into the same object, so they're checked like it
+        let reads_fields = r#struct
+            .fields
+            .iter()
+            .any(|field| !field.spread && self.has_checks(&field.r#type));
+
+        if !reads_fields {
+            return Ok(value.clone());
+        }
+
+        let object = self.body.variable("object");
+        writer.write_fmt_nl(format_args!(
+            "const {object} = {value} as Record<string, unknown>;"
+        ))?;
+        Ok(object)
+    }
+
+    fn field(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        object: &String,
+        field: &Field<TypeScriptMetadata>,
+        path: &Vec<PathPart>,
+    ) -> Result<(String, Vec<PathPart>)> {
+        if field.spread {
+            return Ok((object.clone(), path.clone()));
+        }
+
+        let name = &field.metadata.name;
+        let field_value = self.body.variable("value");
+        writer.write_fmt_nl(format_args!("const {field_value} = {object}.{name};"))?;
+        Ok((field_value, with_literal(path, &key_segment(name))))
+    }
+
+    fn begin_optional(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        field: &Field<TypeScriptMetadata>,
+        value: &String,
+    ) -> Result<bool> {
+        begin_optional(writer, self.context.options, field, value)
+    }
+
+    fn emit_enum(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        r#type: &Type<TypeScriptMetadata>,
+        r#enum: &Enum<TypeScriptMetadata>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<()> {
+        if is_empty_enum(r#type) {
+            return emit_return(writer, NO_VALUE, path);
+        }
+
+        emit_object_check(writer, value, path)?;
+
+        let object = self.body.variable("object");
+        writer.write_fmt_nl(format_args!(
+            "const {object} = {value} as Record<string, unknown>;"
+        ))?;
+
+        let tag = format!("{object}.type");
+        let tag_path = with_literal(path, ".type");
+
+        for (index, variant) in r#enum.variants.iter().enumerate() {
+            writer.write(if index == 0 { "if (" } else { "} else if (" })?;
+            writer.write_fmt(format_args!("{tag} === "))?;
+            write_string_literal(writer, &variant.metadata.name)?;
+            writer.write_nl(") {")?;
+            writer.indent();
+
+            if self.has_checks(&variant.r#type) {
+                let variant_value = self.body.variable("value");
+                let variant_path = with_literal(path, ".value");
+                writer.write_fmt_nl(format_args!("const {variant_value} = {object}.value;"))?;
+                emit_checks(self, writer, &variant.r#type, &variant_value, &variant_path)?;
+            }
+
+            writer.dedent();
+        }
+
+        if r#enum.open {
+            // The tags of variants added in later versions can be any string
+            let condition = format!("typeof {tag} !== \"string\"");
+
+            if r#enum.variants.is_empty() {
+                emit_check(writer, &condition, "expected a string", &tag_path)?;
+            } else {
+                writer.write_fmt_nl(format_args!("}} else if ({condition}) {{"))?;
+                writer.indent();
+                emit_return(writer, "expected a string", &tag_path)?;
+                writer.dedent();
+                writer.write_nl("}")?;
+            }
+        } else {
+            let tags: Vec<_> = r#enum
+                .variants
+                .iter()
+                .map(|variant| format!("\"{}\"", variant.metadata.name))
+                .collect();
+            let expected = match tags.as_slice() {
+                [tag] => format!("expected {tag}"),
+                tags => format!("expected one of {}", tags.join(", ")),
+            };
+
+            writer.write_nl("} else {")?;
+            writer.indent();
+            emit_return(writer, &expected, &tag_path)?;
+            writer.dedent();
+            writer.write_nl("}")?;
+        }
+
+        Ok(())
+    }
+
+    fn begin_list(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<()> {
+        emit_check(
+            writer,
+            &format!("!Array.isArray({value})"),
+            "expected an array",
+            path,
+        )
+    }
+
+    fn begin_loop(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<(String, Vec<PathPart>)> {
+        begin_loop(writer, &mut self.body, value, path)
+    }
+
+    fn begin_tuple(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        tuple: &Tuple<TypeScriptMetadata>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<()> {
+        let length = tuple.elements.len();
+        let expected = if length == 1 {
+            "expected an array of 1 element".to_owned()
+        } else {
+            format!("expected an array of {length} elements")
+        };
+        emit_check(
+            writer,
+            &format!("!Array.isArray({value}) || {value}.length !== {length}"),
+            &expected,
+            path,
+        )
+    }
+
+    fn element(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        value: &String,
+        index: usize,
+        path: &Vec<PathPart>,
+    ) -> Result<(String, Vec<PathPart>)> {
+        let element_value = self.body.variable("element");
+        writer.write_fmt_nl(format_args!("const {element_value} = {value}[{index}];"))?;
+        Ok((element_value, with_literal(path, &format!("[{index}]"))))
+    }
+
+    fn emit_primitive(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        primitive: &Primitive<TypeScriptMetadata>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<()> {
+        let int_encoding = self.context.types.int_encoding;
+        let is_string = format!("typeof {value} !== \"string\"");
+        let (condition, expected) = match primitive.r#type {
+            PrimitiveType::String => (is_string, "expected a string".to_owned()),
+            PrimitiveType::Number => match int_encoding {
+                IntEncoding::Number => (
+                    format!("typeof {value} !== \"number\" || !Number.isInteger({value})"),
+                    "expected an integer".to_owned(),
+                ),
+                IntEncoding::String => (
+                    is_string,
+                    "expected an integer encoded as a string".to_owned(),
+                ),
+            },
+            PrimitiveType::Unit => (format!("{value} !== null"), "expected null".to_owned()),
+            built_in => (
+                is_string,
+                format!("expected {}", formats::expected(built_in)),
+            ),
+        };
+
+        emit_check(writer, &condition, &expected, path)?;
+        emit_primitive_checks(writer, self.patterns, int_encoding, primitive, value, path)
+    }
+
+    fn function_name(&self, identifier: &Identifier<TypeScriptMetadata>) -> String {
+        function_name(
+            CHECK,
+            &self.context.types.types[identifier.metadata.resolution],
+        )
+    }
+
+    fn checked_arguments<'t>(
+        &self,
+        identifier: &'t Identifier<TypeScriptMetadata>,
+    ) -> Vec<&'t Type<TypeScriptMetadata>> {
+        identifier.arguments.iter().collect()
+    }
+
+    fn parameter_index(&self, parameter: &Parameter<TypeScriptMetadata>) -> usize {
+        parameter.metadata.resolution
+    }
+
+    fn begin_call(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        function: &str,
+        value: &String,
+    ) -> Result<String> {
+        begin_call(writer, &mut self.body, function, value)
+    }
+
+    fn end_call(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        result: &str,
+        path: &Vec<PathPart>,
+    ) -> Result<()> {
+        end_call(writer, result, path)
+    }
+
+    fn write_no_checks(&mut self, writer: &mut SourceWriter<impl Write>) -> Result<()> {
+        writer.write("() => undefined")
+    }
+
+    fn begin_closure(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        _type: &Type<TypeScriptMetadata>,
+    ) -> Result<(String, Vec<PathPart>)> {
+        let value = self.body.variable("value");
+        let path = self.body.variable("path");
+
+        writer.write_fmt_nl(format_args!("({value}: unknown, {path}: string) => {{"))?;
+        writer.indent();
+
+        Ok((value, vec![PathPart::Expression(path)]))
+    }
+
+    fn end_closure(&mut self, writer: &mut SourceWriter<impl Write>) -> Result<()> {
+        end_closure(writer)
+    }
+}
+
+/// Emits an `if` returning an error if a value isn't an object.
+fn emit_object_check(
+    writer: &mut SourceWriter<impl Write>,
+    value: &str,
+    path: &[PathPart],
+) -> Result<()> {
+    let condition =
+        format!("typeof {value} !== \"object\" || {value} === null || Array.isArray({value})");
+    emit_check(writer, &condition, "expected an object", path)
+}
+
+/// Emits a statement returning an error.
+fn emit_return(
+    writer: &mut SourceWriter<impl Write>,
+    expected: &str,
+    path: &[PathPart],
+) -> Result<()> {
+    writer.write("return { path: ")?;
+    write_path(writer, path)?;
+    writer.write(", message: ")?;
+    write_string_literal(writer, expected)?;
+    writer.write_nl(" };")
+}
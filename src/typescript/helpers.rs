@@ -0,0 +1,304 @@
+//! The backend for helper namespaces of TypeScript enums, which build and inspect their values.
+//!
+//! Every enum gets a namespace merged with its type, like `Contact`, with a constructor like `Contact.email(value)`
+//! and a type guard like `Contact.isEmail(contact)` for each variant,
+//! and a function `Contact.match(contact, { email: ..., phone: ... })` calling the function of the variant.
+//! Open enums take another function for variants added in later versions,
+//! and get a type guard `Role.isKnown(role)` narrowing a value to the known variants,
+//! as the tag of the member for other variants is any string, so checking the tag doesn't narrow it.
+
+use std::io::{Result, Write};
+
+use crate::{
+    ast::{Enum, Parameter, Type, TypeSet, TypeType, Variant},
+    codegen::{
+        idents::{CamelCase, convert_case, disambiguate},
+        source_writer::SourceWriter,
+    },
+    typescript::{
+        TypeScriptMetadata, TypeScriptOptions,
+        idents::TypeScriptImportIdentRules,
+        types::{emit_type, is_type_name, write_parameters, write_readonly, write_string_literal},
+    },
+};
+
+/// The name of the function calling the function of a variant.
+const MATCH: &str = "match";
+
+/// The name of the type guard checking that the variant of a value of an open enum is known.
+const IS_KNOWN: &str = "isKnown";
+
+/// Emits the helper namespace of an enum, which is generic over `parameters`.
+///
+/// Closed enums without variants get none, as they have no values.
+pub fn emit_helpers(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    r#type: &Type<TypeScriptMetadata>,
+    parameters: &[&Parameter<TypeScriptMetadata>],
+) -> Result<()> {
+    let TypeType::Enum(r#enum) = &r#type.r#type else {
+        unreachable!("only enums to have helpers");
+    };
+
+    if r#enum.variants.is_empty() && !r#enum.open {
+        return Ok(());
+    }
+
+    let mut enum_type = r#type.metadata.name.clone();
+    for (index, parameter) in parameters.iter().enumerate() {
+        enum_type.push_str(if index == 0 { "<" } else { ", " });
+        enum_type.push_str(&parameter.metadata.name);
+    }
+    if !parameters.is_empty() {
+        enum_type.push('>');
+    }
+
+    let (constructors, guards, is_known) = function_names(r#enum);
+
+    writer.write_fmt_nl(format_args!("export namespace {} {{", r#type.metadata.name))?;
+    writer.indent();
+
+    for ((variant, constructor), guard) in r#enum.variants.iter().zip(&constructors).zip(&guards) {
+        let is_unit = variant.r#type.r#type.is_unit();
+
+        writer.write_fmt(format_args!("export function {constructor}"))?;
+        write_parameters(writer, parameters.iter().copied())?;
+
+        if is_unit {
+            writer.write("()")?;
+        } else {
+            writer.write("(value: ")?;
+            emit_type(writer, types, options, &variant.r#type)?;
+            writer.write(")")?;
+        }
+
+        writer.write_fmt_nl(format_args!(": {enum_type} {{"))?;
+        writer.indent();
+        writer.write("return { type: ")?;
+        write_string_literal(writer, &variant.metadata.name)?;
+        writer.write_nl(if is_unit {
+            ", value: null };"
+        } else {
+            ", value };"
+        })?;
+        writer.dedent();
+        writer.write_nl("}")?;
+        writer.blank_line();
+
+        writer.write_fmt(format_args!("export function {guard}"))?;
+        write_parameters(writer, parameters.iter().copied())?;
+        writer.write_fmt(format_args!("(value: {enum_type}): value is "))?;
+        write_variant_type(writer, types, options, variant)?;
+        writer.write_nl(" {")?;
+        writer.indent();
+        writer.write("return value.type === ")?;
+        write_string_literal(writer, &variant.metadata.name)?;
+        writer.write_nl(";")?;
+        writer.dedent();
+        writer.write_nl("}")?;
+        writer.blank_line();
+    }
+
+    if let Some(is_known) = &is_known {
+        emit_is_known(
+            writer, types, options, r#enum, parameters, &enum_type, is_known,
+        )?;
+    }
+
+    emit_match(
+        writer,
+        types,
+        options,
+        r#enum,
+        parameters,
+        &enum_type,
+        is_known.as_deref(),
+    )?;
+
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Emits the type of the values of a variant, like `{ type: "email", value: string }`.
+fn write_variant_type(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    variant: &Variant<TypeScriptMetadata>,
+) -> Result<()> {
+    writer.write("{ ")?;
+    write_readonly(writer, options)?;
+    writer.write("type: ")?;
+    write_string_literal(writer, &variant.metadata.name)?;
+    writer.write(", ")?;
+    write_readonly(writer, options)?;
+    writer.write("value: ")?;
+    emit_type(writer, types, options, &variant.r#type)?;
+    writer.write(" }")
+}
+
+/// Emits the type guard of an open enum narrowing a value to its known variants, named `name`.
+fn emit_is_known(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    r#enum: &Enum<TypeScriptMetadata>,
+    parameters: &[&Parameter<TypeScriptMetadata>],
+    enum_type: &str,
+    name: &str,
+) -> Result<()> {
+    writer.write_fmt(format_args!("export function {name}"))?;
+    write_parameters(writer, parameters.iter().copied())?;
+    writer.write_fmt(format_args!("(value: {enum_type}): value is "))?;
+    for (index, variant) in r#enum.variants.iter().enumerate() {
+        if index > 0 {
+            writer.write(" | ")?;
+        }
+        write_variant_type(writer, types, options, variant)?;
+    }
+    writer.write_nl(" {")?;
+    writer.indent();
+    writer.write("return ")?;
+    for (index, variant) in r#enum.variants.iter().enumerate() {
+        if index > 0 {
+            writer.write(" || ")?;
+        }
+        writer.write("value.type === ")?;
+        write_string_literal(writer, &variant.metadata.name)?;
+    }
+    writer.write_nl(";")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Emits the function calling the function of the variant of a value, named [`MATCH`].
+///
+/// Open enums with variants check them with their type guard named `is_known` first.
+fn emit_match(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    r#enum: &Enum<TypeScriptMetadata>,
+    parameters: &[&Parameter<TypeScriptMetadata>],
+    enum_type: &str,
+    is_known: Option<&str>,
+) -> Result<()> {
+    let mut result = "R".to_owned();
+    disambiguate(&mut result, |name| {
+        is_type_name(types, options, name)
+            || parameters
+                .iter()
+                .any(|parameter| parameter.metadata.name == name)
+    });
+
+    writer.write_fmt(format_args!("export function {MATCH}<"))?;
+    for parameter in parameters {
+        writer.write_fmt(format_args!("{}, ", parameter.metadata.name))?;
+    }
+    writer.write_fmt_nl(format_args!("{result}>("))?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!("value: {enum_type},"))?;
+    writer.write_nl("cases: {")?;
+    writer.indent();
+
+    for variant in &r#enum.variants {
+        write_string_literal(writer, &variant.metadata.name)?;
+        writer.write(": (value: ")?;
+        emit_type(writer, types, options, &variant.r#type)?;
+        writer.write_fmt_nl(format_args!(") => {result},"))?;
+    }
+
+    writer.dedent();
+    writer.write_nl("},")?;
+
+    if r#enum.open {
+        writer.write_fmt_nl(format_args!(
+            "fallback: (value: {{ type: string, value: unknown }}) => {result},"
+        ))?;
+    }
+
+    writer.dedent();
+    writer.write_fmt_nl(format_args!("): {result} {{"))?;
+    writer.indent();
+
+    if let Some(is_known) = is_known {
+        writer.write_fmt_nl(format_args!("if (!{is_known}(value)) {{"))?;
+        writer.indent();
+        writer.write_nl("return fallback(value);")?;
+        writer.dedent();
+        writer.write_nl("}")?;
+    }
+
+    if !r#enum.variants.is_empty() {
+        writer.write_nl("switch (value.type) {")?;
+        writer.indent();
+
+        for variant in &r#enum.variants {
+            writer.write("case ")?;
+            write_string_literal(writer, &variant.metadata.name)?;
+            writer.write(": return cases[")?;
+            write_string_literal(writer, &variant.metadata.name)?;
+            writer.write_nl("](value.value);")?;
+        }
+
+        writer.dedent();
+        writer.write_nl("}")?;
+    }
+
+    // Without variants, every value is of an unknown variant
+    if r#enum.open && is_known.is_none() {
+        writer.write_nl("return fallback(value);")?;
+    }
+
+    writer.dedent();
+    writer.write_nl("}")?;
+
+    Ok(())
+}
+
+/// Gets the names of the constructors and type guards of the variants of an enum,
+/// like `email` and `isEmail`, and of the type guard of an open enum with variants, like [`IS_KNOWN`],
+/// which can't collide with each other or with [`MATCH`].
+fn function_names(r#enum: &Enum<TypeScriptMetadata>) -> (Vec<String>, Vec<String>, Option<String>) {
+    let mut used = vec![MATCH.to_owned()];
+
+    let mut constructors = Vec::with_capacity(r#enum.variants.len());
+    for variant in &r#enum.variants {
+        let mut name = convert_case(
+            [variant.metadata.name.as_str()],
+            CamelCase,
+            TypeScriptImportIdentRules,
+        );
+        disambiguate(&mut name, |name| used.iter().any(|used| used == name));
+        used.push(name.clone());
+        constructors.push(name);
+    }
+
+    let mut guards = Vec::with_capacity(r#enum.variants.len());
+    for variant in &r#enum.variants {
+        let mut name = convert_case(
+            ["is", variant.metadata.name.as_str()],
+            CamelCase,
+            TypeScriptImportIdentRules,
+        );
+        disambiguate(&mut name, |name| used.iter().any(|used| used == name));
+        used.push(name.clone());
+        guards.push(name);
+    }
+
+    let is_known = (r#enum.open && !r#enum.variants.is_empty()).then(|| {
+        let mut name = IS_KNOWN.to_owned();
+        disambiguate(&mut name, |name| used.iter().any(|used| used == name));
+        name
+    });
+
+    (constructors, guards, is_known)
+}
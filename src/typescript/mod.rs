@@ -1,58 +1,136 @@
 //! The TypeScript language backend.
 
-use std::{
-    fs::{File, create_dir_all},
-    io::{BufWriter, Write},
-    path::Path,
-};
+use std::{collections::HashMap, fs::create_dir_all, path::Path};
 
 use crate::{
     ast::TypeSet,
     codegen::{
-        file_patching::add_line_to_file,
+        file_patching::{WriteMode, add_line_to_file, write_file},
         naming_pass::{NameMetadata, name},
+        output::{Output, write_to_stdout},
         source_writer::SourceWriter,
     },
     composite,
     error::{Error, ResultExt},
-    loading::load_file,
+    getter,
+    loading::{check_extern_mappings, check_type_names, load_file},
     mapper,
     preprocessing::{BasicMetadata, ResolutionMetadata},
     typescript::types::emit_types,
+    watch::GeneratedFiles,
 };
 
+mod guards;
+mod helpers;
 mod idents;
 mod types;
+mod validation;
 
 pub use idents::TypeScriptNamingRules;
 
+/// Configuration of the TypeScript type declaration backend.
+#[derive(Debug, Clone, Default)]
+pub struct TypeScriptOptions {
+    envelopes: Vec<String>,
+    optional_defaults: bool,
+    validate: bool,
+    guards: bool,
+    externs: HashMap<String, String>,
+    hoist: bool,
+    interfaces: bool,
+    readonly: bool,
+    enum_helpers: bool,
+}
+
+impl TypeScriptOptions {
+    /// Creates new [`TypeScriptOptions`].
+    ///
+    /// `envelopes` contains the names of top-level types that should get a version-tagged envelope.
+    /// If `optional_defaults` is set, fields with a default value become optional properties.
+    /// If `validate` is set, every named type gets a function checking the refinements of the primitive types inside it.
+    /// If `guards` is set, every named type gets functions checking that a value of unknown type has it.
+    /// `externs` maps the names of extern types to the TypeScript types they stand for.
+    /// If `hoist` is set, nested structs and enums become named types, like `UserAddress`.
+    /// If `interfaces` is set, structs are declared as interfaces.
+    /// If `readonly` is set, properties, arrays and tuples are readonly.
+    /// If `enum_helpers` is set, every enum gets a namespace with functions constructing and matching its variants.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        envelopes: Vec<String>,
+        optional_defaults: bool,
+        validate: bool,
+        guards: bool,
+        externs: HashMap<String, String>,
+        hoist: bool,
+        interfaces: bool,
+        readonly: bool,
+        enum_helpers: bool,
+    ) -> Self {
+        Self {
+            envelopes,
+            optional_defaults,
+            validate,
+            guards,
+            externs,
+            hoist,
+            interfaces,
+            readonly,
+            enum_helpers,
+        }
+    }
+}
+
 /// Runs TypeScript-specific passes to convert [`BasicMetadata`] into [`TypeScriptMetadata`].
 fn convert_types(types: TypeSet<BasicMetadata>) -> TypeSet<TypeScriptMetadata> {
     name(types, TypeScriptNamingRules, AddName)
 }
 
 /// Implements `versed typescript types`.
-pub fn generate_types(path: &Path, output: &Path, to_file: bool) -> Result<(), Error> {
+pub fn generate_types(
+    path: &Path,
+    output: Output,
+    options: &TypeScriptOptions,
+    generated: &mut GeneratedFiles,
+) -> Result<(), Error> {
     let types = load_file(path)?;
+    check_type_names(&types, &options.envelopes)?;
+    check_extern_mappings(&types, &options.externs)?;
     let types = convert_types(types);
 
-    if to_file {
-        write_to_file(&types, output, false)
-    } else {
-        write_to_directory(&types, output)
+    match output {
+        Output::Directory(output) => write_to_directory(&types, options, output, generated),
+        Output::File(output) => write_to_file(&types, options, output, WriteMode::Overwrite),
+        Output::Stdout => write_to_stdout(|writer| emit_types(writer, &types, options)),
     }
 }
 
 /// Saves type declarations into a specific directory and adds a re-export to `index.ts`.
-fn write_to_directory(types: &TypeSet<TypeScriptMetadata>, path: &Path) -> Result<(), Error> {
+///
+/// Files written by an earlier run in watch mode are rewritten in place instead.
+fn write_to_directory(
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    path: &Path,
+    generated: &mut GeneratedFiles,
+) -> Result<(), Error> {
     create_dir_all(path).with_path(path)?;
     let mod_name = &types.metadata.name;
 
     let type_path = path.join(format!("{mod_name}.ts"));
-    write_to_file(types, &type_path, true)?;
+    let rewrite = generated.contains(&type_path);
+    let mode = if rewrite {
+        WriteMode::Replace
+    } else {
+        WriteMode::New
+    };
+    write_to_file(types, options, &type_path, mode)?;
+
+    if !rewrite {
+        let index_path = path.join("index.ts");
+        add_reexport_to_file(mod_name, &index_path)?;
+    }
 
-    let index_path = path.join("index.ts");
-    add_reexport_to_file(mod_name, &index_path)?;
+    generated.insert(type_path);
 
     Ok(())
 }
@@ -60,20 +138,14 @@ fn write_to_directory(types: &TypeSet<TypeScriptMetadata>, path: &Path) -> Resul
 /// Saves type declarations to a specific file.
 fn write_to_file(
     types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
     path: &Path,
-    must_be_new: bool,
+    mode: WriteMode,
 ) -> Result<(), Error> {
-    let file = if must_be_new {
-        File::create_new(path).with_path(path)?
-    } else {
-        File::create(path).with_path(path)?
-    };
-
-    let mut writer = SourceWriter::new(BufWriter::new(file));
-    emit_types(&mut writer, types).with_path(path)?;
-    writer.into_inner().flush().with_path(path)?;
-
-    Ok(())
+    write_file(path, mode, |file| {
+        emit_types(&mut SourceWriter::new(file), types, options)
+    })
+    .with_path(path)
 }
 
 /// Appends a re-export to a file.
@@ -100,3 +172,9 @@ mapper! {
         }
     }
 }
+
+getter! {
+    fn GetResolution(metadata: TypeScriptMetadata) -> ResolutionMetadata {
+        &metadata.resolution
+    }
+}
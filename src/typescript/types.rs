@@ -1,34 +1,112 @@
 //! The backend for TypeScript type declarations.
 
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashSet},
     io::{Result, Write},
+    ptr,
 };
 
 use crate::{
-    ast::{PrimitiveType, Type, TypeSet, TypeType},
-    codegen::source_writer::SourceWriter,
-    typescript::TypeScriptMetadata,
+    ast::{NamedType, Parameter, PrimitiveType, Scope, Struct, Type, TypeSet, TypeType},
+    codegen::{idents::disambiguate, source_writer::SourceWriter},
+    formats::{self, IntEncoding},
+    typescript::{
+        TypeScriptMetadata, TypeScriptOptions,
+        guards::{CHECK, checker_names, emit_guards},
+        helpers::emit_helpers,
+        validation::{Patterns, emit_error_type, emit_patterns, emit_validation, function_name},
+    },
 };
 
 /// Emits all type declarations.
 pub fn emit_types(
     writer: &mut SourceWriter<impl Write>,
     types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
 ) -> Result<()> {
-    for (index, r#type) in types.types.iter().enumerate() {
-        writer.write("export type ")?;
-        writer.write(&r#type.r#type.metadata.name)?;
-        writer.write(" = ")?;
+    let mut built_ins = Vec::new();
+    for r#type in &types.types {
+        find_built_ins(&r#type.r#type, &mut built_ins);
+    }
 
+    for &built_in in PrimitiveType::BUILT_IN
+        .iter()
+        .filter(|built_in| built_ins.contains(built_in))
+    {
+        let expected = formats::expected(built_in);
+        writer.write_fmt_nl(format_args!(
+            "/** {}{}. */",
+            expected[..1].to_uppercase(),
+            &expected[1..]
+        ))?;
+        writer.write_fmt_nl(format_args!(
+            "export type {} = string & {{ readonly __brand: \"{}\" }};",
+            brand_name(types, options, built_in),
+            built_in.name()
+        ))?;
+        writer.blank_line();
+    }
+
+    for (index, r#type) in types.types.iter().enumerate() {
         if is_anomalously_recursive(types, index) {
-            writer.write("never")?;
+            writer.write("export type ")?;
+            writer.write(&r#type.r#type.metadata.name)?;
+            write_parameters(writer, &r#type.parameters)?;
+            writer.write_nl(" = never;")?;
+            writer.blank_line();
         } else {
-            emit_type(writer, types, &r#type.r#type)?;
+            let parameters: Vec<_> = r#type.parameters.iter().collect();
+            emit_declaration(writer, types, options, &r#type.r#type, &parameters)?;
+
+            if options.hoist {
+                emit_nested_declarations(writer, types, options, &r#type.r#type)?;
+            }
         }
+    }
+
+    // The names of the error types of validation functions and type guards, of envelopes and of brands
+    let mut generated_names: HashSet<String> = built_ins
+        .iter()
+        .map(|&built_in| brand_name(types, options, built_in))
+        .collect();
+
+    // The names of the error type and the exception of type guards, which envelopes check their data with
+    let mut guards = None;
 
-        writer.write_nl(";")?;
+    if options.validate || options.guards {
+        let error = emit_error_type(writer, types, options)?;
+
+        // The functions are emitted after the constants of the regular expressions they use
+        let mut patterns = Patterns::new(types, options);
+        let mut functions = SourceWriter::new(Vec::new());
+
+        if options.validate {
+            emit_validation(&mut functions, types, options, &mut patterns, &error)?;
+        }
+
+        if options.guards {
+            let parse_error = emit_guards(&mut functions, types, options, &mut patterns, &error)?;
+            guards = Some((error.clone(), parse_error.clone()));
+            generated_names.insert(parse_error);
+        }
+
+        emit_patterns(writer, &patterns)?;
+        writer.write_source(&String::from_utf8(functions.into_inner()).unwrap())?;
         writer.blank_line();
+
+        generated_names.insert(error);
+    }
+
+    for r#type in &types.types {
+        if options.envelopes.contains(&r#type.name) {
+            let mut name = format!("{}Envelope", r#type.r#type.metadata.name);
+            disambiguate(&mut name, |name| {
+                is_type_name(types, options, name) || generated_names.contains(name)
+            });
+
+            emit_envelope(writer, types, r#type, &name, guards.as_ref())?;
+            generated_names.insert(name);
+        }
     }
 
     if types.types.is_empty() {
@@ -38,29 +116,242 @@ pub fn emit_types(
     Ok(())
 }
 
-/// Visits a type and emits its type declaration.
-fn emit_type(
+/// Emits an envelope type, which tags a top-level type with the version of the schema,
+/// and a function that parses it from JSON, checking the version.
+///
+/// With `guards`, the names of the error type and the exception of type guards,
+/// the function checks the data as well, taking a function checking each type parameter.
+fn emit_envelope(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    r#type: &NamedType<TypeScriptMetadata>,
+    name: &str,
+    guards: Option<&(String, String)>,
+) -> Result<()> {
+    writer.write_fmt(format_args!("export type {name}"))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_nl(" = {")?;
+    writer.indent();
+    writer.write("version: ")?;
+    write_string_literal(writer, &types.version)?;
+    writer.write_nl(",")?;
+    writer.write_fmt(format_args!("data: {}", r#type.r#type.metadata.name))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_nl(",")?;
+    writer.dedent();
+    writer.write_nl("};")?;
+    writer.blank_line();
+
+    let checkers = guards.map_or_else(Vec::new, |_| checker_names(types, r#type));
+
+    if guards.is_some() {
+        writer.write_fmt_nl(format_args!(
+            "/** Parses a {name} from JSON, checking its version and its data. */"
+        ))?;
+    } else {
+        writer.write_fmt_nl(format_args!(
+            "/** Parses a {name} from JSON, checking its version. The data is not checked. */"
+        ))?;
+    }
+
+    writer.write_fmt(format_args!("export function parse{name}"))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write("(json: string")?;
+
+    if let Some((error, _)) = guards {
+        for (_, checker) in &checkers {
+            writer.write_fmt(format_args!(
+                ", {checker}: (value: unknown, path: string) => {error} | undefined"
+            ))?;
+        }
+    }
+
+    writer.write_fmt(format_args!("): {name}"))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_nl(" {")?;
+    writer.indent();
+    writer.write_nl("const envelope = JSON.parse(json);")?;
+    writer.blank_line();
+    writer.write("if (envelope?.version !== ")?;
+    write_string_literal(writer, &types.version)?;
+    writer.write_nl(") {")?;
+    writer.indent();
+    writer.write("throw new Error(")?;
+    write_string_literal(
+        writer,
+        &format!("expected {name} with version {}", types.version),
+    )?;
+    writer.write_nl(");")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    if let Some((_, parse_error)) = guards {
+        writer.write_fmt(format_args!(
+            "const error = {}(envelope.data",
+            function_name(CHECK, r#type)
+        ))?;
+        for (_, checker) in &checkers {
+            writer.write_fmt(format_args!(", {checker}"))?;
+        }
+        writer.write_nl(", \"$.data\");")?;
+        writer.blank_line();
+        writer.write_nl("if (error !== undefined) {")?;
+        writer.indent();
+        writer.write_fmt_nl(format_args!("throw new {parse_error}(error);"))?;
+        writer.dedent();
+        writer.write_nl("}")?;
+        writer.blank_line();
+    }
+
+    writer.write_nl("return envelope;")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Emits the declaration of a named or hoisted type.
+///
+/// Structs become interfaces if [`TypeScriptOptions::interfaces`] is set, which extend the structs they spread.
+/// Enums are followed by their helper namespace if [`TypeScriptOptions::enum_helpers`] is set.
+fn emit_declaration(
     writer: &mut SourceWriter<impl Write>,
     types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    r#type: &Type<TypeScriptMetadata>,
+    parameters: &[&Parameter<TypeScriptMetadata>],
+) -> Result<()> {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) if options.interfaces => {
+            writer.write("export interface ")?;
+            writer.write(&r#type.metadata.name)?;
+            write_parameters(writer, parameters.iter().copied())?;
+
+            let spreads = r#struct.fields.iter().filter(|field| field.spread);
+            for (index, spread) in spreads.enumerate() {
+                writer.write(if index == 0 { " extends " } else { ", " })?;
+                emit_type(writer, types, options, &spread.r#type)?;
+            }
+
+            writer.write(" ")?;
+            emit_fields(writer, types, options, r#struct)?;
+            writer.nl()?;
+        }
+        _ => {
+            writer.write("export type ")?;
+            writer.write(&r#type.metadata.name)?;
+            write_parameters(writer, parameters.iter().copied())?;
+            writer.write(" = ")?;
+            emit_definition(writer, types, options, r#type)?;
+            writer.write_nl(";")?;
+        }
+    }
+
+    writer.blank_line();
+
+    if options.enum_helpers && matches!(r#type.r#type, TypeType::Enum(_)) {
+        emit_helpers(writer, types, options, r#type, parameters)?;
+    }
+
+    Ok(())
+}
+
+/// Emits declarations for the structs and enums nested in a type, recursively.
+///
+/// They are generic over the type parameters they use, in the order of declaration.
+fn emit_nested_declarations(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    r#type: &Type<TypeScriptMetadata>,
+) -> Result<()> {
+    let nested: Vec<_> = match &r#type.r#type {
+        TypeType::Struct(r#struct) => r#struct.fields.iter().map(|field| &field.r#type).collect(),
+        TypeType::Enum(r#enum) => r#enum
+            .variants
+            .iter()
+            .map(|variant| &variant.r#type)
+            .collect(),
+        TypeType::List(list) => vec![&*list.r#type],
+        TypeType::Tuple(tuple) => tuple.elements.iter().collect(),
+        TypeType::Identifier(identifier) => identifier.arguments.iter().collect(),
+        TypeType::Primitive(_) | TypeType::Parameter(_) | TypeType::Extern(_) => Vec::new(),
+    };
+
+    for r#type in nested {
+        if let TypeType::Struct(_) | TypeType::Enum(_) = r#type.r#type {
+            emit_declaration(writer, types, options, r#type, &used_parameters(r#type))?;
+        }
+
+        emit_nested_declarations(writer, types, options, r#type)?;
+    }
+
+    Ok(())
+}
+
+/// Visits a type and emits a reference to it.
+///
+/// Nested structs and enums are written inline, or by name if they are hoisted.
+pub fn emit_type(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    r#type: &Type<TypeScriptMetadata>,
+) -> Result<()> {
+    match &r#type.r#type {
+        TypeType::Struct(_) | TypeType::Enum(_) if options.hoist => {
+            writer.write(&r#type.metadata.name)?;
+            write_parameters(writer, used_parameters(r#type))
+        }
+        _ => emit_definition(writer, types, options, r#type),
+    }
+}
+
+/// Visits a type and emits its definition, with nested types written by [`emit_type`].
+fn emit_definition(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
     r#type: &Type<TypeScriptMetadata>,
 ) -> Result<()> {
     match &r#type.r#type {
         TypeType::Struct(r#struct) => {
-            writer.write_nl("{")?;
-            writer.indent();
+            // Spreads become an intersection, which is parenthesized like unions
+            let spreads: Vec<_> = r#struct
+                .fields
+                .iter()
+                .filter(|field| field.spread)
+                .collect();
 
-            for field in &r#struct.fields {
-                writer.write(&field.metadata.name)?;
-                writer.write(": ")?;
-                emit_type(writer, types, &field.r#type)?;
-                writer.write_nl(",")?;
+            if !spreads.is_empty() {
+                writer.write("(")?;
+
+                for (index, spread) in spreads.iter().enumerate() {
+                    if index > 0 {
+                        writer.write(" & ")?;
+                    }
+
+                    emit_type(writer, types, options, &spread.r#type)?;
+                }
+
+                if spreads.len() == r#struct.fields.len() {
+                    writer.write(")")?;
+                    return Ok(());
+                }
+
+                writer.write(" & ")?;
             }
 
-            writer.dedent();
-            writer.write("}")?;
+            emit_fields(writer, types, options, r#struct)?;
+
+            if !spreads.is_empty() {
+                writer.write(")")?;
+            }
         }
         TypeType::Enum(r#enum) => {
-            if !r#enum.variants.is_empty() {
+            if !r#enum.variants.is_empty() || r#enum.open {
                 writer.write_nl("(")?;
                 writer.indent();
 
@@ -72,18 +363,36 @@ fn emit_type(
                     writer.write_nl("{")?;
                     writer.indent();
 
+                    write_readonly(writer, options)?;
                     writer.write("type: \"")?;
                     writer.write(&variant.metadata.name)?;
                     writer.write_nl("\",")?;
 
+                    write_readonly(writer, options)?;
                     writer.write("value: ")?;
-                    emit_type(writer, types, &variant.r#type)?;
+                    emit_type(writer, types, options, &variant.r#type)?;
                     writer.write_nl(",")?;
 
                     writer.dedent();
                     writer.write("}")?;
                 }
 
+                // Variants added in later versions
+                if r#enum.open {
+                    if !r#enum.variants.is_empty() {
+                        writer.write(" | ")?;
+                    }
+
+                    writer.write_nl("{")?;
+                    writer.indent();
+                    write_readonly(writer, options)?;
+                    writer.write_nl("type: string,")?;
+                    write_readonly(writer, options)?;
+                    writer.write_nl("value: unknown,")?;
+                    writer.dedent();
+                    writer.write("}")?;
+                }
+
                 writer.nl()?;
                 writer.dedent();
                 writer.write(")")?;
@@ -92,48 +401,305 @@ fn emit_type(
             }
         }
         TypeType::List(list) => {
-            emit_type(writer, types, &list.r#type)?;
-            writer.write("[]")?;
-        }
-        TypeType::Primitive(primitive) => {
-            let keyword = match primitive.r#type {
-                PrimitiveType::String => "string",
-                PrimitiveType::Number => "number",
-                PrimitiveType::Unit => "null",
-            };
-            writer.write(keyword)?;
+            // `readonly` only applies to the outermost array type, so nested ones are parenthesized
+            let parenthesize = options.readonly
+                && matches!(list.r#type.r#type, TypeType::List(_) | TypeType::Tuple(_));
+
+            write_readonly(writer, options)?;
+            writer.write(if parenthesize { "(" } else { "" })?;
+            emit_type(writer, types, options, &list.r#type)?;
+            writer.write(if parenthesize { ")[]" } else { "[]" })?;
+        }
+        TypeType::Tuple(tuple) => {
+            write_readonly(writer, options)?;
+            writer.write("[")?;
+
+            for (index, element) in tuple.elements.iter().enumerate() {
+                if index > 0 {
+                    writer.write(", ")?;
+                }
+
+                emit_type(writer, types, options, element)?;
+            }
+
+            writer.write("]")?;
         }
+        TypeType::Primitive(primitive) => match primitive.r#type {
+            PrimitiveType::String => writer.write("string")?,
+            PrimitiveType::Number => match types.int_encoding {
+                IntEncoding::Number => writer.write("number")?,
+                IntEncoding::String => writer.write("`${bigint}`")?,
+            },
+            PrimitiveType::Unit => writer.write("null")?,
+            built_in => writer.write(&brand_name(types, options, built_in))?,
+        },
         TypeType::Identifier(identifier) => {
             let r#type = &types.types[identifier.metadata.resolution].r#type;
             writer.write(&r#type.metadata.name)?;
+
+            for (index, argument) in identifier.arguments.iter().enumerate() {
+                writer.write(if index == 0 { "<" } else { ", " })?;
+                emit_type(writer, types, options, argument)?;
+            }
+
+            if !identifier.arguments.is_empty() {
+                writer.write(">")?;
+            }
+        }
+        TypeType::Parameter(parameter) => writer.write(&parameter.metadata.name)?,
+        TypeType::Extern(r#extern) => writer.write(&options.externs[&r#extern.name])?,
+    }
+
+    Ok(())
+}
+
+/// Emits the fields of a struct other than spreads as an object type.
+fn emit_fields(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    r#struct: &Struct<TypeScriptMetadata>,
+) -> Result<()> {
+    writer.write_nl("{")?;
+    writer.indent();
+
+    for field in r#struct.fields.iter().filter(|field| !field.spread) {
+        write_readonly(writer, options)?;
+        writer.write(&field.metadata.name)?;
+
+        if options.optional_defaults && field.default.is_some() {
+            writer.write("?")?;
+        }
+
+        writer.write(": ")?;
+        emit_type(writer, types, options, &field.r#type)?;
+        writer.write_nl(",")?;
+    }
+
+    writer.dedent();
+    writer.write("}")
+}
+
+/// Writes the `readonly` modifier if [`TypeScriptOptions::readonly`] is set.
+pub fn write_readonly(
+    writer: &mut SourceWriter<impl Write>,
+    options: &TypeScriptOptions,
+) -> Result<()> {
+    if options.readonly {
+        writer.write("readonly ")?;
+    }
+
+    Ok(())
+}
+
+/// Gets the name of the branded string type of a built-in type, like `Timestamp`.
+///
+/// The names are disambiguated against the types of the schema.
+fn brand_name(
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    built_in: PrimitiveType,
+) -> String {
+    let mut name = match built_in {
+        PrimitiveType::Timestamp => "Timestamp",
+        PrimitiveType::Date => "CalendarDate",
+        PrimitiveType::Duration => "Duration",
+        PrimitiveType::Uuid => "Uuid",
+        PrimitiveType::Bytes => "Bytes",
+        PrimitiveType::String | PrimitiveType::Number | PrimitiveType::Unit => {
+            unreachable!("only built-in types to have a brand")
+        }
+    }
+    .to_owned();
+
+    disambiguate(&mut name, |name| is_type_name(types, options, name));
+    name
+}
+
+/// Checks if a type of the schema is declared with a name,
+/// which includes the hoisted types if [`TypeScriptOptions::hoist`] is set.
+pub fn is_type_name(
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    name: &str,
+) -> bool {
+    if types
+        .types
+        .iter()
+        .any(|r#type| r#type.r#type.metadata.name == name)
+    {
+        return true;
+    }
+
+    if !options.hoist {
+        return false;
+    }
+
+    let mut stack: Vec<_> = types.types.iter().map(|r#type| &r#type.r#type).collect();
+
+    while let Some(r#type) = stack.pop() {
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) => {
+                stack.extend(r#struct.fields.iter().map(|field| &field.r#type));
+            }
+            TypeType::Enum(r#enum) => {
+                stack.extend(r#enum.variants.iter().map(|variant| &variant.r#type));
+            }
+            TypeType::List(list) => stack.push(&list.r#type),
+            TypeType::Tuple(tuple) => stack.extend(&tuple.elements),
+            TypeType::Identifier(identifier) => stack.extend(&identifier.arguments),
+            TypeType::Primitive(_) | TypeType::Parameter(_) | TypeType::Extern(_) => continue,
+        }
+
+        if r#type.metadata.name == name {
+            return true;
+        }
+    }
+
+    false
+}
+
+/// Collects the type parameters used in a type, in the order of declaration.
+fn used_parameters(r#type: &Type<TypeScriptMetadata>) -> Vec<&Parameter<TypeScriptMetadata>> {
+    let mut parameters = BTreeMap::new();
+    let mut stack = vec![r#type];
+
+    while let Some(r#type) = stack.pop() {
+        match &r#type.r#type {
+            TypeType::Struct(r#struct) => {
+                stack.extend(r#struct.fields.iter().map(|field| &field.r#type));
+            }
+            TypeType::Enum(r#enum) => {
+                stack.extend(r#enum.variants.iter().map(|variant| &variant.r#type));
+            }
+            TypeType::List(list) => stack.push(&list.r#type),
+            TypeType::Tuple(tuple) => stack.extend(&tuple.elements),
+            TypeType::Primitive(_) | TypeType::Extern(_) => {}
+            TypeType::Identifier(identifier) => stack.extend(&identifier.arguments),
+            TypeType::Parameter(parameter) => {
+                parameters
+                    .entry(parameter.metadata.resolution)
+                    .or_insert(parameter);
+            }
         }
     }
 
+    parameters.into_values().collect()
+}
+
+/// Collects the built-in types used in a type.
+fn find_built_ins(r#type: &Type<TypeScriptMetadata>, built_ins: &mut Vec<PrimitiveType>) {
+    match &r#type.r#type {
+        TypeType::Struct(r#struct) => {
+            for field in &r#struct.fields {
+                find_built_ins(&field.r#type, built_ins);
+            }
+        }
+        TypeType::Enum(r#enum) => {
+            for variant in &r#enum.variants {
+                find_built_ins(&variant.r#type, built_ins);
+            }
+        }
+        TypeType::List(list) => find_built_ins(&list.r#type, built_ins),
+        TypeType::Tuple(tuple) => {
+            for element in &tuple.elements {
+                find_built_ins(element, built_ins);
+            }
+        }
+        TypeType::Primitive(primitive) if primitive.r#type.is_formatted() => {
+            built_ins.push(primitive.r#type);
+        }
+        TypeType::Identifier(identifier) => {
+            for argument in &identifier.arguments {
+                find_built_ins(argument, built_ins);
+            }
+        }
+        TypeType::Primitive(_) | TypeType::Parameter(_) | TypeType::Extern(_) => {}
+    }
+}
+
+/// Writes a list of type parameters, like `<T, U>`, or nothing if it's empty.
+pub fn write_parameters<'a>(
+    writer: &mut SourceWriter<impl Write>,
+    parameters: impl IntoIterator<Item = &'a Parameter<TypeScriptMetadata>>,
+) -> Result<()> {
+    let mut empty = true;
+
+    for parameter in parameters {
+        writer.write(if empty { "<" } else { ", " })?;
+        writer.write(&parameter.metadata.name)?;
+        empty = false;
+    }
+
+    if !empty {
+        writer.write(">")?;
+    }
+
     Ok(())
 }
 
 /// Checks if a type is an alias of itself.
 ///
 /// That is, whether it's a declaration of the form `A = A;`, or `A = B; B = A;`, etc.
+/// Type parameters are followed to their arguments, so `A = Id<A>; Id<T> = T;` counts as well.
 /// Translating it directly to TypeScript results in an error, so it gets turned into `A = never`,
 /// as it cannot get constructed anyway.
-fn is_anomalously_recursive(r#types: &TypeSet<TypeScriptMetadata>, mut index: usize) -> bool {
-    let mut visited = HashSet::new();
+pub fn is_anomalously_recursive(types: &TypeSet<TypeScriptMetadata>, index: usize) -> bool {
+    let mut visited = HashSet::from([(index, ptr::null())]);
+    is_alias_cycle(
+        types,
+        &types.types[index].r#type,
+        &Scope::ROOT,
+        &mut visited,
+    )
+}
+
+/// Follows a chain of aliases, checking if it reaches a named type it has already visited.
+///
+/// Named types are visited with their type arguments, which are identified by their address.
+fn is_alias_cycle(
+    types: &TypeSet<TypeScriptMetadata>,
+    r#type: &Type<TypeScriptMetadata>,
+    scope: &Scope<TypeScriptMetadata>,
+    visited: &mut HashSet<(usize, *const Type<TypeScriptMetadata>)>,
+) -> bool {
+    match &r#type.r#type {
+        TypeType::Identifier(identifier) => {
+            let index = identifier.metadata.resolution;
+            let arguments = if identifier.arguments.is_empty() {
+                ptr::null()
+            } else {
+                identifier.arguments.as_ptr()
+            };
 
-    loop {
-        let r#type = &types.types[index];
+            !visited.insert((index, arguments))
+                || is_alias_cycle(
+                    types,
+                    &types.types[index].r#type,
+                    &scope.enter(&identifier.arguments),
+                    visited,
+                )
+        }
+        TypeType::Parameter(parameter) => match scope.argument(parameter.metadata.resolution) {
+            Some((argument, scope)) => is_alias_cycle(types, argument, scope, visited),
+            None => false,
+        },
+        _ => false,
+    }
+}
 
-        if let TypeType::Identifier(identifier) = &r#type.r#type.r#type {
-            visited.insert(index);
-            index = identifier.metadata.resolution;
+/// Writes a string literal.
+pub fn write_string_literal(writer: &mut SourceWriter<impl Write>, value: &str) -> Result<()> {
+    writer.write("\"")?;
 
-            if visited.contains(&index) {
-                break true;
-            } else {
-                continue;
-            }
-        } else {
-            break false;
+    for ch in value.chars() {
+        match ch {
+            '"' => writer.write("\\\"")?,
+            '\\' => writer.write("\\\\")?,
+            ch if ch.is_control() => writer.write_fmt(format_args!("\\u{{{:x}}}", ch as u32))?,
+            ch => writer.write_fmt(format_args!("{ch}"))?,
         }
     }
+
+    writer.write("\"")
 }
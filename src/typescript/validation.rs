@@ -0,0 +1,639 @@
+//! The backend for TypeScript validation functions, which check the refinements of primitive types.
+//!
+//! Every named type gets a function like `validateUser`, which returns the first invalid value it finds,
+//! with a JSON path like `$.users[0].name` pointing to it, or `undefined` if the value is valid.
+//! The functions of generic types take a function validating each type parameter they check.
+//! They aren't type guards, as their arguments already have the type, which refinements don't narrow,
+//! unlike the guards taking values of unknown type, see [`guards`](super::guards).
+//! The regular expressions they test strings with are compiled once, into module-level constants.
+
+use std::io::{Result, Write};
+
+use crate::{
+    ast::{Enum, Field, Identifier, NamedType, Parameter, Primitive, PrimitiveType, Type, TypeSet},
+    codegen::{
+        idents::disambiguate,
+        source_writer::SourceWriter,
+        validation::{Body, CheckEmitter, Checks, bounds_condition, emit_checks, key_segment},
+    },
+    formats::{self, IntEncoding},
+    typescript::{
+        GetResolution, TypeScriptMetadata, TypeScriptOptions,
+        types::{emit_type, is_type_name, write_parameters, write_string_literal},
+    },
+};
+
+/// The prefix of the names of validation functions, like `validateUser`.
+pub const VALIDATE: &str = "validate";
+
+/// The context for emitting validation functions.
+#[derive(Debug, Clone, Copy)]
+struct ValidationContext<'a> {
+    types: &'a TypeSet<TypeScriptMetadata>,
+    options: &'a TypeScriptOptions,
+    checks: &'a Checks,
+    /// The name of the error type.
+    error: &'a str,
+}
+
+/// A part of an expression evaluating to a JSON path, which concatenates its parts.
+#[derive(Debug, Clone)]
+pub enum PathPart {
+    Literal(String),
+    Expression(String),
+}
+
+/// The regular expressions that validation functions and type guards test strings with.
+///
+/// Each is compiled once, into a module-level constant declared by [`emit_patterns`].
+#[derive(Debug)]
+pub struct Patterns<'a> {
+    types: &'a TypeSet<TypeScriptMetadata>,
+    options: &'a TypeScriptOptions,
+    /// The names of the constants and the expressions compiling the regular expressions, in the order of first use.
+    constants: Vec<(String, String)>,
+}
+
+impl<'a> Patterns<'a> {
+    /// Creates an empty set of regular expressions, for the validation functions and type guards of `types`.
+    pub fn new(types: &'a TypeSet<TypeScriptMetadata>, options: &'a TypeScriptOptions) -> Self {
+        Self {
+            types,
+            options,
+            constants: Vec::new(),
+        }
+    }
+
+    /// Gets the name of the constant holding a regular expression with `flags`, adding it if it's new.
+    ///
+    /// The constant is called `name`, unless that's taken.
+    pub fn constant(&mut self, name: &str, pattern: &str, flags: &str) -> Result<String> {
+        let mut expression = Vec::new();
+        let mut literal = SourceWriter::new(&mut expression);
+        literal.write("new RegExp(")?;
+        write_string_literal(&mut literal, pattern)?;
+
+        if !flags.is_empty() {
+            literal.write(", ")?;
+            write_string_literal(&mut literal, flags)?;
+        }
+
+        literal.write(")")?;
+        let expression = String::from_utf8(expression).expect("generated code is UTF-8");
+
+        if let Some((name, _)) = self
+            .constants
+            .iter()
+            .find(|(_, other)| *other == expression)
+        {
+            return Ok(name.clone());
+        }
+
+        let mut name = name.to_owned();
+        disambiguate(&mut name, |name| {
+            is_type_name(self.types, self.options, name)
+                || self.constants.iter().any(|(other, _)| other == name)
+        });
+        self.constants.push((name.clone(), expression));
+
+        Ok(name)
+    }
+}
+
+/// Emits the constants holding the regular expressions used by validation functions and type guards.
+pub fn emit_patterns(writer: &mut SourceWriter<impl Write>, patterns: &Patterns) -> Result<()> {
+    for (name, expression) in &patterns.constants {
+        writer.write_fmt_nl(format_args!("const {name} = {expression};"))?;
+    }
+
+    writer.blank_line();
+    Ok(())
+}
+
+/// Emits the error type returned by validation functions and type guards.
+///
+/// Returns its name, which is `ValidationError` unless a user-defined type has that name.
+pub fn emit_error_type(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+) -> Result<String> {
+    let mut error = "ValidationError".to_owned();
+    disambiguate(&mut error, |name| is_type_name(types, options, name));
+
+    writer.write_fmt_nl(format_args!("export type {error} = {{"))?;
+    writer.indent();
+    writer.write_nl("/** The JSON path to the invalid value, like `$.users[0].name`. */")?;
+    writer.write_nl("path: string,")?;
+    writer.write_nl("/** What was expected instead of the invalid value. */")?;
+    writer.write_nl("message: string,")?;
+    writer.dedent();
+    writer.write_nl("};")?;
+    writer.blank_line();
+
+    Ok(error)
+}
+
+/// Emits the validation functions of all named types, which return the error type named `error`.
+pub fn emit_validation(
+    writer: &mut SourceWriter<impl Write>,
+    types: &TypeSet<TypeScriptMetadata>,
+    options: &TypeScriptOptions,
+    patterns: &mut Patterns,
+    error: &str,
+) -> Result<()> {
+    // Ints encoded as strings are checked like the formats of built-in types
+    let mut formatted = PrimitiveType::BUILT_IN.to_vec();
+
+    if types.int_encoding == IntEncoding::String {
+        formatted.push(PrimitiveType::Number);
+    }
+
+    let checks = Checks::new(types, GetResolution, formatted);
+
+    let context = ValidationContext {
+        types,
+        options,
+        checks: &checks,
+        error,
+    };
+
+    for (index, r#type) in types.types.iter().enumerate() {
+        emit_function(writer, context, patterns, index, r#type)?;
+    }
+
+    Ok(())
+}
+
+/// Gets the name of a function checking a named type, like `validateUser` with the `prefix` [`VALIDATE`].
+pub fn function_name(prefix: &str, r#type: &NamedType<TypeScriptMetadata>) -> String {
+    format!("{prefix}{}", r#type.r#type.metadata.name)
+}
+
+/// Gets the names of the functions checking some type parameters of a named type,
+/// which its function named with `prefix` takes after the value, like `validateT`.
+pub fn parameter_function_names(
+    types: &TypeSet<TypeScriptMetadata>,
+    r#type: &NamedType<TypeScriptMetadata>,
+    prefix: &str,
+    parameters: impl IntoIterator<Item = usize>,
+) -> Vec<(usize, String)> {
+    let mut functions: Vec<(usize, String)> = Vec::new();
+
+    for parameter in parameters {
+        let mut function = format!("{prefix}{}", r#type.parameters[parameter].metadata.name);
+        disambiguate(&mut function, |function| {
+            types
+                .types
+                .iter()
+                .any(|r#type| function_name(prefix, r#type) == function)
+                || functions.iter().any(|(_, other)| other == function)
+        });
+        functions.push((parameter, function));
+    }
+
+    functions
+}
+
+/// Emits the validation function of a named type.
+fn emit_function(
+    writer: &mut SourceWriter<impl Write>,
+    context: ValidationContext,
+    patterns: &mut Patterns,
+    index: usize,
+    r#type: &NamedType<TypeScriptMetadata>,
+) -> Result<()> {
+    let error = context.error;
+    let validators = parameter_function_names(
+        context.types,
+        r#type,
+        VALIDATE,
+        context.checks.parameters(index).iter().copied(),
+    );
+
+    writer.write_fmt(format_args!(
+        "export function {}",
+        function_name(VALIDATE, r#type)
+    ))?;
+    write_parameters(writer, &r#type.parameters)?;
+    writer.write_fmt(format_args!("(value: {}", r#type.r#type.metadata.name))?;
+    write_parameters(writer, &r#type.parameters)?;
+
+    for (parameter, name) in &validators {
+        writer.write_fmt(format_args!(
+            ", {name}: (value: {}, path: string) => {error} | undefined",
+            r#type.parameters[*parameter].metadata.name
+        ))?;
+    }
+
+    writer.write_fmt_nl(format_args!(", path = \"$\"): {error} | undefined {{"))?;
+    writer.indent();
+
+    if context.checks.is_needed(index) {
+        let mut validator = Validator {
+            context,
+            body: Body::new(validators),
+            patterns,
+        };
+        let value = "value".to_owned();
+        let path = vec![PathPart::Expression("path".to_owned())];
+        emit_checks(&mut validator, writer, &r#type.r#type, &value, &path)?;
+    }
+
+    writer.write_nl("return undefined;")?;
+    writer.dedent();
+    writer.write_nl("}")?;
+    writer.blank_line();
+
+    Ok(())
+}
+
+/// Emits the statements of a validation function, see [`CheckEmitter`].
+///
+/// Values are expressions, which already have the TypeScript type of their type.
+struct Validator<'a, 'b, 'c> {
+    context: ValidationContext<'a>,
+    body: Body,
+    patterns: &'b mut Patterns<'c>,
+}
+
+impl CheckEmitter<TypeScriptMetadata> for Validator<'_, '_, '_> {
+    type Value = String;
+    type Path = Vec<PathPart>;
+
+    fn body(&mut self) -> &mut Body {
+        &mut self.body
+    }
+
+    fn has_checks(&self, r#type: &Type<TypeScriptMetadata>) -> bool {
+        self.context.checks.has_checks(r#type, GetResolution)
+    }
+
+    fn field(
+        &mut self,
+        _writer: &mut SourceWriter<impl Write>,
+        object: &String,
+        field: &Field<TypeScriptMetadata>,
+        path: &Vec<PathPart>,
+    ) -> Result<(String, Vec<PathPart>)> {
+        if field.spread {
+            return Ok((object.clone(), path.clone()));
+        }
+
+        let name = &field.metadata.name;
+        Ok((
+            format!("{object}.{name}"),
+            with_literal(path, &key_segment(name)),
+        ))
+    }
+
+    fn begin_optional(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        field: &Field<TypeScriptMetadata>,
+        value: &String,
+    ) -> Result<bool> {
+        begin_optional(writer, self.context.options, field, value)
+    }
+
+    fn emit_enum(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        _type: &Type<TypeScriptMetadata>,
+        r#enum: &Enum<TypeScriptMetadata>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<()> {
+        for variant in &r#enum.variants {
+            if !self.has_checks(&variant.r#type) {
+                continue;
+            }
+
+            writer.write_fmt(format_args!("if ({value}.type === "))?;
+            write_string_literal(writer, &variant.metadata.name)?;
+            writer.write_nl(") {")?;
+            writer.indent();
+
+            // The variants of open enums aren't narrowed, as the tag of unknown variants is any string
+            let variant_value = if r#enum.open {
+                let variable = self.body.variable("value");
+                writer.write_fmt(format_args!("const {variable} = {value}.value as "))?;
+                emit_type(
+                    writer,
+                    self.context.types,
+                    self.context.options,
+                    &variant.r#type,
+                )?;
+                writer.write_nl(";")?;
+                variable
+            } else {
+                format!("{value}.value")
+            };
+
+            let variant_path = with_literal(path, ".value");
+            emit_checks(self, writer, &variant.r#type, &variant_value, &variant_path)?;
+            writer.dedent();
+            writer.write_nl("}")?;
+        }
+
+        Ok(())
+    }
+
+    fn begin_loop(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<(String, Vec<PathPart>)> {
+        begin_loop(writer, &mut self.body, value, path)
+    }
+
+    fn element(
+        &mut self,
+        _writer: &mut SourceWriter<impl Write>,
+        value: &String,
+        index: usize,
+        path: &Vec<PathPart>,
+    ) -> Result<(String, Vec<PathPart>)> {
+        Ok((
+            format!("{value}[{index}]"),
+            with_literal(path, &format!("[{index}]")),
+        ))
+    }
+
+    fn emit_primitive(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        primitive: &Primitive<TypeScriptMetadata>,
+        value: &String,
+        path: &Vec<PathPart>,
+    ) -> Result<()> {
+        let int_encoding = self.context.types.int_encoding;
+        emit_primitive_checks(writer, self.patterns, int_encoding, primitive, value, path)
+    }
+
+    fn function_name(&self, identifier: &Identifier<TypeScriptMetadata>) -> String {
+        function_name(
+            VALIDATE,
+            &self.context.types.types[identifier.metadata.resolution],
+        )
+    }
+
+    fn checked_arguments<'t>(
+        &self,
+        identifier: &'t Identifier<TypeScriptMetadata>,
+    ) -> Vec<&'t Type<TypeScriptMetadata>> {
+        self.context
+            .checks
+            .parameters(identifier.metadata.resolution)
+            .iter()
+            .map(|&parameter| &identifier.arguments[parameter])
+            .collect()
+    }
+
+    fn parameter_index(&self, parameter: &Parameter<TypeScriptMetadata>) -> usize {
+        parameter.metadata.resolution
+    }
+
+    fn begin_call(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        function: &str,
+        value: &String,
+    ) -> Result<String> {
+        begin_call(writer, &mut self.body, function, value)
+    }
+
+    fn end_call(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        result: &str,
+        path: &Vec<PathPart>,
+    ) -> Result<()> {
+        end_call(writer, result, path)
+    }
+
+    fn write_no_checks(&mut self, writer: &mut SourceWriter<impl Write>) -> Result<()> {
+        writer.write("() => undefined")
+    }
+
+    fn begin_closure(
+        &mut self,
+        writer: &mut SourceWriter<impl Write>,
+        r#type: &Type<TypeScriptMetadata>,
+    ) -> Result<(String, Vec<PathPart>)> {
+        let value = self.body.variable("value");
+        let path = self.body.variable("path");
+
+        writer.write_fmt(format_args!("({value}: "))?;
+        emit_type(writer, self.context.types, self.context.options, r#type)?;
+        writer.write_fmt_nl(format_args!(", {path}: string) => {{"))?;
+        writer.indent();
+
+        Ok((value, vec![PathPart::Expression(path)]))
+    }
+
+    fn end_closure(&mut self, writer: &mut SourceWriter<impl Write>) -> Result<()> {
+        end_closure(writer)
+    }
+}
+
+/// Emits the start of a block only checking a field with a default value if it's present,
+/// if such fields are optional.
+pub fn begin_optional(
+    writer: &mut SourceWriter<impl Write>,
+    options: &TypeScriptOptions,
+    field: &Field<TypeScriptMetadata>,
+    value: &str,
+) -> Result<bool> {
+    let optional = options.optional_defaults && field.default.is_some();
+
+    if optional {
+        writer.write_fmt_nl(format_args!("if ({value} !== undefined) {{"))?;
+        writer.indent();
+    }
+
+    Ok(optional)
+}
+
+/// Emits the start of a loop over the elements of an array, returning an element and its path.
+pub fn begin_loop(
+    writer: &mut SourceWriter<impl Write>,
+    body: &mut Body,
+    value: &str,
+    path: &[PathPart],
+) -> Result<(String, Vec<PathPart>)> {
+    let index = body.variable("index");
+    let element = body.variable("element");
+
+    writer.write_fmt_nl(format_args!(
+        "for (const [{index}, {element}] of {value}.entries()) {{"
+    ))?;
+    writer.indent();
+
+    let mut element_path = with_literal(path, "[");
+    element_path.push(PathPart::Expression(index));
+    element_path.push(PathPart::Literal("]".to_owned()));
+
+    Ok((element, element_path))
+}
+
+/// Emits the start of a call of a check function, assigning the error it returns to a new variable.
+///
+/// Returns the name of the variable.
+pub fn begin_call(
+    writer: &mut SourceWriter<impl Write>,
+    body: &mut Body,
+    function: &str,
+    value: &str,
+) -> Result<String> {
+    let error = body.variable("error");
+    writer.write_fmt(format_args!("const {error} = {function}({value}"))?;
+    Ok(error)
+}
+
+/// Emits the end of a call of a check function, passing the path and returning its error, if there is one.
+pub fn end_call(
+    writer: &mut SourceWriter<impl Write>,
+    error: &str,
+    path: &[PathPart],
+) -> Result<()> {
+    writer.write(", ")?;
+    write_path(writer, path)?;
+    writer.write_nl(");")?;
+    emit_return_error(writer, error)
+}
+
+/// Emits the end of a function checking a type argument.
+pub fn end_closure(writer: &mut SourceWriter<impl Write>) -> Result<()> {
+    writer.write_nl("return undefined;")?;
+    writer.dedent();
+    writer.write("}")
+}
+
+/// Emits the checks of the refinements of a primitive type, and of the format of built-in types.
+///
+/// The value is assumed to have the primitive's TypeScript type already.
+pub fn emit_primitive_checks(
+    writer: &mut SourceWriter<impl Write>,
+    patterns: &mut Patterns,
+    int_encoding: IntEncoding,
+    primitive: &Primitive<TypeScriptMetadata>,
+    value: &str,
+    path: &[PathPart],
+) -> Result<()> {
+    let refinements = &primitive.refinements;
+
+    match primitive.r#type {
+        PrimitiveType::Number if int_encoding == IntEncoding::String => {
+            // Ints are compared as bigints, which keep all 64 bits
+            let pattern = patterns.constant("INT_PATTERN", formats::INT_PATTERN, "")?;
+            let condition = format!(
+                "!{pattern}.test({value}) || BigInt.asIntN(64, BigInt({value})) !== BigInt({value})"
+            );
+            emit_check(
+                writer,
+                &condition,
+                "expected an integer encoded as a string",
+                path,
+            )?;
+
+            if let Some(expected) = refinements.expected_int() {
+                let number = format!("BigInt({value})");
+                let condition =
+                    bounds_condition(refinements.min, refinements.max, &number, "n", None);
+                emit_check(writer, &condition, &expected, path)?;
+            }
+        }
+        PrimitiveType::Number => {
+            if let Some(expected) = refinements.expected_int() {
+                let condition = bounds_condition(refinements.min, refinements.max, value, "", None);
+                emit_check(writer, &condition, &expected, path)?;
+            }
+        }
+        PrimitiveType::String => {
+            // The length is counted in code points, which iterating over the string yields
+            if let Some(expected) = refinements.expected_length() {
+                let length = format!("[...{value}].length");
+                let condition =
+                    bounds_condition(refinements.min, refinements.max, &length, "", None);
+                emit_check(writer, &condition, &expected, path)?;
+            }
+
+            if let (Some(pattern), Some(expected)) =
+                (&refinements.pattern, refinements.expected_pattern())
+            {
+                let pattern = patterns.constant("PATTERN", pattern.as_str(), "u")?;
+                let condition = format!("!{pattern}.test({value})");
+                emit_check(writer, &condition, &expected, path)?;
+            }
+        }
+        PrimitiveType::Unit => {}
+        // The pattern doesn't check the days of dates against the length of the month
+        built_in => {
+            let name = format!("{}_PATTERN", built_in.name().to_uppercase());
+            let pattern = formats::pattern(built_in).expect("built-in types to have a pattern");
+            let pattern = patterns.constant(&name, pattern, "")?;
+            let condition = format!("!{pattern}.test({value})");
+            let expected = format!("expected {}", formats::expected(built_in));
+            emit_check(writer, &condition, &expected, path)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Emits an `if` returning an error if a condition is true.
+pub fn emit_check(
+    writer: &mut SourceWriter<impl Write>,
+    condition: &str,
+    expected: &str,
+    path: &[PathPart],
+) -> Result<()> {
+    writer.write_fmt_nl(format_args!("if ({condition}) {{"))?;
+    writer.indent();
+    writer.write("return { path: ")?;
+    write_path(writer, path)?;
+    writer.write(", message: ")?;
+    write_string_literal(writer, expected)?;
+    writer.write_nl(" };")?;
+    writer.dedent();
+    writer.write_nl("}")
+}
+
+/// Emits an `if` returning the error in a variable, if there is one.
+pub fn emit_return_error(writer: &mut SourceWriter<impl Write>, error: &str) -> Result<()> {
+    writer.write_fmt_nl(format_args!("if ({error} !== undefined) {{"))?;
+    writer.indent();
+    writer.write_fmt_nl(format_args!("return {error};"))?;
+    writer.dedent();
+    writer.write_nl("}")
+}
+
+/// Appends a literal segment to a JSON path.
+pub fn with_literal(path: &[PathPart], segment: &str) -> Vec<PathPart> {
+    let mut path = path.to_vec();
+
+    match path.last_mut() {
+        Some(PathPart::Literal(literal)) => literal.push_str(segment),
+        _ => path.push(PathPart::Literal(segment.to_owned())),
+    }
+
+    path
+}
+
+/// Writes an expression evaluating to a JSON path.
+pub fn write_path(writer: &mut SourceWriter<impl Write>, path: &[PathPart]) -> Result<()> {
+    for (index, part) in path.iter().enumerate() {
+        if index > 0 {
+            writer.write(" + ")?;
+        }
+
+        match part {
+            PathPart::Literal(literal) => write_string_literal(writer, literal)?,
+            PathPart::Expression(expression) => writer.write(expression)?,
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,127 @@
+//! Reruns commands whenever their input file changes.
+
+use std::{
+    collections::HashSet,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::mpsc::{RecvTimeoutError, channel},
+    time::{Duration, Instant},
+};
+
+use anstream::stderr;
+use notify::{Event, EventKind, RecursiveMode, Watcher, recommended_watcher};
+
+use crate::error::{Error, ResultExt};
+
+/// How long the file has to stay unchanged before the command is rerun.
+///
+/// Editors often write a file in several steps, which should only trigger a single rerun.
+const DEBOUNCE: Duration = Duration::from_millis(200);
+
+/// The files written by earlier runs of a watched command, which may be rewritten in place.
+///
+/// Commands that refuse to overwrite existing files or append to index files
+/// use this to only do so the first time they write a file.
+#[derive(Debug, Clone, Default)]
+pub struct GeneratedFiles {
+    paths: HashSet<PathBuf>,
+}
+
+impl GeneratedFiles {
+    /// Checks whether a file was written by an earlier run.
+    pub fn contains(&self, path: &Path) -> bool {
+        self.paths.contains(path)
+    }
+
+    /// Records that a file was written.
+    pub fn insert(&mut self, path: PathBuf) {
+        self.paths.insert(path);
+    }
+}
+
+/// Runs a command, then reruns it whenever the file at `path` changes, until the process is killed.
+///
+/// Errors returned by the command are passed to `report` and don't stop the watching.
+/// Schemas can't refer to other files, so only the file itself is watched.
+pub fn watch(
+    path: &Path,
+    mut run: impl FnMut(&mut GeneratedFiles) -> Result<(), Error>,
+    mut report: impl FnMut(Error),
+) -> Result<(), Error> {
+    let watch_error = |error| Error::Watch {
+        error,
+        path: path.to_path_buf(),
+    };
+
+    // The directory is watched instead of the file,
+    // because editors often save files by replacing them
+    let directory = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    // The events refer to files by the path of the directory as it was watched,
+    // so it's canonicalized to compare them to the path of the file
+    let directory = directory.canonicalize().with_path(directory)?;
+    let watched = match path.file_name() {
+        Some(file_name) => directory.join(file_name),
+        None => directory.clone(),
+    };
+
+    let (sender, receiver) = channel();
+    let mut watcher = recommended_watcher(sender).map_err(watch_error)?;
+    watcher
+        .watch(&directory, RecursiveMode::NonRecursive)
+        .map_err(watch_error)?;
+
+    let mut generated = GeneratedFiles::default();
+    let mut run_and_report = |generated: &mut GeneratedFiles| -> Result<(), Error> {
+        match run(generated) {
+            Ok(()) => print_status(path, "Generated outputs from"),
+            Err(error) => {
+                report(error);
+                print_status(path, "Waiting for changes to")
+            }
+        }
+    };
+
+    run_and_report(&mut generated)?;
+
+    while let Ok(event) = receiver.recv() {
+        if !is_relevant(&event.map_err(watch_error)?, &watched) {
+            continue;
+        }
+
+        // Only changes to the file itself postpone the rerun
+        let mut deadline = Instant::now() + DEBOUNCE;
+
+        loop {
+            match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                Ok(event) => {
+                    if is_relevant(&event.map_err(watch_error)?, &watched) {
+                        deadline = Instant::now() + DEBOUNCE;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return Ok(()),
+            }
+        }
+
+        run_and_report(&mut generated)?;
+    }
+
+    Ok(())
+}
+
+/// Checks whether an event is a change to the watched file.
+fn is_relevant(event: &Event, watched: &Path) -> bool {
+    !matches!(event.kind, EventKind::Access(_)) && event.paths.iter().any(|path| path == watched)
+}
+
+/// Prints a message about the watched file to standard error.
+fn print_status(path: &Path, message: &str) -> Result<(), Error> {
+    let mut stream = stderr().lock();
+
+    writeln!(stream, "{message} {}", path.display())
+        .and_then(|()| stream.flush())
+        .with_stderr()
+}
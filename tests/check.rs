@@ -39,3 +39,42 @@ fn unnormalized_escapes() {
         \"c\\u{30c}\" = enum { value: \"\\u{10d}\", none };
     "});
 }
+
+#[test]
+fn built_in_types() {
+    check(indoc! {r#"
+        version v1;
+
+        Record = struct {
+            uuid: uuid,
+            at: [timestamp],
+            day: (date, duration),
+            data: bytes,
+            other: "date",
+        };
+        date = struct { year: int };
+    "#});
+}
+
+#[test]
+fn int_encoding() {
+    check(indoc! {"
+        version v1;
+        int_encoding string;
+
+        int_encoding = struct { value: int };
+    "});
+
+    let file = NamedTempFile::new().unwrap();
+    fs::write(file.path(), "version v1;\nint_encoding bigint;\n").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .arg("check")
+        .arg(file.path())
+        .output()
+        .unwrap();
+
+    assert!(!output.status.success());
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("unknown int encoding 'bigint'"), "{error}");
+}
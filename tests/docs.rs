@@ -0,0 +1,226 @@
+use std::{env, fs, process::Command};
+
+use indoc::indoc;
+use tempfile::{TempDir, tempdir};
+
+use utils::CommandExt;
+
+mod utils;
+
+const SCHEMA: &str = indoc! {r#"
+    version v3;
+
+    /// A user of the service.
+    ///
+    /// Users can have `tags`.
+    User = struct {
+        /// The name shown | everywhere.
+        full_name: string = "anonymous",
+        // Not a doc comment
+        tags: [Tag],
+        address: struct { city: City },
+    };
+    Tag = enum { first_tag, text: string };
+    City = string;
+"#};
+
+const FIRST_MIGRATION: &str = indoc! {"
+    version v1;
+
+    User = #1 struct { name: #2 string, tags: #3 [#4 Tag], id: #5 int };
+    Tag = #6 enum { none: #7 unit, text: #8 string };
+    Legacy = #9 struct { user: #10 User };
+
+    version v2;
+
+    User = #1 struct { full_name: #2 string, tags: #3 [#4 Label], id: #5 string };
+    Label = #6 enum { none: #7 unit, text: #8 string };
+"};
+
+const SECOND_MIGRATION: &str = indoc! {r#"
+    version v2;
+
+    User = #1 struct { full_name: #2 string, tags: #3 [#4 Label], id: #5 string };
+    Label = #6 enum { none: #7 unit, text: #8 string };
+
+    version v3;
+
+    User = #1 struct {
+        full_name: #2 string = "anonymous",
+        tags: #3 [#4 Label],
+        email: string,
+    };
+    Label = #6 enum { none: #7 unit, text: #8 string, first_tag };
+"#};
+
+/// Runs `versed docs` in a temporary directory, which is returned.
+fn docs(args: &[&str], migrations: &[(&str, &str)]) -> TempDir {
+    let dir = tempdir().unwrap();
+
+    let schema_path = dir.path().join("schema.vs");
+    fs::write(&schema_path, SCHEMA).unwrap();
+
+    let migrations_path = dir.path().join("migrations");
+    fs::create_dir(&migrations_path).unwrap();
+
+    for (name, content) in migrations {
+        fs::write(migrations_path.join(name), content).unwrap();
+    }
+
+    let mut command = Command::new(env!("CARGO_BIN_EXE_versed"));
+    command
+        .arg("docs")
+        .args(args)
+        .arg(&schema_path)
+        .arg(dir.path().join("out"));
+
+    if !migrations.is_empty() {
+        command.arg("--migrations").arg(&migrations_path);
+    }
+
+    command.run_and_check();
+    dir
+}
+
+#[test]
+fn markdown() {
+    let dir = docs(&[], &[]);
+    let output = fs::read_to_string(dir.path().join("out/index.md")).unwrap();
+
+    assert!(output.starts_with("# Version v3\n\n- [`User`](#user)\n"));
+    assert!(output.contains(indoc! {r#"
+        <a id="user"></a>
+
+        ## `User`
+
+        A user of the service.
+
+        Users can have `tags`.
+
+        A struct, named `User` in Rust and `User` in TypeScript.
+
+        | Field | JSON key | Rust name | Type | Default | Description |
+        | ----- | -------- | --------- | ---- | ------- | ----------- |
+        | `full_name` | `fullName` | `full_name` | `string` | `"anonymous"` | The name shown \| everywhere. |
+        | `tags` | `tags` | `tags` | `[`[`Tag`](#tag)`]` |  |  |
+        | `address` | `address` | `address` | `struct { city: `[`City`](#city)` }` |  |  |
+
+        In JSON:
+
+        ```
+        {
+          "fullName": string,
+          "tags": [Tag],
+          "address": {
+            "city": City
+          }
+        }
+        ```
+    "#}));
+    assert!(output.contains("| `first_tag` | `first-tag` | `FirstTag` | `unit` |  |\n"));
+    assert!(output.contains(indoc! {r#"
+        ```
+        { "type": "first-tag", "value": null }
+        | { "type": "text", "value": string }
+        ```
+    "#}));
+    assert!(
+        output.contains("An alias, named `City` in Rust and `City` in TypeScript, of `string`.\n")
+    );
+    assert!(!dir.path().join("out/changelog.md").exists());
+}
+
+#[test]
+fn external_tag() {
+    let dir = docs(&["--external-tag"], &[]);
+    let output = fs::read_to_string(dir.path().join("out/index.md")).unwrap();
+
+    assert!(output.contains("{ \"first-tag\": null }\n| { \"text\": string }\n"));
+}
+
+#[test]
+fn html() {
+    let dir = docs(&["--format", "html"], &[]);
+    let output = fs::read_to_string(dir.path().join("out/index.html")).unwrap();
+
+    assert!(output.starts_with("<!DOCTYPE html>\n"));
+    assert!(output.contains(r#"<h2 id="user"><code>User</code></h2>"#));
+    assert!(output.contains("<p>Users can have <code>tags</code>.</p>"));
+    assert!(
+        output.contains(
+            r##"<td><code>[</code><a href="#tag"><code>Tag</code></a><code>]</code></td>"##
+        )
+    );
+    assert!(output.contains(r##"  &quot;tags&quot;: [<a href="#tag">Tag</a>],"##));
+    assert!(output.ends_with("</body>\n</html>\n"));
+}
+
+#[test]
+fn changelog() {
+    // The files are named so that their order has to be determined from the versions
+    let dir = docs(
+        &[],
+        &[
+            ("b.vsm", FIRST_MIGRATION),
+            ("a.vsm", SECOND_MIGRATION),
+            ("notes.txt", "not a migration"),
+        ],
+    );
+
+    let index = fs::read_to_string(dir.path().join("out/index.md")).unwrap();
+    assert!(index.contains("See the [changelog](changelog.md)"));
+
+    let output = fs::read_to_string(dir.path().join("out/changelog.md")).unwrap();
+    assert_eq!(
+        output,
+        indoc! {r#"
+            # Changelog
+
+            ## v3
+
+            Changes since v2:
+
+            - Added the default value `"anonymous"` to the field `full_name` of `User`.
+            - Added the field `email: string` to `User`.
+            - Removed the field `id` from `User`.
+            - Added the variant `first_tag` to `Label`.
+
+            ## v2
+
+            Changes since v1:
+
+            - Renamed the field `name` of `User` to `full_name`.
+            - Changed the type of the field `id` of `User` from `int` to `string`.
+            - Renamed the type `Tag` to `Label`.
+            - Removed the type `Legacy`.
+        "#}
+    );
+}
+
+#[test]
+fn changelog_gap() {
+    let dir = tempdir().unwrap();
+
+    let schema_path = dir.path().join("schema.vs");
+    fs::write(&schema_path, SCHEMA).unwrap();
+
+    let migrations_path = dir.path().join("migrations");
+    fs::create_dir(&migrations_path).unwrap();
+    fs::write(migrations_path.join("a.vsm"), FIRST_MIGRATION).unwrap();
+    fs::write(
+        migrations_path.join("b.vsm"),
+        SECOND_MIGRATION.replace("version v2;", "version v9;"),
+    )
+    .unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .arg("docs")
+        .arg(&schema_path)
+        .arg(dir.path().join("out"))
+        .arg("--migrations")
+        .arg(&migrations_path)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+}
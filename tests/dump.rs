@@ -0,0 +1,110 @@
+use std::{env, fs, process::Command};
+
+use indoc::indoc;
+use serde_json::{Value as Json, json};
+use tempfile::NamedTempFile;
+
+use utils::CommandExt;
+
+mod utils;
+
+/// Runs `versed` with the arguments followed by the path to a file with `content`, parsing its output.
+fn dump(args: &[&str], content: &str) -> Json {
+    let file = NamedTempFile::new().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(args)
+        .arg(file.path())
+        .run_and_check();
+
+    serde_json::from_str(&output).unwrap()
+}
+
+#[test]
+fn schema() {
+    let schema = indoc! {"
+        version v1;
+
+        User = #1 struct { tags: [Tag] = [\"new\"] };
+        Tag = string;
+    "};
+    let dump = dump(&["dump", "--format", "json"], schema);
+
+    assert_eq!(dump["version"], "v1");
+    assert_eq!(dump["versionSpan"], json!({ "start": 8, "end": 10 }));
+
+    let user = &dump["types"][0];
+    assert_eq!(user["name"], "User");
+    assert_eq!(user["type"]["kind"], "struct");
+    assert_eq!(user["type"]["number"], 1);
+
+    let number_span = &user["type"]["numberSpan"];
+    let start = number_span["start"].as_u64().unwrap() as usize;
+    let end = number_span["end"].as_u64().unwrap() as usize;
+    assert_eq!(&schema[start..end], "#1");
+
+    let field = &user["type"]["fields"][0];
+    assert_eq!(field["default"], json!(["new"]));
+    assert_eq!(field["type"]["kind"], "list");
+
+    let tag_start = schema.find("Tag]").unwrap();
+    assert_eq!(
+        field["type"]["element"],
+        json!({
+            "kind": "identifier",
+            "number": null,
+            "span": { "start": tag_start, "end": tag_start + 3 },
+            "numberSpan": null,
+            "name": "Tag",
+            "index": 1,
+            "arguments": [],
+        })
+    );
+
+    assert_eq!(dump["types"][1]["type"]["primitive"], "string");
+}
+
+#[test]
+fn migration() {
+    let migration = indoc! {"
+        version v1;
+        User = #1 struct { name: #2 string, gone: #3 int };
+
+        version v2;
+        int_encoding string;
+        User = #1 struct { full_name: #2 string };
+    "};
+    let dump = dump(&["migration", "dump"], migration);
+
+    assert_eq!(dump["old"]["version"], "v1");
+    assert_eq!(dump["new"]["version"], "v2");
+    assert_eq!(dump["old"]["intEncoding"], "number");
+    assert_eq!(dump["new"]["intEncoding"], "string");
+    assert_eq!(
+        dump["pairs"],
+        json!([
+            { "number": 1, "old": "/old/types/0/type", "new": "/new/types/0/type" },
+            {
+                "number": 2,
+                "old": "/old/types/0/type/fields/0/type",
+                "new": "/new/types/0/type/fields/0/type",
+            },
+        ])
+    );
+}
+
+#[test]
+fn malformed() {
+    let file = NamedTempFile::new().unwrap();
+    fs::write(file.path(), "version v1; User = Missing;").unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .arg("dump")
+        .arg(file.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty());
+}
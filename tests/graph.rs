@@ -0,0 +1,116 @@
+use std::{env, fs, process::Command};
+
+use indoc::indoc;
+use tempfile::NamedTempFile;
+
+use utils::CommandExt;
+
+mod utils;
+
+const SCHEMA: &str = indoc! {r#"
+    version v1;
+
+    User = struct {
+        name: string = "anonymous",
+        tags: [Tag],
+        friend: Friend,
+        address: struct { city: City },
+    };
+    Tag = enum { none, text: string };
+    Friend = enum { none, some: User };
+    City = string;
+    "Many users" = [[User]];
+"#};
+
+const MIGRATION: &str = indoc! {"
+    version v1;
+
+    User = #1 struct { name: #2 string, tags: #3 [#4 Tag] };
+    Tag = #5 enum { none: #6 unit, text: #7 string };
+    Legacy = #8 struct { user: #9 User };
+
+    version v2;
+
+    User = #1 struct { name: #2 string, tags: #3 [#4 Tag], email: string };
+    Tag = #5 enum { none: #6 unit, text: #7 string };
+    Group = struct { members: [User] };
+"};
+
+fn graph(content: &str, args: &[&str]) -> String {
+    let file = NamedTempFile::new().unwrap();
+    fs::write(file.path(), content).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .arg("graph")
+        .args(args)
+        .arg(file.path())
+        .run_and_check()
+}
+
+#[test]
+fn dot() {
+    let output = graph(SCHEMA, &[]);
+
+    assert!(output.starts_with("digraph \"v1\" {\n"));
+    assert!(output.contains(
+        r#""User" [label="struct User\n\nname: string = \"anonymous\"\ltags: [Tag]\lfriend: Friend\laddress: struct { city: City }\l"];"#
+    ));
+    assert!(output.contains(r#""Tag" [label="enum Tag\n\nnone\ltext: string\l"];"#));
+    assert!(output.contains(r#""User" -> "Tag" [label="tags", headlabel="*"];"#));
+    assert!(output.contains(r#""User" -> "City" [label="address.city"];"#));
+    assert!(
+        output.contains(
+            r#""User" -> "Friend" [label="friend", color=red, fontcolor=red, penwidth=2];"#
+        )
+    );
+    assert!(
+        output.contains(
+            r#""Friend" -> "User" [label="some", color=red, fontcolor=red, penwidth=2];"#
+        )
+    );
+    assert!(output.contains(r#""Many users" -> "User" [label="", headlabel="*"];"#));
+}
+
+#[test]
+fn mermaid() {
+    let output = graph(SCHEMA, &["--format", "mermaid"]);
+
+    assert!(output.contains("classDiagram\n"));
+    assert!(output.contains(concat!(
+        "    class Tag {\n",
+        "        <<enum>>\n",
+        "        none\n",
+        "        text: string\n",
+        "    }\n",
+    )));
+    assert!(output.contains("    class Many_users[\"Many users\"]\n"));
+    assert!(output.contains("address: struct #123; city: City #125;\n"));
+    assert!(output.contains("    User --> \"*\" Tag : tags\n"));
+    assert!(output.contains("    Friend --> User : some (recursive)\n"));
+    assert!(output.contains("    Many_users --> \"*\" User\n"));
+}
+
+#[test]
+fn dot_migration() {
+    let output = graph(MIGRATION, &["--migration"]);
+
+    assert!(output.starts_with("digraph \"v2\" {\n"));
+    assert!(output.contains(r##"fillcolor="#fff9c4"];"##));
+    assert!(output.contains(
+        r##""Group" [label="struct Group\n\nmembers: [User]\l", style=filled, fillcolor="#c8e6c9"];"##
+    ));
+    assert!(output.contains(
+        r##""Legacy" [label="struct Legacy\n\nuser: User\l", style=filled, fillcolor="#ffcdd2"];"##
+    ));
+    assert!(output.contains(r#""Tag" [label="enum Tag\n\nnone\ltext: string\l"];"#));
+    assert!(output.contains(r#""Legacy" -> "User" [label="user"];"#));
+}
+
+#[test]
+fn mermaid_migration() {
+    let output = graph(MIGRATION, &["--migration", "--format", "mermaid"]);
+
+    assert!(output.contains("cssClass \"Group\" added\n"));
+    assert!(output.contains("cssClass \"Legacy\" removed\n"));
+    assert!(output.contains("cssClass \"User\" changed\n"));
+}
@@ -0,0 +1,554 @@
+use std::{env, fs, process::Command};
+
+use indoc::indoc;
+use tempfile::tempdir;
+
+const MIGRATION: &str = indoc! {r#"
+    version v1;
+
+    User = #1 struct { name: #2 string, age: #3 int, tags: #4 [#5 Tag], id: #6 int };
+    Tag = #7 enum { first_tag: #8 unit, second: #9 int };
+    Wrapper = #10 struct { value: #11 Id };
+    Id = #12 int;
+    Page<T> = #13 struct { items: #14 [#15 T] };
+    Tags = #16 Page<#17 Tag>;
+    Range = #18 (#19 int, #20 string);
+    Stamps = #21 struct { created_at: #22 int };
+    Post = #23 struct { ..#24 Stamps, title: #25 string };
+    Status = #26 open enum { active: #27 unit };
+    Level = #28 open enum { low: #29 unit };
+    Score = #30 int(0..);
+    extern #31 Timestamp;
+    Event = #32 struct { at: #33 Timestamp };
+    Record = #34 struct { id: #35 uuid, at: #36 timestamp, day: #37 date, length: #38 duration, data: #39 bytes };
+    Flag = #40 unit;
+
+    version v2;
+
+    User = #1 struct {
+        full_name: #2 string,
+        age: #3 int,
+        tags: #4 [#5 Tag],
+        friends: [string] = [],
+        email: string = "unknown",
+        id: #6 string,
+    };
+    Tag = #7 enum { first_tag: #8 unit, second: #9 int, third: string };
+    Wrapper = #10 Id;
+    Id = #12 int;
+    Page<T> = #13 struct { items: #14 [#15 T], total: int = 0 };
+    Tags = #16 Page<#17 Tag>;
+    Range = #18 (#20 string, #19 int);
+    Stamps = #21 struct { created_at: #22 int, updated_at: int = 0 };
+    Post = #23 struct { ..#24 Stamps, title: #25 string };
+    Status = #26 open enum { active: #27 unit, banned: string };
+    Level = #28 enum { low: #29 unit };
+    Score = #30 int(0..100);
+    extern #31 Timestamp;
+    Event = #32 struct { at: #33 Timestamp, name: string = "" };
+    Record = #34 struct { id: #35 uuid, at: #36 timestamp, day: #37 date, length: #38 duration, data: #39 bytes };
+    Flag = #40 [int];
+"#};
+
+/// Runs `versed migrate-data` and returns the output document, or the error message on failure.
+fn migrate(input: &str, args: &[&str]) -> Result<String, String> {
+    migrate_with(MIGRATION, input, args)
+}
+
+/// Runs `versed migrate-data` like [`migrate`], with another migration file.
+fn migrate_with(migration: &str, input: &str, args: &[&str]) -> Result<String, String> {
+    let dir = tempdir().unwrap();
+
+    let migration_path = dir.path().join("schema.vsm");
+    fs::write(&migration_path, migration).unwrap();
+
+    let input_path = dir.path().join("in.json");
+    fs::write(&input_path, input).unwrap();
+
+    let output_path = dir.path().join("out.json");
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .arg("migrate-data")
+        .args(args)
+        .arg(&migration_path)
+        .arg(&input_path)
+        .arg(&output_path)
+        .output()
+        .unwrap();
+
+    if output.status.success() {
+        Ok(fs::read_to_string(output_path).unwrap())
+    } else {
+        assert_eq!(output.status.code(), Some(1));
+        Err(String::from_utf8(output.stderr).unwrap())
+    }
+}
+
+#[test]
+fn upgrade() {
+    let output = migrate(
+        r#"{"name": "Alice", "age": 42, "tags": [{"type": "first-tag", "value": null}, {"type": "second", "value": 3}], "id": 7}"#,
+        &["--type", "User"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "fullName": "Alice",
+              "age": 42,
+              "tags": [
+                {
+                  "type": "first-tag",
+                  "value": null
+                },
+                {
+                  "type": "second",
+                  "value": 3
+                }
+              ],
+              "friends": [],
+              "email": "unknown",
+              "id": "7"
+            }
+        "#}
+    );
+}
+
+#[test]
+fn downgrade() {
+    let output = migrate(
+        r#"{"fullName": "Alice", "age": 42, "tags": [{"type": "first-tag", "value": null}], "friends": ["Bob"], "email": "", "id": "7"}"#,
+        &["--type", "User", "--downgrade"],
+    );
+
+    let error = output.unwrap_err();
+    assert!(error.contains("$.id"), "{error}");
+    assert!(
+        error.contains("a string can't be converted to an int"),
+        "{error}"
+    );
+}
+
+#[test]
+fn downgrade_missing_variant() {
+    let output = migrate(
+        r#"{"type": "third", "value": "x"}"#,
+        &["--type", "Tag", "-d"],
+    );
+
+    let error = output.unwrap_err();
+    assert!(
+        error.contains("$: the variant 'third' has no counterpart in version v1"),
+        "{error}"
+    );
+}
+
+#[test]
+fn nested_error_path() {
+    let output = migrate(
+        r#"{"name": "Alice", "age": 42, "tags": [{"type": "second", "value": 1}, {"type": "second", "value": "x"}], "id": 7}"#,
+        &["--type", "User"],
+    );
+
+    let error = output.unwrap_err();
+    assert!(
+        error.contains("$.tags[1].value: expected an integer"),
+        "{error}"
+    );
+}
+
+#[test]
+fn added_field_error_path() {
+    let migration = indoc! {r#"
+        version v1;
+        User = #1 struct { name: #2 string };
+        version v2;
+        User = #1 struct { name: #2 string, email: string };
+    "#};
+
+    let output = migrate_with(migration, r#"{"name": "Alice"}"#, &["--type", "User"]);
+
+    let error = output.unwrap_err();
+    assert!(
+        error.contains("$.email: the field of version v2 has no counterpart"),
+        "{error}"
+    );
+}
+
+#[test]
+fn unwrap_and_wrap() {
+    let output = migrate(r#"{"value": 5}"#, &["--type", "Wrapper"]).unwrap();
+    assert_eq!(output, "5\n");
+
+    let output = migrate("5", &["--type", "Wrapper", "--downgrade"]).unwrap();
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "value": 5
+            }
+        "#}
+    );
+}
+
+#[test]
+fn external_tag() {
+    let output = migrate(
+        r#"{"first-tag": null}"#,
+        &["--type", "Tag", "--external-tag"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "first-tag": null
+            }
+        "#}
+    );
+}
+
+#[test]
+fn generic_type() {
+    let output = migrate(
+        r#"{"items": [{"type": "first-tag", "value": null}]}"#,
+        &["--type", "Tags"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "items": [
+                {
+                  "type": "first-tag",
+                  "value": null
+                }
+              ],
+              "total": 0
+            }
+        "#}
+    );
+}
+
+#[test]
+fn tuple() {
+    let output = migrate(r#"[5, "x"]"#, &["--type", "Range"]).unwrap();
+    assert_eq!(
+        output,
+        indoc! {r#"
+            [
+              "x",
+              5
+            ]
+        "#}
+    );
+
+    let error = migrate("[5]", &["--type", "Range"]).unwrap_err();
+    assert!(
+        error.contains("$: expected an array of length 2"),
+        "{error}"
+    );
+}
+
+#[test]
+fn no_safe_conversion() {
+    // Values that would have to be made up, like an empty list for unit, are left to the user
+    let error = migrate("null", &["--type", "Flag"]).unwrap_err();
+    assert!(
+        error.contains("$: unit can't be converted to a list"),
+        "{error}"
+    );
+}
+
+#[test]
+fn spread() {
+    let output = migrate(r#"{"createdAt": 1, "title": "Hello"}"#, &["--type", "Post"]).unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "createdAt": 1,
+              "updatedAt": 0,
+              "title": "Hello"
+            }
+        "#}
+    );
+}
+
+#[test]
+fn spread_named_like_field() {
+    // The spread doesn't appear in the JSON, so it mustn't rename the field named like it
+    let migration = indoc! {r#"
+        version v1;
+        Timestamps = #1 struct { created_at: #2 int };
+        User = #3 struct { ..#4 Timestamps, timestamps: #5 string };
+        version v2;
+        Timestamps = #1 struct { created_at: #2 int };
+        User = #3 struct { ..#4 Timestamps, timestamps: #5 string };
+    "#};
+
+    let output = migrate_with(
+        migration,
+        r#"{"createdAt": 1, "timestamps": "now"}"#,
+        &["--type", "User"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "createdAt": 1,
+              "timestamps": "now"
+            }
+        "#}
+    );
+}
+
+#[test]
+fn variant_gained_fields() {
+    let migration = indoc! {r#"
+        version v1;
+        Event = #1 enum { created: #2 unit };
+        version v2;
+        Event = #1 enum { created: #2 struct { by: string = "system", count: int = 0 } };
+    "#};
+
+    let output = migrate_with(
+        migration,
+        r#"{"type": "created", "value": null}"#,
+        &["--type", "Event"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "type": "created",
+              "value": {
+                "by": "system",
+                "count": 0
+              }
+            }
+        "#}
+    );
+
+    let output = migrate_with(
+        migration,
+        r#"{"type": "created", "value": {"by": "admin", "count": 2}}"#,
+        &["--type", "Event", "--downgrade"],
+    )
+    .unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "type": "created",
+              "value": null
+            }
+        "#}
+    );
+}
+
+#[test]
+fn open_enum() {
+    let output = migrate(r#"{"type": "archived", "value": 3}"#, &["--type", "Status"]).unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "type": "archived",
+              "value": 3
+            }
+        "#}
+    );
+
+    let error = migrate(r#"{"type": "high", "value": null}"#, &["--type", "Level"]).unwrap_err();
+    assert!(
+        error.contains("the unknown variant 'high' can't be passed through"),
+        "{error}"
+    );
+
+    let error = migrate(r#"{"type": "gone", "value": null}"#, &["--type", "Tag"]).unwrap_err();
+    assert!(error.contains("unknown variant 'gone'"), "{error}");
+}
+
+#[test]
+fn refinements() {
+    assert_eq!(migrate("42", &["--type", "Score"]).unwrap().trim(), "42");
+
+    let error = migrate("-1", &["--type", "Score"]).unwrap_err();
+    assert!(error.contains("expected an int of at least 0"), "{error}");
+
+    let error = migrate("150", &["--type", "Score"]).unwrap_err();
+    assert!(
+        error.contains("expected an int between 0 and 100 in version v2"),
+        "{error}"
+    );
+}
+
+#[test]
+fn extern_type() {
+    let output = migrate(r#"{"at": {"secs": 1, "nanos": 0}}"#, &["--type", "Event"]).unwrap();
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            {
+              "at": {
+                "secs": 1,
+                "nanos": 0
+              },
+              "name": ""
+            }
+        "#}
+    );
+}
+
+#[test]
+fn built_in_types() {
+    let record = r#"{"id": "67e55044-10b1-426f-9247-bb680e5fe0c8", "at": "2024-05-06T07:08:09.5+02:00", "day": "2024-02-29", "length": "-PT1.5S", "data": "AAEC"}"#;
+    let output = migrate(record, &["--type", "Record"]).unwrap();
+    assert!(
+        output.contains(r#""at": "2024-05-06T07:08:09.5+02:00""#),
+        "{output}"
+    );
+
+    let error = migrate(
+        &record.replace("2024-02-29", "2023-02-29"),
+        &["--type", "Record"],
+    );
+    let error = error.unwrap_err();
+    assert!(error.contains("$.day"), "{error}");
+    assert!(
+        error.contains("expected a date, like 2024-05-06"),
+        "{error}"
+    );
+
+    let error = migrate(&record.replace("T07", " 07"), &["--type", "Record"]).unwrap_err();
+    assert!(error.contains("expected an RFC 3339 timestamp"), "{error}");
+
+    let error = migrate(&record.replace("-PT1.5S", "PT1M"), &["--type", "Record"]).unwrap_err();
+    assert!(error.contains("expected an ISO 8601 duration"), "{error}");
+
+    let error = migrate(&record.replace("-10b1-", "-10b1"), &["--type", "Record"]).unwrap_err();
+    assert!(error.contains("expected a hyphenated UUID"), "{error}");
+
+    let error = migrate(&record.replace("AAEC", "AAE"), &["--type", "Record"]).unwrap_err();
+    assert!(error.contains("expected a Base64 string"), "{error}");
+}
+
+#[test]
+fn int_encoding() {
+    let migration = MIGRATION
+        .replace("version v1;", "version v1;\nint_encoding string;")
+        .replace("version v2;", "version v2;\nint_encoding string;");
+
+    let output = migrate_with(
+        &migration,
+        r#"{"name": "Alice", "age": "9007199254740993", "tags": [{"type": "second", "value": "3"}], "id": "7"}"#,
+        &["--type", "User"],
+    )
+    .unwrap();
+    assert!(output.contains(r#""age": "9007199254740993""#), "{output}");
+    assert!(output.contains(r#""value": "3""#), "{output}");
+    assert!(output.contains(r#""id": "7""#), "{output}");
+
+    let output = migrate_with(
+        &migration,
+        r#"{"items": [{"type": "second", "value": "3"}]}"#,
+        &["--type", "Tags"],
+    )
+    .unwrap();
+    assert!(output.contains(r#""total": "0""#), "{output}");
+
+    let error = migrate_with(&migration, "42", &["--type", "Score"]).unwrap_err();
+    assert!(
+        error.contains("expected an integer encoded as a string"),
+        "{error}"
+    );
+
+    let error = migrate_with(&migration, r#""150""#, &["--type", "Score"]).unwrap_err();
+    assert!(
+        error.contains("expected an int between 0 and 100 in version v2"),
+        "{error}"
+    );
+}
+
+#[test]
+fn int_encoding_change() {
+    let migration = MIGRATION.replace("version v2;", "version v2;\nint_encoding string;");
+
+    let output = migrate_with(
+        &migration,
+        r#"{"items": [{"type": "second", "value": 3}]}"#,
+        &["--type", "Tags"],
+    )
+    .unwrap();
+    assert!(output.contains(r#""value": "3""#), "{output}");
+    assert!(output.contains(r#""total": "0""#), "{output}");
+
+    let output = migrate_with(
+        &migration,
+        r#"{"items": [{"type": "second", "value": "3"}], "total": "5"}"#,
+        &["--type", "Tags", "--downgrade"],
+    )
+    .unwrap();
+    assert!(output.contains(r#""value": 3"#), "{output}");
+
+    let error = migrate_with(&migration, r#""42""#, &["--type", "Score"]).unwrap_err();
+    assert!(error.contains("expected an integer"), "{error}");
+}
+
+#[test]
+fn invalid_json() {
+    let error = migrate("{", &["--type", "User"]).unwrap_err();
+    assert!(error.contains("as JSON"), "{error}");
+}
+
+#[test]
+fn unknown_type() {
+    let dir = tempdir().unwrap();
+
+    let migration_path = dir.path().join("schema.vsm");
+    fs::write(&migration_path, MIGRATION).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["migrate-data", "--type", "Nope"])
+        .arg(&migration_path)
+        .arg(dir.path().join("in.json"))
+        .arg(dir.path().join("out.json"))
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn generic_type_without_arguments() {
+    let dir = tempdir().unwrap();
+
+    let migration_path = dir.path().join("schema.vsm");
+    fs::write(&migration_path, MIGRATION).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["migrate-data", "--type", "Page"])
+        .arg(&migration_path)
+        .arg(dir.path().join("in.json"))
+        .arg(dir.path().join("out.json"))
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("is generic"), "{error}");
+}
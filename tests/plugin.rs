@@ -0,0 +1,210 @@
+#![cfg(unix)]
+
+use std::{
+    env, fs,
+    os::unix::fs::PermissionsExt,
+    path::Path,
+    process::{Command, Output},
+};
+
+use indoc::indoc;
+use serde_json::{Value as Json, json};
+use tempfile::{TempDir, tempdir};
+
+/// A plugin that saves the request and prints the response stored in `response.json`.
+const PLUGIN: &str = indoc! {"
+    #!/bin/sh
+    cat > request.json
+    cat response.json
+"};
+
+const SCHEMA: &str = indoc! {r#"
+    version v1;
+
+    User = struct { full_name: string = "anonymous", role: Role };
+    Role = enum { admin, guest };
+"#};
+
+const MIGRATION: &str = indoc! {"
+    version v1;
+    User = #1 struct { name: #2 string };
+
+    version v2;
+    User = #1 struct { full_name: #2 string, age: int };
+"};
+
+/// Creates a directory with the plugin `versed-gen-test`, an input file and the response of the plugin.
+fn setup(input: &str, response: &Json) -> TempDir {
+    let dir = tempdir().unwrap();
+
+    let plugin_path = dir.path().join("versed-gen-test");
+    fs::write(&plugin_path, PLUGIN).unwrap();
+    fs::set_permissions(&plugin_path, fs::Permissions::from_mode(0o755)).unwrap();
+
+    fs::write(dir.path().join("input.vs"), input).unwrap();
+    fs::write(dir.path().join("response.json"), response.to_string()).unwrap();
+
+    dir
+}
+
+/// Runs `versed plugin test` in a directory, with the directory added to `PATH`.
+fn run(dir: &Path, args: &[&str]) -> Output {
+    let path = env::join_paths(
+        [dir.to_path_buf()]
+            .into_iter()
+            .chain(env::split_paths(&env::var_os("PATH").unwrap_or_default())),
+    )
+    .unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["plugin", "test", "input.vs", "out"])
+        .args(args)
+        .env("PATH", path)
+        .current_dir(dir)
+        .output()
+        .unwrap()
+}
+
+/// Reads the request received by the plugin.
+fn request(dir: &Path) -> Json {
+    serde_json::from_str(&fs::read_to_string(dir.join("request.json")).unwrap()).unwrap()
+}
+
+#[test]
+fn schema() {
+    let dir = setup(
+        SCHEMA,
+        &json!({
+            "protocolVersion": 1,
+            "files": [{ "path": "gen/v1.txt", "content": "generated\n", "overwrite": true }],
+            "patches": [{ "path": "gen/index.txt", "appendLine": "v1" }],
+        }),
+    );
+
+    for _ in 0..2 {
+        let output = run(dir.path(), &["--naming", "rust,typescript", "-p", "a=b"]);
+        assert!(output.status.success());
+    }
+
+    let generated = fs::read_to_string(dir.path().join("out/gen/v1.txt")).unwrap();
+    assert_eq!(generated, "generated\n");
+    let index = fs::read_to_string(dir.path().join("out/gen/index.txt")).unwrap();
+    assert_eq!(index, "v1\n");
+
+    let request = request(dir.path());
+    assert_eq!(request["protocolVersion"], 1);
+    assert_eq!(request["kind"], "schema");
+    assert_eq!(request["source"], SCHEMA);
+    assert_eq!(request["parameters"], json!(["a=b"]));
+
+    let schema = &request["schema"];
+    assert_eq!(schema["version"], "v1");
+    assert_eq!(schema["names"], json!({ "rust": "v1", "typescript": "v1" }));
+
+    let field = &schema["types"][0]["type"]["fields"][0];
+    assert_eq!(field["name"], "full_name");
+    assert_eq!(
+        field["names"],
+        json!({ "rust": "full_name", "typescript": "fullName" })
+    );
+    assert_eq!(field["default"], "anonymous");
+    assert_eq!(field["type"]["primitive"], "string");
+
+    let span = &field["span"];
+    let start = span["start"].as_u64().unwrap() as usize;
+    let end = span["end"].as_u64().unwrap() as usize;
+    assert_eq!(&SCHEMA[start..end], "full_name");
+
+    let role = &schema["types"][0]["type"]["fields"][1]["type"];
+    assert_eq!(role["kind"], "identifier");
+    assert_eq!(role["index"], 1);
+    assert_eq!(schema["types"][1]["type"]["variants"][1]["name"], "guest");
+}
+
+#[test]
+fn migration() {
+    let dir = setup(MIGRATION, &json!({ "protocolVersion": 1 }));
+
+    let output = run(dir.path(), &["--migration"]);
+    assert!(output.status.success());
+
+    let request = request(dir.path());
+    assert_eq!(request["kind"], "migration");
+
+    let migration = &request["migration"];
+    assert_eq!(migration["old"]["version"], "v1");
+    assert_eq!(migration["new"]["version"], "v2");
+    assert_eq!(migration["old"]["types"][0]["type"]["names"], json!({}));
+
+    let pairs = migration["pairs"].as_array().unwrap();
+    assert_eq!(pairs.len(), 2);
+
+    for pair in pairs {
+        let old = request.pointer(pair["old"].as_str().unwrap()).unwrap();
+        let new = request.pointer(pair["new"].as_str().unwrap()).unwrap();
+
+        assert_eq!(old["number"], pair["number"]);
+        assert_eq!(new["number"], pair["number"]);
+    }
+}
+
+#[test]
+fn reported_error() {
+    let dir = setup(
+        SCHEMA,
+        &json!({ "protocolVersion": 1, "error": "unsupported schema" }),
+    );
+
+    let output = run(dir.path(), &[]);
+    assert_eq!(output.status.code(), Some(4));
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("unsupported schema")
+    );
+}
+
+#[test]
+fn invalid_response() {
+    for response in [
+        json!({ "protocolVersion": 2 }),
+        json!({ "protocolVersion": 1, "files": [{ "path": "../escape.txt", "content": "" }] }),
+        json!({ "protocolVersion": 1, "patches": [{ "path": "a", "appendLine": "a\nb" }] }),
+    ] {
+        let dir = setup(SCHEMA, &response);
+
+        let output = run(dir.path(), &[]);
+        assert_eq!(output.status.code(), Some(4), "{response}");
+        assert!(!dir.path().join("escape.txt").exists());
+    }
+}
+
+#[test]
+fn existing_file() {
+    let dir = setup(
+        SCHEMA,
+        &json!({ "protocolVersion": 1, "files": [{ "path": "v1.txt", "content": "new" }] }),
+    );
+    fs::create_dir(dir.path().join("out")).unwrap();
+    fs::write(dir.path().join("out/v1.txt"), "old").unwrap();
+
+    let output = run(dir.path(), &[]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        fs::read_to_string(dir.path().join("out/v1.txt")).unwrap(),
+        "old"
+    );
+}
+
+#[test]
+fn missing_plugin() {
+    let dir = setup(SCHEMA, &json!({ "protocolVersion": 1 }));
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["plugin", "does-not-exist", "input.vs", "out"])
+        .current_dir(dir.path())
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(3));
+}
@@ -0,0 +1,153 @@
+use std::{env, fs, process::Command};
+
+use indoc::indoc;
+use tempfile::tempdir;
+
+use utils::CommandExt;
+
+mod utils;
+
+const MANIFEST_CONTENT: &str = indoc! {r#"
+    [package]
+    name = "versed_fixture"
+    version = "0.1.0"
+    edition = "2024"
+
+    [[bin]]
+    name = "versed_fixture"
+    path = "src/mod.rs"
+
+    [dependencies]
+    chrono = { version = "0.4.38", features = ["serde"] }
+    serde = { version = "1.0.219", features = ["derive"] }
+    serde_json = "1.0.143"
+    serde_with = { version = "3.12.0", features = ["base64", "time_0_3"] }
+    time = { version = "0.3.37", features = ["serde-human-readable", "macros"] }
+    uuid = { version = "1.16.0", features = ["serde"] }
+"#};
+
+const SCHEMA: &str = indoc! {r#"
+    version v1;
+
+    Event = struct {
+        id: uuid,
+        at: timestamp,
+        day: date,
+        length: duration,
+        payload: bytes,
+        chunks: [bytes],
+        window: (timestamp, duration),
+        pair: Pair<duration>,
+        kind: Kind,
+        hash: Hash,
+    };
+    Pair<T> = (T, T);
+    Kind = enum { timed: duration, empty: unit };
+    Hash = bytes;
+"#};
+
+const JSON: &str = indoc! {r#"
+    {
+        "id": "67e55044-10b1-426f-9247-bb680e5fe0c8",
+        "at": "2024-05-06T07:08:09Z",
+        "day": "2024-02-29",
+        "length": "-PT1.25S",
+        "payload": "AAEC",
+        "chunks": ["", "/w=="],
+        "window": ["1970-01-01T00:00:00Z", "PT90S"],
+        "pair": ["PT0S", "PT0.000000001S"],
+        "kind": { "type": "timed", "value": "PT3S" },
+        "hash": "3q2+7w=="
+    }
+"#};
+
+/// Generates the types with `--serde` and runs a main function checking that the example JSON roundtrips.
+fn check(time_crate: &str, main: &str) {
+    let dir = tempdir().unwrap();
+
+    let manifest_path = dir.path().join("Cargo.toml");
+    fs::write(&manifest_path, MANIFEST_CONTENT).unwrap();
+
+    let src_path = dir.path().join("src");
+    fs::create_dir(&src_path).unwrap();
+
+    let schema_path = dir.path().join("schema.vd");
+    fs::write(&schema_path, SCHEMA).unwrap();
+
+    let mod_content = format!(
+        "#![allow(dead_code)]\n\nuse v1::*;\n\nconst JSON: &str = r#\"{JSON}\"#;\n\n{main}"
+    );
+    fs::write(src_path.join("mod.rs"), mod_content).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["rust", "types", "--serde", "--derive", "PartialEq"])
+        .args(["--time-crate", time_crate])
+        .arg(schema_path)
+        .arg(&src_path)
+        .run_and_check();
+
+    Command::new("cargo")
+        .arg("run")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .run_and_check();
+}
+
+const ROUNDTRIP: &str = indoc! {r#"
+    fn roundtrip(expected: Event) {
+        let event: Event = serde_json::from_str(JSON).unwrap();
+        assert_eq!(event, expected);
+
+        let json = serde_json::to_value(&event).unwrap();
+        assert_eq!(json, serde_json::from_str::<serde_json::Value>(JSON).unwrap());
+
+        let invalid = JSON.replace("-PT1.25S", "PT1M");
+        assert!(serde_json::from_str::<Event>(&invalid).is_err());
+    }
+"#};
+
+#[test]
+fn chrono() {
+    let main = indoc! {r#"
+        use chrono::{DateTime, NaiveDate, TimeDelta};
+
+        fn main() {
+            roundtrip(Event {
+                id: uuid::Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8),
+                at: DateTime::from_timestamp(1_714_979_289, 0).unwrap(),
+                day: NaiveDate::from_ymd_opt(2024, 2, 29).unwrap(),
+                length: -TimeDelta::milliseconds(1250),
+                payload: vec![0, 1, 2],
+                chunks: vec![vec![], vec![255]],
+                window: (DateTime::UNIX_EPOCH, TimeDelta::seconds(90)),
+                pair: (TimeDelta::zero(), TimeDelta::nanoseconds(1)),
+                kind: Kind::Timed(TimeDelta::seconds(3)),
+                hash: vec![0xde, 0xad, 0xbe, 0xef],
+            });
+        }
+    "#};
+    check("chrono", &format!("{ROUNDTRIP}\n{main}"));
+}
+
+#[test]
+fn time() {
+    let main = indoc! {r#"
+        use time::{Duration, OffsetDateTime, macros::{date, datetime}};
+
+        fn main() {
+            roundtrip(Event {
+                id: uuid::Uuid::from_u128(0x67e55044_10b1_426f_9247_bb680e5fe0c8),
+                at: datetime!(2024-05-06 07:08:09 UTC),
+                day: date!(2024-02-29),
+                length: -Duration::milliseconds(1250),
+                payload: vec![0, 1, 2],
+                chunks: vec![vec![], vec![255]],
+                window: (OffsetDateTime::UNIX_EPOCH, Duration::seconds(90)),
+                pair: (Duration::ZERO, Duration::nanoseconds(1)),
+                kind: Kind::Timed(Duration::seconds(3)),
+                hash: vec![0xde, 0xad, 0xbe, 0xef],
+            });
+        }
+    "#};
+    check("time", &format!("{ROUNDTRIP}\n{main}"));
+}
@@ -3,7 +3,7 @@ use std::{env, fs, process::Command};
 use indoc::indoc;
 use tempfile::tempdir;
 
-use utils::CommandExt;
+use utils::{CommandExt, RUST_EXTERNS};
 
 mod utils;
 
@@ -13,10 +13,19 @@ const MOD_CONTENT: &str = concat!(
     "fn main() {}\n"
 );
 
-const EXTRA_ARGS: [&[&str]; 2] = [&[], &["--derive", "Eq", "--derive", "PartialEq"]];
+const EXTRA_ARGS: [&[&str]; 3] = [
+    &[],
+    &["--derive", "Eq", "--derive", "PartialEq"],
+    &["--validate"],
+];
 
 fn check(schema: &str) {
     for extra_args in EXTRA_ARGS {
+        // Validating patterns needs the regex crate, which is covered by the rust_validate tests
+        if extra_args.contains(&"--validate") && schema.contains("pattern") {
+            continue;
+        }
+
         let dir = tempdir().unwrap();
 
         let mod_path = dir.path().join("mod.rs");
@@ -28,6 +37,7 @@ fn check(schema: &str) {
         Command::new(env!("CARGO_BIN_EXE_versed"))
             .arg("rust")
             .arg("types")
+            .args(RUST_EXTERNS)
             .args(extra_args)
             .arg(schema_path)
             .arg(dir.path())
@@ -43,3 +53,32 @@ fn check(schema: &str) {
 
 include!("utils/test_schemas.inc.rs");
 include!("utils/rust_schemas.inc.rs");
+
+/// Checks that only the default values `#[serde(default = "...")]` refers to get functions.
+#[test]
+fn default_functions() {
+    let schema = indoc! {r#"
+        version v1;
+
+        Settings = struct { retries: int = 3, tags: [string] = [] };
+    "#};
+
+    for (args, expected) in [(&["--serde"][..], true), (&[], false)] {
+        let dir = tempdir().unwrap();
+
+        let schema_path = dir.path().join("schema.vd");
+        fs::write(&schema_path, schema).unwrap();
+
+        Command::new(env!("CARGO_BIN_EXE_versed"))
+            .arg("rust")
+            .arg("types")
+            .args(args)
+            .arg(schema_path)
+            .arg(dir.path())
+            .run_and_check();
+
+        let types = fs::read_to_string(dir.path().join("v1.rs")).unwrap();
+        assert_eq!(types.contains("fn default_retries()"), expected, "{types}");
+        assert!(!types.contains("fn default_tags()"), "{types}");
+    }
+}
@@ -0,0 +1,147 @@
+use std::{env, fs, process::Command};
+
+use indoc::indoc;
+use tempfile::tempdir;
+
+use utils::CommandExt;
+
+mod utils;
+
+const MANIFEST_CONTENT: &str = indoc! {r#"
+    [package]
+    name = "versed_fixture"
+    version = "0.1.0"
+    edition = "2024"
+
+    [[bin]]
+    name = "versed_fixture"
+    path = "src/mod.rs"
+
+    [dependencies]
+    serde = { version = "1.0.219", features = ["derive"] }
+    serde_json = "1.0.143"
+    serde_with = "3.12.0"
+"#};
+
+const SCHEMA: &str = indoc! {r#"
+    version v1;
+    int_encoding string;
+
+    Account = struct {
+        id: Id,
+        balance: int,
+        history: [int],
+        range: (int, string),
+        status: Status,
+        age: int(0..150) = 18,
+    };
+    Id = int;
+    Status = enum { active, frozen: int };
+"#};
+
+const JSON: &str = indoc! {r#"
+    {
+        "id": "9007199254740993",
+        "balance": "-9223372036854775808",
+        "history": ["1", "9223372036854775807"],
+        "range": ["0", "zero"],
+        "status": { "type": "frozen", "value": "42" },
+        "age": "30"
+    }
+"#};
+
+const MAIN: &str = indoc! {r#"
+    fn main() {
+        let account: Account = serde_json::from_str(JSON).unwrap();
+        assert_eq!(account.id, 9_007_199_254_740_993);
+        assert_eq!(account.balance, i64::MIN);
+        assert_eq!(account.history, [1, i64::MAX]);
+        assert_eq!(account.status, Status::Frozen(42));
+
+        let json = serde_json::to_value(&account).unwrap();
+        assert_eq!(json, serde_json::from_str::<serde_json::Value>(JSON).unwrap());
+
+        let number = JSON.replace("\"30\"", "30");
+        assert!(serde_json::from_str::<Account>(&number).is_err());
+
+        let overflow = JSON.replace("9223372036854775807", "9223372036854775808");
+        assert!(serde_json::from_str::<Account>(&overflow).is_err());
+
+        for sample in serde_json::from_str::<Vec<Account>>(include_str!("samples.json")).unwrap() {
+            assert!((0..=150).contains(&sample.age));
+        }
+    }
+"#};
+
+/// Checks that ints roundtrip as strings, including ones JavaScript can't represent as numbers,
+/// and that `versed sample` generates values in the same encoding.
+#[test]
+fn roundtrip() {
+    let dir = tempdir().unwrap();
+
+    let manifest_path = dir.path().join("Cargo.toml");
+    fs::write(&manifest_path, MANIFEST_CONTENT).unwrap();
+
+    let src_path = dir.path().join("src");
+    fs::create_dir(&src_path).unwrap();
+
+    let schema_path = dir.path().join("schema.vd");
+    fs::write(&schema_path, SCHEMA).unwrap();
+
+    let samples = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["sample", "--count", "20", "--seed", "7"])
+        .arg(&schema_path)
+        .arg("Account")
+        .run_and_check();
+    fs::write(src_path.join("samples.json"), samples).unwrap();
+
+    let mod_content = format!(
+        "#![allow(dead_code)]\n\nuse v1::*;\n\nconst JSON: &str = r#\"{JSON}\"#;\n\n{MAIN}"
+    );
+    fs::write(src_path.join("mod.rs"), mod_content).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["rust", "types", "--serde", "--derive", "PartialEq"])
+        .arg(schema_path)
+        .arg(&src_path)
+        .run_and_check();
+
+    Command::new("cargo")
+        .arg("run")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .run_and_check();
+}
+
+#[test]
+fn generic_type_argument() {
+    let dir = tempdir().unwrap();
+
+    let schema = indoc! {"
+        version v1;
+        int_encoding string;
+
+        Page<T> = struct { items: [T] };
+        Ids = Page<int>;
+    "};
+    let schema_path = dir.path().join("schema.vd");
+    fs::write(&schema_path, schema).unwrap();
+
+    let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["rust", "types", "--serde", "--stdout"])
+        .arg(&schema_path)
+        .output()
+        .unwrap();
+
+    assert_eq!(output.status.code(), Some(2));
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("'Ids'"), "{error}");
+
+    // Without the string encoding, ints need no adapter
+    fs::write(&schema_path, schema.replace("int_encoding string;", "")).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["rust", "types", "--serde", "--stdout"])
+        .arg(&schema_path)
+        .run_and_check();
+}
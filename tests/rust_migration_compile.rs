@@ -1,9 +1,9 @@
 use std::{env, fs, path::Path, process::Command};
 
 use indoc::indoc;
-use tempfile::tempdir;
+use tempfile::{TempDir, tempdir};
 
-use utils::CommandExt;
+use utils::{CommandExt, RUST_EXTERNS};
 
 mod utils;
 
@@ -25,14 +25,17 @@ fn compile_schema(dir: &Path, name: &str, content: &str) {
     Command::new(env!("CARGO_BIN_EXE_versed"))
         .arg("rust")
         .arg("types")
+        .args(RUST_EXTERNS)
         .arg(path)
         .arg(dir)
         .run_and_check();
 }
 
 fn check_with_mod(old: &str, new: &str, r#mod: &str) {
-    let dir = tempdir().unwrap();
+    check_in_dir(&tempdir().unwrap(), old, new, r#mod, &[]);
+}
 
+fn check_in_dir(dir: &TempDir, old: &str, new: &str, r#mod: &str, args: &[&str]) {
     let mod_path = dir.path().join("mod.rs");
     fs::write(&mod_path, r#mod).unwrap();
 
@@ -46,6 +49,8 @@ fn check_with_mod(old: &str, new: &str, r#mod: &str) {
     Command::new(env!("CARGO_BIN_EXE_versed"))
         .arg("rust")
         .arg("migration")
+        .args(RUST_EXTERNS)
+        .args(args)
         .arg(migration_path)
         .arg(dir.path())
         .run_and_check();
@@ -160,6 +165,46 @@ fn struct_add_field() {
     );
 }
 
+#[test]
+fn struct_add_field_with_default() {
+    let dir = tempdir().unwrap();
+
+    check_in_dir(
+        &dir,
+        indoc! {r#"
+            version v1;
+
+            User = #1 struct {
+                name: #2 string,
+            };
+        "#},
+        indoc! {r#"
+            version v2;
+
+            User = #1 struct {
+                name: #2 string,
+                logins: int = 1,
+                roles: [string] = ["user"],
+            };
+        "#},
+        indoc! {r#"
+            use migrations::v2::upgrade;
+
+            fn main() {
+                let user = upgrade::upgrade_user(v1::User { name: String::new() });
+                assert_eq!(user.logins, 1);
+                assert_eq!(user.roles, ["user"]);
+            }
+        "#},
+        &[],
+    );
+
+    let migration = fs::read_to_string(dir.path().join("migrations").join("v2.rs")).unwrap();
+    assert!(!migration.contains("todo!()"));
+
+    Command::new(dir.path().join("mod")).run_and_check();
+}
+
 #[test]
 fn struct_remove_field() {
     check(
@@ -405,6 +450,285 @@ fn change_boxedness_alias() {
     );
 }
 
+#[test]
+fn variant_gained_fields() {
+    let dir = tempdir().unwrap();
+
+    check_in_dir(
+        &dir,
+        indoc! {r#"
+            version v1;
+
+            Event = #1 enum { created: #2 unit, deleted: #3 unit };
+        "#},
+        indoc! {r#"
+            version v2;
+
+            Event = #1 enum {
+                created: #2 struct { by: string = "system", tags: [string] = [] },
+                deleted: #3 struct { reason: string },
+            };
+        "#},
+        MOD_CONTENT,
+        &[],
+    );
+
+    let migration = fs::read_to_string(dir.path().join("migrations").join("v2.rs")).unwrap();
+    let (upgrade, downgrade) = migration.split_once("pub mod downgrade").unwrap();
+    // Only payloads whose new fields all have a default value are filled in
+    assert_eq!(upgrade.matches("todo!()").count(), 1);
+    assert_eq!(downgrade.matches("todo!()").count(), 1);
+    assert!(upgrade.contains("// Auto-filled: unit to a struct of default values"));
+    assert!(downgrade.contains("// Auto-filled: struct of default values to unit"));
+}
+
+#[test]
+fn auto_filled() {
+    let dir = tempdir().unwrap();
+
+    check_in_dir(
+        &dir,
+        indoc! {r#"
+            version v1;
+
+            Point = #1 struct { x: #2 int };
+            User = #3 struct {
+                age: #4 int,
+                point: #5 Point,
+                nick: #6 string,
+                kind: #7 enum { admin: #8 unit, guest: #9 struct { name: #10 string } },
+            };
+        "#},
+        indoc! {r#"
+            version v2;
+
+            Point = #1 struct { x: #2 int };
+            User = #3 struct {
+                age: #4 string,
+                point: #5 [Point],
+                nick: #6 struct { value: string },
+                kind: #7 enum {
+                    admin: #8 struct { roles: [string] },
+                    guest: #9 struct { name: #10 string, tags: [string] },
+                },
+                history: struct { events: [int] },
+            };
+        "#},
+        MOD_CONTENT,
+        &[],
+    );
+
+    let migration = fs::read_to_string(dir.path().join("migrations").join("v2.rs")).unwrap();
+    let (upgrade, downgrade) = migration.split_once("pub mod downgrade").unwrap();
+    // Values that would have to be made up, like new fields without a default, are left to the user
+    assert_eq!(upgrade.matches("todo!()").count(), 3);
+    assert_eq!(downgrade.matches("todo!()").count(), 3);
+    assert!(migration.contains("// Auto-filled: int to string"));
+    assert!(migration.contains("// Auto-filled: value to a list with one element"));
+    assert!(migration.contains("// Auto-filled: value to a struct with one field"));
+    assert!(migration.contains("// Auto-filled: struct with one field to its field"));
+}
+
+#[test]
+fn generic_type() {
+    let dir = tempdir().unwrap();
+
+    check_in_dir(
+        &dir,
+        indoc! {r#"
+            version v1;
+
+            Page<T> = #1 struct { items: #2 [#3 T], cursor: #4 string };
+            User = #5 struct { name: #6 string };
+            Users = #7 Page<#8 User>;
+        "#},
+        indoc! {r#"
+            version v2;
+
+            Page<T> = #1 struct { items: #2 [#3 T], cursor: #4 string, total: int = 0 };
+            User = #5 struct { name: #6 string, age: int = 1 };
+            Users = #7 Page<#8 User>;
+        "#},
+        indoc! {r#"
+            use migrations::v2::upgrade;
+
+            fn main() {
+                let user = v1::User { name: String::new() };
+                let users = v1::Page { items: vec![user], cursor: String::new() };
+                let users = upgrade::upgrade_users(users);
+                assert_eq!(users.total, 0);
+                assert_eq!(users.items[0].age, 1);
+            }
+        "#},
+        &[],
+    );
+
+    let migration = fs::read_to_string(dir.path().join("migrations").join("v2.rs")).unwrap();
+    assert!(!migration.contains("todo!()"));
+
+    Command::new(dir.path().join("mod")).run_and_check();
+}
+
+#[test]
+fn tuple() {
+    let dir = tempdir().unwrap();
+
+    check_in_dir(
+        &dir,
+        indoc! {r#"
+            version v1;
+
+            Entry = #1 struct { pair: #2 (#3 int, #4 string) };
+        "#},
+        indoc! {r#"
+            version v2;
+
+            Entry = #1 struct { pair: #2 (#4 string, #3 int) };
+        "#},
+        indoc! {r#"
+            use migrations::v2::{downgrade, upgrade};
+
+            fn main() {
+                let entry = v1::Entry { pair: (1, "one".to_owned()) };
+                let entry = upgrade::upgrade_entry(entry);
+                assert_eq!(entry.pair, ("one".to_owned(), 1));
+
+                let entry = downgrade::downgrade_entry(entry);
+                assert_eq!(entry.pair, (1, "one".to_owned()));
+            }
+        "#},
+        &[],
+    );
+
+    let migration = fs::read_to_string(dir.path().join("migrations").join("v2.rs")).unwrap();
+    assert!(!migration.contains("todo!()"));
+
+    Command::new(dir.path().join("mod")).run_and_check();
+}
+
+#[test]
+fn fallible_downgrades() {
+    let dir = tempdir().unwrap();
+
+    check_in_dir(
+        &dir,
+        indoc! {r#"
+            version v1;
+
+            Kind = #1 enum { admin: #2 unit };
+            User = #3 struct { kind: #4 Kind, kinds: #5 [#6 Kind] };
+        "#},
+        indoc! {r#"
+            version v2;
+
+            Kind = #1 enum { admin: #2 unit, banned: string };
+            User = #3 struct { kind: #4 Kind, kinds: #5 [#6 Kind] };
+        "#},
+        indoc! {r#"
+            use migrations::v2::{DowngradeError, downgrade};
+
+            fn main() {
+                let user = v2::User { kind: v2::Kind::Admin(()), kinds: vec![v2::Kind::Admin(())] };
+                assert!(downgrade::downgrade_user(user).is_ok());
+
+                let banned = v2::Kind::Banned(String::new());
+                let user = v2::User { kind: v2::Kind::Admin(()), kinds: vec![banned] };
+                let error = DowngradeError { type_name: "Kind", variant: "Banned" };
+                assert_eq!(downgrade::downgrade_user(user).unwrap_err(), error);
+            }
+        "#},
+        &["--fallible-downgrades"],
+    );
+
+    Command::new(dir.path().join("mod")).run_and_check();
+}
+
+#[test]
+fn open_enum() {
+    let dir = tempdir().unwrap();
+
+    check_in_dir(
+        &dir,
+        indoc! {r#"
+            version v1;
+
+            Status = #1 open enum { active: #2 unit };
+            Level = #3 open enum { low: #4 unit };
+        "#},
+        indoc! {r#"
+            version v2;
+
+            Status = #1 open enum { active: #2 unit, banned: string };
+            Level = #3 enum { low: #4 unit };
+        "#},
+        indoc! {r#"
+            use migrations::v2::{DowngradeError, downgrade, upgrade};
+
+            fn main() {
+                let status = upgrade::upgrade_status(v1::Status::Unknown(()));
+                assert!(matches!(status, v2::Status::Unknown(())));
+
+                let status = downgrade::downgrade_status(v2::Status::Unknown(())).unwrap();
+                assert!(matches!(status, v1::Status::Unknown(())));
+
+                let banned = v2::Status::Banned(String::new());
+                let error = DowngradeError { type_name: "Status", variant: "Banned" };
+                assert_eq!(downgrade::downgrade_status(banned).unwrap_err(), error);
+
+                let level = downgrade::downgrade_level(v2::Level::Low(())).unwrap();
+                assert!(matches!(level, v1::Level::Low(())));
+            }
+        "#},
+        &["--fallible-downgrades"],
+    );
+
+    let migration = fs::read_to_string(dir.path().join("migrations").join("v2.rs")).unwrap();
+    assert!(migration.contains("v1::Level::Unknown(_) => todo!()"));
+
+    Command::new(dir.path().join("mod")).run_and_check();
+}
+
+#[test]
+fn extern_type() {
+    let dir = tempdir().unwrap();
+
+    check_in_dir(
+        &dir,
+        indoc! {r#"
+            version v1;
+
+            extern #1 Timestamp;
+            extern #2 Blob;
+            Event = #3 struct { at: #4 Timestamp, payload: #5 Blob };
+        "#},
+        indoc! {r#"
+            version v2;
+
+            extern #1 Clock;
+            extern #2 Timestamp;
+            Event = #3 struct { at: #4 Clock, payload: #5 Timestamp };
+        "#},
+        indoc! {r#"
+            #![allow(unused_imports)]
+
+            use std::time::SystemTime;
+
+            use migrations::v2::{downgrade, upgrade};
+
+            fn main() {
+                let clock = upgrade::upgrade_clock(SystemTime::UNIX_EPOCH);
+                assert_eq!(clock, SystemTime::UNIX_EPOCH);
+            }
+        "#},
+        &[],
+    );
+
+    let migration = fs::read_to_string(dir.path().join("migrations").join("v2.rs")).unwrap();
+    assert!(migration.contains("todo!()"));
+
+    Command::new(dir.path().join("mod")).run_and_check();
+}
+
 #[test]
 fn version_named_like_upgrade() {
     check_with_mod(
@@ -456,3 +780,81 @@ fn type_named_like_version() {
         "},
     );
 }
+
+#[test]
+fn chain() {
+    let dir = tempdir().unwrap();
+
+    let mod_path = dir.path().join("mod.rs");
+    fs::write(
+        &mod_path,
+        indoc! {r#"
+            #![allow(unused_imports)]
+
+            use migrations::{Version, person, tag};
+
+            fn main() {
+                let user = person::AnyVersion::V1(v1::User { name: String::new(), age: 1 });
+                let _: v3::Person = user.clone().upgrade_to_latest();
+                let _: person::AnyVersion = user.downgrade_to(Version::V1);
+                let _: Version = tag::AnyVersion::V3(String::new()).version();
+            }
+        "#},
+    )
+    .unwrap();
+
+    let versions = [
+        indoc! {r#"
+            version v1;
+
+            User = #1 struct { name: #2 string, age: #3 int };
+            Tag = #4 string;
+            Removed = #5 int;
+        "#},
+        indoc! {r#"
+            version v2;
+
+            Person = #1 struct { name: #2 string, age: #3 int, email: #4 string };
+            Tag = #5 string;
+        "#},
+        indoc! {r#"
+            version v3;
+
+            Person = #1 struct { name: #2 string, email: #4 string };
+            Tag = #5 string;
+        "#},
+    ];
+
+    let mut migrations = vec![];
+
+    for (index, version) in versions.iter().enumerate() {
+        compile_schema(dir.path(), &format!("schema{index}"), version);
+
+        if let Some(previous) = index.checked_sub(1).map(|i| versions[i]) {
+            let migration_path = dir.path().join(format!("schema{index}.vsm"));
+            fs::write(&migration_path, format!("{previous}\n{version}")).unwrap();
+
+            Command::new(env!("CARGO_BIN_EXE_versed"))
+                .arg("rust")
+                .arg("migration")
+                .arg(&migration_path)
+                .arg(dir.path())
+                .run_and_check();
+
+            migrations.push(migration_path);
+        }
+    }
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .arg("rust")
+        .arg("chain")
+        .arg(dir.path())
+        .args(&migrations)
+        .run_and_check();
+
+    Command::new("rustc")
+        .arg(mod_path)
+        .arg("--out-dir")
+        .arg(dir.path())
+        .run_and_check();
+}
@@ -0,0 +1,207 @@
+use std::{env, fs, path::Path, process::Command};
+
+use indoc::indoc;
+use tempfile::tempdir;
+
+use utils::CommandExt;
+
+mod utils;
+
+const MANIFEST_CONTENT: &str = indoc! {r#"
+    [package]
+    name = "versed_fixture"
+    version = "0.1.0"
+    edition = "2024"
+
+    [[bin]]
+    name = "versed_fixture"
+    path = "src/mod.rs"
+
+    [dev-dependencies]
+    proptest = "1"
+"#};
+
+const MOD_CONTENT: &str = indoc! {"
+    #![allow(dead_code)]
+
+    fn main() {}
+"};
+
+fn run_versed(args: &[&str], paths: &[&Path]) {
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(args)
+        .args(paths)
+        .run_and_check();
+}
+
+/// Generates the property tests for a migration, runs them and returns the generated migration.
+fn check(old: &str, new: &str, args: &[&str]) -> String {
+    check_with(MANIFEST_CONTENT, old, new, &[], args)
+}
+
+/// Like [`check`], with another manifest and arguments for generating the types.
+fn check_with(manifest: &str, old: &str, new: &str, types_args: &[&str], args: &[&str]) -> String {
+    let dir = tempdir().unwrap();
+
+    let manifest_path = dir.path().join("Cargo.toml");
+    fs::write(&manifest_path, manifest).unwrap();
+
+    let src_path = dir.path().join("src");
+    fs::create_dir(&src_path).unwrap();
+    fs::write(src_path.join("mod.rs"), MOD_CONTENT).unwrap();
+
+    for (name, schema) in [("old", old), ("new", new)] {
+        let schema_path = dir.path().join(format!("{name}.vs"));
+        fs::write(&schema_path, schema).unwrap();
+
+        let mut command = vec!["rust", "types", "--derive", "PartialEq"];
+        command.extend(types_args);
+        run_versed(&command, &[&schema_path, &src_path]);
+    }
+
+    let migration_path = dir.path().join("schema.vsm");
+    fs::write(&migration_path, format!("{old}\n{new}")).unwrap();
+
+    let mut migration_args = vec!["rust", "migration", "--property-tests"];
+    migration_args.extend(args);
+    run_versed(&migration_args, &[&migration_path, &src_path]);
+
+    Command::new("cargo")
+        .arg("test")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .run_and_check();
+
+    fs::read_to_string(src_path.join("migrations").join("v2.rs")).unwrap()
+}
+
+#[test]
+fn lossless() {
+    let migration = check(
+        indoc! {"
+            version v1;
+
+            User = #1 struct { name: #2 string, age: #3 int, tags: #4 [#12 Tag] };
+            Tag = #5 enum { a: #6 unit, b: #7 int };
+            Tree = #8 enum { leaf: #9 Id, node: #10 [#13 Tree] };
+            Id = #11 int;
+        "},
+        indoc! {"
+            version v2;
+
+            User = #1 struct {
+                full_name: #2 string,
+                age: #3 int,
+                tags: #4 [#12 Tag],
+                friends: [string] = [],
+                email: string = \"\",
+            };
+            Tag = #5 enum { a: #6 unit, b: #7 int, c: string };
+            Tree = #8 enum { leaf: #9 Id, node: #10 [#13 Tree] };
+            Id = #11 struct { value: int };
+        "},
+        &[],
+    );
+
+    assert!(migration.contains("fn round_trip_user("));
+    assert!(migration.contains("fn round_trip_tag("));
+    assert!(migration.contains("fn round_trip_tree("));
+    assert!(migration.contains("fn round_trip_id("));
+}
+
+#[test]
+fn lossy() {
+    let migration = check(
+        indoc! {"
+            version v1;
+
+            User = #1 struct { name: #2 string, age: #3 int };
+            Tag = #4 enum { a: #5 unit, b: #6 int };
+            Count = #7 int;
+        "},
+        indoc! {"
+            version v2;
+
+            User = #1 struct { name: #2 string, age: #3 int, id: int };
+            Tag = #4 enum { a: #5 unit };
+            Count = #7 string;
+        "},
+        &["--fallible-downgrades"],
+    );
+
+    assert!(
+        migration
+            .contains("// User can't round-trip, because the new field 'id' has no default value.")
+    );
+    assert!(migration.contains(
+        "// Tag can't round-trip, because the variant 'b' doesn't exist in the new version."
+    ));
+    assert!(migration.contains("// Count can't round-trip, because an int can't be converted"));
+}
+
+#[test]
+fn open_enum() {
+    let migration = check(
+        indoc! {"
+            version v1;
+
+            Status = #1 open enum { active: #2 unit };
+        "},
+        indoc! {"
+            version v2;
+
+            Status = #1 open enum { active: #2 unit, banned: string };
+        "},
+        &["--fallible-downgrades"],
+    );
+
+    assert!(migration.contains("fn round_trip_status("));
+}
+
+#[test]
+fn built_in_types() {
+    let manifest = format!(
+        "{MANIFEST_CONTENT}\n{}",
+        indoc! {r#"
+            [dependencies]
+            chrono = "0.4.38"
+            time = "0.3.37"
+            uuid = "1.16.0"
+        "#}
+    );
+    let old = indoc! {"
+        version v1;
+
+        Event = #1 struct {
+            id: #2 uuid,
+            at: #3 timestamp,
+            day: #4 date,
+            length: #5 duration,
+            payload: #6 bytes,
+        };
+    "};
+    let new = indoc! {"
+        version v2;
+
+        Event = #1 struct {
+            id: #2 uuid,
+            at: #3 timestamp,
+            day: #4 date,
+            length: #5 duration,
+            payload: #6 bytes,
+            tags: [string] = [],
+        };
+    "};
+
+    for time_crate in ["chrono", "time"] {
+        let migration = check_with(
+            &manifest,
+            old,
+            new,
+            &["--time-crate", time_crate],
+            &["--time-crate", time_crate],
+        );
+
+        assert!(migration.contains("fn round_trip_event("));
+    }
+}
@@ -3,7 +3,7 @@ use std::{env, fs, process::Command};
 use indoc::indoc;
 use tempfile::tempdir;
 
-use utils::CommandExt;
+use utils::{CommandExt, RUST_EXTERNS};
 
 mod utils;
 
@@ -25,6 +25,7 @@ const MANIFEST_CONTENT: &str = indoc! {r#"
 
     [dependencies]
     serde = { version = "1.0.219", features = ["derive"] }
+    serde_json = "1.0.143"
 "#};
 
 fn check(schema: &str) {
@@ -46,6 +47,7 @@ fn check(schema: &str) {
         .arg("rust")
         .arg("types")
         .arg("--serde")
+        .args(RUST_EXTERNS)
         .arg(schema_path)
         .arg(src_path)
         .run_and_check();
@@ -0,0 +1,161 @@
+use std::{env, fs, path::Path, process::Command};
+
+use indoc::indoc;
+use tempfile::tempdir;
+
+use utils::CommandExt;
+
+mod utils;
+
+const MANIFEST_CONTENT: &str = indoc! {r#"
+    [package]
+    name = "versed_fixture"
+    version = "0.1.0"
+    edition = "2024"
+
+    [[bin]]
+    name = "versed_fixture"
+    path = "src/mod.rs"
+
+    [dependencies]
+    serde = { version = "1.0.219", features = ["derive"] }
+    serde_json = "1.0.143"
+"#};
+
+fn run_versed(args: &[&str], paths: &[&Path]) {
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(args)
+        .args(paths)
+        .run_and_check();
+}
+
+fn check(versions: &[&str], r#type: &str, main: &str) {
+    let dir = tempdir().unwrap();
+
+    let manifest_path = dir.path().join("Cargo.toml");
+    fs::write(&manifest_path, MANIFEST_CONTENT).unwrap();
+
+    let src_path = dir.path().join("src");
+    fs::create_dir(&src_path).unwrap();
+    fs::write(src_path.join("mod.rs"), main).unwrap();
+
+    let mut migrations = vec![];
+
+    for (index, version) in versions.iter().enumerate() {
+        let schema_path = dir.path().join(format!("schema{index}.vs"));
+        fs::write(&schema_path, version).unwrap();
+
+        run_versed(
+            &["rust", "types", "--serde", "--envelope", r#type],
+            &[&schema_path, &src_path],
+        );
+
+        if let Some(previous) = index.checked_sub(1).map(|i| versions[i]) {
+            let migration_path = dir.path().join(format!("schema{index}.vsm"));
+            fs::write(&migration_path, format!("{previous}\n{version}")).unwrap();
+
+            run_versed(&["rust", "migration"], &[&migration_path, &src_path]);
+            migrations.push(migration_path);
+        }
+    }
+
+    let mut paths: Vec<&Path> = vec![&src_path];
+    paths.extend(migrations.iter().map(|path| path.as_path()));
+    run_versed(&["rust", "chain", "--serde"], &paths);
+
+    Command::new("cargo")
+        .arg("run")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .run_and_check();
+}
+
+#[test]
+fn upgrade_on_read() {
+    check(
+        &[
+            indoc! {r#"
+                version v1;
+
+                User = #1 struct { name: #2 string, age: #3 int };
+            "#},
+            indoc! {r#"
+                version v2;
+
+                User = #1 struct { name: #2 string, age: #3 int };
+            "#},
+            indoc! {r#"
+                version v3;
+
+                User = #1 struct { full_name: #2 string, age: #3 int };
+            "#},
+        ],
+        "User",
+        indoc! {r#"
+            #![allow(dead_code)]
+
+            use serde::Deserialize;
+
+            fn main() {
+                let user = v1::UserEnvelope::V1(v1::User { name: "Alice".to_owned(), age: 42 });
+                let json = serde_json::to_value(&user).unwrap();
+                assert_eq!(
+                    json,
+                    serde_json::json!({ "version": "v1", "data": { "name": "Alice", "age": 42 } })
+                );
+
+                let user = migrations::user::deserialize_any_version(json).unwrap();
+                assert_eq!(user.full_name, "Alice");
+                assert_eq!(user.age, 42);
+
+                let json = serde_json::json!({ "version": "v3", "data": { "fullName": "Bob", "age": 1 } });
+                let user = migrations::user::AnyVersion::deserialize(json).unwrap();
+                assert_eq!(user.version(), migrations::Version::V3);
+
+                let json = serde_json::json!({ "version": "v4", "data": {} });
+                assert!(migrations::user::deserialize_any_version(json).is_err());
+            }
+        "#},
+    );
+}
+
+#[test]
+fn unknown_variant() {
+    check(
+        &[
+            indoc! {r#"
+                version v1;
+
+                Status = #1 open enum { active: #2 unit };
+            "#},
+            indoc! {r#"
+                version v2;
+
+                Status = #1 open enum { active: #2 unit, banned: string };
+            "#},
+        ],
+        "Status",
+        indoc! {r#"
+            #![allow(dead_code)]
+
+            fn main() {
+                let json = serde_json::to_value(v2::Status::Banned("spam".to_owned())).unwrap();
+
+                let status: v1::Status = serde_json::from_value(json.clone()).unwrap();
+                assert!(matches!(status, v1::Status::Unknown(_)));
+                assert_eq!(serde_json::to_value(&status).unwrap(), json);
+
+                let status = migrations::v2::upgrade::upgrade_status(status);
+                assert_eq!(serde_json::to_value(&status).unwrap(), json);
+
+                let json = serde_json::json!({ "type": "active", "value": null });
+                let status: v1::Status = serde_json::from_value(json).unwrap();
+                assert!(matches!(status, v1::Status::Active(())));
+
+                // Invalid values of known variants aren't mistaken for unknown variants
+                let json = serde_json::json!({ "type": "banned", "value": 3 });
+                assert!(serde_json::from_value::<v2::Status>(json).is_err());
+            }
+        "#},
+    );
+}
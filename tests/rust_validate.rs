@@ -0,0 +1,143 @@
+use std::{env, fs, process::Command};
+
+use indoc::indoc;
+use tempfile::tempdir;
+
+use utils::CommandExt;
+
+mod utils;
+
+const MANIFEST_CONTENT: &str = indoc! {r#"
+    [package]
+    name = "versed_fixture"
+    version = "0.1.0"
+    edition = "2024"
+
+    [[bin]]
+    name = "versed_fixture"
+    path = "src/mod.rs"
+
+    [dependencies]
+    regex = "1.11.1"
+    serde = { version = "1.0.219", features = ["derive"] }
+    serde_json = "1.0.143"
+"#};
+
+const SCHEMA: &str = indoc! {r#"
+    version v1;
+
+    User = struct {
+        name: string(min = 1, max = 8, pattern = "^[a-z]+$"),
+        age: int(0..150),
+        "home page": Page<Tag>,
+        ids: Page<int>,
+        role: Role,
+        position: (int(-90..90), int(-180..180)),
+        ..Timestamps,
+    };
+    Tag = string(max = 4);
+    Role = open enum { admin: int(..10), guest: unit };
+    Page<T> = struct { items: [T], total: int(0..) };
+    Timestamps = struct { created: int(0..) };
+    Tree = struct { depth: int(..2), children: [Tree] };
+"#};
+
+/// Generates the types with validation functions and runs a main function using them.
+fn check(args: &[&str], main: &str) {
+    let dir = tempdir().unwrap();
+
+    let manifest_path = dir.path().join("Cargo.toml");
+    fs::write(&manifest_path, MANIFEST_CONTENT).unwrap();
+
+    let src_path = dir.path().join("src");
+    fs::create_dir(&src_path).unwrap();
+
+    let schema_path = dir.path().join("schema.vd");
+    fs::write(&schema_path, SCHEMA).unwrap();
+
+    let mod_content = format!("#![allow(dead_code)]\n\nuse v1::*;\n\n{main}");
+    fs::write(src_path.join("mod.rs"), mod_content).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["rust", "types", "--validate"])
+        .args(args)
+        .arg(schema_path)
+        .arg(&src_path)
+        .run_and_check();
+
+    Command::new("cargo")
+        .arg("run")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .run_and_check();
+}
+
+const MAIN: &str = indoc! {r#"
+    fn user() -> User {
+        User {
+            name: "ada".to_owned(),
+            age: 36,
+            home_page: Page { items: vec!["a".to_owned()], total: 1 },
+            ids: Page { items: vec![-1], total: 1 },
+            role: Role::Admin(3),
+            position: (0, 0),
+            timestamps: Timestamps { created: 0 },
+        }
+    }
+
+    fn error(user: &User) -> String {
+        user.validate().unwrap_err().to_string()
+    }
+
+    fn main() {
+        assert_eq!(user().validate(), Ok(()));
+        assert_eq!(validate_tag(&"tag".to_owned()), Ok(()));
+
+        let mut user = user();
+        user.name = String::new();
+        assert_eq!(error(&user), "$.name: expected a string of length between 1 and 8");
+        user.name = "ÄDA".to_owned();
+        assert_eq!(error(&user), "$.name: expected a string matching the pattern \"^[a-z]+$\"");
+
+        let mut user = self::user();
+        user.home_page.items.push("tooLong".to_owned());
+        assert_eq!(error(&user), "$.homePage.items[1]: expected a string of length at most 4");
+
+        let mut user = self::user();
+        user.position.1 = 200;
+        assert_eq!(error(&user), "$.position[1]: expected an int between -180 and 180");
+
+        let mut user = self::user();
+        user.timestamps.created = -1;
+        assert_eq!(error(&user), "$.created: expected an int of at least 0");
+
+        let mut user = self::user();
+        user.role = Role::Admin(11);
+        assert_eq!(error(&user), ROLE_ERROR);
+
+        let tree = Tree {
+            depth: 0,
+            children: vec![Tree { depth: 3, children: vec![] }],
+        };
+        assert_eq!(
+            tree.validate().unwrap_err().to_string(),
+            "$.children[0].depth: expected an int of at most 2"
+        );
+    }
+"#};
+
+#[test]
+fn adjacent_tag() {
+    let main = format!(
+        "{MAIN}\nconst ROLE_ERROR: &str = \"$.role.value: expected an int of at most 10\";\n"
+    );
+    check(&["--serde"], &main);
+}
+
+#[test]
+fn external_tag() {
+    let main = format!(
+        "{MAIN}\nconst ROLE_ERROR: &str = \"$.role.admin: expected an int of at most 10\";\n"
+    );
+    check(&["--serde", "--serde-external-tag"], &main);
+}
@@ -0,0 +1,309 @@
+use std::{
+    env, fs,
+    process::{Command, Output},
+};
+
+use indoc::indoc;
+use tempfile::{NamedTempFile, tempdir};
+
+use utils::CommandExt;
+
+mod utils;
+
+const MANIFEST_CONTENT: &str = indoc! {r#"
+    [package]
+    name = "versed_fixture"
+    version = "0.1.0"
+    edition = "2024"
+
+    [[bin]]
+    name = "versed_fixture"
+    path = "src/mod.rs"
+
+    [dependencies]
+    regex = "1.11.1"
+    serde = { version = "1.0.219", features = ["derive"] }
+    serde_json = "1.0.143"
+"#};
+
+const SCHEMA: &str = indoc! {"
+    version v1;
+
+    User = struct {
+        full_name: string,
+        age: int,
+        tags: [[Tag]],
+        best_friend: Friend,
+        id: Id,
+        nothing: unit,
+        empty: struct {},
+    };
+    Tag = enum { first_tag, second: int, third: struct { value: string } };
+    Friend = enum { none, some: User };
+    Id = int;
+    Tree = enum { leaf, node: struct { left: Tree, right: Tree } };
+"};
+
+fn run_sample(schema: &str, args: &[&str]) -> Output {
+    let file = NamedTempFile::new().unwrap();
+    fs::write(file.path(), schema).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .arg("sample")
+        .arg(file.path())
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn sample(schema: &str, args: &[&str]) -> String {
+    let output = run_sample(schema, args);
+    assert!(output.status.success());
+    String::from_utf8(output.stdout).unwrap()
+}
+
+const REFINED_SCHEMA: &str = indoc! {r#"
+    version v1;
+
+    Account = struct {
+        name: string(min = 3, max = 12),
+        email: string(max = 40, pattern = "^[a-z]{2,8}@[a-z]+\\.(com|org)$"),
+        age: int(18..150),
+        balance: int(..-5000),
+        scores: [int(0..10)],
+        code: string(pattern = "[0-9a-f]+"),
+    };
+"#};
+
+/// Checks that the generated values can be deserialized into the types generated with serde.
+///
+/// With `--validate`, it also checks that the values satisfy the refinements.
+fn check_serde(types_args: &[&str], sample_args: &[&str]) {
+    check_serde_schema(SCHEMA, &["User", "Tag", "Tree"], types_args, sample_args);
+}
+
+fn check_serde_schema(schema: &str, names: &[&str], types_args: &[&str], sample_args: &[&str]) {
+    let dir = tempdir().unwrap();
+
+    let manifest_path = dir.path().join("Cargo.toml");
+    fs::write(&manifest_path, MANIFEST_CONTENT).unwrap();
+
+    let src_path = dir.path().join("src");
+    fs::create_dir(&src_path).unwrap();
+
+    let schema_path = dir.path().join("schema.vs");
+    fs::write(&schema_path, schema).unwrap();
+
+    let mut main = String::from("fn main() {\n");
+
+    for &name in names {
+        let output = Command::new(env!("CARGO_BIN_EXE_versed"))
+            .args(["sample", "--count", "20", "--seed", "7"])
+            .args(sample_args)
+            .arg(&schema_path)
+            .arg(name)
+            .run_and_check();
+
+        fs::write(src_path.join(format!("{name}.json")), output).unwrap();
+        main.push_str(&format!(
+            "    let values: Vec<v1::{name}> = serde_json::from_str(include_str!(\"{name}.json\")).unwrap();\n    assert_eq!(values.len(), 20);\n"
+        ));
+
+        if types_args.contains(&"--validate") {
+            main.push_str(
+                "    for value in &values {\n        value.validate().unwrap();\n    }\n",
+            );
+        }
+    }
+
+    main.push_str("}\n");
+    fs::write(src_path.join("mod.rs"), main).unwrap();
+
+    Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(["rust", "types", "--serde"])
+        .args(types_args)
+        .arg(&schema_path)
+        .arg(&src_path)
+        .run_and_check();
+
+    Command::new("cargo")
+        .arg("run")
+        .arg("--manifest-path")
+        .arg(&manifest_path)
+        .run_and_check();
+}
+
+#[test]
+fn serde_adjacent_tag() {
+    check_serde(&[], &[]);
+}
+
+#[test]
+fn serde_external_tag() {
+    check_serde(&["--serde-external-tag"], &["--external-tag"]);
+}
+
+#[test]
+fn refinements() {
+    check_serde_schema(REFINED_SCHEMA, &["Account"], &["--validate"], &[]);
+}
+
+#[test]
+fn seed_is_deterministic() {
+    let first = sample(SCHEMA, &["User", "--seed", "42"]);
+    let second = sample(SCHEMA, &["User", "--seed", "42"]);
+    let other = sample(SCHEMA, &["User", "--seed", "43"]);
+
+    assert_eq!(first, second);
+    assert_ne!(first, other);
+}
+
+#[test]
+fn representation() {
+    let output = sample(
+        indoc! {"
+            version v1;
+
+            Value = enum { some_value: struct { field_name: unit } };
+        "},
+        &["Value", "--count", "1"],
+    );
+
+    assert_eq!(
+        output,
+        indoc! {r#"
+            [
+              {
+                "type": "some-value",
+                "value": {
+                  "fieldName": null
+                }
+              }
+            ]
+        "#}
+    );
+}
+
+#[test]
+fn built_in_types() {
+    let output = sample(
+        indoc! {"
+            version v1;
+
+            Record = struct { id: uuid, at: timestamp, day: date, length: duration, data: bytes };
+        "},
+        &["Record", "--seed", "1"],
+    );
+
+    let value = |key: &str| {
+        let line = output
+            .lines()
+            .find(|line| line.trim_start().starts_with(&format!("\"{key}\"")))
+            .unwrap();
+        line.split('"').nth(3).unwrap().to_owned()
+    };
+
+    let id = value("id");
+    let groups: Vec<_> = id.split('-').map(str::len).collect();
+    assert_eq!(groups, [8, 4, 4, 4, 12], "{id}");
+
+    let at = value("at");
+    assert_eq!(at.len(), 20, "{at}");
+    assert_eq!(&at[10..11], "T", "{at}");
+    assert!(at.ends_with('Z'), "{at}");
+
+    let day = value("day");
+    assert_eq!(day.len(), 10, "{day}");
+    assert!(day.starts_with("19") || day.starts_with("20"), "{day}");
+
+    let length = value("length");
+    assert!(
+        length.starts_with("PT") && length.ends_with('S'),
+        "{length}"
+    );
+
+    let data = value("data");
+    assert_eq!(data.len() % 4, 0, "{data}");
+}
+
+#[test]
+fn int_encoding() {
+    let output = sample(
+        indoc! {"
+            version v1;
+            int_encoding string;
+
+            Account = struct { id: int, age: int(18..150), name: string };
+        "},
+        &["Account", "--seed", "1"],
+    );
+
+    let value = |key: &str| {
+        let line = output
+            .lines()
+            .find(|line| line.trim_start().starts_with(&format!("\"{key}\"")))
+            .unwrap();
+        line.split('"').nth(3).unwrap().to_owned()
+    };
+
+    value("id").parse::<i64>().unwrap();
+    let age: i64 = value("age").parse().unwrap();
+    assert!((18..=150).contains(&age), "{age}");
+}
+
+#[test]
+fn infinite_depth() {
+    let output = run_sample(
+        indoc! {"
+            version v1;
+
+            Loop = struct { next: Loop };
+        "},
+        &["Loop"],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+}
+
+#[test]
+fn extern_value() {
+    let output = run_sample(
+        indoc! {"
+            version v1;
+
+            extern Timestamp;
+            Event = struct { at: Timestamp };
+        "},
+        &["Event"],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("extern type"), "{error}");
+}
+
+#[test]
+fn infinite_depth_with_unrelated_extern() {
+    let output = run_sample(
+        indoc! {"
+            version v1;
+
+            extern Timestamp;
+            Event = struct { at: Timestamp };
+            Loop = struct { next: Loop };
+        "},
+        &["Loop"],
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+    let error = String::from_utf8(output.stderr).unwrap();
+    assert!(error.contains("has no values of finite depth"), "{error}");
+    assert!(!error.contains("extern type"), "{error}");
+}
+
+#[test]
+fn unknown_type() {
+    let output = run_sample(SCHEMA, &["Nope"]);
+
+    assert_eq!(output.status.code(), Some(2));
+}
@@ -0,0 +1,115 @@
+use std::{
+    env, fs,
+    io::Write,
+    path::Path,
+    process::{Command, Output, Stdio},
+};
+
+use indoc::indoc;
+use tempfile::tempdir;
+
+const SCHEMA: &str = "version v1; User = struct { name: string };";
+
+/// Runs `versed` in a directory, passing `input` to its standard input.
+fn run(args: &[&str], input: &str, dir: &Path) -> Output {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(args)
+        .current_dir(dir)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+
+    child
+        .stdin
+        .take()
+        .unwrap()
+        .write_all(input.as_bytes())
+        .unwrap();
+
+    child.wait_with_output().unwrap()
+}
+
+/// Runs `versed`, checking that it succeeds and leaves the directory empty.
+fn run_to_stdout(args: &[&str], input: &str) -> String {
+    let dir = tempdir().unwrap();
+    let output = run(args, input, dir.path());
+
+    assert!(
+        output.status.success(),
+        "{}",
+        String::from_utf8_lossy(&output.stderr)
+    );
+    assert_eq!(dir.path().read_dir().unwrap().count(), 0);
+
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn rust_types() {
+    let output = run_to_stdout(&["rust", "types", "-", "--stdout"], SCHEMA);
+
+    assert!(output.contains("pub struct User {\n    pub name: String,\n}\n"));
+}
+
+#[test]
+fn typescript_types() {
+    let output = run_to_stdout(&["typescript", "types", "-", "--stdout"], SCHEMA);
+
+    assert!(output.contains("name: string"));
+}
+
+#[test]
+fn rust_migration() {
+    let migration = indoc! {"
+        version v1;
+        User = #1 struct { name: #2 string };
+        version v2;
+        User = #1 struct { full_name: #2 string };
+    "};
+    let output = run_to_stdout(&["rust", "migration", "-", "--stdout"], migration);
+
+    assert!(output.contains("pub fn upgrade_user(user: v1::User) -> v2::User {"));
+}
+
+#[test]
+fn stdout_from_file() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("schema.vs"), SCHEMA).unwrap();
+
+    let output = run(&["rust", "types", "schema.vs", "--stdout"], "", dir.path());
+
+    assert!(output.status.success());
+    assert!(
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .contains("pub struct User")
+    );
+    assert!(!dir.path().join("mod.rs").exists());
+}
+
+#[test]
+fn errors_name_stdin() {
+    let dir = tempdir().unwrap();
+    let output = run(&["check", "-"], "version v1; User = struct {", dir.path());
+
+    assert_eq!(output.status.code(), Some(1));
+    assert!(
+        String::from_utf8(output.stderr)
+            .unwrap()
+            .contains("<stdin>")
+    );
+}
+
+#[test]
+fn watch_rejects_stdin() {
+    let dir = tempdir().unwrap();
+    let output = run(
+        &["typescript", "types", "-", "--stdout", "--watch"],
+        SCHEMA,
+        dir.path(),
+    );
+
+    assert_eq!(output.status.code(), Some(2));
+}
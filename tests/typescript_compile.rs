@@ -5,16 +5,16 @@ use tempfile::tempdir;
 
 use utils::CommandExt;
 
-use crate::utils::{TSC_COMMAND, TSC_OPTIONS};
+use crate::utils::{TSC_COMMAND, TSC_OPTIONS, TYPESCRIPT_EXTERNS};
 
 mod utils;
 
-fn check_with_version(schema: &str, version: &str) {
+fn check_with_options(schema: &str, version: &str, args: &[&str], code: &str) {
     let dir = tempdir().unwrap();
 
     let entrypoint_path = dir.path().join("main.ts");
     let entrypoint_content =
-        format!("import {{ {version} }} from \"./index\";\nlet a: object = {version};\n");
+        format!("import {{ {version} }} from \"./index\";\nlet a: object = {version};\n{code}");
     fs::write(&entrypoint_path, entrypoint_content).unwrap();
 
     let schema_path = dir.path().join("schema.vd");
@@ -23,6 +23,8 @@ fn check_with_version(schema: &str, version: &str) {
     Command::new(env!("CARGO_BIN_EXE_versed"))
         .arg("typescript")
         .arg("types")
+        .args(TYPESCRIPT_EXTERNS)
+        .args(args)
         .arg(schema_path)
         .arg(dir.path())
         .run_and_check();
@@ -33,6 +35,23 @@ fn check_with_version(schema: &str, version: &str) {
         .run_and_check();
 }
 
+fn check_with_version(schema: &str, version: &str) {
+    check_with_options(schema, version, &[], "");
+    check_with_options(schema, version, &["--guards"], "");
+    check_with_options(
+        schema,
+        version,
+        &[
+            "--hoist",
+            "--interfaces",
+            "--readonly",
+            "--validate",
+            "--enum-helpers",
+        ],
+        "",
+    );
+}
+
 fn check(schema: &str) {
     check_with_version(schema, "v1");
 }
@@ -72,3 +91,299 @@ fn keyword_idents() {
 fn keyword_version() {
     check_with_version("version yield;", "yield_");
 }
+
+#[test]
+fn envelope() {
+    check_with_options(
+        indoc! {r#"
+            version "v\"1";
+
+            User = struct { name: string };
+            UserEnvelope = int;
+        "#},
+        "v1",
+        &["--envelope", "User"],
+        indoc! {r#"
+            let envelope: v1.UserEnvelope2 = v1.parseUserEnvelope2("{}");
+            let user: v1.User = envelope.data;
+        "#},
+    );
+}
+
+#[test]
+fn envelope_guards() {
+    check_with_options(
+        indoc! {r#"
+            version v1;
+
+            User = struct { name: string };
+            Page<T> = struct { items: [T] };
+        "#},
+        "v1",
+        &["--guards", "--envelope", "User", "--envelope", "Page"],
+        indoc! {r#"
+            try {
+                let user: v1.User = v1.parseUserEnvelope("{}").data;
+                let page: v1.Page<v1.User> = v1.parsePageEnvelope("{}", v1.checkUser).data;
+            } catch (error) {
+                if (error instanceof v1.ParseError) {
+                    let path: string = error.path;
+                }
+            }
+        "#},
+    );
+}
+
+#[test]
+fn validation() {
+    check_with_options(
+        indoc! {r#"
+            version v1;
+
+            User = struct {
+                name: string(min = 1, max = 64, pattern = "^[a-z]+$"),
+                age: int(0..150) = 18,
+                tags: [Tag],
+                pages: Page<Tag>,
+                ids: Page<int>,
+                role: open enum { admin: int(..10), guest: unit },
+                position: (int(-90..90), int(-180..180)),
+                ..Timestamps,
+            };
+            Tag = string(max = 16);
+            Page<T> = struct { items: [T], total: int(0..) };
+            Timestamps = struct { created: int(0..) };
+            ValidationError = unit;
+        "#},
+        "v1",
+        &["--validate", "--optional-defaults"],
+        indoc! {r#"
+            let user: v1.User = {
+                name: "ada",
+                tags: [],
+                pages: { items: [], total: 0 },
+                ids: { items: [], total: 0 },
+                role: { type: "guest", value: null },
+                position: [0, 0],
+                created: 0,
+            };
+            let error: v1.ValidationError2 | undefined = v1.validateUser(user, () => undefined);
+            let pageError = v1.validatePage({ items: [1], total: 1 }, () => undefined, "$.page");
+        "#},
+    );
+}
+
+#[test]
+fn guards() {
+    check_with_options(
+        indoc! {r#"
+            version v1;
+
+            User = struct {
+                name: string(min = 1),
+                age: int(0..150) = 18,
+                id: uuid,
+                friends: [User],
+                pages: Page<Tag>,
+                role: open enum { admin: int(..10), guest: unit },
+                position: (int, int),
+                at: Timestamp,
+                ..Stamps,
+            };
+            Tag = enum { first, text: string };
+            Page<T> = struct { items: [T], next: Option<Page<T>> };
+            Option<T> = enum { none, some: T };
+            Stamps = struct { created: int(0..) };
+            Never = enum {};
+            Loop = Loop;
+            extern Timestamp;
+            ParseError = unit;
+        "#},
+        "v1",
+        &["--guards", "--validate", "--optional-defaults"],
+        indoc! {r#"
+            let json: unknown = JSON.parse("{}");
+            let error: v1.ValidationError | undefined = v1.checkUser(json);
+
+            if (v1.isUser(json)) {
+                let user: v1.User = json;
+                let age: number | undefined = user.age;
+            }
+
+            try {
+                let user: v1.User = v1.parseUser(json);
+                let page: v1.Page<v1.Tag> = v1.parsePage(json, v1.checkTag);
+                let never: v1.Never = v1.parseNever(json);
+                let loop: v1.Loop = v1.parseLoop(json);
+            } catch (error) {
+                if (error instanceof v1.ParseError2) {
+                    let path: string = error.path;
+                    let message: string = error.message;
+                }
+            }
+        "#},
+    );
+}
+
+#[test]
+fn style_options() {
+    check_with_options(
+        indoc! {r#"
+            version v1;
+
+            User<T> = struct {
+                name: string,
+                age: int(0..150),
+                address: struct { street: string, extra: T },
+                contact: enum { email: string, phone: struct { number: string } },
+                tags: [[string]],
+                pair: (int, [string]),
+                ..Stamps,
+            };
+            Stamps = struct { created: int };
+            ValidationError = struct { at: timestamp };
+            Timestamp = unit;
+        "#},
+        "v1",
+        &["--hoist", "--interfaces", "--readonly", "--validate"],
+        indoc! {r#"
+            let address: v1.UserAddress<boolean> = { street: "Main", extra: true };
+            let phone: v1.UserContactPhone = { number: "123" };
+            let contact: v1.UserContact = { type: "phone", value: phone };
+            let user: v1.User<boolean> = {
+                name: "Ann",
+                age: 30,
+                address,
+                contact,
+                tags: [["a"]],
+                pair: [1, []],
+                created: 0,
+            };
+            let stamps: v1.Stamps = user;
+            let error: v1.ValidationError2 | undefined = v1.validateUser(user, () => undefined);
+            let at: v1.Timestamp2 | undefined = undefined;
+
+            // @ts-expect-error
+            user.name = "Bob";
+            // @ts-expect-error
+            user.tags[0].push("b");
+            // @ts-expect-error
+            user.pair[0] = 2;
+        "#},
+    );
+}
+
+#[test]
+fn enum_helpers() {
+    check_with_options(
+        indoc! {r#"
+            version v1;
+
+            Contact = enum {
+                email: string,
+                phone_number: struct { number: string },
+                match,
+                is_email: unit,
+            };
+            Option<T> = enum { none, some: T };
+            Role = open enum { admin: int, guest };
+            Never = enum {};
+            R = unit;
+        "#},
+        "v1",
+        &["--enum-helpers"],
+        indoc! {r#"
+            let email: v1.Contact = v1.Contact.email("a@b");
+            let phone: v1.Contact = v1.Contact.phoneNumber({ number: "123" });
+            let other: v1.Contact = v1.Contact.match2();
+
+            if (v1.Contact.isEmail2(email)) {
+                let address: string = email.value;
+            }
+
+            let description: string = v1.Contact.match(phone, {
+                email: (address) => address,
+                "phone-number": (phone) => phone.number,
+                match: () => "match",
+                "is-email": () => "is email",
+            });
+
+            let some: v1.Option<number> = v1.Option.some(1);
+            let none: v1.Option<number> = v1.Option.none();
+            let value: number = v1.Option.match(some, { none: () => 0, some: (value) => value });
+
+            let role: v1.Role = v1.Role.admin(1);
+            let level: number = v1.Role.match(
+                role,
+                { admin: (level) => level, guest: () => 0 },
+                (other) => -1,
+            );
+
+            if (v1.Role.isKnown(role)) {
+                if (role.type === "admin") {
+                    let level: number = role.value;
+                }
+            } else {
+                let tag: string = role.type;
+            }
+        "#},
+    );
+}
+
+#[test]
+fn int_encoding() {
+    check_with_options(
+        indoc! {r#"
+            version v1;
+            int_encoding string;
+
+            Account = struct {
+                id: int,
+                age: int(0..150),
+                history: [int],
+            };
+        "#},
+        "v1",
+        &["--validate"],
+        indoc! {r#"
+            let account: v1.Account = {
+                id: "9007199254740993",
+                age: "30",
+                history: [],
+            };
+            let id: bigint = BigInt(account.id);
+            let error: v1.ValidationError | undefined = v1.validateAccount(account);
+        "#},
+    );
+}
+
+#[test]
+fn built_in_types() {
+    check_with_options(
+        indoc! {r#"
+            version v1;
+
+            Event = struct {
+                id: uuid,
+                at: timestamp,
+                days: [date],
+                window: (timestamp, duration),
+                payload: bytes,
+            };
+            Timestamp = string;
+        "#},
+        "v1",
+        &["--validate"],
+        indoc! {r#"
+            let event: v1.Event = {
+                id: "67e55044-10b1-426f-9247-bb680e5fe0c8" as v1.Uuid,
+                at: "2024-05-06T07:08:09Z" as v1.Timestamp2,
+                days: ["2024-05-06" as v1.CalendarDate],
+                window: ["2024-05-06T07:08:09Z" as v1.Timestamp2, "PT1.5S" as v1.Duration],
+                payload: "AAEC" as v1.Bytes,
+            };
+            let error: v1.ValidationError | undefined = v1.validateEvent(event);
+            let timestamp: v1.Timestamp = "not branded";
+        "#},
+    );
+}
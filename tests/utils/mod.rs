@@ -33,3 +33,16 @@ pub const TSC_OPTIONS: &[&str] = &[
 pub const TSC_COMMAND: &str = "tsc";
 #[cfg(target_os = "windows")]
 pub const TSC_COMMAND: &str = "tsc.cmd";
+
+/// Mappings for the extern types used by the test schemas.
+pub const RUST_EXTERNS: &[&str] = &[
+    "--extern",
+    "Timestamp=std::time::SystemTime",
+    "--extern",
+    "Clock=std::time::SystemTime",
+    "--extern",
+    "Blob=Vec<u8>",
+];
+
+pub const TYPESCRIPT_EXTERNS: &[&str] =
+    &["--extern", "Timestamp=string", "--extern", "Blob=number[]"];
@@ -152,6 +152,74 @@ mod basic_types {
     }
 }
 
+mod default_values {
+    use super::check;
+    use indoc::indoc;
+
+    #[test]
+    fn primitives() {
+        check(indoc! {r#"
+            version v1;
+
+            Settings = struct {
+                retries: int = 3,
+                offset: int = -1,
+                zero: int = 0,
+                name: string = "anonymous \"user\"",
+                empty: string = "",
+            };
+        "#});
+    }
+
+    #[test]
+    fn lists() {
+        check(indoc! {r#"
+            version v1;
+
+            Settings = struct {
+                tags: [string] = ["a", "b",],
+                empty: [int] = [],
+                matrix: [[int]] = [[1, 2], [], [3]],
+            };
+        "#});
+    }
+
+    #[test]
+    fn through_alias() {
+        check(indoc! {r#"
+            version v1;
+
+            Count = int;
+            Tags = [Tag];
+            Tag = string;
+
+            Settings = struct {
+                count: Count = 10,
+                tags: Tags = ["default"],
+                nested: struct { type: string = "inner" },
+            };
+        "#});
+    }
+
+    #[test]
+    fn through_newtype() {
+        check(indoc! {r#"
+            version v1;
+
+            Ids = [int];
+            Tree = [Tree];
+            Forest = Tree;
+
+            S = struct {
+                ids: Ids = [],
+                tree: Tree = [],
+                forest: Forest = [[]],
+                trees: [Tree] = [[[]]],
+            };
+        "#});
+    }
+}
+
 mod tricky_names {
     use super::check;
     use indoc::indoc;
@@ -356,3 +424,394 @@ mod recursive {
         "});
     }
 }
+
+mod generics {
+    use super::check;
+    use indoc::indoc;
+
+    #[test]
+    fn generic_struct() {
+        check(indoc! {"
+            version v1;
+
+            Page<T> = struct {
+                items: [T],
+                cursor: string,
+            };
+
+            User = struct { name: string };
+            Users = Page<User>;
+            Numbers = struct { page: Page<int> };
+        "});
+    }
+
+    #[test]
+    fn generic_enum() {
+        check(indoc! {"
+            version v1;
+
+            Outcome<T, E> = enum {
+                success: T,
+                failure: E,
+                pending,
+            };
+
+            Response = struct { outcome: Outcome<[string], int> };
+        "});
+    }
+
+    #[test]
+    fn generic_alias() {
+        check(indoc! {"
+            version v1;
+
+            Items<T> = [T];
+            Names = Items<string>;
+        "});
+    }
+
+    #[test]
+    fn nested_anonymous_types() {
+        check(indoc! {"
+            version v1;
+
+            Tagged<T, U> = struct {
+                label: string,
+                inner: struct { value: T, other: enum { empty, full: U } },
+                unrelated: struct { id: int },
+            };
+
+            Document = Tagged<int, [string]>;
+        "});
+    }
+
+    #[test]
+    fn nested_arguments() {
+        check(indoc! {"
+            version v1;
+
+            Pair<A, B> = struct { first: A, second: B };
+            Wrapper<T> = struct { value: T };
+
+            Document = Pair<Wrapper<Pair<int, string>>, Wrapper<[int]>>;
+        "});
+    }
+
+    #[test]
+    fn recursive_generic() {
+        check(indoc! {"
+            version v1;
+
+            List<T> = enum {
+                cons: struct { head: T, tail: List<T> },
+                nil,
+            };
+
+            Numbers = List<int>;
+        "});
+    }
+
+    #[test]
+    fn recursion_through_argument() {
+        check(indoc! {"
+            version v1;
+
+            Wrapper<T> = struct { value: T };
+            Loop = enum { more: Wrapper<Loop>, done };
+        "});
+    }
+
+    #[test]
+    fn default_values() {
+        check(indoc! {"
+            version v1;
+
+            Page<T> = struct {
+                items: [T],
+                size: int = 20,
+                cursor: string = \"start\",
+            };
+
+            Users = Page<string>;
+        "});
+    }
+}
+
+mod tuples {
+    use super::check;
+    use indoc::indoc;
+
+    #[test]
+    fn tuple_alias() {
+        check(indoc! {"
+            version v1;
+
+            Range = (int, int);
+        "});
+    }
+
+    #[test]
+    fn tuple_field() {
+        check(indoc! {"
+            version v1;
+
+            Entry = struct {
+                pair: (string, [int]),
+                pairs: [(int, string, unit)],
+            };
+        "});
+    }
+
+    #[test]
+    fn nested_anonymous_types() {
+        check(indoc! {"
+            version v1;
+
+            Point = ((int, int), struct { label: string }, enum { on, off });
+        "});
+    }
+
+    #[test]
+    fn grouping() {
+        check(indoc! {"
+            version v1;
+
+            Id = (int);
+            Ids = [(Id)];
+        "});
+    }
+
+    #[test]
+    fn recursive_tuple() {
+        check(indoc! {"
+            version v1;
+
+            Chain = enum { more: (int, Chain), done };
+        "});
+    }
+
+    #[test]
+    fn generic_tuple() {
+        check(indoc! {"
+            version v1;
+
+            Pair<A, B> = (A, B);
+            Entry = Pair<string, Pair<int, int>>;
+        "});
+    }
+}
+
+mod spreads {
+    use super::check;
+    use indoc::indoc;
+
+    #[test]
+    fn spread() {
+        check(indoc! {"
+            version v1;
+
+            Timestamps = struct { created_at: int, updated_at: int };
+            User = struct { id: int, ..Timestamps, name: string };
+        "});
+    }
+
+    #[test]
+    fn nested_spreads() {
+        check(indoc! {"
+            version v1;
+
+            Timestamps = struct { created_at: int, updated_at: int };
+            Entity = struct { id: int, ..Timestamps };
+            User = struct { ..Entity, name: string };
+            Empty = struct { };
+            Everything = struct { ..User, ..Empty };
+        "});
+    }
+
+    #[test]
+    fn anonymous_struct() {
+        check(indoc! {"
+            version v1;
+
+            Timestamps = struct { created_at: int };
+            Log = [struct { ..Timestamps, message: string }];
+        "});
+    }
+
+    #[test]
+    fn generic_spread() {
+        check(indoc! {"
+            version v1;
+
+            Page<T> = struct { items: [T], cursor: string };
+            Listing<T> = struct { ..Page<T>, title: string };
+            Names = Listing<string>;
+        "});
+    }
+
+    #[test]
+    fn recursive_spread() {
+        check(indoc! {"
+            version v1;
+
+            Node = struct { id: int };
+            Tree = struct { ..Node, children: [Tree] };
+        "});
+    }
+}
+
+mod open_enums {
+    use super::check;
+    use indoc::indoc;
+
+    #[test]
+    fn open_enum() {
+        check(indoc! {"
+            version v1;
+
+            Status = open enum { active: unit, banned: string };
+            User = struct { status: Status, history: [open enum { created: int }] };
+        "});
+    }
+
+    #[test]
+    fn empty_open_enum() {
+        check(indoc! {"
+            version v1;
+
+            Nothing = open enum { };
+        "});
+    }
+
+    #[test]
+    fn generic_open_enum() {
+        check(indoc! {"
+            version v1;
+
+            Outcome<T> = open enum { done: T, failed: string };
+            Job = struct { outcome: Outcome<int> };
+        "});
+    }
+
+    #[test]
+    fn variant_named_unknown() {
+        check(indoc! {"
+            version v1;
+
+            Reply = open enum { unknown: unit, unknown2: int, yes: unit };
+        "});
+    }
+
+    #[test]
+    fn named_open() {
+        check(indoc! {"
+            version v1;
+
+            open = struct { open: int };
+            Door = open enum { open: open, closed: unit };
+        "});
+    }
+}
+
+mod refinements {
+    use super::check;
+    use indoc::indoc;
+
+    #[test]
+    fn refined_primitives() {
+        check(indoc! {"
+            version v1;
+
+            User = struct {
+                name: string(min = 1, max = 64),
+                age: int(0..150),
+                score: int(..100) = 0,
+                tags: [string(max = 16)],
+                position: (int(-90..90), int(-180..180)),
+            };
+        "});
+    }
+
+    #[test]
+    fn refinements_in_nested_types() {
+        check(indoc! {"
+            version v1;
+
+            Percentage = int(0..100);
+            Page<T> = struct { items: [T], total: int(0..) };
+            Id<T> = T;
+            Stats = struct {
+                pages: Page<Percentage>,
+                plain: Page<int>,
+                id: Id<Percentage>,
+                status: open enum { done: Percentage, pending: unit },
+                shape: enum { square: int(1..), circle: int(1..), dot: unit },
+            };
+            Tree = struct { depth: int(0..8), children: [Tree] };
+        "});
+    }
+
+    #[test]
+    fn pattern() {
+        check(indoc! {r#"
+            version v1;
+
+            Email = string(max = 254, pattern = "^[^@]+@[^@]+$");
+        "#});
+    }
+}
+
+mod externs {
+    use super::check;
+    use indoc::indoc;
+
+    #[test]
+    fn extern_types() {
+        check(indoc! {"
+            version v1;
+
+            extern Timestamp;
+            extern Blob;
+            Event = struct {
+                at: Timestamp,
+                payload: Blob,
+                history: [Timestamp],
+                span: (Timestamp, Timestamp),
+            };
+        "});
+    }
+
+    #[test]
+    fn extern_in_nested_types() {
+        check(indoc! {"
+            version v1;
+
+            extern Timestamp;
+            Page<T> = struct { items: [T] };
+            Timeline = Page<Timestamp>;
+            Change = open enum { created: Timestamp, deleted: unit };
+            Tree = struct { at: Timestamp, children: [Tree] };
+        "});
+    }
+
+    #[test]
+    fn extern_with_marker() {
+        check(indoc! {"
+            version v1;
+
+            extern #1 Timestamp;
+            Event = #2 struct { at: Timestamp };
+        "});
+    }
+
+    #[test]
+    fn named_extern() {
+        check(indoc! {"
+            version v1;
+
+            extern = struct { extern: int };
+            extern Blob;
+            File = struct { extern: extern, content: Blob };
+        "});
+    }
+}
@@ -0,0 +1,131 @@
+use std::{
+    env, fs,
+    path::Path,
+    process::{Child, Command, Stdio},
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+use tempfile::tempdir;
+
+/// Kills the watching process when dropped, even if the test fails.
+struct KillOnDrop(Child);
+
+impl Drop for KillOnDrop {
+    fn drop(&mut self) {
+        let _ = self.0.kill();
+        let _ = self.0.wait();
+    }
+}
+
+/// Starts `versed` with the given arguments in the background.
+fn spawn(args: &[&str], dir: &Path) -> KillOnDrop {
+    let child = Command::new(env!("CARGO_BIN_EXE_versed"))
+        .args(args)
+        .current_dir(dir)
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .unwrap();
+
+    KillOnDrop(child)
+}
+
+/// Waits until a file exists and contains `needle`, returning its contents.
+fn wait_for(path: &Path, needle: &str) -> String {
+    let start = Instant::now();
+
+    loop {
+        if let Ok(content) = fs::read_to_string(path)
+            && content.contains(needle)
+        {
+            return content;
+        }
+
+        assert!(
+            start.elapsed() < Duration::from_secs(10),
+            "{} never contained {needle:?}",
+            path.display()
+        );
+        sleep(Duration::from_millis(50));
+    }
+}
+
+#[test]
+fn rust_rewrites_in_place() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("schema.vs"),
+        "version v1; User = struct { name: string };",
+    )
+    .unwrap();
+
+    let _process = spawn(
+        &["rust", "types", "--watch", "schema.vs", "out"],
+        dir.path(),
+    );
+    wait_for(&dir.path().join("out/v1.rs"), "pub name: String");
+
+    fs::write(
+        dir.path().join("schema.vs"),
+        "version v1; User = struct { name: string, age: int };",
+    )
+    .unwrap();
+    wait_for(&dir.path().join("out/v1.rs"), "pub age: i64");
+
+    let mod_rs = fs::read_to_string(dir.path().join("out/mod.rs")).unwrap();
+    assert_eq!(mod_rs, "pub mod v1;\n");
+    assert!(!dir.path().join("out/v1.rs.tmp").exists());
+}
+
+#[test]
+fn typescript_recovers_from_errors() {
+    let dir = tempdir().unwrap();
+    fs::write(dir.path().join("schema.vs"), "version v1; User = struct {").unwrap();
+
+    let _process = spawn(
+        &["typescript", "types", "-w", "-f", "schema.vs", "types.ts"],
+        dir.path(),
+    );
+
+    // Give the first run some time to fail
+    sleep(Duration::from_millis(500));
+    assert!(!dir.path().join("types.ts").exists());
+
+    fs::write(
+        dir.path().join("schema.vs"),
+        "version v1; User = struct { name: string };",
+    )
+    .unwrap();
+    wait_for(&dir.path().join("types.ts"), "name: string");
+}
+
+#[test]
+fn rust_keeps_existing_files() {
+    let dir = tempdir().unwrap();
+    fs::write(
+        dir.path().join("schema.vs"),
+        "version v1; User = struct { name: string };",
+    )
+    .unwrap();
+    fs::create_dir(dir.path().join("out")).unwrap();
+    fs::write(dir.path().join("out/v1.rs"), "// Written by hand\n").unwrap();
+
+    let _process = spawn(
+        &["rust", "types", "--watch", "schema.vs", "out"],
+        dir.path(),
+    );
+
+    // Files the process didn't generate itself are never replaced, not even by later runs
+    sleep(Duration::from_millis(500));
+    fs::write(
+        dir.path().join("schema.vs"),
+        "version v1; User = struct { name: string, age: int };",
+    )
+    .unwrap();
+    sleep(Duration::from_millis(500));
+
+    let content = fs::read_to_string(dir.path().join("out/v1.rs")).unwrap();
+    assert_eq!(content, "// Written by hand\n");
+    assert!(!dir.path().join("out/mod.rs").exists());
+}